@@ -0,0 +1,491 @@
+//! Admin endpoints
+//!
+//! - `POST /admin/providers/{name}/rotate-key` replaces a provider's API key at
+//!   runtime. Requests already sent upstream keep the key they were built with;
+//!   every request after the rotation uses the new one.
+//! - `POST /admin/route/explain` reports the routing decision for a chat
+//!   completion request without calling a provider.
+//! - `GET /admin/cache/stats` reports the cache key version, entry counts,
+//!   hit rates and per-tier latency percentiles.
+//! - `GET /admin/failures` lists the most recent requests that failed
+//!   upstream, newest first.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    routing::{get, post},
+    Json, Router,
+};
+use llm_edge_cache::metrics::{CacheTier, LatencyPercentiles};
+use llm_edge_proxy::middleware::{auth_middleware, require_scope, SCOPE_ADMIN};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::integration::AppState;
+use crate::proxy::{
+    resolve_provider_override, validate_request, ChatCompletionRequest, ProxyError,
+};
+use crate::route::{explain_route, provider_for, resolve_strategy, RouteExplanation};
+
+/// Key rotation request body
+#[derive(Debug, Deserialize)]
+pub struct RotateKeyRequest {
+    pub api_key: String,
+}
+
+/// Admin routes, protected by API key auth and the `admin` scope
+pub fn admin_routes(auth_config: llm_edge_proxy::Config) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route(
+            "/admin/providers/{name}/rotate-key",
+            post(handle_rotate_key),
+        )
+        .route("/admin/route/explain", post(handle_route_explain))
+        .route("/admin/cache/stats", get(handle_cache_stats))
+        .route("/admin/failures", get(handle_failures));
+
+    require_scope(routes, SCOPE_ADMIN).route_layer(axum::middleware::from_fn_with_state(
+        auth_config,
+        auth_middleware,
+    ))
+}
+
+/// Rotate the API key of a configured provider
+pub async fn handle_rotate_key(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<RotateKeyRequest>,
+) -> Result<Json<serde_json::Value>, ProxyError> {
+    if body.api_key.trim().is_empty() {
+        return Err(ProxyError::invalid_param(
+            "api_key",
+            "API key must not be empty",
+        ));
+    }
+
+    if !matches!(name.as_str(), "openai" | "anthropic") {
+        return Err(ProxyError::validation(format!(
+            "Unknown provider: {}",
+            name
+        )));
+    }
+    let provider = provider_for(&state, &name).ok_or_else(|| {
+        ProxyError::ServiceUnavailable(format!("Provider '{}' is not configured", name))
+    })?;
+
+    provider.rotate_key(body.api_key).map_err(|e| {
+        warn!(provider = %name, error = %e, "API key rotation failed");
+        ProxyError::InternalError(e.to_string())
+    })?;
+
+    info!(provider = %name, "Provider API key rotated via admin endpoint");
+
+    Ok(Json(serde_json::json!({
+        "provider": name,
+        "rotated": true,
+    })))
+}
+
+/// Explain how a chat completion request would be routed
+///
+/// Honours the same `X-Provider` and `X-Routing-Strategy` headers and
+/// `provider/model` prefix as the proxy endpoint. Nothing is sent upstream
+/// and the cache is not consulted.
+pub async fn handle_route_explain(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
+) -> Result<Json<RouteExplanation>, ProxyError> {
+    validate_request(&request, &state.config)?;
+    let pinned = resolve_provider_override(&headers, &mut request)?;
+    let strategy = resolve_strategy(&headers, &state)?;
+
+    Ok(Json(
+        explain_route(&state, &request.model, pinned, strategy).await,
+    ))
+}
+
+/// Cache key version, entry counts, hit rates and latency percentiles
+pub async fn handle_cache_stats(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let cache = &state.cache_manager;
+    let metrics = cache.metrics_snapshot();
+    let latency = |tier| latency_json(cache.metrics().latency_percentiles(tier));
+
+    Json(serde_json::json!({
+        "key_version": cache.key_version(),
+        "l1": {
+            "entries": cache.l1_entry_count(),
+            "hits": metrics.l1_hits,
+            "misses": metrics.l1_misses,
+            "hit_rate": metrics.l1_hit_rate(),
+            "latency_ms": latency(CacheTier::L1),
+        },
+        "l2": {
+            "configured": cache.has_l2(),
+            "approximate_entries": cache.l2_approximate_size().await,
+            "hits": metrics.l2_hits,
+            "misses": metrics.l2_misses,
+            "hit_rate": metrics.l2_hit_rate(),
+            "latency_ms": latency(CacheTier::L2),
+        },
+        "stale_served": metrics.stale_served,
+        "total_requests": metrics.total_requests,
+    }))
+}
+
+/// `null` until the tier has seen an operation
+fn latency_json(percentiles: Option<LatencyPercentiles>) -> serde_json::Value {
+    percentiles.map_or(serde_json::Value::Null, |p| {
+        serde_json::json!({
+            "p50": p.p50_ms,
+            "p95": p.p95_ms,
+            "p99": p.p99_ms,
+            "samples": p.samples,
+        })
+    })
+}
+
+/// Most recent requests that failed upstream, from the dead-letter log
+pub async fn handle_failures(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let failures = state.dead_letters.recent();
+
+    Json(serde_json::json!({
+        "count": failures.len(),
+        "failures": failures,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use async_trait::async_trait;
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use llm_edge_cache::CacheManager;
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        LLMProvider, ProviderResult, UnifiedRequest, UnifiedResponse,
+    };
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    /// Provider that remembers the key it would authenticate with
+    struct KeyProbe {
+        key: parking_lot::Mutex<String>,
+        status: HealthStatus,
+    }
+
+    impl KeyProbe {
+        fn new(status: HealthStatus) -> Arc<Self> {
+            Arc::new(Self {
+                key: parking_lot::Mutex::new("sk-old".to_string()),
+                status,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for KeyProbe {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            unimplemented!("not used by admin tests")
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            self.status.clone()
+        }
+
+        fn rotate_key(&self, new_key: String) -> ProviderResult<()> {
+            *self.key.lock() = new_key;
+            Ok(())
+        }
+    }
+
+    fn auth_config() -> llm_edge_proxy::Config {
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+
+        llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: vec!["ops-key".to_string(), "app-key".to_string()],
+                require_auth_for_health: false,
+                key_scopes: HashMap::from([
+                    ("ops-key".to_string(), vec![SCOPE_ADMIN.to_string()]),
+                    ("app-key".to_string(), vec!["inference".to_string()]),
+                ]),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    fn app(probe: Arc<KeyProbe>) -> Router {
+        app_with(probe, None)
+    }
+
+    fn app_with(probe: Arc<KeyProbe>, anthropic: Option<Arc<KeyProbe>>) -> Router {
+        let state = Arc::new(AppState {
+            anthropic_provider: anthropic.map(|p| p as Arc<dyn LLMProvider>),
+            ..AppState::new(AppConfig::default()).with_openai(probe)
+        });
+        admin_routes(auth_config()).with_state(state)
+    }
+
+    fn rotate(provider: &str, key: &str, api_key: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/admin/providers/{}/rotate-key", provider))
+            .header("content-type", "application/json")
+            .header("x-api-key", key)
+            .body(Body::from(
+                serde_json::json!({ "api_key": api_key }).to_string(),
+            ))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_key_rotates_provider_key() {
+        let probe = KeyProbe::new(HealthStatus::Healthy);
+        let app = app(probe.clone());
+
+        let response = app
+            .clone()
+            .oneshot(rotate("openai", "ops-key", "sk-new"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(*probe.key.lock(), "sk-new");
+
+        // Not configured
+        let response = app
+            .clone()
+            .oneshot(rotate("anthropic", "ops-key", "sk-ant"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Empty key
+        let response = app.oneshot(rotate("openai", "ops-key", " ")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(*probe.key.lock(), "sk-new");
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_requires_admin_scope() {
+        let probe = KeyProbe::new(HealthStatus::Healthy);
+
+        let response = app(probe.clone())
+            .oneshot(rotate("openai", "app-key", "sk-new"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(*probe.key.lock(), "sk-old");
+    }
+
+    #[tokio::test]
+    async fn test_route_explain_lists_unhealthy_provider_as_excluded() {
+        let app = app_with(
+            KeyProbe::new(HealthStatus::Unhealthy),
+            Some(KeyProbe::new(HealthStatus::Healthy)),
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/route/explain")
+                    .header("content-type", "application/json")
+                    .header("x-api-key", "ops-key")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "model": "gpt-4",
+                            "messages": [{"role": "user", "content": "Hello"}]
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let explanation: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(explanation["selected"], "anthropic");
+        assert_eq!(explanation["strategy"], "model_family");
+        let openai = &explanation["candidates"][0];
+        assert_eq!(openai["provider"], "openai");
+        assert_eq!(openai["health"], "unhealthy");
+        assert_eq!(openai["excluded"], "unhealthy");
+        assert!(explanation["candidates"][1]["excluded"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_report_key_version() {
+        let state = Arc::new(
+            AppState::new(AppConfig::default())
+                .with_cache_manager(CacheManager::new().with_key_version(3)),
+        );
+        let stats = |key: &'static str| {
+            Request::builder()
+                .uri("/admin/cache/stats")
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let app = admin_routes(auth_config()).with_state(state);
+        let response = app.clone().oneshot(stats("ops-key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let stats_body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(stats_body["key_version"], 3);
+        assert_eq!(stats_body["l2"]["configured"], false);
+        assert!(stats_body["l1"]["latency_ms"].is_null());
+
+        let response = app.oneshot(stats("app-key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_report_tier_latency_percentiles() {
+        let cache = CacheManager::new();
+        for ms in 1..=100 {
+            cache
+                .metrics()
+                .record_latency(CacheTier::L1, std::time::Duration::from_millis(ms));
+        }
+        let state = Arc::new(AppState::new(AppConfig::default()).with_cache_manager(cache));
+
+        let Json(stats) = handle_cache_stats(State(state)).await;
+
+        let l1 = &stats["l1"]["latency_ms"];
+        assert_eq!(l1["samples"], 100);
+        assert_eq!(l1["p50"], 50.0);
+        assert_eq!(l1["p95"], 95.0);
+        assert_eq!(l1["p99"], 99.0);
+        assert!(stats["l2"]["latency_ms"].is_null());
+    }
+
+    /// Provider whose upstream is down, echoing the prompt in its error
+    struct DownProvider;
+
+    #[async_trait]
+    impl LLMProvider for DownProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Err(llm_edge_providers::ProviderError::ApiError {
+                status: 503,
+                message: format!(
+                    "overloaded while handling '{}'",
+                    request.messages[0].content
+                ),
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_listed_in_failures() {
+        use crate::proxy::handle_chat_completions;
+
+        let state =
+            Arc::new(AppState::new(AppConfig::default()).with_openai(Arc::new(DownProvider)));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req-dead-1".parse().unwrap());
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Email jane@example.com"}]
+        }))
+        .unwrap();
+
+        let (status, _) =
+            handle_chat_completions(State(state.clone()), headers, None, Json(request))
+                .await
+                .unwrap_err()
+                .into_parts();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        let failures = |key: &'static str| {
+            Request::builder()
+                .uri("/admin/failures")
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let app = admin_routes(auth_config()).with_state(state);
+        let response = app.clone().oneshot(failures("ops-key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["count"], 1);
+        let failure = &body["failures"][0];
+        assert_eq!(failure["request_id"], "req-dead-1");
+        assert_eq!(failure["model"], "gpt-4");
+        assert_eq!(
+            failure["attempted_providers"],
+            serde_json::json!(["openai"])
+        );
+        assert_eq!(failure["reason"], "provider_error");
+        assert_eq!(failure["served_fallback"], false);
+        let error = failure["error"].as_str().unwrap();
+        assert!(error.contains("overloaded"), "{error}");
+        assert!(!error.contains("jane@example.com"), "{error}");
+
+        let response = app.oneshot(failures("app-key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
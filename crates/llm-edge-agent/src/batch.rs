@@ -0,0 +1,446 @@
+//! Batch chat completions
+//!
+//! `POST /v1/chat/completions/batch` accepts a JSON array of chat completion
+//! requests and runs each one through the normal cache/route/provider path
+//! with bounded concurrency. Results come back in input order; a failed item
+//! carries an error object instead of failing the whole batch.
+
+use axum::{extract::State, http::HeaderMap, routing::post, Extension, Json, Router};
+use futures::stream::{self, StreamExt};
+use llm_edge_proxy::middleware::{
+    auth_middleware, rate_limit_middleware, require_scope, AllowedModels, RateLimiter,
+    SCOPE_INFERENCE,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::info;
+
+use crate::integration::AppState;
+use crate::proxy::{
+    debug_trace_gate, handle_chat_completions, ChatCompletionRequest, ChatCompletionResponse,
+    ProxyError,
+};
+
+/// Result for a single batch item
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    /// Position of the item in the request array
+    pub index: usize,
+    /// HTTP status the item would have returned on its own
+    pub status: u16,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<ChatCompletionResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<serde_json::Value>,
+}
+
+/// Batch-level summary
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cached: usize,
+    pub latency_ms: u64,
+}
+
+/// Batch completions response
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub object: String,
+    pub results: Vec<BatchItemResult>,
+    pub summary: BatchSummary,
+}
+
+/// Batch routes, protected by API key auth and the `inference` scope
+///
+/// A batch counts as one request in the API key's `limiter` bucket.
+pub fn batch_routes(
+    auth_config: llm_edge_proxy::Config,
+    limiter: RateLimiter,
+) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/v1/chat/completions/batch", post(handle_batch_completions))
+        .route_layer(axum::middleware::from_fn(debug_trace_gate))
+        .route_layer(axum::middleware::from_fn_with_state(
+            limiter,
+            rate_limit_middleware,
+        ));
+
+    require_scope(routes, SCOPE_INFERENCE).route_layer(axum::middleware::from_fn_with_state(
+        auth_config,
+        auth_middleware,
+    ))
+}
+
+/// Batch chat completions handler
+pub async fn handle_batch_completions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    allowed_models: Option<Extension<AllowedModels>>,
+    Json(items): Json<Vec<serde_json::Value>>,
+) -> Result<Json<BatchResponse>, ProxyError> {
+    let start_time = Instant::now();
+
+    if items.is_empty() {
+        return Err(ProxyError::validation("Batch cannot be empty"));
+    }
+
+    if items.len() > state.config.max_batch_size {
+        return Err(ProxyError::validation(format!(
+            "Batch size {} exceeds maximum of {}",
+            items.len(),
+            state.config.max_batch_size
+        )));
+    }
+
+    let total = items.len();
+    info!(
+        batch_size = total,
+        concurrency = state.config.batch_concurrency,
+        "Processing batch completion request"
+    );
+
+    // `buffered` keeps input order while running up to N items at once
+    let results: Vec<BatchItemResult> = stream::iter(items.into_iter().enumerate())
+        .map(|(index, item)| {
+            process_item(
+                state.clone(),
+                headers.clone(),
+                allowed_models.clone(),
+                index,
+                item,
+            )
+        })
+        .buffered(state.config.batch_concurrency.max(1))
+        .collect()
+        .await;
+
+    let succeeded = results.iter().filter(|r| r.response.is_some()).count();
+    let cached = results
+        .iter()
+        .filter_map(|r| r.response.as_ref()?.metadata.as_ref())
+        .filter(|m| m.cached)
+        .count();
+
+    Ok(Json(BatchResponse {
+        object: "batch".to_string(),
+        results,
+        summary: BatchSummary {
+            total,
+            succeeded,
+            failed: total - succeeded,
+            cached,
+            latency_ms: start_time.elapsed().as_millis() as u64,
+        },
+    }))
+}
+
+/// Run one batch item through the regular chat completions path
+async fn process_item(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    allowed_models: Option<Extension<AllowedModels>>,
+    index: usize,
+    item: serde_json::Value,
+) -> BatchItemResult {
+    let start_time = Instant::now();
+
+    let outcome = match serde_json::from_value::<ChatCompletionRequest>(item) {
+        Ok(request) => {
+            handle_chat_completions(State(state), headers, allowed_models, Json(request))
+                .await
+                .map(|Json(response)| response)
+        }
+        Err(e) => Err(ProxyError::validation(format!("Invalid request: {}", e))),
+    };
+
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+
+    match outcome {
+        Ok(response) => BatchItemResult {
+            index,
+            status: 200,
+            latency_ms,
+            response: Some(response),
+            error: None,
+        },
+        Err(e) => {
+            let (status, error) = e.into_parts();
+            BatchItemResult {
+                index,
+                status: status.as_u16(),
+                latency_ms,
+                response: None,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use async_trait::async_trait;
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    /// Provider that echoes the last message back
+    struct EchoProvider;
+
+    #[async_trait]
+    impl LLMProvider for EchoProvider {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            let content = request.messages.last().unwrap().content.clone();
+
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content,
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: "echo".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn test_state(max_batch_size: usize) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(AppConfig {
+                max_batch_size,
+                batch_concurrency: 2,
+                ..AppConfig::default()
+            })
+            .with_openai(Arc::new(EchoProvider)),
+        )
+    }
+
+    fn item(model: &str, content: &str) -> serde_json::Value {
+        json!({
+            "model": model,
+            "messages": [{"role": "user", "content": content}],
+            "max_tokens": 16
+        })
+    }
+
+    fn auth_config(enabled: bool) -> llm_edge_proxy::Config {
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+
+        llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled,
+                api_keys: vec!["batch-key".to_string()],
+                require_auth_for_health: false,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mixed_batch_outcomes_in_order() {
+        let state = test_state(10);
+
+        // Warm the cache for the first item
+        let warm = serde_json::from_value(item("gpt-4", "cached")).unwrap();
+        let _ = handle_chat_completions(State(state.clone()), HeaderMap::new(), None, Json(warm))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let Json(batch) = handle_batch_completions(
+            State(state),
+            HeaderMap::new(),
+            None,
+            Json(vec![
+                item("gpt-4", "cached"),
+                item("gpt-4", "fresh one"),
+                item("", "invalid model"),
+                item("gpt-4", "fresh two"),
+            ]),
+        )
+        .await
+        .unwrap();
+
+        let indexes: Vec<usize> = batch.results.iter().map(|r| r.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2, 3]);
+
+        let cached = batch.results[0].response.as_ref().unwrap();
+        assert!(cached.metadata.as_ref().unwrap().cached);
+
+        let fresh = batch.results[1].response.as_ref().unwrap();
+        assert!(!fresh.metadata.as_ref().unwrap().cached);
+        assert_eq!(fresh.choices[0].message.content, "fresh one");
+
+        assert_eq!(batch.results[2].status, 400);
+        assert!(batch.results[2].response.is_none());
+        let error = batch.results[2].error.as_ref().unwrap();
+        assert_eq!(error["type"], "invalid_request_error");
+        assert_eq!(error["param"], "model");
+
+        let last = batch.results[3].response.as_ref().unwrap();
+        assert_eq!(last.choices[0].message.content, "fresh two");
+
+        assert_eq!(batch.summary.total, 4);
+        assert_eq!(batch.summary.succeeded, 3);
+        assert_eq!(batch.summary.failed, 1);
+        assert_eq!(batch.summary.cached, 1);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_item_fails_alone() {
+        let Json(batch) = handle_batch_completions(
+            State(test_state(10)),
+            HeaderMap::new(),
+            None,
+            Json(vec![json!({"model": "gpt-4"}), item("gpt-4", "ok")]),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(batch.results[0].status, 400);
+        assert_eq!(batch.results[1].status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limit_enforced() {
+        let result = handle_batch_completions(
+            State(test_state(2)),
+            HeaderMap::new(),
+            None,
+            Json(vec![
+                item("gpt-4", "a"),
+                item("gpt-4", "b"),
+                item("gpt-4", "c"),
+            ]),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ProxyError::ValidationError { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_batch_route_requires_auth() {
+        let config = auth_config(true);
+        let limiter = RateLimiter::from_config(&config.rate_limit);
+        let app = batch_routes(config, limiter).with_state(test_state(10));
+        let body = serde_json::to_vec(&vec![item("gpt-4", "hi")]).unwrap();
+
+        let request = |key: Option<&str>| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions/batch")
+                .header("content-type", "application/json");
+            if let Some(key) = key {
+                builder = builder.header("x-api-key", key);
+            }
+            builder.body(Body::from(body.clone())).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(request(Some("batch-key"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_restricted_key_limited_to_allowed_models() {
+        let mut config = auth_config(true);
+        config.auth.key_models.insert(
+            "batch-key".to_string(),
+            vec!["gpt-3.5-turbo".to_string(), "claude-3-haiku*".to_string()],
+        );
+        let limiter = RateLimiter::from_config(&config.rate_limit);
+        let app = batch_routes(config, limiter).with_state(test_state(10));
+        let body =
+            serde_json::to_vec(&vec![item("gpt-3.5-turbo", "hi"), item("gpt-4", "hi")]).unwrap();
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions/batch")
+            .header("content-type", "application/json")
+            .header("x-api-key", "batch-key")
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let batch: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(batch["results"][0]["status"], 200);
+        assert_eq!(batch["results"][1]["status"], 403);
+        assert_eq!(
+            batch["results"][1]["error"]["code"],
+            crate::proxy::CODE_MODEL_NOT_ALLOWED
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! Response cacheability policy
+//!
+//! [`CachePolicy`] is consulted after a successful provider call and before
+//! the response is written to the cache. Provider errors never reach it; they
+//! are returned to the client without caching.
+//!
+//! It also decides whether a request may be answered from the cache at all:
+//! high-temperature requests ask for varied output, so they skip the lookup.
+
+use llm_edge_providers::{FinishReason, UnifiedResponse};
+
+use crate::proxy::ChatCompletionRequest;
+
+/// Rules deciding whether a provider response is written to the cache
+///
+/// The default caches every non-empty response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachePolicy {
+    /// Minimum response length in characters (0 caches any non-empty response)
+    pub min_content_length: usize,
+
+    /// Only cache responses that finished with [`FinishReason::Stop`]
+    pub require_stop_finish_reason: bool,
+
+    /// Skip caching when the request temperature is above this value
+    ///
+    /// Requests without an explicit temperature are always cacheable.
+    pub max_temperature: Option<f32>,
+
+    /// Skip the cache lookup when the request temperature is above this value
+    ///
+    /// Independent of `max_temperature`, which only governs storing.
+    pub lookup_max_temperature: Option<f32>,
+}
+
+/// Default `lookup_max_temperature`
+pub const DEFAULT_LOOKUP_MAX_TEMPERATURE: f32 = 0.8;
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            min_content_length: 0,
+            require_stop_finish_reason: false,
+            max_temperature: None,
+            lookup_max_temperature: Some(DEFAULT_LOOKUP_MAX_TEMPERATURE),
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Load the policy from environment variables
+    pub fn from_env() -> Self {
+        Self {
+            min_content_length: std::env::var("CACHE_MIN_RESPONSE_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            require_stop_finish_reason: std::env::var("CACHE_REQUIRE_STOP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            max_temperature: std::env::var("CACHE_MAX_TEMPERATURE")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            lookup_max_temperature: Some(
+                std::env::var("CACHE_LOOKUP_MAX_TEMPERATURE")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_LOOKUP_MAX_TEMPERATURE),
+            ),
+        }
+    }
+
+    /// Whether `request` must bypass the cache lookup because of its temperature
+    ///
+    /// Requests without an explicit temperature may always be served from cache.
+    pub fn skips_lookup(&self, request: &ChatCompletionRequest) -> bool {
+        matches!(
+            (self.lookup_max_temperature, request.temperature),
+            (Some(max), Some(temperature)) if temperature > max
+        )
+    }
+
+    /// Why `response` should not be cached, or `None` if it may be
+    pub fn skip_reason(
+        &self,
+        request: &ChatCompletionRequest,
+        response: &UnifiedResponse,
+    ) -> Option<&'static str> {
+        // CachedResponse has no room for logprobs
+        if request.wants_logprobs() {
+            return Some("logprobs");
+        }
+
+        let Some(choice) = response.choices.first() else {
+            return Some("no_choices");
+        };
+
+        let content = choice.message.content.trim();
+        if content.is_empty() {
+            return Some("empty");
+        }
+        if content.chars().count() < self.min_content_length {
+            return Some("too_short");
+        }
+
+        if self.require_stop_finish_reason && choice.finish_reason != Some(FinishReason::Stop) {
+            return Some("finish_reason");
+        }
+
+        if let (Some(max), Some(temperature)) = (self.max_temperature, request.temperature) {
+            if temperature > max {
+                return Some("temperature");
+            }
+        }
+
+        None
+    }
+
+    /// Whether `response` may be written to the cache
+    pub fn should_cache(
+        &self,
+        request: &ChatCompletionRequest,
+        response: &UnifiedResponse,
+    ) -> bool {
+        self.skip_reason(request, response).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{convert_to_cacheable, handle_chat_completions, ChatMessage};
+    use async_trait::async_trait;
+    use axum::{extract::State, http::HeaderMap, Json};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, Usage,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn response(content: &str, finish_reason: &str) -> UnifiedResponse {
+        UnifiedResponse {
+            id: "resp-1".to_string(),
+            model: "gpt-4".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content: content.to_string(),
+                },
+                finish_reason: Some(FinishReason::from_native(finish_reason)),
+                native_finish_reason: Some(finish_reason.to_string()),
+                logprobs: None,
+                tool_calls: Vec::new(),
+            }],
+            usage: Usage {
+                prompt_tokens: 1,
+                completion_tokens: 1,
+                total_tokens: 2,
+            },
+            metadata: ResponseMetadata {
+                provider: "openai".to_string(),
+                cached: false,
+                latency_ms: 0,
+                cost_usd: None,
+                usage_estimated: false,
+            },
+        }
+    }
+
+    fn request(temperature: Option<f32>) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Write a haiku".to_string(),
+            }],
+            temperature,
+            max_tokens: Some(32),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    /// Provider returning a fixed completion
+    #[derive(Default)]
+    struct FixedProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for FixedProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(response("Autumn moonlight", "stop"))
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[test]
+    fn test_default_policy_skips_only_empty_responses() {
+        let policy = CachePolicy::default();
+
+        assert!(policy.should_cache(&request(Some(1.9)), &response("x", "length")));
+        assert_eq!(
+            policy.skip_reason(&request(None), &response("  ", "stop")),
+            Some("empty")
+        );
+    }
+
+    #[test]
+    fn test_policy_rules() {
+        let policy = CachePolicy {
+            min_content_length: 5,
+            require_stop_finish_reason: true,
+            max_temperature: Some(1.0),
+            lookup_max_temperature: None,
+        };
+
+        assert_eq!(
+            policy.skip_reason(&request(None), &response("hi", "stop")),
+            Some("too_short")
+        );
+        assert_eq!(
+            policy.skip_reason(&request(None), &response("long enough", "length")),
+            Some("finish_reason")
+        );
+        assert_eq!(
+            policy.skip_reason(&request(None), &response("long enough", "content_filter")),
+            Some("finish_reason")
+        );
+        assert!(policy.should_cache(&request(None), &response("long enough", "end_turn")));
+    }
+
+    #[test]
+    fn test_lookup_skipped_above_temperature_threshold() {
+        let policy = CachePolicy::default();
+
+        assert!(policy.skips_lookup(&request(Some(1.0))));
+        assert!(!policy.skips_lookup(&request(Some(0.8))));
+        assert!(!policy.skips_lookup(&request(None)));
+
+        let policy = CachePolicy {
+            lookup_max_temperature: None,
+            ..CachePolicy::default()
+        };
+        assert!(!policy.skips_lookup(&request(Some(1.9))));
+    }
+
+    #[tokio::test]
+    async fn test_high_temperature_request_bypasses_cached_entry() {
+        let provider = Arc::new(FixedProvider::default());
+        let state = Arc::new(AppState::new(AppConfig::default()).with_openai(provider.clone()));
+
+        for temperature in [1.0, 0.2] {
+            state
+                .cache_manager
+                .store(
+                    &convert_to_cacheable(&request(Some(temperature))),
+                    llm_edge_cache::l1::CachedResponse {
+                        content: "From cache".to_string(),
+                        tokens: None,
+                        model: "gpt-4".to_string(),
+                        cached_at: chrono::Utc::now().timestamp(),
+                    },
+                )
+                .await;
+        }
+
+        for _ in 0..2 {
+            let Json(response) = handle_chat_completions(
+                State(state.clone()),
+                HeaderMap::new(),
+                None,
+                Json(request(Some(1.0))),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.choices[0].message.content, "Autumn moonlight");
+            assert!(!response.metadata.unwrap().cached);
+        }
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+
+        // Low-temperature requests are still served from the cache
+        let Json(response) = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            None,
+            Json(request(Some(0.2))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.choices[0].message.content, "From cache");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_high_temperature_response_not_cached() {
+        let state = Arc::new(
+            AppState::new(AppConfig {
+                cache_policy: CachePolicy {
+                    max_temperature: Some(1.0),
+                    ..CachePolicy::default()
+                },
+                ..AppConfig::default()
+            })
+            .with_openai(Arc::new(FixedProvider::default())),
+        );
+
+        let cached_on_repeat = |temperature: f32| {
+            let state = state.clone();
+            async move {
+                for _ in 0..2 {
+                    let Json(response) = handle_chat_completions(
+                        State(state.clone()),
+                        HeaderMap::new(),
+                        None,
+                        Json(request(Some(temperature))),
+                    )
+                    .await
+                    .unwrap();
+                    // Cache writes happen in the background; the clock is
+                    // paused, so this only waits for them to land
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    if response.metadata.unwrap().cached {
+                        return true;
+                    }
+                }
+                false
+            }
+        };
+
+        assert!(!cached_on_repeat(1.9).await);
+        assert!(cached_on_repeat(0.0).await);
+    }
+}
@@ -0,0 +1,205 @@
+//! Global cap on concurrent `/v1/*` requests
+//!
+//! Rate limiting works per minute, so a burst can still open enough
+//! connections to run out of file descriptors or memory before it kicks in.
+//! With `MAX_CONCURRENT_REQUESTS` set, every `/v1/*` request holds a permit
+//! from one shared semaphore while it is handled; once they are all taken,
+//! new requests get `503` with `Retry-After` straight away instead of
+//! queueing. The permit is held until the response body is finished, so a
+//! streamed completion counts for as long as it is being sent.
+//!
+//! Free permits are exported as the `llm_concurrency_permits_available`
+//! gauge.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use llm_edge_monitoring::metrics;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::proxy::ProxyError;
+
+/// `Retry-After` sent with requests rejected at the cap, in seconds
+pub const CONCURRENCY_RETRY_AFTER_SECONDS: u32 = 1;
+
+/// Shared permits for in-flight `/v1/*` requests
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimit {
+    /// `None` when unlimited
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl ConcurrencyLimit {
+    /// At most `max` requests at once; 0 means unlimited
+    pub fn new(max: usize) -> Self {
+        let permits = (max > 0).then(|| Arc::new(Semaphore::new(max)));
+        if let Some(permits) = &permits {
+            metrics::record_concurrency_permits_available(permits.available_permits());
+        }
+        Self { permits }
+    }
+
+    /// Permits not currently held, or `None` when unlimited
+    pub fn available(&self) -> Option<usize> {
+        self.permits
+            .as_ref()
+            .map(|permits| permits.available_permits())
+    }
+}
+
+/// Holds a permit until the response body is done, then updates the gauge
+///
+/// Dropping covers handlers that fail, panic or are cancelled, and clients
+/// that disconnect mid-body.
+struct PermitGuard {
+    permit: Option<OwnedSemaphorePermit>,
+    permits: Arc<Semaphore>,
+}
+
+impl Drop for PermitGuard {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        metrics::record_concurrency_permits_available(self.permits.available_permits());
+    }
+}
+
+/// Concurrency cap middleware for the `/v1/*` routes
+pub async fn limit_concurrency(
+    State(limit): State<ConcurrencyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(permits) = limit.permits else {
+        return next.run(request).await;
+    };
+
+    let Ok(permit) = permits.clone().try_acquire_owned() else {
+        let (status, error) =
+            ProxyError::ServiceUnavailable("Too many concurrent requests".to_string()).into_parts();
+        let mut response = (status, Json(serde_json::json!({ "error": error }))).into_response();
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(CONCURRENCY_RETRY_AFTER_SECONDS),
+        );
+        return response;
+    };
+    metrics::record_concurrency_permits_available(permits.available_permits());
+
+    let guard = PermitGuard {
+        permit: Some(permit),
+        permits,
+    };
+    next.run(request).await.map(|body| {
+        Body::from_stream(body.into_data_stream().map(move |chunk| {
+            let _held = &guard;
+            chunk
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn get_request() -> Request<Body> {
+        Request::builder()
+            .uri("/v1/slow")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_overflow_rejected_until_permits_return() {
+        let limit = ConcurrencyLimit::new(2);
+        // Each request waits here for a permit the test hands out
+        let release = Arc::new(Semaphore::new(0));
+        let app = Router::new()
+            .route(
+                "/v1/slow",
+                get({
+                    let release = release.clone();
+                    move || async move {
+                        release.acquire().await.unwrap().forget();
+                        "done"
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limit.clone(),
+                limit_concurrency,
+            ));
+
+        let in_flight: Vec<_> = (0..2)
+            .map(|_| tokio::spawn(app.clone().oneshot(get_request())))
+            .collect();
+        while limit.available() != Some(0) {
+            tokio::task::yield_now().await;
+        }
+
+        let response = app.clone().oneshot(get_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "1");
+
+        release.add_permits(2);
+        for request in in_flight {
+            assert_eq!(request.await.unwrap().unwrap().status(), StatusCode::OK);
+        }
+        assert_eq!(limit.available(), Some(2));
+
+        // Capacity is back once the in-flight requests finish
+        let next = tokio::spawn(app.oneshot(get_request()));
+        while limit.available() != Some(1) {
+            tokio::task::yield_now().await;
+        }
+        release.add_permits(1);
+        assert_eq!(next.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_holds_permit_until_finished() {
+        let limit = ConcurrencyLimit::new(1);
+        let (sender, receiver) = futures::channel::mpsc::unbounded::<&'static str>();
+        let receiver = Arc::new(std::sync::Mutex::new(Some(receiver)));
+        let app = Router::new()
+            .route(
+                "/v1/slow",
+                get(move || async move {
+                    let receiver = receiver.lock().unwrap().take().unwrap();
+                    Body::from_stream(receiver.map(Ok::<_, std::convert::Infallible>))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limit.clone(),
+                limit_concurrency,
+            ));
+
+        // The handler has returned, but the body is still streaming
+        let response = app.clone().oneshot(get_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(limit.available(), Some(0));
+        let rejected = app.clone().oneshot(get_request()).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        sender.unbounded_send("data: done\n\n").unwrap();
+        drop(sender);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"data: done\n\n");
+        assert_eq!(limit.available(), Some(1));
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        assert_eq!(ConcurrencyLimit::new(0).available(), None);
+    }
+}
@@ -0,0 +1,284 @@
+//! Per-request cost ceiling
+//!
+//! Before a request is sent upstream its worst-case cost is estimated from the
+//! prompt size, the resolved `max_tokens` and the selected provider's pricing.
+//! Requests estimated above the ceiling are rejected with
+//! `request_too_expensive` and never reach the provider.
+
+use axum::http::HeaderMap;
+use llm_edge_providers::adapter::PricingInfo;
+use llm_edge_proxy::middleware::{find_api_key, presented_api_key};
+use std::collections::HashMap;
+
+use crate::proxy::{ChatCompletionRequest, ProxyError};
+
+/// Error code returned when a request exceeds its cost ceiling
+pub const CODE_REQUEST_TOO_EXPENSIVE: &str = "request_too_expensive";
+
+/// Characters per token assumed when estimating token counts
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Maximum estimated cost of a single request, in USD
+///
+/// The default has no ceiling.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostCeiling {
+    /// Ceiling for every request (`None` disables the guard)
+    pub max_request_cost: Option<f64>,
+
+    /// Ceilings for individual API keys, overriding `max_request_cost`
+    ///
+    /// Keyed like `API_KEYS`: plain-text keys or their SHA-256 hashes.
+    pub per_key: HashMap<String, f64>,
+}
+
+impl CostCeiling {
+    /// Load the ceiling from environment variables
+    pub fn from_env() -> Self {
+        Self {
+            max_request_cost: std::env::var("MAX_REQUEST_COST_USD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|c: &f64| *c > 0.0),
+            per_key: parse_per_key(&std::env::var("MAX_REQUEST_COST_PER_KEY").unwrap_or_default()),
+        }
+    }
+
+    /// Ceiling applying to the API key presented in `headers`
+    pub fn ceiling_for(&self, headers: &HeaderMap) -> Option<f64> {
+        presented_api_key(headers)
+            .and_then(|key| find_api_key(&key, self.per_key.keys()).map(|k| self.per_key[k]))
+            .or(self.max_request_cost)
+    }
+
+    /// Reject `request` if its worst-case cost exceeds the applicable ceiling
+    ///
+    /// Requests are let through when the model has no known pricing.
+    pub fn check(
+        &self,
+        headers: &HeaderMap,
+        request: &ChatCompletionRequest,
+        max_tokens: Option<u32>,
+        pricing: Option<&PricingInfo>,
+    ) -> Result<(), ProxyError> {
+        let (Some(ceiling), Some(pricing)) = (self.ceiling_for(headers), pricing) else {
+            return Ok(());
+        };
+
+        let estimate = estimate_max_cost(pricing, estimate_prompt_tokens(request), max_tokens);
+        if estimate <= ceiling {
+            return Ok(());
+        }
+
+        Err(ProxyError::ValidationError {
+            message: format!(
+                "Estimated worst-case cost ${:.4} exceeds the ${:.4} per-request ceiling; lower max_tokens or shorten the prompt",
+                estimate, ceiling
+            ),
+            param: Some("max_tokens".to_string()),
+            code: Some(CODE_REQUEST_TOO_EXPENSIVE.to_string()),
+        })
+    }
+}
+
+/// Rough prompt token count (about four characters per token)
+pub fn estimate_prompt_tokens(request: &ChatCompletionRequest) -> u32 {
+    request
+        .messages
+        .iter()
+        .map(|m| m.content.chars().count().div_ceil(CHARS_PER_TOKEN) as u32)
+        .sum()
+}
+
+/// Cost if the model generates every one of `max_tokens`
+pub fn estimate_max_cost(
+    pricing: &PricingInfo,
+    prompt_tokens: u32,
+    max_tokens: Option<u32>,
+) -> f64 {
+    let input_cost = (prompt_tokens as f64 / 1000.0) * pricing.input_cost_per_1k;
+    let output_cost = (max_tokens.unwrap_or(0) as f64 / 1000.0) * pricing.output_cost_per_1k;
+    input_cost + output_cost
+}
+
+/// Parse `MAX_REQUEST_COST_PER_KEY` (`key:usd,key:usd`)
+fn parse_per_key(raw: &str) -> HashMap<String, f64> {
+    raw.split(',')
+        .filter_map(|entry| entry.trim().split_once(':'))
+        .filter(|(key, _)| !key.is_empty())
+        .filter_map(|(key, cost)| {
+            let cost: f64 = cost.trim().parse().ok()?;
+            (cost > 0.0).then(|| (key.to_string(), cost))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{handle_chat_completions, ChatMessage};
+    use async_trait::async_trait;
+    use axum::{extract::State, http::HeaderValue, Json};
+    use llm_edge_providers::{
+        adapter::HealthStatus,
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Priced provider that counts the requests it receives
+    struct PricedProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for PricedProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "fine".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 2,
+                    completion_tokens: 1,
+                    total_tokens: 3,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            Some(PricingInfo {
+                input_cost_per_1k: 0.03,
+                output_cost_per_1k: 0.06,
+            })
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn state(ceiling: CostCeiling) -> (Arc<AppState>, Arc<PricedProvider>) {
+        let provider = Arc::new(PricedProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let state = Arc::new(
+            AppState::new(AppConfig {
+                cost_ceiling: ceiling,
+                ..AppConfig::default()
+            })
+            .with_openai(provider.clone()),
+        );
+        (state, provider)
+    }
+
+    fn request(max_tokens: u32) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: format!("Write {} tokens", max_tokens),
+            }],
+            temperature: None,
+            max_tokens: Some(max_tokens),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_over_ceiling_rejected_before_provider() {
+        let (state, provider) = state(CostCeiling {
+            max_request_cost: Some(1.0),
+            ..CostCeiling::default()
+        });
+
+        // 100k output tokens at $0.06/1k is $6
+        let err =
+            handle_chat_completions(State(state), HeaderMap::new(), None, Json(request(100_000)))
+                .await
+                .err()
+                .unwrap();
+
+        let (status, error) = err.into_parts();
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(error["code"], CODE_REQUEST_TOO_EXPENSIVE);
+        assert_eq!(error["param"], "max_tokens");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_under_ceiling_proceeds() {
+        let (state, provider) = state(CostCeiling {
+            max_request_cost: Some(1.0),
+            ..CostCeiling::default()
+        });
+
+        // 1k output tokens at $0.06/1k is $0.06
+        let _ = handle_chat_completions(State(state), HeaderMap::new(), None, Json(request(1_000)))
+            .await
+            .unwrap();
+
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_per_key_ceiling_overrides_global() {
+        let ceiling = CostCeiling {
+            max_request_cost: Some(1.0),
+            per_key: parse_per_key("big-spender:25, broken, zero:0"),
+        };
+        assert_eq!(ceiling.per_key.len(), 1);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(ceiling.ceiling_for(&headers), Some(1.0));
+
+        headers.insert("x-api-key", HeaderValue::from_static("big-spender"));
+        assert_eq!(ceiling.ceiling_for(&headers), Some(25.0));
+
+        let pricing = PricingInfo {
+            input_cost_per_1k: 0.03,
+            output_cost_per_1k: 0.06,
+        };
+        assert!(ceiling
+            .check(&headers, &request(100_000), Some(100_000), Some(&pricing))
+            .is_ok());
+        assert!(ceiling
+            .check(
+                &HeaderMap::new(),
+                &request(100_000),
+                Some(100_000),
+                Some(&pricing)
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Dead-letter log for requests that fail upstream
+//!
+//! When a chat completion fails for good (the provider errored, timed out or
+//! kept rate limiting, or no provider could be routed to), the request id,
+//! model, providers tried and the terminal error are recorded, PII-redacted.
+//! The most recent `DEAD_LETTER_CAPACITY` failures are kept in memory for
+//! `GET /admin/failures`; with `DEAD_LETTER_PATH` set, every failure is also
+//! appended there as JSONL by a single background writer.
+//!
+//! Failures answered with the static fallback are recorded too, marked
+//! `served_fallback`.
+
+use llm_edge_security::sanitize_log_data;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::warn;
+
+use crate::shadow::append_jsonl;
+
+/// Failures kept in memory when `DEAD_LETTER_CAPACITY` is not set
+pub const DEFAULT_DEAD_LETTER_CAPACITY: usize = 100;
+
+/// Longest terminal error kept in a record, in bytes
+const DEAD_LETTER_ERROR_MAX_LENGTH: usize = 4096;
+
+/// Failures waiting for the JSONL writer before new ones are dropped
+const DEAD_LETTER_QUEUE_CAPACITY: usize = 1024;
+
+/// How many failures are kept and where they are written
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetterConfig {
+    /// Failures kept in memory; 0 keeps none
+    pub capacity: usize,
+
+    /// JSONL file failures are appended to, if any
+    pub output_path: Option<PathBuf>,
+}
+
+impl Default for DeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_DEAD_LETTER_CAPACITY,
+            output_path: None,
+        }
+    }
+}
+
+impl DeadLetterConfig {
+    /// Load from `DEAD_LETTER_CAPACITY` and `DEAD_LETTER_PATH`
+    pub fn from_env() -> Self {
+        Self {
+            capacity: std::env::var("DEAD_LETTER_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_DEAD_LETTER_CAPACITY),
+            output_path: std::env::var("DEAD_LETTER_PATH")
+                .ok()
+                .filter(|p| !p.trim().is_empty())
+                .map(PathBuf::from),
+        }
+    }
+}
+
+/// A request that failed upstream
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedRequest {
+    pub request_id: String,
+    pub recorded_at: String,
+    pub model: String,
+    /// Providers the request was sent to, in order
+    pub attempted_providers: Vec<String>,
+    /// Failure kind: `provider_error`, `timeout`, `rate_limited` or `no_provider`
+    pub reason: String,
+    /// Terminal error, PII-redacted
+    pub error: String,
+    /// Whether the client got the static fallback response instead
+    pub served_fallback: bool,
+}
+
+impl FailedRequest {
+    pub fn new(
+        request_id: &str,
+        model: &str,
+        attempted_providers: &[&str],
+        reason: &str,
+        error: &str,
+        served_fallback: bool,
+    ) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            model: model.to_string(),
+            attempted_providers: attempted_providers.iter().map(|p| p.to_string()).collect(),
+            reason: reason.to_string(),
+            error: sanitize_log_data(error, DEAD_LETTER_ERROR_MAX_LENGTH),
+            served_fallback,
+        }
+    }
+}
+
+/// Bounded log of recent upstream failures
+#[derive(Debug)]
+pub struct DeadLetterLog {
+    failures: Mutex<VecDeque<FailedRequest>>,
+    capacity: usize,
+    writer: Option<mpsc::Sender<FailedRequest>>,
+}
+
+impl Default for DeadLetterLog {
+    fn default() -> Self {
+        Self::new(&DeadLetterConfig::default())
+    }
+}
+
+impl DeadLetterLog {
+    /// Create the log; with an output path this spawns the JSONL writer, so it
+    /// must be called inside a tokio runtime
+    pub fn new(config: &DeadLetterConfig) -> Self {
+        Self {
+            failures: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            capacity: config.capacity,
+            writer: config.output_path.clone().map(spawn_writer),
+        }
+    }
+
+    /// Keep `failure`, dropping the oldest one if the log is full, and queue
+    /// it for the JSONL file
+    pub fn record(&self, failure: FailedRequest) {
+        if let Some(writer) = &self.writer {
+            if let Err(TrySendError::Full(dropped)) = writer.try_send(failure.clone()) {
+                warn!(
+                    request_id = %dropped.request_id,
+                    "Dead-letter writer is behind, failure not written to file"
+                );
+            }
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        if failures.len() == self.capacity {
+            failures.pop_front();
+        }
+        failures.push_back(failure);
+    }
+
+    /// Failures still in memory, newest first
+    pub fn recent(&self) -> Vec<FailedRequest> {
+        self.failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Append queued failures to `path`, one at a time, until the log is dropped
+fn spawn_writer(path: PathBuf) -> mpsc::Sender<FailedRequest> {
+    let (tx, mut rx) = mpsc::channel::<FailedRequest>(DEAD_LETTER_QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(failure) = rx.recv().await {
+            append_jsonl(&path, &failure).await;
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(request_id: &str) -> FailedRequest {
+        FailedRequest::new(
+            request_id,
+            "gpt-4",
+            &["openai"],
+            "provider_error",
+            "Provider error: upstream closed the connection",
+            false,
+        )
+    }
+
+    #[test]
+    fn test_log_keeps_most_recent_failures() {
+        let log = DeadLetterLog::new(&DeadLetterConfig {
+            capacity: 2,
+            output_path: None,
+        });
+        for id in ["req-1", "req-2", "req-3"] {
+            log.record(failure(id));
+        }
+
+        let ids: Vec<_> = log.recent().into_iter().map(|f| f.request_id).collect();
+        assert_eq!(ids, ["req-3", "req-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_appended_to_jsonl_file() {
+        let path = std::env::temp_dir().join(format!("dead-letter-{}.jsonl", uuid::Uuid::new_v4()));
+        let log = DeadLetterLog::new(&DeadLetterConfig {
+            capacity: 0,
+            output_path: Some(path.clone()),
+        });
+        log.record(failure("req-1"));
+        log.record(failure("req-2"));
+
+        let mut lines = Vec::new();
+        for _ in 0..100 {
+            let contents = std::fs::read_to_string(&path).unwrap_or_default();
+            lines = contents.lines().map(str::to_string).collect();
+            if lines.len() == 2 && contents.ends_with('\n') {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(first["attempted_providers"], serde_json::json!(["openai"]));
+        assert_eq!(first["reason"], "provider_error");
+        // Nothing is kept in memory with a capacity of 0
+        assert!(log.recent().is_empty());
+    }
+}
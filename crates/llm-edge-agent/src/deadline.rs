@@ -0,0 +1,196 @@
+//! Request-scoped deadlines
+//!
+//! A [`RequestDeadline`] is fixed when the request enters the handler and
+//! bounds every later await, so the provider call only gets whatever budget
+//! is left rather than a fresh fixed timeout.
+
+use axum::http::HeaderMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::proxy::ProxyError;
+
+/// Header carrying a client-supplied budget in milliseconds
+pub const DEADLINE_HEADER: &str = "x-deadline";
+
+/// Absolute deadline for a single request
+#[derive(Debug, Clone, Copy)]
+pub struct RequestDeadline {
+    at: Instant,
+}
+
+impl RequestDeadline {
+    /// Deadline `timeout` from now
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+        }
+    }
+
+    /// Compute the deadline at request entry
+    ///
+    /// Uses the server timeout, shortened by `X-Deadline` (milliseconds) when
+    /// the client sends a smaller budget. Invalid header values are ignored.
+    pub fn from_headers(headers: &HeaderMap, server_timeout: Duration) -> Self {
+        let client_budget = headers
+            .get(DEADLINE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_millis);
+
+        match client_budget {
+            Some(budget) if budget < server_timeout => Self::after(budget),
+            _ => Self::after(server_timeout),
+        }
+    }
+
+    /// Instant at which the request expires
+    pub fn instant(&self) -> Instant {
+        self.at
+    }
+
+    /// Budget left before the deadline (zero once expired)
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// Run `future`, failing with [`ProxyError::Timeout`] if the deadline passes first
+    pub async fn run<T, F>(&self, future: F) -> Result<T, ProxyError>
+    where
+        F: Future<Output = T>,
+    {
+        tokio::time::timeout_at(self.at, future)
+            .await
+            .map_err(|_| ProxyError::Timeout("Request deadline exceeded".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{handle_chat_completions, ChatCompletionRequest, ChatMessage};
+    use async_trait::async_trait;
+    use axum::{
+        extract::State,
+        http::{HeaderValue, StatusCode},
+        response::IntoResponse,
+        Json,
+    };
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        LLMProvider, ProviderResult, UnifiedRequest, UnifiedResponse,
+    };
+    use std::sync::Arc;
+
+    /// Provider that never answers within the test budget
+    struct SlowProvider {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl LLMProvider for SlowProvider {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            tokio::time::sleep(self.delay).await;
+            panic!("deadline should have cut the provider call short");
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn slow_state(delay: Duration, timeout_seconds: u64) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(AppConfig {
+                request_timeout_seconds: timeout_seconds,
+                ..AppConfig::default()
+            })
+            .with_openai(Arc::new(SlowProvider { delay })),
+        )
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_header_shortens_server_timeout() {
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("250"));
+
+        let deadline = RequestDeadline::from_headers(&headers, Duration::from_secs(30));
+        assert_eq!(deadline.remaining(), Duration::from_millis(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_header_cannot_extend_server_timeout() {
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("60000"));
+
+        let deadline = RequestDeadline::from_headers(&headers, Duration::from_secs(30));
+        assert_eq!(deadline.remaining(), Duration::from_secs(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_invalid_header_is_ignored() {
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("soon"));
+
+        let deadline = RequestDeadline::from_headers(&headers, Duration::from_secs(5));
+        assert_eq!(deadline.remaining(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_provider_cut_off_at_deadline() {
+        let state = slow_state(Duration::from_secs(120), 30);
+        let mut headers = HeaderMap::new();
+        headers.insert(DEADLINE_HEADER, HeaderValue::from_static("500"));
+
+        let start = Instant::now();
+        let result = handle_chat_completions(State(state), headers, None, Json(request())).await;
+        let elapsed = start.elapsed();
+
+        let err = result.expect_err("request should time out");
+        assert!(matches!(err, ProxyError::Timeout(_)));
+        assert!(elapsed >= Duration::from_millis(500));
+        assert!(elapsed < Duration::from_secs(1));
+        assert_eq!(err.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_server_timeout_bounds_provider_without_header() {
+        let state = slow_state(Duration::from_secs(120), 2);
+
+        let start = Instant::now();
+        let result =
+            handle_chat_completions(State(state), HeaderMap::new(), None, Json(request())).await;
+
+        assert!(matches!(result, Err(ProxyError::Timeout(_))));
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,268 @@
+//! Drain mode for maintenance
+//!
+//! - `POST /admin/drain` stops accepting new `/v1/*` requests: they get `503`
+//!   with `"draining": true` and `Retry-After`. Requests already in flight run
+//!   to completion, and health checks keep reporting.
+//! - `POST /admin/undrain` accepts requests again.
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use llm_edge_proxy::middleware::{auth_middleware, require_scope, SCOPE_ADMIN};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::proxy::ProxyError;
+
+/// `Retry-After` sent with requests rejected while draining, in seconds
+pub const DRAIN_RETRY_AFTER_SECONDS: u32 = 30;
+
+/// Shared flag telling the `/v1/*` routes to reject new requests
+#[derive(Debug, Clone, Default)]
+pub struct DrainSwitch(Arc<AtomicBool>);
+
+impl DrainSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether new requests are being rejected
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Start or stop draining; returns the previous state
+    pub fn set_draining(&self, draining: bool) -> bool {
+        self.0.swap(draining, Ordering::AcqRel)
+    }
+}
+
+/// Drain admin routes, protected by API key auth and the `admin` scope
+pub fn drain_routes<S>(switch: DrainSwitch, auth_config: llm_edge_proxy::Config) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let routes = Router::new()
+        .route("/admin/drain", post(handle_drain))
+        .route("/admin/undrain", post(handle_undrain));
+
+    require_scope(routes, SCOPE_ADMIN)
+        .route_layer(axum::middleware::from_fn_with_state(
+            auth_config,
+            auth_middleware,
+        ))
+        .with_state(switch)
+}
+
+/// Stop accepting new `/v1/*` requests
+pub async fn handle_drain(State(switch): State<DrainSwitch>) -> Json<serde_json::Value> {
+    if !switch.set_draining(true) {
+        warn!("Draining: rejecting new requests until undrained");
+    }
+    Json(serde_json::json!({ "draining": true }))
+}
+
+/// Accept `/v1/*` requests again
+pub async fn handle_undrain(State(switch): State<DrainSwitch>) -> Json<serde_json::Value> {
+    if switch.set_draining(false) {
+        info!("Undrained: accepting new requests");
+    }
+    Json(serde_json::json!({ "draining": false }))
+}
+
+/// Drain middleware for the `/v1/*` routes
+///
+/// Rejects requests that arrive while draining; requests admitted earlier
+/// are unaffected.
+pub async fn reject_while_draining(
+    State(switch): State<DrainSwitch>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !switch.is_draining() {
+        return next.run(request).await;
+    }
+
+    let (status, error) =
+        ProxyError::ServiceUnavailable("Server is draining for maintenance".to_string())
+            .into_parts();
+    let mut response = (
+        status,
+        Json(serde_json::json!({ "error": error, "draining": true })),
+    )
+        .into_response();
+    response.headers_mut().insert(
+        axum::http::header::RETRY_AFTER,
+        HeaderValue::from(DRAIN_RETRY_AFTER_SECONDS),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    fn auth_config() -> llm_edge_proxy::Config {
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+
+        llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: vec!["ops-key".to_string(), "app-key".to_string()],
+                require_auth_for_health: false,
+                key_scopes: HashMap::from([
+                    ("ops-key".to_string(), vec![SCOPE_ADMIN.to_string()]),
+                    ("app-key".to_string(), vec!["inference".to_string()]),
+                ]),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    /// `/v1/ping` behind the drain middleware, `/health` outside it
+    fn app(switch: DrainSwitch) -> Router {
+        Router::new()
+            .route("/v1/ping", get(|| async { "pong" }))
+            .layer(axum::middleware::from_fn_with_state(
+                switch.clone(),
+                reject_while_draining,
+            ))
+            .route("/health", get(|| async { "ok" }))
+            .merge(drain_routes(switch, auth_config()))
+    }
+
+    fn get_request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn admin(uri: &str, key: &str) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_drain_rejects_until_undrained() {
+        let switch = DrainSwitch::new();
+        let app = app(switch.clone());
+
+        let response = app.clone().oneshot(get_request("/v1/ping")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(admin("/admin/drain", "ops-key"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(switch.is_draining());
+
+        let response = app.clone().oneshot(get_request("/v1/ping")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()["retry-after"], "30");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["draining"], true);
+
+        // Health checks still report while draining
+        let response = app.clone().oneshot(get_request("/health")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(admin("/admin/undrain", "ops-key"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(get_request("/v1/ping")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_request_completes_while_draining() {
+        let switch = DrainSwitch::new();
+        let release = Arc::new(tokio::sync::Notify::new());
+        let app = Router::new()
+            .route(
+                "/v1/slow",
+                get({
+                    let switch = switch.clone();
+                    let release = release.clone();
+                    move || async move {
+                        switch.set_draining(true);
+                        release.notified().await;
+                        "done"
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                switch.clone(),
+                reject_while_draining,
+            ));
+
+        // Admitted before draining started, so it finishes normally
+        let in_flight = tokio::spawn(app.clone().oneshot(get_request("/v1/slow")));
+        while !switch.is_draining() {
+            tokio::task::yield_now().await;
+        }
+        release.notify_one();
+        assert_eq!(in_flight.await.unwrap().unwrap().status(), StatusCode::OK);
+
+        let response = app.oneshot(get_request("/v1/slow")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_drain_requires_admin_scope() {
+        let switch = DrainSwitch::new();
+
+        let response = app(switch.clone())
+            .oneshot(admin("/admin/drain", "app-key"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!switch.is_draining());
+    }
+}
@@ -0,0 +1,279 @@
+//! Static fallback response
+//!
+//! Kiosk and demo deployments would rather show a friendly message than an
+//! error during a total outage. With `ENABLE_STATIC_FALLBACK`, a request that
+//! missed the cache and could not be served by any provider gets a canned
+//! assistant message with `200` and `metadata.provider = "fallback"`.
+//!
+//! Unlike a stale cache hit, the content has nothing to do with the request,
+//! so every fallback is logged at WARN and counted in
+//! `llm_edge_static_fallback_total` for alerting.
+
+use llm_edge_providers::FinishReason;
+use uuid::Uuid;
+
+use crate::proxy::{
+    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ResponseMetadata, Usage,
+};
+
+/// `metadata.provider` of a static fallback response
+pub const FALLBACK_PROVIDER: &str = "fallback";
+
+/// Message used when `STATIC_FALLBACK_MESSAGE` is not set
+pub const DEFAULT_FALLBACK_MESSAGE: &str =
+    "Sorry, the assistant is unavailable right now. Please try again in a few minutes.";
+
+/// Canned assistant message served when nothing else can answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackResponse {
+    pub message: String,
+}
+
+impl Default for FallbackResponse {
+    fn default() -> Self {
+        Self {
+            message: DEFAULT_FALLBACK_MESSAGE.to_string(),
+        }
+    }
+}
+
+impl FallbackResponse {
+    /// Load the message from `STATIC_FALLBACK_MESSAGE`
+    pub fn from_env() -> Self {
+        std::env::var("STATIC_FALLBACK_MESSAGE")
+            .ok()
+            .filter(|m| !m.trim().is_empty())
+            .map(|message| Self { message })
+            .unwrap_or_default()
+    }
+
+    /// The fallback as a completion of `request`
+    pub fn to_completion(
+        &self,
+        request: &ChatCompletionRequest,
+        latency_ms: u64,
+    ) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: format!("chatcmpl-{}", Uuid::new_v4()),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model.clone(),
+            choices: vec![ChatChoice {
+                index: 0,
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content: self.message.clone(),
+                },
+                finish_reason: FinishReason::Stop,
+                logprobs: None,
+            }],
+            usage: Usage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+            },
+            metadata: Some(ResponseMetadata {
+                provider: FALLBACK_PROVIDER.to_string(),
+                cached: false,
+                cache_tier: None,
+                latency_ms,
+                cost_usd: Some(0.0),
+                input_cost_usd: Some(0.0),
+                output_cost_usd: Some(0.0),
+                cache_key_version: None,
+                max_tokens: None,
+                native_finish_reason: None,
+                provider_extras: Default::default(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{convert_to_cacheable, handle_chat_completions, ProxyError};
+    use async_trait::async_trait;
+    use axum::{
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        Json,
+    };
+    use llm_edge_cache::l1::CachedResponse;
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        LLMProvider, ProviderError, ProviderResult, UnifiedRequest, UnifiedResponse,
+    };
+    use std::sync::Arc;
+
+    /// Provider whose every request fails
+    struct DownProvider;
+
+    #[async_trait]
+    impl LLMProvider for DownProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Err(ProviderError::Internal("upstream is down".to_string()))
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Provider that turns every request away with a client-side error
+    struct RejectingProvider(fn() -> ProviderError);
+
+    #[async_trait]
+    impl LLMProvider for RejectingProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Err((self.0)())
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn state(provider: Option<Arc<dyn LLMProvider>>, enable_static_fallback: bool) -> AppState {
+        AppState {
+            openai_provider: provider,
+            ..AppState::new(AppConfig {
+                enable_static_fallback,
+                ..AppConfig::default()
+            })
+        }
+    }
+
+    fn request(content: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    async fn complete(
+        state: AppState,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ProxyError> {
+        handle_chat_completions(
+            State(Arc::new(state)),
+            HeaderMap::new(),
+            None,
+            Json(request),
+        )
+        .await
+        .map(|Json(response)| response)
+    }
+
+    #[tokio::test]
+    async fn test_fallback_served_when_providers_fail() {
+        for provider in [None, Some(Arc::new(DownProvider) as Arc<dyn LLMProvider>)] {
+            let response = complete(state(provider, true), request("Hello"))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                response.choices[0].message.content,
+                DEFAULT_FALLBACK_MESSAGE
+            );
+            let metadata = response.metadata.unwrap();
+            assert_eq!(metadata.provider, FALLBACK_PROVIDER);
+            assert!(!metadata.cached);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_not_served_for_client_errors() {
+        let rejections: [(fn() -> ProviderError, StatusCode); 3] = [
+            (
+                || ProviderError::InvalidRequest("messages must alternate".to_string()),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                || ProviderError::ModelNotFound("gpt-4".to_string()),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                || ProviderError::RateLimitExceeded,
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
+        ];
+        for (error, status) in rejections {
+            let provider = Arc::new(RejectingProvider(error)) as Arc<dyn LLMProvider>;
+            let state = state(Some(provider), true);
+            let dead_letters = state.dead_letters.clone();
+            let err = complete(state, request("Hello")).await.unwrap_err();
+            assert_eq!(err.status(), status);
+
+            // Logged as failed, not as answered by the fallback
+            let failures = dead_letters.recent();
+            assert_eq!(failures.len(), 1);
+            assert!(!failures[0].served_fallback);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_off_by_default() {
+        assert!(!AppConfig::default().enable_static_fallback);
+
+        let err = complete(state(Some(Arc::new(DownProvider)), false), request("Hello"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProxyError::ProviderError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_not_used_while_anything_else_answers() {
+        // A cache hit still wins over the fallback
+        let cached = state(Some(Arc::new(DownProvider)), true);
+        cached
+            .cache_manager
+            .store(
+                &convert_to_cacheable(&request("Hello")),
+                CachedResponse {
+                    content: "cached answer".to_string(),
+                    tokens: None,
+                    model: "gpt-4".to_string(),
+                    cached_at: chrono::Utc::now().timestamp(),
+                },
+            )
+            .await;
+        let response = complete(cached, request("Hello")).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "cached answer");
+
+        // Client errors are not outages
+        let mut invalid = request("Hello");
+        invalid.model.clear();
+        let err = complete(state(None, true), invalid).await.unwrap_err();
+        assert!(matches!(err, ProxyError::ValidationError { .. }));
+    }
+}
@@ -0,0 +1,87 @@
+//! Declarative health check policy
+//!
+//! [`HealthCheckSpec`] decides how each component's health feeds into the
+//! overall [`HealthState`](crate::HealthState): a failing critical component
+//! makes the system unhealthy, a failing optional one only degrades it, and
+//! unlisted components are reported but ignored. Components that are not
+//! configured (e.g. L2 without Redis) are never checked.
+
+/// L1 (in-memory) cache
+pub const COMPONENT_CACHE_L1: &str = "cache_l1";
+
+/// L2 (distributed) cache
+pub const COMPONENT_CACHE_L2: &str = "cache_l2";
+
+/// At least one registered provider; individual providers are listed by name
+pub const COMPONENT_PROVIDERS: &str = "providers";
+
+/// How a component's failure affects overall health
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criticality {
+    /// Failure makes the system unhealthy
+    Critical,
+    /// Failure makes the system degraded
+    Optional,
+}
+
+/// Which components readiness depends on
+///
+/// Components are [`COMPONENT_CACHE_L1`], [`COMPONENT_CACHE_L2`],
+/// [`COMPONENT_PROVIDERS`] or a provider name such as `openai`. The default
+/// requires L1 and one healthy provider, and treats L2 as optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheckSpec {
+    /// Components whose failure makes the system unhealthy
+    pub critical: Vec<String>,
+
+    /// Components whose failure makes the system degraded
+    pub optional: Vec<String>,
+}
+
+impl Default for HealthCheckSpec {
+    fn default() -> Self {
+        Self {
+            critical: vec![
+                COMPONENT_CACHE_L1.to_string(),
+                COMPONENT_PROVIDERS.to_string(),
+            ],
+            optional: vec![COMPONENT_CACHE_L2.to_string()],
+        }
+    }
+}
+
+impl HealthCheckSpec {
+    /// Load the spec from environment variables, falling back to the default lists
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            critical: parse_components(std::env::var("HEALTH_CRITICAL_COMPONENTS").ok())
+                .unwrap_or(default.critical),
+            optional: parse_components(std::env::var("HEALTH_OPTIONAL_COMPONENTS").ok())
+                .unwrap_or(default.optional),
+        }
+    }
+
+    /// How `component` affects overall health, if at all
+    ///
+    /// A component listed in both lists is critical.
+    pub fn criticality(&self, component: &str) -> Option<Criticality> {
+        if self.critical.iter().any(|c| c == component) {
+            Some(Criticality::Critical)
+        } else if self.optional.iter().any(|c| c == component) {
+            Some(Criticality::Optional)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse a comma-separated component list (an empty value lists nothing)
+fn parse_components(raw: Option<String>) -> Option<Vec<String>> {
+    raw.map(|v| {
+        v.split(',')
+            .map(|c| c.trim().to_ascii_lowercase())
+            .filter(|c| !c.is_empty())
+            .collect()
+    })
+}
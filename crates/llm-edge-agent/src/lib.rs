@@ -9,8 +9,56 @@
 //! - Layer 3: Provider adapters (OpenAI, Anthropic)
 //! - Cross-cutting: Observability (Prometheus, OpenTelemetry, Logging)
 
+pub mod admin;
+pub mod batch;
+pub mod cache_policy;
+pub mod concurrency;
+pub mod cost_ceiling;
+pub mod dead_letter;
+pub mod deadline;
+pub mod drain;
+pub mod fallback;
+pub mod health;
 pub mod integration;
+pub mod model_resolution;
+pub mod models;
+pub mod processor;
 pub mod proxy;
+pub mod route;
+pub mod router;
+pub mod shadow;
+pub mod stream_flush;
+pub mod streaming;
+pub mod tags;
+pub mod token_budget;
+pub mod upstream;
 
-pub use integration::{check_system_health, initialize_app_state, AppConfig, AppState};
-pub use proxy::{handle_chat_completions, ChatCompletionRequest, ChatCompletionResponse};
+pub use admin::admin_routes;
+pub use batch::{batch_routes, handle_batch_completions, BatchResponse};
+pub use cache_policy::CachePolicy;
+pub use concurrency::{limit_concurrency, ConcurrencyLimit};
+pub use cost_ceiling::CostCeiling;
+pub use dead_letter::{DeadLetterConfig, DeadLetterLog, FailedRequest};
+pub use deadline::RequestDeadline;
+pub use drain::{drain_routes, reject_while_draining, DrainSwitch};
+pub use fallback::FallbackResponse;
+pub use health::{Criticality, HealthCheckSpec};
+pub use integration::{
+    check_system_health, initialize_app_state, spawn_provider_prewarm, AppConfig, AppState,
+    DetailedHealth, HealthState, ProviderHealth, SystemHealthStatus,
+};
+pub use model_resolution::{ModelResolution, ModelResolutionMode};
+pub use models::{list_models, models_routes, ModelInfo, ModelList};
+pub use processor::{RequestProcessor, SystemPromptProcessor};
+pub use proxy::{
+    chat_completions, chat_routes, debug_trace_gate, handle_chat_completions,
+    ChatCompletionRequest, ChatCompletionResponse, ProxyError,
+};
+pub use route::{explain_route, RouteCandidate, RouteExplanation, RouteStrategy};
+pub use router::build_router;
+pub use shadow::ShadowConfig;
+pub use stream_flush::StreamFlushPolicy;
+pub use streaming::handle_chat_completions_stream;
+pub use tags::{RequestTagPolicy, RequestTags};
+pub use token_budget::{TokenBudget, TokenBudgetProcessor, TokenBudgetTier};
+pub use upstream::{integration_health_routes, IntegrationHealthSource};
@@ -1,11 +1,8 @@
 use anyhow::Result;
-use axum::{
-    routing::{get, post},
-    Router,
-};
 use llm_edge_agent::{
-    check_system_health, handle_chat_completions, initialize_app_state, AppConfig,
+    build_router, check_system_health, initialize_app_state, AppConfig, DrainSwitch,
 };
+use llm_edge_integrations::{IntegrationConfig, IntegrationManager};
 use metrics_exporter_prometheus::PrometheusBuilder;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -14,7 +11,15 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing/logging
+    // Load configuration from environment (it decides whether spans carry
+    // trace context, so this happens before logging is up)
+    let mut config = AppConfig::from_env();
+
+    // Initialize tracing/logging, with W3C trace context propagation when
+    // tracing is enabled
+    if config.enable_tracing {
+        llm_edge_monitoring::tracing::init_propagation();
+    }
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
@@ -22,13 +27,14 @@ async fn main() -> Result<()> {
             }),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(
+            config
+                .enable_tracing
+                .then(|| llm_edge_monitoring::tracing::context_layer(None)),
+        )
         .init();
 
     info!("Starting LLM Edge Agent v{}", env!("CARGO_PKG_VERSION"));
-
-    // Load configuration from environment
-    info!("Loading configuration");
-    let config = AppConfig::from_env();
     info!(
         "Configuration loaded: host={}, port={}, l2_cache_enabled={}",
         config.host, config.port, config.enable_l2_cache
@@ -45,6 +51,24 @@ async fn main() -> Result<()> {
             .expect("Failed to install Prometheus exporter");
     }
 
+    // Proxy settings: API key auth for the batch/admin routes, TLS for the
+    // listener and the server timeout every request deadline starts from
+    let proxy_config = llm_edge_proxy::Config::from_env()?;
+    if let Err(errors) = proxy_config.validate() {
+        for e in &errors {
+            error!("Invalid configuration: {}", e);
+        }
+        anyhow::bail!(
+            "Invalid configuration:\n{}",
+            errors
+                .iter()
+                .map(|e| format!("  - {}", e))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    config.request_timeout_seconds = proxy_config.server.timeout_seconds;
+
     // Initialize application state (cache, providers, etc.)
     info!("Initializing application state");
     let app_state = match initialize_app_state(config.clone()).await {
@@ -62,98 +86,52 @@ async fn main() -> Result<()> {
     info!("Performing initial health check");
     let health = check_system_health(&app_state).await;
     info!(
-        "Health check: status={}, cache_l1={}, cache_l2={}, openai={}, anthropic={}",
+        "Health check: status={}, cache_l1={}, cache_l2={}, providers={:?}",
         health.status_string(),
         health.cache_l1_healthy,
         health.cache_l2_healthy,
-        health.openai_healthy,
-        health.anthropic_healthy
+        health
+            .providers
+            .iter()
+            .map(|p| (p.name.as_str(), p.healthy))
+            .collect::<Vec<_>>()
     );
 
-    if !health.is_healthy() {
+    if !health.is_operational() {
         warn!("System health check failed, but continuing startup");
+    } else if !health.is_healthy() {
+        warn!("System is degraded, continuing startup");
+    }
+
+    // Upstream integrations compiled into this build; failures are non-fatal
+    let mut integrations = IntegrationManager::new();
+    if let Err(e) = integrations
+        .initialize(&IntegrationConfig::from_env())
+        .await
+    {
+        warn!("Upstream integrations failed to initialize: {}", e);
     }
 
     // Build the HTTP router
     info!("Building HTTP router");
-    let app = Router::new()
-        // Health check endpoints
-        .route("/health", get(health_handler))
-        .route("/health/ready", get(readiness_handler))
-        .route("/health/live", get(liveness_handler))
-        // Metrics endpoint
-        .route("/metrics", get(metrics_handler))
-        // Main proxy endpoints (OpenAI-compatible)
-        .route("/v1/chat/completions", post(handle_chat_completions))
-        // Share application state with handlers
-        .with_state(app_state.clone());
-
-    // Start the HTTP server
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    info!("Starting HTTP server on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    info!("LLM Edge Agent is ready to accept requests!");
-
-    axum::serve(listener, app).await?;
-
-    Ok(())
-}
-
-/// Health check handler
-async fn health_handler(
-    axum::extract::State(state): axum::extract::State<Arc<llm_edge_agent::AppState>>,
-) -> axum::Json<serde_json::Value> {
-    let health = check_system_health(&state).await;
 
-    axum::Json(serde_json::json!({
-        "status": health.status_string(),
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-        "version": env!("CARGO_PKG_VERSION"),
-        "cache": {
-            "l1_healthy": health.cache_l1_healthy,
-            "l2_healthy": health.cache_l2_healthy,
-            "l2_configured": health.cache_l2_configured,
-        },
-        "providers": {
-            "openai": {
-                "configured": health.openai_configured,
-                "healthy": health.openai_healthy,
-            },
-            "anthropic": {
-                "configured": health.anthropic_configured,
-                "healthy": health.anthropic_healthy,
-            },
-        },
-    }))
-}
-
-/// Readiness check handler
-async fn readiness_handler(
-    axum::extract::State(state): axum::extract::State<Arc<llm_edge_agent::AppState>>,
-) -> axum::Json<serde_json::Value> {
-    let health = check_system_health(&state).await;
+    // Flipped by /admin/drain and /admin/undrain
+    let drain = DrainSwitch::new();
+    let app = build_router(
+        app_state.clone(),
+        Arc::new(integrations),
+        &proxy_config,
+        drain,
+    );
 
-    let ready = health.is_healthy();
+    // Start the server (HTTPS when ENABLE_TLS is set)
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    info!("Starting server on {}", addr);
 
-    axum::Json(serde_json::json!({
-        "ready": ready,
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-    }))
-}
+    let served = llm_edge_proxy::serve(addr, app, &proxy_config.server).await;
 
-/// Liveness check handler
-async fn liveness_handler() -> axum::Json<serde_json::Value> {
-    axum::Json(serde_json::json!({
-        "alive": true,
-        "timestamp": chrono::Utc::now().to_rfc3339(),
-    }))
-}
+    // Stop background cache maintenance before exiting
+    app_state.cache_manager.shutdown();
 
-/// Prometheus metrics handler
-async fn metrics_handler() -> String {
-    // Get the metrics handle from the global registry
-    // In a real implementation, we'd store this in the app state
-    // For now, return a basic response
-    "# Prometheus metrics\n# See /health for detailed status\n".to_string()
+    served
 }
@@ -0,0 +1,299 @@
+//! Model resolution for requests without a model
+//!
+//! Some clients leave `model` out and expect the gateway to pick one; others
+//! want a missing model rejected. [`ModelResolution`] makes that one decision
+//! before the request is validated:
+//!
+//! - `strict` (the default) rejects a model-less request, as before
+//! - `default_model` uses `DEFAULT_MODEL`
+//! - `infer_by_capability` picks a model from what the request asks for:
+//!   `REASONING_MODEL` when it sets `reasoning_effort`, then
+//!   `STRUCTURED_OUTPUT_MODEL` when it sets a JSON `response_format`, and
+//!   `DEFAULT_MODEL` otherwise
+//!
+//! Capabilities are only inferred from what [`ChatCompletionRequest`] can
+//! carry. Its messages are plain text and it has no `tools`, so requests
+//! needing vision or tool use cannot be told apart and get `DEFAULT_MODEL`;
+//! such clients should name the model.
+//!
+//! A request that names a model is never changed.
+
+use llm_edge_providers::ResponseFormat;
+use tracing::{debug, warn};
+
+use crate::proxy::ChatCompletionRequest;
+
+/// How a request without a model is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModelResolutionMode {
+    /// Reject the request
+    #[default]
+    Strict,
+    /// Use the configured default model
+    DefaultModel,
+    /// Choose a model by the capabilities the request needs, as far as
+    /// `reasoning_effort` and `response_format` show them
+    InferByCapability,
+}
+
+impl ModelResolutionMode {
+    /// Parse `strict`, `default_model` or `infer_by_capability`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "" | "strict" => Some(ModelResolutionMode::Strict),
+            "default_model" => Some(ModelResolutionMode::DefaultModel),
+            "infer_by_capability" => Some(ModelResolutionMode::InferByCapability),
+            _ => None,
+        }
+    }
+}
+
+/// Which model a request without one is sent to
+///
+/// The default is strict and fills in nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelResolution {
+    pub mode: ModelResolutionMode,
+
+    /// Model for requests without one (`default_model`), and the fallback
+    /// when no capability matches (`infer_by_capability`)
+    pub default_model: Option<String>,
+
+    /// Model for requests setting `reasoning_effort`
+    pub reasoning_model: Option<String>,
+
+    /// Model for requests asking for JSON output
+    pub structured_output_model: Option<String>,
+}
+
+impl ModelResolution {
+    /// Load the mode and models from environment variables
+    pub fn from_env() -> Self {
+        let model = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+        };
+        let raw_mode = std::env::var("MODEL_RESOLUTION").unwrap_or_default();
+        let mode = ModelResolutionMode::parse(&raw_mode).unwrap_or_else(|| {
+            warn!(value = %raw_mode, "Invalid MODEL_RESOLUTION, using strict");
+            ModelResolutionMode::Strict
+        });
+
+        Self {
+            mode,
+            default_model: model("DEFAULT_MODEL"),
+            reasoning_model: model("REASONING_MODEL"),
+            structured_output_model: model("STRUCTURED_OUTPUT_MODEL"),
+        }
+    }
+
+    /// Fill in `request.model` if it is empty
+    ///
+    /// Leaves it empty when the mode is strict or no model fits, so
+    /// validation rejects the request as usual.
+    pub fn resolve(&self, request: &mut ChatCompletionRequest) {
+        if !request.model.trim().is_empty() {
+            return;
+        }
+
+        let model = match self.mode {
+            ModelResolutionMode::Strict => None,
+            ModelResolutionMode::DefaultModel => self.default_model.as_ref(),
+            ModelResolutionMode::InferByCapability => self.infer(request),
+        };
+
+        if let Some(model) = model {
+            debug!(model = %model, mode = ?self.mode, "Resolved model for request without one");
+            request.model = model.clone();
+        }
+    }
+
+    /// Model matching the first capability `request` needs
+    fn infer(&self, request: &ChatCompletionRequest) -> Option<&String> {
+        let wants_json = matches!(
+            request.response_format,
+            Some(ResponseFormat::JsonObject | ResponseFormat::JsonSchema { .. })
+        );
+
+        [
+            (request.reasoning_effort.is_some(), &self.reasoning_model),
+            (wants_json, &self.structured_output_model),
+        ]
+        .into_iter()
+        .filter(|(needed, _)| *needed)
+        .find_map(|(_, model)| model.as_ref())
+        .or(self.default_model.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{handle_chat_completions, ProxyError};
+    use async_trait::async_trait;
+    use axum::{extract::State, http::HeaderMap, Json};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use std::sync::Arc;
+
+    /// Provider answering with the model it was sent
+    struct EchoModel;
+
+    #[async_trait]
+    impl LLMProvider for EchoModel {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model.clone(),
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: request.model,
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn resolution(mode: ModelResolutionMode) -> ModelResolution {
+        ModelResolution {
+            mode,
+            default_model: Some("gpt-4o-mini".to_string()),
+            reasoning_model: Some("o3-mini".to_string()),
+            structured_output_model: Some("gpt-4o".to_string()),
+        }
+    }
+
+    /// Model-less request, as sent by a client leaving the choice to us
+    fn request(extra: serde_json::Value) -> ChatCompletionRequest {
+        let mut body = serde_json::json!({
+            "messages": [{"role": "user", "content": "Hello"}],
+            "max_tokens": 16
+        });
+        body.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(body).unwrap()
+    }
+
+    async fn model_answered(
+        mode: ModelResolutionMode,
+        request: ChatCompletionRequest,
+    ) -> Result<String, ProxyError> {
+        let state = Arc::new(
+            AppState::new(AppConfig {
+                model_resolution: resolution(mode),
+                ..AppConfig::default()
+            })
+            .with_openai(Arc::new(EchoModel)),
+        );
+
+        handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+            .await
+            .map(|Json(response)| response.model)
+    }
+
+    #[tokio::test]
+    async fn test_strict_rejects_request_without_model() {
+        assert_eq!(
+            AppConfig::default().model_resolution.mode,
+            ModelResolutionMode::Strict
+        );
+
+        let (_, error) =
+            model_answered(ModelResolutionMode::Strict, request(serde_json::json!({})))
+                .await
+                .unwrap_err()
+                .into_parts();
+        assert_eq!(error["param"], "model");
+        assert_eq!(error["message"], "Model is required");
+    }
+
+    #[tokio::test]
+    async fn test_default_model_fills_in_missing_model() {
+        let model = model_answered(
+            ModelResolutionMode::DefaultModel,
+            request(serde_json::json!({"reasoning_effort": "high"})),
+        )
+        .await
+        .unwrap();
+        assert_eq!(model, "gpt-4o-mini");
+
+        // An explicit model is left alone
+        let mut explicit = request(serde_json::json!({}));
+        explicit.model = "gpt-4".to_string();
+        let model = model_answered(ModelResolutionMode::DefaultModel, explicit)
+            .await
+            .unwrap();
+        assert_eq!(model, "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_infer_by_capability_picks_model_for_request() {
+        for (extra, expected) in [
+            (serde_json::json!({"reasoning_effort": "low"}), "o3-mini"),
+            (
+                serde_json::json!({"response_format": {"type": "json_object"}}),
+                "gpt-4o",
+            ),
+            (serde_json::json!({}), "gpt-4o-mini"),
+        ] {
+            let model = model_answered(ModelResolutionMode::InferByCapability, request(extra))
+                .await
+                .unwrap();
+            assert_eq!(model, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(
+            ModelResolutionMode::parse("Infer-By-Capability"),
+            Some(ModelResolutionMode::InferByCapability)
+        );
+        assert_eq!(
+            ModelResolutionMode::parse("default_model"),
+            Some(ModelResolutionMode::DefaultModel)
+        );
+        assert_eq!(
+            ModelResolutionMode::parse(""),
+            Some(ModelResolutionMode::Strict)
+        );
+        assert_eq!(ModelResolutionMode::parse("guess"), None);
+    }
+}
@@ -0,0 +1,131 @@
+//! Model listing
+//!
+//! `GET /v1/models` lists the models of every configured provider in
+//! OpenAI's model list shape, with each model's capabilities and list price
+//! so clients can check context windows and vision/tool support before
+//! sending a request. Keys with a model allowlist only see the models they
+//! may use.
+
+use axum::{extract::State, routing::get, Extension, Json, Router};
+use llm_edge_providers::adapter::{ModelCapabilities, PricingInfo};
+use llm_edge_proxy::middleware::{auth_middleware, require_scope, AllowedModels, SCOPE_INFERENCE};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::integration::AppState;
+
+/// Entry in the model list: an OpenAI model object plus capabilities
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub id: String,
+    /// Always `"model"`
+    pub object: &'static str,
+    /// Provider serving the model
+    pub owned_by: String,
+    /// Left out for models the provider has no capabilities for
+    #[serde(flatten)]
+    pub capabilities: Option<ModelCapabilities>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<PricingInfo>,
+}
+
+/// `GET /v1/models` response body
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelList {
+    /// Always `"list"`
+    pub object: &'static str,
+    pub data: Vec<ModelInfo>,
+}
+
+/// Model list route, protected by API key auth and the `inference` scope
+pub fn models_routes(auth_config: llm_edge_proxy::Config) -> Router<Arc<AppState>> {
+    let routes = Router::new().route("/v1/models", get(list_models));
+
+    require_scope(routes, SCOPE_INFERENCE).route_layer(axum::middleware::from_fn_with_state(
+        auth_config,
+        auth_middleware,
+    ))
+}
+
+/// Models of every configured provider, sorted by id
+pub async fn list_models(
+    State(state): State<Arc<AppState>>,
+    allowed_models: Option<Extension<AllowedModels>>,
+) -> Json<ModelList> {
+    let mut data: Vec<ModelInfo> = state
+        .providers()
+        .iter()
+        .flat_map(|provider| {
+            provider
+                .list_models()
+                .into_iter()
+                .map(|model| ModelInfo {
+                    capabilities: provider.capabilities(&model),
+                    pricing: provider.get_pricing(&model),
+                    owned_by: provider.name().to_string(),
+                    object: "model",
+                    id: model,
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|info| {
+            allowed_models
+                .as_ref()
+                .map_or(true, |Extension(allowed)| allowed.allows(&info.id))
+        })
+        .collect();
+    data.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Json(ModelList {
+        object: "list",
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use llm_edge_providers::{anthropic::AnthropicAdapter, openai::OpenAIAdapter};
+
+    fn state() -> Arc<AppState> {
+        Arc::new(
+            AppState::new(AppConfig::default())
+                .with_openai(Arc::new(OpenAIAdapter::new("sk-test".to_string())))
+                .with_anthropic(Arc::new(AnthropicAdapter::new("sk-test".to_string()))),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_models_listed_with_capabilities_and_pricing() {
+        let Json(list) = list_models(State(state()), None).await;
+        let list = serde_json::to_value(list).unwrap();
+        assert_eq!(list["object"], "list");
+
+        let models = list["data"].as_array().unwrap();
+        let opus = models
+            .iter()
+            .find(|m| m["id"] == "claude-3-opus-20240229")
+            .unwrap();
+        assert_eq!(opus["object"], "model");
+        assert_eq!(opus["owned_by"], "anthropic");
+        assert_eq!(opus["supports_vision"], true);
+        assert_eq!(opus["supports_tools"], true);
+        assert_eq!(opus["max_context_tokens"], 200_000);
+        assert!(opus["pricing"]["input_cost_per_1k"].as_f64().unwrap() > 0.0);
+
+        let gpt4 = models.iter().find(|m| m["id"] == "gpt-4").unwrap();
+        assert_eq!(gpt4["owned_by"], "openai");
+        assert_eq!(gpt4["supports_vision"], false);
+        assert_eq!(gpt4["max_context_tokens"], 8192);
+    }
+
+    #[tokio::test]
+    async fn test_models_filtered_by_key_allowlist() {
+        let allowed = AllowedModels::Only(vec!["claude-3-haiku*".to_string()]);
+        let Json(list) = list_models(State(state()), Some(Extension(allowed))).await;
+
+        let ids: Vec<&str> = list.data.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["claude-3-haiku-20240307"]);
+    }
+}
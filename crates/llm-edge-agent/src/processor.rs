@@ -0,0 +1,248 @@
+//! Request processing pipeline
+//!
+//! Request processors run in order before the cache lookup, so any rewrite
+//! they make (system prompt injection, parameter stripping, model rewrites)
+//! is reflected consistently in both the cache key and the provider request.
+
+use async_trait::async_trait;
+
+use crate::proxy::{ChatCompletionRequest, ChatMessage, ProxyError};
+
+/// Hook that can inspect and rewrite a request before it is cached or routed
+#[async_trait]
+pub trait RequestProcessor: Send + Sync {
+    /// Returns the processor name (used in logs)
+    fn name(&self) -> &str;
+
+    /// Processes the request in place; returning an error aborts the request
+    async fn process(&self, request: &mut ChatCompletionRequest) -> Result<(), ProxyError>;
+}
+
+/// Prepends a standard system prompt unless the request already has one
+pub struct SystemPromptProcessor {
+    prompt: String,
+}
+
+impl SystemPromptProcessor {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl RequestProcessor for SystemPromptProcessor {
+    fn name(&self) -> &str {
+        "system_prompt"
+    }
+
+    async fn process(&self, request: &mut ChatCompletionRequest) -> Result<(), ProxyError> {
+        if request.messages.iter().any(|m| m.role == "system") {
+            return Ok(());
+        }
+
+        request.messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: self.prompt.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{convert_to_cacheable, handle_chat_completions};
+    use axum::{extract::State, http::HeaderMap, Json};
+    use llm_edge_cache::CacheLookupResult;
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Provider that records the last request it received
+    #[derive(Default)]
+    struct RecordingProvider {
+        last_request: Mutex<Option<UnifiedRequest>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for RecordingProvider {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            let model = request.model.clone();
+            *self.last_request.lock() = Some(request);
+
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "ok".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: "recording".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Processor that always rejects
+    struct RejectingProcessor;
+
+    #[async_trait]
+    impl RequestProcessor for RejectingProcessor {
+        fn name(&self) -> &str {
+            "reject"
+        }
+
+        async fn process(&self, _request: &mut ChatCompletionRequest) -> Result<(), ProxyError> {
+            Err(ProxyError::validation("rejected"))
+        }
+    }
+
+    fn test_state(
+        provider: Arc<RecordingProvider>,
+        processors: Vec<Arc<dyn RequestProcessor>>,
+    ) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(AppConfig::default())
+                .with_openai(provider)
+                .with_request_processors(processors),
+        )
+    }
+
+    fn user_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_system_prompt_processor_affects_provider_and_cache_key() {
+        let provider = Arc::new(RecordingProvider::default());
+        let state = test_state(
+            provider.clone(),
+            vec![Arc::new(SystemPromptProcessor::new("Be concise."))],
+        );
+
+        let Json(response) = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            None,
+            Json(user_request()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.choices[0].message.content, "ok");
+
+        // Provider saw the injected system message first
+        let sent = provider.last_request.lock().clone().unwrap();
+        assert_eq!(sent.messages.len(), 2);
+        assert_eq!(sent.messages[0].role, "system");
+        assert_eq!(sent.messages[0].content, "Be concise.");
+
+        // Cache write is async; let it land (the clock is paused, so this
+        // only waits for the runtime to go idle)
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let mut processed = user_request();
+        SystemPromptProcessor::new("Be concise.")
+            .process(&mut processed)
+            .await
+            .unwrap();
+        let processed_key = convert_to_cacheable(&processed);
+        let raw_key = convert_to_cacheable(&user_request());
+
+        assert!(matches!(
+            state.cache_manager.lookup(&processed_key).await,
+            CacheLookupResult::L1Hit(_)
+        ));
+        assert!(matches!(
+            state.cache_manager.lookup(&raw_key).await,
+            CacheLookupResult::Miss
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_processor_keeps_existing_system_message() {
+        let mut request = user_request();
+        request.messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: "Custom".to_string(),
+            },
+        );
+
+        SystemPromptProcessor::new("Be concise.")
+            .process(&mut request)
+            .await
+            .unwrap();
+
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].content, "Custom");
+    }
+
+    #[tokio::test]
+    async fn test_processor_error_aborts_request() {
+        let provider = Arc::new(RecordingProvider::default());
+        let state = test_state(provider.clone(), vec![Arc::new(RejectingProcessor)]);
+
+        let result =
+            handle_chat_completions(State(state), HeaderMap::new(), None, Json(user_request()))
+                .await;
+
+        assert!(matches!(result, Err(ProxyError::ValidationError { .. })));
+        assert!(provider.last_request.lock().is_none());
+    }
+}
@@ -11,35 +11,119 @@
 //! 8. Response transformation and return
 
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
-    Json,
+    routing::post,
+    Extension, Json, Router,
 };
 use llm_edge_cache::CacheLookupResult;
 use llm_edge_monitoring::metrics;
-use llm_edge_providers::{LLMProvider, UnifiedRequest, UnifiedResponse};
+use llm_edge_providers::{
+    adapter::{check_content_filter, filter_passthrough_headers},
+    error_to_status, FinishReason, LLMProvider, ProviderError, ResponseFormat, UnifiedRequest,
+    UnifiedResponse, USER_ID_METADATA_KEY,
+};
+use llm_edge_proxy::middleware::{
+    api_key_identity, auth_middleware, presented_api_key, rate_limit_middleware,
+    request_id_from_headers, require_scope, AllowedModels, GrantedScopes, RateLimiter, SCOPE_ADMIN,
+    SCOPE_INFERENCE,
+};
+use llm_edge_security::sanitize_log_data;
+use opentelemetry_http::HeaderExtractor;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, error, info, instrument, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use uuid::Uuid;
 
-use crate::integration::AppState;
+use crate::dead_letter::FailedRequest;
+use crate::deadline::RequestDeadline;
+use crate::integration::{AppConfig, AppState};
+use crate::route::{explain_route, resolve_strategy, RouteStrategy};
+use crate::shadow::{spawn_shadow, ShadowOutcome};
+use crate::streaming::handle_chat_completions_stream;
 
 /// OpenAI-compatible chat completion request
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChatCompletionRequest {
+    /// May be left out; see [`crate::model_resolution`]
+    #[serde(default)]
     pub model: String,
     pub messages: Vec<ChatMessage>,
     #[serde(default)]
     pub temperature: Option<f32>,
     #[serde(default)]
     pub max_tokens: Option<u32>,
+    /// Reasoning effort for o1/o3-style models
+    #[serde(default)]
+    pub reasoning_effort: Option<String>,
+    /// Return log probabilities of the output tokens (responses are not cached)
+    #[serde(default)]
+    pub logprobs: Option<bool>,
+    /// Most likely alternatives to return per token, 0-20 (requires `logprobs`)
+    #[serde(default)]
+    pub top_logprobs: Option<u8>,
+    /// Structured output format (`json_object` or `json_schema`)
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    /// End-user id passed to the provider for abuse monitoring; not part of
+    /// the cache key
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Cache control for this request; never sent to providers and not part
+    /// of the cache key
+    #[serde(default)]
+    pub cache: Option<CacheDirectives>,
     #[serde(default)]
     pub stream: bool,
 }
 
+impl ChatCompletionRequest {
+    /// Whether the client asked for token log probabilities
+    pub fn wants_logprobs(&self) -> bool {
+        self.logprobs == Some(true)
+    }
+
+    /// Whether the body lets the response be served from cache
+    pub fn cache_lookup_allowed(&self) -> bool {
+        self.cache.as_ref().map_or(true, |cache| cache.enabled)
+    }
+
+    /// Whether the body lets the response be written to cache
+    pub fn cache_store_allowed(&self) -> bool {
+        self.cache
+            .as_ref()
+            .map_or(true, |cache| cache.enabled && !cache.no_store)
+    }
+}
+
+/// In-body alternative to the cache headers (`"cache": {...}`)
+///
+/// Where both are given, headers win: `X-Cache-TTL` overrides `ttl_seconds`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CacheDirectives {
+    /// `false` neither reads nor writes the cache
+    pub enabled: bool,
+    /// L2 TTL for this response, like `X-Cache-TTL`
+    pub ttl_seconds: Option<u64>,
+    /// Read the cache but do not store this response
+    pub no_store: bool,
+}
+
+impl Default for CacheDirectives {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_seconds: None,
+            no_store: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChatMessage {
     pub role: String,
@@ -63,7 +147,9 @@ pub struct ChatCompletionResponse {
 pub struct ChatChoice {
     pub index: u32,
     pub message: ChatMessage,
-    pub finish_reason: String,
+    pub finish_reason: FinishReason,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -79,38 +165,283 @@ pub struct ResponseMetadata {
     pub cached: bool,
     pub cache_tier: Option<String>,
     pub latency_ms: u64,
+    /// Total cost; the sum of `input_cost_usd` and `output_cost_usd`
     pub cost_usd: Option<f64>,
+    /// Cost of the prompt tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_cost_usd: Option<f64>,
+    /// Cost of the completion tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_cost_usd: Option<f64>,
+    /// Cache key version the response was looked up and stored under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_key_version: Option<u32>,
+    /// `max_tokens` sent upstream (client value or the per-model default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Provider's own stop reason for the first choice (e.g. `end_turn`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_finish_reason: Option<String>,
+    /// Provider fields with no place in OpenAI's schema (e.g. a message role
+    /// other than `assistant`), moved here by `build_response_from_provider`
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub provider_extras: serde_json::Map<String, serde_json::Value>,
 }
 
+/// Error code returned when an API key may not use the requested model
+pub const CODE_MODEL_NOT_ALLOWED: &str = "model_not_allowed";
+
+/// Error code returned when no provider serves the requested model
+pub const CODE_MODEL_NOT_FOUND: &str = "model_not_found";
+
+/// Error code returned when the provider is rate limiting the gateway
+pub const CODE_RATE_LIMIT_EXCEEDED: &str = "rate_limit_exceeded";
+
+/// `Retry-After` sent when a rate-limited provider gave no reset time, in
+/// seconds
+pub const DEFAULT_RATE_LIMIT_RETRY_AFTER_SECONDS: u64 = 1;
+
+/// Longest `Retry-After` sent for provider rate limiting, in seconds
+///
+/// A provider reporting a daily quota reset should not tell clients to stay
+/// away for a day; they can retry and be told again.
+pub const MAX_RATE_LIMIT_RETRY_AFTER_SECONDS: u64 = 3600;
+
 /// Error type for proxy operations
 #[derive(Debug)]
 pub enum ProxyError {
     CacheError(String),
     ProviderError(String),
-    ValidationError(String),
+    /// Invalid client request; `param` names the offending field
+    ValidationError {
+        message: String,
+        param: Option<String>,
+        code: Option<String>,
+    },
     InternalError(String),
+    Timeout(String),
+    ServiceUnavailable(String),
+    /// The provider refused or filtered the completion
+    ContentPolicyViolation(String),
+    /// The API key may not use the requested model
+    ModelNotAllowed(String),
+    /// The provider does not serve the requested model
+    ModelNotFound(String),
+    /// The provider kept answering 429; clients should back off for
+    /// `retry_after_secs`
+    RateLimited {
+        message: String,
+        retry_after_secs: u64,
+    },
+}
+
+impl ProxyError {
+    /// Validation error not tied to a single parameter
+    pub fn validation(message: impl Into<String>) -> Self {
+        ProxyError::ValidationError {
+            message: message.into(),
+            param: None,
+            code: None,
+        }
+    }
+
+    /// Validation error for the request parameter `param`
+    pub fn invalid_param(param: impl Into<String>, message: impl Into<String>) -> Self {
+        ProxyError::ValidationError {
+            message: message.into(),
+            param: Some(param.into()),
+            code: None,
+        }
+    }
+
+    /// Message carried by this error
+    pub fn message(&self) -> &str {
+        match self {
+            ProxyError::ValidationError { message, .. }
+            | ProxyError::RateLimited { message, .. } => message,
+            ProxyError::CacheError(message)
+            | ProxyError::ProviderError(message)
+            | ProxyError::InternalError(message)
+            | ProxyError::Timeout(message)
+            | ProxyError::ServiceUnavailable(message)
+            | ProxyError::ContentPolicyViolation(message)
+            | ProxyError::ModelNotAllowed(message)
+            | ProxyError::ModelNotFound(message) => message,
+        }
+    }
+
+    /// HTTP status this error is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ProxyError::ValidationError { .. } => StatusCode::BAD_REQUEST,
+            ProxyError::ContentPolicyViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ProxyError::ModelNotAllowed(_) => StatusCode::FORBIDDEN,
+            ProxyError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+            ProxyError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ProxyError::ProviderError(_) => StatusCode::BAD_GATEWAY,
+            ProxyError::CacheError(_) | ProxyError::InternalError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            ProxyError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ProxyError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// HTTP status and client-facing error object for this error
+    ///
+    /// Follows OpenAI's error shape (`message`, `type`, `param`, `code`).
+    pub fn into_parts(self) -> (StatusCode, serde_json::Value) {
+        let status = self.status();
+        let message = match self {
+            ProxyError::ValidationError {
+                message,
+                param,
+                code,
+            } => {
+                let error = serde_json::json!({
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "param": param,
+                    "code": code,
+                });
+                return (status, error);
+            }
+            ProxyError::ContentPolicyViolation(message) => {
+                let error = serde_json::json!({
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": "content_policy_violation",
+                });
+                return (status, error);
+            }
+            ProxyError::ModelNotAllowed(model) => {
+                let error = serde_json::json!({
+                    "message": format!("This API key may not use model '{}'", model),
+                    "type": "invalid_request_error",
+                    "param": "model",
+                    "code": CODE_MODEL_NOT_ALLOWED,
+                });
+                return (status, error);
+            }
+            ProxyError::ModelNotFound(message) => {
+                let error = serde_json::json!({
+                    "message": message,
+                    "type": "invalid_request_error",
+                    "param": "model",
+                    "code": CODE_MODEL_NOT_FOUND,
+                });
+                return (status, error);
+            }
+            ProxyError::RateLimited { message, .. } => {
+                let error = serde_json::json!({
+                    "message": message,
+                    "type": "rate_limit_error",
+                    "param": null,
+                    "code": CODE_RATE_LIMIT_EXCEEDED,
+                });
+                return (status, error);
+            }
+            ProxyError::CacheError(msg) => format!("Cache error: {}", msg),
+            ProxyError::ProviderError(msg)
+            | ProxyError::InternalError(msg)
+            | ProxyError::Timeout(msg)
+            | ProxyError::ServiceUnavailable(msg) => msg,
+        };
+
+        let error = serde_json::json!({
+            "message": message,
+            "type": "proxy_error",
+            "param": null,
+            "code": null,
+        });
+
+        (status, error)
+    }
+}
+
+/// Provider failures take the status [`error_to_status`] gives them
+///
+/// Rate limiting gets the default `Retry-After` here; the handler replaces it
+/// with the provider's own reset time when it knows one.
+impl From<ProviderError> for ProxyError {
+    fn from(e: ProviderError) -> Self {
+        match error_to_status(&e) {
+            StatusCode::TOO_MANY_REQUESTS => ProxyError::RateLimited {
+                message: e.to_string(),
+                retry_after_secs: DEFAULT_RATE_LIMIT_RETRY_AFTER_SECONDS,
+            },
+            StatusCode::NOT_FOUND => ProxyError::ModelNotFound(e.to_string()),
+            StatusCode::GATEWAY_TIMEOUT => ProxyError::Timeout(e.to_string()),
+            StatusCode::UNPROCESSABLE_ENTITY => ProxyError::ContentPolicyViolation(e.to_string()),
+            StatusCode::BAD_REQUEST => match e {
+                ProviderError::UnsupportedParameter { param, message } => {
+                    ProxyError::invalid_param(param, message)
+                }
+                e => ProxyError::validation(e.to_string()),
+            },
+            StatusCode::INTERNAL_SERVER_ERROR => ProxyError::InternalError(e.to_string()),
+            _ => ProxyError::ProviderError(format!("Provider error: {}", e)),
+        }
+    }
 }
 
 impl IntoResponse for ProxyError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            ProxyError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
-            ProxyError::ProviderError(msg) => (StatusCode::BAD_GATEWAY, msg),
-            ProxyError::CacheError(msg) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Cache error: {}", msg),
-            ),
-            ProxyError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        let retry_after = match &self {
+            ProxyError::RateLimited {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            _ => None,
         };
+        let (status, error) = self.into_parts();
+        let mut response = (status, Json(serde_json::json!({ "error": error }))).into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
 
-        let body = serde_json::json!({
-            "error": {
-                "message": message,
-                "type": "proxy_error",
-            }
-        });
+/// Chat completion routes, protected by API key auth and the `inference` scope
+///
+/// Each API key's requests are counted in its own `limiter` bucket.
+pub fn chat_routes(
+    auth_config: llm_edge_proxy::Config,
+    limiter: RateLimiter,
+) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route_layer(axum::middleware::from_fn(debug_trace_gate))
+        .route_layer(axum::middleware::from_fn_with_state(
+            limiter,
+            rate_limit_middleware,
+        ));
+
+    require_scope(routes, SCOPE_INFERENCE).route_layer(axum::middleware::from_fn_with_state(
+        auth_config,
+        auth_middleware,
+    ))
+}
 
-        (status, Json(body)).into_response()
+/// `POST /v1/chat/completions`, answered as server-sent events when the
+/// request sets `stream`
+pub async fn chat_completions(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    allowed_models: Option<Extension<AllowedModels>>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    if request.stream {
+        handle_chat_completions_stream(state, headers, allowed_models, Json(request))
+            .await
+            .into_response()
+    } else {
+        handle_chat_completions(state, headers, allowed_models, Json(request))
+            .await
+            .into_response()
     }
 }
 
@@ -118,17 +449,35 @@ impl IntoResponse for ProxyError {
 ///
 /// This is the core handler that processes all chat completion requests.
 /// It orchestrates the entire request flow through caching, routing, and provider layers.
-#[instrument(name = "proxy_chat_completions", skip(state, request), fields(
-    request_id = %Uuid::new_v4(),
+#[instrument(name = "proxy_chat_completions", skip(state, headers, allowed_models, request), fields(
+    request_id = tracing::field::Empty,
+    request_tags = tracing::field::Empty,
     model = %request.model,
     message_count = request.messages.len(),
 ))]
 pub async fn handle_chat_completions(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ChatCompletionRequest>,
+    headers: HeaderMap,
+    allowed_models: Option<Extension<AllowedModels>>,
+    Json(mut request): Json<ChatCompletionRequest>,
 ) -> Result<Json<ChatCompletionResponse>, ProxyError> {
+    // Continue the caller's trace; provider adapters propagate it upstream
+    if headers.contains_key(TRACEPARENT_HEADER) {
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(&headers))
+        });
+        tracing::Span::current().set_parent(parent);
+    }
+
     let start_time = Instant::now();
-    let request_id = Uuid::new_v4().to_string();
+    // Already resolved (and the header rewritten) by request_id_middleware
+    let request_id = request_id_from_headers(&headers);
+    tracing::Span::current().record("request_id", request_id.as_str());
+    let tags = state.config.request_tags.extract(&headers);
+    if !tags.is_empty() {
+        tracing::Span::current().record("request_tags", tracing::field::display(&tags));
+    }
+    let deadline = RequestDeadline::from_headers(&headers, state.config.request_timeout());
 
     info!(
         request_id = %request_id,
@@ -136,14 +485,65 @@ pub async fn handle_chat_completions(
         "Processing chat completion request"
     );
 
-    // Step 1: Validate request
-    validate_request(&request)?;
+    // Streams are answered by chat_completions; batches and warm-ups cannot stream
+    if request.stream {
+        return Err(ProxyError::invalid_param(
+            "stream",
+            "Streamed requests must be sent to /v1/chat/completions",
+        ));
+    }
+
+    // Step 1: Validate, rewrite and authorize the request
+    let (pinned_provider, strategy) = prepare_request(
+        &state,
+        &headers,
+        allowed_models.as_ref(),
+        &mut request,
+        &request_id,
+    )
+    .await?;
+    let cache_ttl = resolve_cache_ttl(&headers, &request, state.config.max_cache_ttl_seconds)?;
+    let trace_bodies = state.config.debug_body_logging || headers.contains_key(DEBUG_TRACE_HEADER);
+
+    if trace_bodies {
+        log_body(&request_id, "request", &request);
+    }
 
     // Step 2: Convert to cacheable format
     let cacheable_req = convert_to_cacheable(&request);
 
-    // Step 3: Check cache (L1 -> L2)
-    let cache_lookup = state.cache_manager.lookup(&cacheable_req).await;
+    // Step 3: Check cache (L1 -> L2); cached responses carry no logprobs
+    let cache_lookup = if request.wants_logprobs() {
+        debug!(request_id = %request_id, "Logprobs requested, bypassing cache");
+        CacheLookupResult::Miss
+    } else if !request.cache_lookup_allowed() {
+        debug!(request_id = %request_id, "Cache disabled by request, bypassing cache");
+        CacheLookupResult::Miss
+    } else if state.config.cache_policy.skips_lookup(&request) {
+        debug!(
+            request_id = %request_id,
+            temperature = ?request.temperature,
+            "High temperature, bypassing cache lookup"
+        );
+        metrics::record_cache_skipped_high_temp();
+        CacheLookupResult::Miss
+    } else {
+        // A soft-stale hit is served now and refreshed from the provider
+        state
+            .cache_manager
+            .lookup_or_refresh(&cacheable_req, || {
+                refresh_cached_completion(
+                    state.clone(),
+                    headers.clone(),
+                    request.clone(),
+                    request_id.clone(),
+                    tags.labels().to_vec(),
+                    pinned_provider,
+                    strategy,
+                )
+            })
+            .await
+    };
 
     match cache_lookup {
         CacheLookupResult::L1Hit(cached_response) => {
@@ -155,7 +555,11 @@ pub async fn handle_chat_completions(
                 &cached_response,
                 "l1",
                 start_time.elapsed().as_millis() as u64,
+                state.cache_manager.key_version(),
             );
+            if trace_bodies {
+                log_body(&request_id, "response", &response);
+            }
 
             return Ok(Json(response));
         }
@@ -168,7 +572,11 @@ pub async fn handle_chat_completions(
                 &cached_response,
                 "l2",
                 start_time.elapsed().as_millis() as u64,
+                state.cache_manager.key_version(),
             );
+            if trace_bodies {
+                log_body(&request_id, "response", &response);
+            }
 
             return Ok(Json(response));
         }
@@ -178,35 +586,192 @@ pub async fn handle_chat_completions(
         }
     }
 
-    // Step 4: Route to provider
-    let (provider, provider_name) = select_provider(&state, &request)?;
+    // Step 4: Route to provider (an explicit override bypasses selection)
+    let route = explain_route(&state, &request.model, pinned_provider, strategy).await;
+    debug!(request_id = %request_id, reason = %route.reason, "Routing decision");
+    let fallbacks = route.fallbacks(&state);
+    let selected = match route.into_provider(&state) {
+        Ok(selected) => selected,
+        Err(e) => {
+            return static_fallback_or(
+                &state,
+                &request,
+                &request_id,
+                &[],
+                start_time,
+                "no_provider",
+                e,
+            )
+        }
+    };
 
     // Step 5: Convert to unified request format
-    let unified_request = convert_to_unified(&request);
-
-    // Step 6: Send to provider
-    info!(
-        request_id = %request_id,
-        provider = %provider_name,
-        "Sending request to provider"
-    );
+    let mut base_request = convert_to_unified(&request);
+    base_request.extra_headers =
+        filter_passthrough_headers(&headers, &state.config.passthrough_headers);
+
+    // Sampled once up front, so a failover does not change the odds
+    let shadow_sampled = state
+        .config
+        .shadow
+        .as_ref()
+        .is_some_and(|shadow| shadow.sample());
+
+    // A provider answering 429 hands the request to the next eligible
+    // candidate. Only the first candidate's failures reach the client;
+    // failover candidates that cannot take the request are skipped.
+    let mut candidates = std::iter::once(selected).chain(fallbacks);
+    let mut attempted: Vec<String> = Vec::new();
+    let mut retry_after_secs: Option<u64> = None;
+    let (
+        provider,
+        provider_name,
+        resolved_max_tokens,
+        shadow_request,
+        provider_response,
+        provider_start,
+    ) = loop {
+        let Some((provider, provider_name)) = candidates.next() else {
+            // Only reached once every candidate tried was rate limiting
+            let e = ProxyError::RateLimited {
+                message: format!(
+                    "Every provider is rate limiting requests ({})",
+                    attempted.join(", ")
+                ),
+                retry_after_secs: retry_after_secs
+                    .unwrap_or(DEFAULT_RATE_LIMIT_RETRY_AFTER_SECONDS),
+            };
+            return static_fallback_or(
+                &state,
+                &request,
+                &request_id,
+                &attempted,
+                start_time,
+                "rate_limited",
+                e,
+            );
+        };
+        let is_failover = !attempted.is_empty();
+        attempted.push(provider_name.clone());
+
+        // Step 5b: Check the provider can take the request within the cost ceiling
+        let checked = request_for_provider(
+            &state,
+            &headers,
+            &request,
+            &base_request,
+            &provider,
+            &provider_name,
+            &request_id,
+        );
+        let (unified_request, resolved_max_tokens) = match checked {
+            Ok(checked) => checked,
+            Err(_) if is_failover => {
+                debug!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    "Skipping failover candidate that cannot take the request"
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
 
-    let provider_start = Instant::now();
-    let provider_response = provider.send(unified_request).await.map_err(|e| {
-        error!(
+        // Step 6: Send to provider
+        info!(
             request_id = %request_id,
             provider = %provider_name,
-            error = %e,
-            "Provider request failed"
+            "Sending request to provider"
         );
-        metrics::record_request_failure(&provider_name, &request.model, "provider_error");
-        ProxyError::ProviderError(format!("Provider error: {}", e))
-    })?;
+
+        // Cloned up front, since the request is consumed by the send
+        let shadow_request = shadow_sampled.then(|| unified_request.clone());
+
+        let provider_start = Instant::now();
+        let provider_result = match deadline.run(provider.send(unified_request)).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    "Request deadline exceeded waiting for provider"
+                );
+                metrics::record_request_failure(&provider_name, &request.model, "timeout");
+                return static_fallback_or(
+                    &state,
+                    &request,
+                    &request_id,
+                    &attempted,
+                    start_time,
+                    "timeout",
+                    e,
+                );
+            }
+        };
+        // Filtered completions fail here, before anything is cached
+        match provider_result.and_then(|response| check_content_filter(&response).map(|_| response))
+        {
+            Ok(response) => {
+                break (
+                    provider,
+                    provider_name,
+                    resolved_max_tokens,
+                    shadow_request,
+                    response,
+                    provider_start,
+                )
+            }
+            Err(e @ ProviderError::ContentFiltered { .. }) => {
+                warn!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    error = %e,
+                    "Provider filtered the completion"
+                );
+                metrics::record_request_failure(&provider_name, &request.model, "content_filtered");
+                return Err(e.into());
+            }
+            // Backpressure, not an outage: once no provider is left, pass the
+            // soonest reset on so clients back off instead of retrying a 502
+            // straight away
+            Err(e) if e.is_rate_limited() => {
+                let wait = rate_limit_retry_after(provider.as_ref());
+                warn!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    error = %e,
+                    retry_after_secs = wait,
+                    "Provider is rate limiting requests"
+                );
+                metrics::record_request_failure(&provider_name, &request.model, "rate_limited");
+                retry_after_secs = Some(retry_after_secs.map_or(wait, |soonest| soonest.min(wait)));
+            }
+            Err(e) => {
+                error!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    error = %e,
+                    "Provider request failed"
+                );
+                metrics::record_request_failure(&provider_name, &request.model, "provider_error");
+                let e = ProxyError::from(e);
+                return static_fallback_or(
+                    &state,
+                    &request,
+                    &request_id,
+                    &attempted,
+                    start_time,
+                    "provider_error",
+                    e,
+                );
+            }
+        }
+    };
 
     let provider_latency = provider_start.elapsed().as_millis() as u64;
 
     // Step 7: Calculate cost
-    let cost_usd = calculate_cost(&provider, &request.model, &provider_response);
+    let cost = calculate_cost(&provider, &request.model, &provider_response.usage);
 
     // Step 8: Record metrics
     metrics::record_request_success(&provider_name, &request.model, provider_latency);
@@ -215,29 +780,68 @@ pub async fn handle_chat_completions(
         &request.model,
         provider_response.usage.prompt_tokens,
         provider_response.usage.completion_tokens,
+        tags.labels(),
     );
-    if let Some(cost) = cost_usd {
-        metrics::record_cost(&provider_name, &request.model, cost);
+    if let Some(cost) = cost {
+        metrics::record_cost(&provider_name, &request.model, cost.total(), tags.labels());
     }
 
-    // Step 9: Store in cache (async, non-blocking)
-    let cache_response = convert_provider_to_cache(&provider_response);
-    tokio::spawn({
-        let cache_manager = state.cache_manager.clone();
-        let cacheable_req = cacheable_req.clone();
-        async move {
-            cache_manager.store(&cacheable_req, cache_response).await;
+    // Step 9: Store in cache (async, non-blocking) if the policy allows it
+    let skip_reason = if request.cache_store_allowed() {
+        state
+            .config
+            .cache_policy
+            .skip_reason(&request, &provider_response)
+    } else {
+        Some("no_store requested")
+    };
+    match skip_reason {
+        None => {
+            let cache_response = convert_provider_to_cache(&provider_response);
+            tokio::spawn({
+                let cache_manager = state.cache_manager.clone();
+                let cacheable_req = cacheable_req.clone();
+                async move {
+                    match cache_ttl {
+                        Some(ttl) => {
+                            cache_manager
+                                .store_with_ttl(&cacheable_req, cache_response, ttl)
+                                .await
+                        }
+                        None => cache_manager.store(&cacheable_req, cache_response).await,
+                    }
+                }
+            });
         }
-    });
+        Some(reason) => debug!(
+            request_id = %request_id,
+            reason,
+            "Response not cached by cache policy"
+        ),
+    }
 
-    // Step 10: Build and return response
+    // Step 10: Shadow the request to the candidate provider in the background
+    if let Some(shadow_request) = shadow_request {
+        let primary = ShadowOutcome::response(
+            &provider_name,
+            &request.model,
+            &provider_response,
+            provider_latency,
+            cost.map(|c| c.total()),
+        );
+        spawn_shadow(&state, &request_id, shadow_request, primary);
+    }
+
+    // Step 11: Build and return response
     let total_latency = start_time.elapsed().as_millis() as u64;
     let response = build_response_from_provider(
         &request,
         provider_response,
         &provider_name,
         total_latency,
-        cost_usd,
+        cost,
+        resolved_max_tokens,
+        state.cache_manager.key_version(),
     );
 
     info!(
@@ -247,33 +851,161 @@ pub async fn handle_chat_completions(
         provider_latency_ms = provider_latency,
         "Request completed successfully"
     );
+    if trace_bodies {
+        log_body(&request_id, "response", &response);
+    }
 
     Ok(Json(response))
 }
 
+/// Seconds until `provider`'s rate-limit window resets, rounded up and at
+/// most [`MAX_RATE_LIMIT_RETRY_AFTER_SECONDS`]
+///
+/// Falls back to [`DEFAULT_RATE_LIMIT_RETRY_AFTER_SECONDS`] when the provider
+/// reported no reset, or one already in the past.
+fn rate_limit_retry_after(provider: &dyn LLMProvider) -> u64 {
+    provider
+        .rate_limit_state()
+        .and_then(|state| state.reset_at)
+        .map(|reset_at| reset_at.saturating_duration_since(Instant::now()))
+        .filter(|wait| !wait.is_zero())
+        .map_or(DEFAULT_RATE_LIMIT_RETRY_AFTER_SECONDS, |wait| {
+            (wait.as_secs() + u64::from(wait.subsec_nanos() > 0))
+                .min(MAX_RATE_LIMIT_RETRY_AFTER_SECONDS)
+        })
+}
+
+/// Serve the static fallback instead of a routing or provider failure, if enabled
+///
+/// Only reached after a cache miss, so the fallback never hides a cached answer.
+/// Only outages (5xx: no provider, upstream failure, timeout) are covered;
+/// a request the provider rejected (4xx) or rate limiting (429) is passed
+/// on, since the client can act on it. Either way the failure goes to the
+/// dead-letter log along with the `attempted` providers.
+fn static_fallback_or(
+    state: &AppState,
+    request: &ChatCompletionRequest,
+    request_id: &str,
+    attempted: &[String],
+    start_time: Instant,
+    reason: &'static str,
+    error: ProxyError,
+) -> Result<Json<ChatCompletionResponse>, ProxyError> {
+    let serve_fallback = state.config.enable_static_fallback && error.status().is_server_error();
+    let attempted: Vec<&str> = attempted.iter().map(String::as_str).collect();
+    state.dead_letters.record(FailedRequest::new(
+        request_id,
+        &request.model,
+        &attempted,
+        reason,
+        error.message(),
+        serve_fallback,
+    ));
+
+    if !serve_fallback {
+        return Err(error);
+    }
+
+    warn!(
+        request_id = %request_id,
+        reason,
+        error = ?error,
+        "Serving static fallback response"
+    );
+    metrics::record_static_fallback(reason);
+    let latency_ms = start_time.elapsed().as_millis() as u64;
+    Ok(Json(
+        state
+            .config
+            .fallback_response
+            .to_completion(request, latency_ms),
+    ))
+}
+
 /// Validate the incoming request
-fn validate_request(request: &ChatCompletionRequest) -> Result<(), ProxyError> {
+pub(crate) fn validate_request(
+    request: &ChatCompletionRequest,
+    config: &AppConfig,
+) -> Result<(), ProxyError> {
     if request.model.is_empty() {
-        return Err(ProxyError::ValidationError("Model is required".to_string()));
+        return Err(ProxyError::invalid_param("model", "Model is required"));
     }
 
     if request.messages.is_empty() {
-        return Err(ProxyError::ValidationError(
-            "Messages cannot be empty".to_string(),
+        return Err(ProxyError::invalid_param(
+            "messages",
+            "Messages cannot be empty",
         ));
     }
 
-    if request.stream {
-        return Err(ProxyError::ValidationError(
-            "Streaming is not yet supported".to_string(),
+    // Checked before anything else walks or serializes the history
+    if request.messages.len() > config.max_messages {
+        return Err(ProxyError::invalid_param(
+            "messages",
+            format!(
+                "Too many messages: {} exceeds the limit of {}",
+                request.messages.len(),
+                config.max_messages
+            ),
+        ));
+    }
+
+    let total_chars: usize = request
+        .messages
+        .iter()
+        .map(|m| m.content.chars().count())
+        .sum();
+    if total_chars > config.max_total_prompt_chars {
+        return Err(ProxyError::invalid_param(
+            "messages",
+            format!(
+                "Message content is {} characters, exceeding the limit of {}",
+                total_chars, config.max_total_prompt_chars
+            ),
         ));
     }
 
+    if let Some(temperature) = request.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(ProxyError::invalid_param(
+                "temperature",
+                format!("Temperature must be between 0 and 2, got {}", temperature),
+            ));
+        }
+    }
+
+    if request.max_tokens == Some(0) {
+        return Err(ProxyError::invalid_param(
+            "max_tokens",
+            "max_tokens must be at least 1",
+        ));
+    }
+
+    if let Some(top_logprobs) = request.top_logprobs {
+        if top_logprobs > 20 {
+            return Err(ProxyError::invalid_param(
+                "top_logprobs",
+                format!(
+                    "top_logprobs must be between 0 and 20, got {}",
+                    top_logprobs
+                ),
+            ));
+        }
+        if !request.wants_logprobs() {
+            return Err(ProxyError::invalid_param(
+                "top_logprobs",
+                "top_logprobs requires logprobs to be true",
+            ));
+        }
+    }
+
     Ok(())
 }
 
 /// Convert chat completion request to cacheable format
-fn convert_to_cacheable(request: &ChatCompletionRequest) -> llm_edge_cache::key::CacheableRequest {
+pub(crate) fn convert_to_cacheable(
+    request: &ChatCompletionRequest,
+) -> llm_edge_cache::key::CacheableRequest {
     // Concatenate all messages into a single prompt for caching
     let prompt = request
         .messages
@@ -292,12 +1024,25 @@ fn convert_to_cacheable(request: &ChatCompletionRequest) -> llm_edge_cache::key:
         cacheable = cacheable.with_max_tokens(max_tokens);
     }
 
+    if let Some(ref effort) = request.reasoning_effort {
+        cacheable = cacheable.with_parameter("reasoning_effort", serde_json::json!(effort));
+    }
+
+    // Changes the shape of the output, so JSON and text answers never mix
+    if let Some(ref format) = request.response_format {
+        cacheable = cacheable.with_parameter("response_format", serde_json::json!(format));
+    }
+
     cacheable
 }
 
 /// Convert chat completion request to unified format
-fn convert_to_unified(request: &ChatCompletionRequest) -> UnifiedRequest {
-    use std::collections::HashMap;
+pub(crate) fn convert_to_unified(request: &ChatCompletionRequest) -> UnifiedRequest {
+    let metadata = request
+        .user
+        .iter()
+        .map(|user| (USER_ID_METADATA_KEY.to_string(), user.clone()))
+        .collect();
 
     UnifiedRequest {
         model: request.model.clone(),
@@ -311,60 +1056,298 @@ fn convert_to_unified(request: &ChatCompletionRequest) -> UnifiedRequest {
             .collect(),
         temperature: request.temperature,
         max_tokens: request.max_tokens.map(|t| t as usize),
+        reasoning_effort: request.reasoning_effort.clone(),
+        logprobs: request.logprobs,
+        top_logprobs: request.top_logprobs,
+        response_format: request.response_format.clone(),
         stream: request.stream,
-        metadata: HashMap::new(),
+        metadata,
+        extra_headers: Default::default(),
     }
 }
 
-/// Select the appropriate provider for the request
-fn select_provider(
-    state: &AppState,
+/// W3C trace context header carrying the caller's trace and span id
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Header that pins the request to a provider
+pub const PROVIDER_HEADER: &str = "x-provider";
+
+/// Header overriding the L2 cache TTL (seconds) for this request's response
+pub const CACHE_TTL_HEADER: &str = "x-cache-ttl";
+
+/// Parse the `X-Cache-TTL` override, clamped to `max_seconds`
+///
+/// Returns `None` when the header is absent; anything but a positive integer
+/// is rejected with `400`.
+fn parse_cache_ttl(headers: &HeaderMap, max_seconds: u64) -> Result<Option<u64>, ProxyError> {
+    let Some(value) = headers.get(CACHE_TTL_HEADER) else {
+        return Ok(None);
+    };
+
+    match value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        Some(ttl) if ttl > 0 => Ok(Some(ttl.min(max_seconds))),
+        _ => Err(ProxyError::validation(format!(
+            "Invalid {} header: expected a positive integer number of seconds",
+            CACHE_TTL_HEADER
+        ))),
+    }
+}
+
+/// TTL override from the `X-Cache-TTL` header, else from the body's
+/// `cache.ttl_seconds`, clamped to `max_seconds`
+fn resolve_cache_ttl(
+    headers: &HeaderMap,
     request: &ChatCompletionRequest,
-) -> Result<(Arc<dyn LLMProvider>, String), ProxyError> {
-    // For MVP, use simple model-based routing
-    // In production, this would use the routing engine
+    max_seconds: u64,
+) -> Result<Option<u64>, ProxyError> {
+    if let Some(ttl) = parse_cache_ttl(headers, max_seconds)? {
+        return Ok(Some(ttl));
+    }
+
+    match request.cache.as_ref().and_then(|cache| cache.ttl_seconds) {
+        None => Ok(None),
+        Some(0) => Err(ProxyError::invalid_param(
+            "cache.ttl_seconds",
+            "cache.ttl_seconds must be a positive number of seconds",
+        )),
+        Some(ttl) => Ok(Some(ttl.min(max_seconds))),
+    }
+}
+
+/// Header requesting debug body logging for this request
+///
+/// Only honoured for callers holding the `admin` scope; [`debug_trace_gate`]
+/// strips it from everyone else.
+pub const DEBUG_TRACE_HEADER: &str = "x-debug-trace";
+
+/// Upper bound on the size of a logged body, in bytes
+const DEBUG_BODY_MAX_LENGTH: usize = 8192;
+
+/// Drop `X-Debug-Trace` unless the caller holds the `admin` scope
+///
+/// Must sit inside the auth middleware. Without [`GrantedScopes`] (routes
+/// that are not authenticated) the header is always removed.
+pub async fn debug_trace_gate(mut request: Request, next: Next) -> Response {
+    if request.headers().contains_key(DEBUG_TRACE_HEADER) {
+        let allowed = request
+            .extensions()
+            .get::<GrantedScopes>()
+            .is_some_and(|scopes| scopes.allows(SCOPE_ADMIN));
+        if !allowed {
+            warn!("Ignoring X-Debug-Trace from caller without the admin scope");
+            request.headers_mut().remove(DEBUG_TRACE_HEADER);
+        }
+    }
 
-    let model_lower = request.model.to_lowercase();
+    next.run(request).await
+}
 
-    if model_lower.contains("gpt") || model_lower.contains("openai") {
-        if let Some(provider) = &state.openai_provider {
-            return Ok((provider.clone(), "openai".to_string()));
+/// Log a PII-redacted, size-capped copy of a request or response body
+fn log_body(request_id: &str, kind: &'static str, body: &impl Serialize) {
+    match serde_json::to_string(body) {
+        Ok(json) => debug!(
+            request_id = %request_id,
+            kind,
+            body = %sanitize_log_data(&json, DEBUG_BODY_MAX_LENGTH),
+            "Debug body trace"
+        ),
+        Err(e) => {
+            warn!(request_id = %request_id, kind, error = %e, "Failed to serialize body for debug trace")
         }
     }
+}
+
+/// Provider names accepted in `X-Provider` and `provider/model` prefixes
+const KNOWN_PROVIDERS: &[&str] = &["openai", "anthropic"];
+
+/// Resolve an explicit provider override
+///
+/// Accepts either a `provider/model` prefix (stripped from `request.model`)
+/// or an `X-Provider` header. Both may be given only if they agree.
+pub(crate) fn resolve_provider_override(
+    headers: &HeaderMap,
+    request: &mut ChatCompletionRequest,
+) -> Result<Option<&'static str>, ProxyError> {
+    let known = |name: &str| {
+        KNOWN_PROVIDERS
+            .iter()
+            .copied()
+            .find(|p| p.eq_ignore_ascii_case(name))
+    };
+
+    let from_prefix = match request.model.split_once('/') {
+        Some((prefix, model)) if !model.is_empty() => known(prefix).map(|p| (p, model.to_string())),
+        _ => None,
+    };
+
+    let from_header =
+        match headers.get(PROVIDER_HEADER) {
+            Some(value) => {
+                let value = value.to_str().unwrap_or_default().trim();
+                Some(known(value).ok_or_else(|| {
+                    ProxyError::validation(format!("Unknown provider: {}", value))
+                })?)
+            }
+            None => None,
+        };
 
-    if model_lower.contains("claude") || model_lower.contains("anthropic") {
-        if let Some(provider) = &state.anthropic_provider {
-            return Ok((provider.clone(), "anthropic".to_string()));
+    match (from_prefix, from_header) {
+        (Some((prefix, _)), Some(header)) if prefix != header => Err(ProxyError::invalid_param(
+            "model",
+            format!(
+                "Model prefix '{}' conflicts with {} header '{}'",
+                prefix, PROVIDER_HEADER, header
+            ),
+        )),
+        (Some((prefix, model)), _) => {
+            request.model = model;
+            Ok(Some(prefix))
         }
+        (None, header) => Ok(header),
+    }
+}
+
+/// Validate, rewrite and authorize a request before the cache and routing
+///
+/// Fills in a missing model, strips a provider prefix, runs the request
+/// processors, checks the key's model allowlist and derives the provider
+/// `user`. Returns the pinned provider, if any, and the routing strategy.
+pub(crate) async fn prepare_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    allowed_models: Option<&Extension<AllowedModels>>,
+    request: &mut ChatCompletionRequest,
+    request_id: &str,
+) -> Result<(Option<&'static str>, RouteStrategy), ProxyError> {
+    state.config.model_resolution.resolve(request);
+    tracing::Span::current().record("model", request.model.as_str());
+    validate_request(request, &state.config)?;
+    let pinned_provider = resolve_provider_override(headers, request)?;
+    let strategy = resolve_strategy(headers, state)?;
+
+    // Processors run before the cache key is derived
+    for processor in &state.request_processors {
+        processor.process(request).await.map_err(|e| {
+            warn!(
+                request_id = %request_id,
+                processor = processor.name(),
+                "Request processor rejected request"
+            );
+            e
+        })?;
     }
 
-    // Fallback to first available provider
-    if let Some(provider) = &state.openai_provider {
-        warn!("Using fallback provider: openai");
-        return Ok((provider.clone(), "openai".to_string()));
+    // Checked once aliases are resolved
+    if let Some(Extension(allowed_models)) = allowed_models {
+        if !allowed_models.allows(&request.model) {
+            warn!(
+                request_id = %request_id,
+                model = %request.model,
+                "API key may not use the requested model"
+            );
+            return Err(ProxyError::ModelNotAllowed(request.model.clone()));
+        }
     }
 
-    if let Some(provider) = &state.anthropic_provider {
-        warn!("Using fallback provider: anthropic");
-        return Ok((provider.clone(), "anthropic".to_string()));
+    // Identify the caller to the provider if the client did not
+    if request.user.is_none() && state.config.derive_user_from_api_key {
+        request.user = presented_api_key(headers).map(|key| api_key_identity(&key));
     }
 
-    Err(ProxyError::InternalError(
-        "No providers configured".to_string(),
-    ))
+    Ok((pinned_provider, strategy))
+}
+
+/// The request as `provider` will be sent it, with its resolved `max_tokens`
+///
+/// Parameters the provider cannot honour are client errors, as is a
+/// worst-case cost above the ceiling.
+pub(crate) fn request_for_provider(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+    base_request: &UnifiedRequest,
+    provider: &Arc<dyn LLMProvider>,
+    provider_name: &str,
+    request_id: &str,
+) -> Result<(UnifiedRequest, Option<u32>), ProxyError> {
+    let resolved_max_tokens =
+        resolve_max_tokens(request, provider, state.config.default_max_tokens_fraction);
+    let mut unified_request = base_request.clone();
+    unified_request.max_tokens = resolved_max_tokens.map(|t| t as usize);
+
+    provider.check_request(&unified_request).map_err(|e| {
+        warn!(
+            request_id = %request_id,
+            provider = %provider_name,
+            error = %e,
+            "Request not supported by provider"
+        );
+        ProxyError::from(e)
+    })?;
+    state
+        .config
+        .cost_ceiling
+        .check(
+            headers,
+            request,
+            resolved_max_tokens,
+            provider.get_pricing(&request.model).as_ref(),
+        )
+        .map_err(|e| {
+            warn!(
+                request_id = %request_id,
+                provider = %provider_name,
+                "Request rejected by cost ceiling"
+            );
+            e
+        })?;
+
+    Ok((unified_request, resolved_max_tokens))
+}
+
+/// Resolve the `max_tokens` to send upstream
+///
+/// An explicit client value always wins. Otherwise default to `fraction` of the
+/// model's max output tokens, so we neither rely on tiny provider defaults nor
+/// request more than the model can produce.
+fn resolve_max_tokens(
+    request: &ChatCompletionRequest,
+    provider: &Arc<dyn LLMProvider>,
+    fraction: f64,
+) -> Option<u32> {
+    request.max_tokens.or_else(|| {
+        provider
+            .max_output_tokens(&request.model)
+            .map(|max| ((max as f64 * fraction) as u32).max(1))
+    })
+}
+
+/// Cost of a response in USD, split by token type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostBreakdown {
+    pub input: f64,
+    pub output: f64,
 }
 
-/// Calculate the cost of a request
-fn calculate_cost(
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.input + self.output
+    }
+}
+
+/// Calculate the cost of the tokens a request used
+pub(crate) fn calculate_cost(
     provider: &Arc<dyn LLMProvider>,
     model: &str,
-    response: &UnifiedResponse,
-) -> Option<f64> {
-    provider.get_pricing(model).map(|pricing| {
-        let input_cost = (response.usage.prompt_tokens as f64 / 1000.0) * pricing.input_cost_per_1k;
-        let output_cost =
-            (response.usage.completion_tokens as f64 / 1000.0) * pricing.output_cost_per_1k;
-        input_cost + output_cost
+    usage: &llm_edge_providers::Usage,
+) -> Option<CostBreakdown> {
+    provider.get_pricing(model).map(|pricing| CostBreakdown {
+        input: (usage.prompt_tokens as f64 / 1000.0) * pricing.input_cost_per_1k,
+        output: (usage.completion_tokens as f64 / 1000.0) * pricing.output_cost_per_1k,
     })
 }
 
@@ -374,6 +1357,7 @@ fn build_response_from_cache(
     cached: &llm_edge_cache::l1::CachedResponse,
     cache_tier: &str,
     latency_ms: u64,
+    cache_key_version: u32,
 ) -> ChatCompletionResponse {
     ChatCompletionResponse {
         id: format!("chatcmpl-{}", Uuid::new_v4()),
@@ -386,7 +1370,8 @@ fn build_response_from_cache(
                 role: "assistant".to_string(),
                 content: cached.content.clone(),
             },
-            finish_reason: "stop".to_string(),
+            finish_reason: FinishReason::Stop,
+            logprobs: None,
         }],
         usage: Usage {
             prompt_tokens: cached.tokens.as_ref().map(|t| t.prompt_tokens).unwrap_or(0),
@@ -403,10 +1388,87 @@ fn build_response_from_cache(
             cache_tier: Some(cache_tier.to_string()),
             latency_ms,
             cost_usd: Some(0.0), // Cached responses have zero cost
+            input_cost_usd: Some(0.0),
+            output_cost_usd: Some(0.0),
+            cache_key_version: Some(cache_key_version),
+            max_tokens: None,
+            native_finish_reason: None,
+            provider_extras: Default::default(),
         }),
     }
 }
 
+/// Fetch a fresh completion for a stale cache entry
+///
+/// Runs in the background after the stale entry was served. The request goes
+/// through the same provider checks and cost ceiling as one from the client,
+/// and its usage and cost are recorded like any other, but no client headers
+/// are forwarded upstream. Failures, and responses the cache policy would not
+/// store, leave the stale entry in place until its hard TTL.
+async fn refresh_cached_completion(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    request: ChatCompletionRequest,
+    request_id: String,
+    tags: Vec<(String, String)>,
+    pinned_provider: Option<&'static str>,
+    strategy: RouteStrategy,
+) -> Option<llm_edge_cache::l1::CachedResponse> {
+    let route = explain_route(&state, &request.model, pinned_provider, strategy).await;
+    let (provider, provider_name) = route.into_provider(&state).ok()?;
+    let (unified_request, _) = request_for_provider(
+        &state,
+        &headers,
+        &request,
+        &convert_to_unified(&request),
+        &provider,
+        &provider_name,
+        &request_id,
+    )
+    .ok()?;
+
+    let provider_start = Instant::now();
+    let response = match tokio::time::timeout(
+        state.config.request_timeout(),
+        provider.send(unified_request),
+    )
+    .await
+    {
+        Ok(Ok(response)) if check_content_filter(&response).is_ok() => response,
+        _ => {
+            warn!(
+                request_id = %request_id,
+                provider = %provider_name,
+                model = %request.model,
+                "Background refresh of stale cache entry failed"
+            );
+            return None;
+        }
+    };
+
+    metrics::record_request_success(
+        &provider_name,
+        &request.model,
+        provider_start.elapsed().as_millis() as u64,
+    );
+    metrics::record_token_usage(
+        &provider_name,
+        &request.model,
+        response.usage.prompt_tokens,
+        response.usage.completion_tokens,
+        &tags,
+    );
+    if let Some(cost) = calculate_cost(&provider, &request.model, &response.usage) {
+        metrics::record_cost(&provider_name, &request.model, cost.total(), &tags);
+    }
+
+    if let Some(reason) = state.config.cache_policy.skip_reason(&request, &response) {
+        debug!(reason, "Refreshed response not cached by cache policy");
+        return None;
+    }
+    Some(convert_provider_to_cache(&response))
+}
+
 /// Convert provider response to cache format
 fn convert_provider_to_cache(response: &UnifiedResponse) -> llm_edge_cache::l1::CachedResponse {
     let content = response
@@ -428,30 +1490,60 @@ fn convert_provider_to_cache(response: &UnifiedResponse) -> llm_edge_cache::l1::
 }
 
 /// Build response from provider data
+///
+/// The result only carries fields from OpenAI's chat completion schema, so
+/// SDKs parse it the same whichever provider answered; provider-specific
+/// values go under `metadata`.
 fn build_response_from_provider(
     request: &ChatCompletionRequest,
     provider_response: UnifiedResponse,
     provider_name: &str,
     latency_ms: u64,
-    cost_usd: Option<f64>,
+    cost: Option<CostBreakdown>,
+    max_tokens: Option<u32>,
+    cache_key_version: u32,
 ) -> ChatCompletionResponse {
-    ChatCompletionResponse {
-        id: provider_response.id,
-        object: "chat.completion".to_string(),
-        created: chrono::Utc::now().timestamp(),
-        model: request.model.clone(),
-        choices: provider_response
-            .choices
-            .into_iter()
-            .map(|c| ChatChoice {
+    let native_finish_reason = provider_response
+        .choices
+        .first()
+        .and_then(|c| c.native_finish_reason.clone());
+
+    // OpenAI completions always come from the assistant; keep any other
+    // native role (e.g. Gemini's `model`) under metadata instead
+    let mut native_roles = Vec::new();
+    let choices = provider_response
+        .choices
+        .into_iter()
+        .map(|c| {
+            if c.message.role != "assistant" {
+                native_roles.push(serde_json::json!({
+                    "index": c.index,
+                    "role": c.message.role,
+                }));
+            }
+            ChatChoice {
                 index: c.index as u32,
                 message: ChatMessage {
-                    role: c.message.role,
+                    role: "assistant".to_string(),
                     content: c.message.content,
                 },
-                finish_reason: c.finish_reason.unwrap_or_else(|| "stop".to_string()),
-            })
-            .collect(),
+                finish_reason: c.finish_reason.unwrap_or(FinishReason::Stop),
+                logprobs: c.logprobs,
+            }
+        })
+        .collect();
+
+    let mut provider_extras = serde_json::Map::new();
+    if !native_roles.is_empty() {
+        provider_extras.insert("native_roles".to_string(), native_roles.into());
+    }
+
+    ChatCompletionResponse {
+        id: provider_response.id,
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: request.model.clone(),
+        choices,
         usage: Usage {
             prompt_tokens: provider_response.usage.prompt_tokens as u32,
             completion_tokens: provider_response.usage.completion_tokens as u32,
@@ -462,7 +1554,13 @@ fn build_response_from_provider(
             cached: false,
             cache_tier: None,
             latency_ms,
-            cost_usd,
+            cost_usd: cost.map(|c| c.total()),
+            input_cost_usd: cost.map(|c| c.input),
+            output_cost_usd: cost.map(|c| c.output),
+            cache_key_version: Some(cache_key_version),
+            max_tokens,
+            native_finish_reason,
+            provider_extras,
         }),
     }
 }
@@ -470,6 +1568,8 @@ fn build_response_from_provider(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use llm_edge_providers::adapter::HealthStatus;
+    use llm_edge_providers::error_code;
 
     #[test]
     fn test_validate_request_valid() {
@@ -481,10 +1581,16 @@ mod tests {
             }],
             temperature: Some(0.7),
             max_tokens: Some(100),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
             stream: false,
         };
 
-        assert!(validate_request(&request).is_ok());
+        assert!(validate_request(&request, &AppConfig::default()).is_ok());
     }
 
     #[test]
@@ -497,10 +1603,16 @@ mod tests {
             }],
             temperature: None,
             max_tokens: None,
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
             stream: false,
         };
 
-        assert!(validate_request(&request).is_err());
+        assert!(validate_request(&request, &AppConfig::default()).is_err());
     }
 
     #[test]
@@ -510,10 +1622,16 @@ mod tests {
             messages: vec![],
             temperature: None,
             max_tokens: None,
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
             stream: false,
         };
 
-        assert!(validate_request(&request).is_err());
+        assert!(validate_request(&request, &AppConfig::default()).is_err());
     }
 
     #[test]
@@ -532,6 +1650,12 @@ mod tests {
             ],
             temperature: Some(0.7),
             max_tokens: Some(100),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
             stream: false,
         };
 
@@ -540,4 +1664,1653 @@ mod tests {
         assert_eq!(cacheable.temperature, Some(0.7));
         assert_eq!(cacheable.max_tokens, Some(100));
     }
+
+    #[test]
+    fn test_response_format_changes_cache_key() {
+        use llm_edge_cache::key::generate_cache_key;
+
+        let text = request_for("gpt-4", Some(16));
+        let mut json = text.clone();
+        json.response_format = Some(ResponseFormat::JsonObject);
+
+        assert_ne!(
+            generate_cache_key(&convert_to_cacheable(&text)),
+            generate_cache_key(&convert_to_cacheable(&json))
+        );
+        assert_eq!(
+            convert_to_unified(&json).response_format,
+            Some(ResponseFormat::JsonObject)
+        );
+    }
+
+    #[test]
+    fn test_user_forwarded_but_not_in_cache_key() {
+        use llm_edge_cache::key::generate_cache_key;
+        use llm_edge_providers::openai::OpenAIAdapter;
+
+        let anonymous = request_for("gpt-4", Some(16));
+        let mut identified = anonymous.clone();
+        identified.user = Some("user-1234".to_string());
+
+        assert_eq!(
+            generate_cache_key(&convert_to_cacheable(&anonymous)),
+            generate_cache_key(&convert_to_cacheable(&identified))
+        );
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let body = |request: &ChatCompletionRequest| {
+            serde_json::to_value(adapter.build_request_body(&convert_to_unified(request))).unwrap()
+        };
+        assert_eq!(body(&identified)["user"], "user-1234");
+        assert!(body(&anonymous).get("user").is_none());
+    }
+
+    fn validation_body(request: &ChatCompletionRequest) -> serde_json::Value {
+        let (status, error) = validate_request(request, &AppConfig::default())
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        error
+    }
+
+    #[test]
+    fn test_validation_error_param_for_empty_model() {
+        let error = validation_body(&request_for("", Some(16)));
+
+        assert_eq!(error["type"], "invalid_request_error");
+        assert_eq!(error["param"], "model");
+        assert!(error["code"].is_null());
+        assert_eq!(error["message"], "Model is required");
+    }
+
+    #[test]
+    fn test_validation_error_param_for_out_of_range_values() {
+        let mut request = request_for("gpt-4", Some(16));
+        request.temperature = Some(3.5);
+        assert_eq!(validation_body(&request)["param"], "temperature");
+
+        let request = request_for("gpt-4", Some(0));
+        assert_eq!(validation_body(&request)["param"], "max_tokens");
+    }
+
+    #[test]
+    fn test_non_validation_errors_keep_openai_shape() {
+        let (status, error) = ProxyError::ProviderError("boom".to_string()).into_parts();
+
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(error["type"], "proxy_error");
+        assert!(error["param"].is_null());
+        assert!(error["code"].is_null());
+    }
+
+    #[test]
+    fn test_provider_errors_answered_per_mapping_table() {
+        let errors = [
+            ProviderError::RateLimitExceeded,
+            ProviderError::InvalidApiKey,
+            ProviderError::ModelNotFound("gpt-9".to_string()),
+            ProviderError::Timeout,
+            ProviderError::InvalidRequest("messages must not be empty".to_string()),
+            ProviderError::UnsupportedParameter {
+                param: "logprobs".to_string(),
+                message: "not supported".to_string(),
+            },
+            ProviderError::ContentFiltered {
+                provider: "openai".to_string(),
+                details: "content_filter".to_string(),
+            },
+            ProviderError::Configuration("no API key".to_string()),
+            ProviderError::Internal("boom".to_string()),
+            ProviderError::ApiError {
+                status: 503,
+                message: "overloaded".to_string(),
+            },
+        ];
+
+        for e in errors {
+            let (expected_status, expected_code) = (error_to_status(&e), error_code(&e));
+            let description = e.to_string();
+            let (status, error) = ProxyError::from(e).into_parts();
+
+            assert_eq!(status, expected_status, "{description}");
+            assert_eq!(error["code"].as_str(), expected_code, "{description}");
+        }
+
+        let (_, error) = ProxyError::from(ProviderError::UnsupportedParameter {
+            param: "logprobs".to_string(),
+            message: "not supported".to_string(),
+        })
+        .into_parts();
+        assert_eq!(error["param"], "logprobs");
+    }
+
+    fn request_for(model: &str, max_tokens: Option<u32>) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: None,
+            max_tokens,
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    #[test]
+    fn test_resolve_max_tokens_defaults_per_model() {
+        let openai: Arc<dyn LLMProvider> = Arc::new(
+            llm_edge_providers::openai::OpenAIAdapter::new("test".to_string()),
+        );
+        let anthropic: Arc<dyn LLMProvider> = Arc::new(
+            llm_edge_providers::anthropic::AnthropicAdapter::new("test".to_string()),
+        );
+
+        assert_eq!(
+            resolve_max_tokens(&request_for("gpt-4", None), &openai, 0.5),
+            Some(2048)
+        );
+        assert_eq!(
+            resolve_max_tokens(&request_for("gpt-3.5-turbo", None), &openai, 0.25),
+            Some(1024)
+        );
+        assert_eq!(
+            resolve_max_tokens(
+                &request_for("claude-3-5-sonnet-20241022", None),
+                &anthropic,
+                0.5
+            ),
+            Some(4096)
+        );
+        assert_eq!(
+            resolve_max_tokens(&request_for("unknown-model", None), &openai, 0.5),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_max_tokens_keeps_explicit_value() {
+        let openai: Arc<dyn LLMProvider> = Arc::new(
+            llm_edge_providers::openai::OpenAIAdapter::new("test".to_string()),
+        );
+
+        assert_eq!(
+            resolve_max_tokens(&request_for("gpt-4", Some(100)), &openai, 0.5),
+            Some(100)
+        );
+        assert_eq!(
+            resolve_max_tokens(&request_for("unknown-model", Some(32)), &openai, 0.5),
+            Some(32)
+        );
+    }
+
+    /// Provider with a fixed health status that records the model and user
+    /// it was sent
+    struct PinProbe {
+        name: &'static str,
+        status: HealthStatus,
+        finish_reason: &'static str,
+        calls: std::sync::atomic::AtomicUsize,
+        last_model: parking_lot::Mutex<Option<String>>,
+        last_user: parking_lot::Mutex<Option<String>>,
+        /// Model named in responses, like an adapter with a model map
+        upstream_model: parking_lot::Mutex<Option<String>>,
+    }
+
+    impl PinProbe {
+        fn new(name: &'static str, status: HealthStatus) -> Arc<Self> {
+            Self::finishing_with(name, status, "stop")
+        }
+
+        fn finishing_with(
+            name: &'static str,
+            status: HealthStatus,
+            finish_reason: &'static str,
+        ) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                status,
+                finish_reason,
+                calls: Default::default(),
+                last_model: Default::default(),
+                last_user: Default::default(),
+                upstream_model: Default::default(),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for PinProbe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(
+            &self,
+            request: UnifiedRequest,
+        ) -> llm_edge_providers::ProviderResult<UnifiedResponse> {
+            *self.last_model.lock() = Some(request.model.clone());
+            *self.last_user.lock() = request.metadata.get(USER_ID_METADATA_KEY).cloned();
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: self.upstream_model.lock().clone().unwrap_or(request.model),
+                choices: vec![llm_edge_providers::types::Choice {
+                    index: 0,
+                    message: llm_edge_providers::Message {
+                        role: "assistant".to_string(),
+                        content: self.name.to_string(),
+                    },
+                    finish_reason: Some(FinishReason::from_native(self.finish_reason)),
+                    native_finish_reason: Some(self.finish_reason.to_string()),
+                    // Echo what was forwarded so tests can see it
+                    logprobs: request.logprobs.filter(|l| *l).map(|_| {
+                        serde_json::json!({ "content": [], "top_logprobs": request.top_logprobs })
+                    }),
+                    tool_calls: Vec::new(),
+                }],
+                usage: llm_edge_providers::Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: llm_edge_providers::types::ResponseMetadata {
+                    provider: self.name.to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<llm_edge_providers::adapter::PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            self.status.clone()
+        }
+    }
+
+    fn pin_state(openai: Arc<PinProbe>, anthropic: Arc<PinProbe>) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(crate::integration::AppConfig::default())
+                .with_openai(openai)
+                .with_anthropic(anthropic),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_response_and_cache_keep_client_model_name() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        *openai.upstream_model.lock() = Some("prod-gpt4-deployment".to_string());
+        let state = pin_state(
+            openai.clone(),
+            PinProbe::new("anthropic", HealthStatus::Healthy),
+        );
+        let request = request_for("gpt-4", Some(16));
+
+        let Json(response) = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            None,
+            Json(request.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.model, "gpt-4");
+
+        // Stored under the client-facing name, and served back with it
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let Json(cached) =
+            handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+                .await
+                .unwrap();
+        assert_eq!(openai.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(cached.model, "gpt-4");
+        assert!(cached.metadata.unwrap().cached);
+    }
+
+    #[tokio::test]
+    async fn test_model_prefix_pins_provider_and_is_stripped() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Healthy);
+        let state = pin_state(openai.clone(), anthropic.clone());
+
+        let Json(response) = handle_chat_completions(
+            State(state),
+            HeaderMap::new(),
+            None,
+            Json(request_for("anthropic/claude-3-5-sonnet", Some(16))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.model, "claude-3-5-sonnet");
+        assert_eq!(response.metadata.unwrap().provider, "anthropic");
+        assert_eq!(
+            anthropic.last_model.lock().as_deref(),
+            Some("claude-3-5-sonnet")
+        );
+        assert!(openai.last_model.lock().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_user_derived_from_api_key_when_enabled() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig {
+                derive_user_from_api_key: true,
+                ..Default::default()
+            })
+            .with_openai(openai.clone()),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer sk-team-a".parse().unwrap());
+
+        let mut request = request_for("gpt-4", Some(16));
+        let _ = handle_chat_completions(
+            State(state.clone()),
+            headers.clone(),
+            None,
+            Json(request.clone()),
+        )
+        .await
+        .unwrap();
+        let derived = openai.last_user.lock().clone().unwrap();
+        assert_eq!(derived, api_key_identity("sk-team-a"));
+        assert!(!derived.contains("sk-team-a"));
+
+        // A user set by the client wins
+        request.user = Some("user-1234".to_string());
+        request.messages[0].content = "Hello again".to_string();
+        let _ = handle_chat_completions(State(state), headers, None, Json(request))
+            .await
+            .unwrap();
+        assert_eq!(openai.last_user.lock().as_deref(), Some("user-1234"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_logprobs_forwarded_and_never_cached() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Healthy);
+        let state = pin_state(openai.clone(), anthropic);
+
+        let mut request = request_for("gpt-4", Some(16));
+        request.logprobs = Some(true);
+        request.top_logprobs = Some(3);
+
+        for _ in 0..2 {
+            let Json(response) = handle_chat_completions(
+                State(state.clone()),
+                HeaderMap::new(),
+                None,
+                Json(request.clone()),
+            )
+            .await
+            .unwrap();
+            let logprobs = response.choices[0].logprobs.as_ref().unwrap();
+            assert_eq!(logprobs["top_logprobs"], 3);
+        }
+        // Paused clock: lets any cache write land without waiting
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // Nothing was written, so the same prompt without logprobs misses too
+        let Json(response) = handle_chat_completions(
+            State(state),
+            HeaderMap::new(),
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .unwrap();
+        assert!(response.choices[0].logprobs.is_none());
+        assert_eq!(openai.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_max_messages_boundary() {
+        let config = AppConfig {
+            max_messages: 3,
+            ..AppConfig::default()
+        };
+        let mut request = request_for("gpt-4", None);
+        request.messages = vec![request.messages[0].clone(); 3];
+        assert!(validate_request(&request, &config).is_ok());
+
+        request.messages.push(request.messages[0].clone());
+        let (status, error) = validate_request(&request, &config)
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["param"], "messages");
+        assert!(error["message"]
+            .as_str()
+            .unwrap()
+            .contains("4 exceeds the limit of 3"));
+    }
+
+    #[test]
+    fn test_max_total_prompt_chars_boundary() {
+        let config = AppConfig {
+            max_total_prompt_chars: 10,
+            ..AppConfig::default()
+        };
+        // "Hello" twice is exactly 10 characters; multi-byte characters count once
+        let mut request = request_for("gpt-4", None);
+        request.messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: "héllo".to_string(),
+        });
+        assert!(validate_request(&request, &config).is_ok());
+
+        request.messages[1].content.push('!');
+        let (status, error) = validate_request(&request, &config)
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["param"], "messages");
+        assert!(error["message"].as_str().unwrap().contains("11 characters"));
+    }
+
+    #[test]
+    fn test_top_logprobs_requires_logprobs() {
+        let mut request = request_for("gpt-4", None);
+        request.top_logprobs = Some(3);
+        assert_eq!(validation_body(&request)["param"], "top_logprobs");
+
+        request.logprobs = Some(true);
+        assert!(validate_request(&request, &AppConfig::default()).is_ok());
+
+        request.top_logprobs = Some(21);
+        assert_eq!(validation_body(&request)["param"], "top_logprobs");
+    }
+
+    #[tokio::test]
+    async fn test_provider_header_overrides_routing() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Healthy);
+        let state = pin_state(openai.clone(), anthropic.clone());
+
+        // gpt-* would normally route to OpenAI
+        let mut headers = HeaderMap::new();
+        headers.insert(PROVIDER_HEADER, "anthropic".parse().unwrap());
+
+        let Json(response) = handle_chat_completions(
+            State(state),
+            headers,
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.metadata.unwrap().provider, "anthropic");
+        assert!(openai.last_model.lock().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_pinned_provider_returns_503() {
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Unhealthy);
+        let state = pin_state(openai.clone(), anthropic);
+
+        let result = handle_chat_completions(
+            State(state),
+            HeaderMap::new(),
+            None,
+            Json(request_for("anthropic/claude-3-opus", Some(16))),
+        )
+        .await;
+
+        let err = result.expect_err("pinned provider is unhealthy");
+        assert_eq!(err.into_parts().0, StatusCode::SERVICE_UNAVAILABLE);
+        // No silent fallback
+        assert!(openai.last_model.lock().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_response_format_rejected_before_provider_call() {
+        let state = Arc::new(AppState::new(AppConfig::default()).with_anthropic(Arc::new(
+            llm_edge_providers::anthropic::AnthropicAdapter::new("test".to_string()),
+        )));
+        let mut request = request_for("claude-3-opus-20240229", Some(16));
+        request.response_format = Some(
+            serde_json::from_value(serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "tags", "schema": {"type": "array"}}
+            }))
+            .unwrap(),
+        );
+
+        let err = handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+            .await
+            .unwrap_err();
+
+        let (status, body) = err.into_parts();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["param"], "response_format");
+        assert!(body["message"].as_str().unwrap().contains("'tags'"));
+    }
+
+    #[tokio::test]
+    async fn test_batches_reject_streaming() {
+        let state = Arc::new(AppState::new(AppConfig::default()));
+        let request = ChatCompletionRequest {
+            stream: true,
+            ..request_for("gpt-4", Some(16))
+        };
+
+        let err = handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+            .await
+            .expect_err("streams are answered by chat_completions");
+
+        assert!(matches!(
+            err,
+            ProxyError::ValidationError { ref param, .. } if param.as_deref() == Some("stream")
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_filtered_completion_is_content_policy_violation() {
+        for (name, reason, model) in [
+            ("openai", "content_filter", "gpt-4"),
+            ("anthropic", "refusal", "claude-3-opus"),
+        ] {
+            let probe = PinProbe::finishing_with(name, HealthStatus::Healthy, reason);
+            let other = PinProbe::new("other", HealthStatus::Healthy);
+            let state = if name == "openai" {
+                pin_state(probe.clone(), other.clone())
+            } else {
+                pin_state(other.clone(), probe.clone())
+            };
+
+            for _ in 0..2 {
+                let err = handle_chat_completions(
+                    State(state.clone()),
+                    HeaderMap::new(),
+                    None,
+                    Json(request_for(model, Some(16))),
+                )
+                .await
+                .expect_err("filtered completion must fail");
+
+                assert!(matches!(err, ProxyError::ContentPolicyViolation(_)));
+                let (status, error) = err.into_parts();
+                assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+                assert_eq!(error["code"], "content_policy_violation");
+                // Paused clock: lets any cache write land without waiting
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+
+            // One upstream call per request: no retry, no fallback, nothing cached
+            assert_eq!(probe.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+            assert!(other.last_model.lock().is_none());
+        }
+    }
+
+    /// Provider answering every request with 429, its window resetting in
+    /// `reset_in`
+    struct Throttled {
+        name: &'static str,
+        reset_in: Option<std::time::Duration>,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Throttled {
+        fn new(name: &'static str, reset_in: Option<std::time::Duration>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                reset_in,
+                calls: Default::default(),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for Throttled {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(
+            &self,
+            _request: UnifiedRequest,
+        ) -> llm_edge_providers::ProviderResult<UnifiedResponse> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(ProviderError::ApiError {
+                status: 429,
+                message: "Rate limit reached for requests".to_string(),
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<llm_edge_providers::adapter::PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+
+        fn rate_limit_state(&self) -> Option<llm_edge_providers::RateLimitState> {
+            Some(llm_edge_providers::RateLimitState {
+                remaining: 0,
+                reset_at: self.reset_in.map(|delay| Instant::now() + delay),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_providers_return_429_with_retry_after() {
+        let secs = std::time::Duration::from_secs;
+        for (openai_reset, anthropic_reset, retry_after) in [
+            // The soonest reset of any provider
+            (
+                Some(std::time::Duration::from_millis(29_500)),
+                Some(secs(45)),
+                "30",
+            ),
+            (Some(secs(45)), Some(secs(10)), "10"),
+            (None, None, "1"),
+            // A daily quota is not passed on as a day-long wait
+            (Some(secs(86_400)), Some(secs(86_400)), "3600"),
+        ] {
+            let openai = Throttled::new("openai", openai_reset);
+            let anthropic = Throttled::new("anthropic", anthropic_reset);
+            let state = Arc::new(
+                AppState::new(AppConfig::default())
+                    .with_openai(openai.clone())
+                    .with_anthropic(anthropic.clone()),
+            );
+
+            for model in ["gpt-4", "claude-3-opus"] {
+                let err = handle_chat_completions(
+                    State(state.clone()),
+                    HeaderMap::new(),
+                    None,
+                    Json(request_for(model, Some(16))),
+                )
+                .await
+                .expect_err("every provider is rate limiting");
+
+                assert!(matches!(err, ProxyError::RateLimited { .. }));
+                let response = err.into_response();
+                assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+                assert_eq!(response.headers()[header::RETRY_AFTER], retry_after);
+            }
+
+            // Each request failed over to the other provider before giving up
+            assert_eq!(openai.calls(), 2);
+            assert_eq!(anthropic.calls(), 2);
+        }
+
+        let (_, error) = ProxyError::RateLimited {
+            message: "slow down".to_string(),
+            retry_after_secs: 5,
+        }
+        .into_parts();
+        assert_eq!(error["type"], "rate_limit_error");
+        assert_eq!(error["code"], CODE_RATE_LIMIT_EXCEEDED);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_provider_fails_over() {
+        // No reset reported, so routing still prefers it
+        let openai = Throttled::new("openai", None);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Healthy);
+        let state = Arc::new(
+            AppState::new(AppConfig::default())
+                .with_openai(openai.clone())
+                .with_anthropic(anthropic.clone()),
+        );
+
+        let Json(response) = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.choices[0].message.content, "anthropic");
+        assert_eq!(openai.calls(), 1);
+
+        // A pinned provider is never replaced
+        let mut headers = HeaderMap::new();
+        headers.insert(PROVIDER_HEADER, "openai".parse().unwrap());
+        let err = handle_chat_completions(
+            State(state),
+            headers,
+            None,
+            Json(request_for("gpt-4", Some(32))),
+        )
+        .await
+        .expect_err("pinned provider is rate limiting");
+        assert!(matches!(err, ProxyError::RateLimited { .. }));
+        assert_eq!(openai.calls(), 2);
+        assert_eq!(anthropic.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failover_stays_within_model_family() {
+        let openai = Throttled::new("openai", None);
+        let azure = PinProbe::new("azure", HealthStatus::Healthy);
+        let anthropic = PinProbe::new("anthropic", HealthStatus::Healthy);
+        let families = || {
+            llm_edge_routing::ModelFamilyRouter::new()
+                .with_provider(openai.clone(), [("gpt-4", "gpt-4")])
+                .with_provider(anthropic.clone(), [("claude-3-opus", "claude-3-opus")])
+        };
+        let state = |families| {
+            Arc::new(
+                AppState::new(AppConfig::default())
+                    .with_openai(openai.clone())
+                    .with_anthropic(anthropic.clone())
+                    .with_model_families(families),
+            )
+        };
+
+        // Spills over to the Azure deployment of gpt-4, under its name there
+        let with_azure =
+            families().with_provider(azure.clone(), [("gpt-4", "gpt4-prod-deployment")]);
+        let Json(response) = handle_chat_completions(
+            State(state(with_azure)),
+            HeaderMap::new(),
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.choices[0].message.content, "azure");
+        assert_eq!(response.model, "gpt-4");
+        assert_eq!(
+            azure.last_model.lock().as_deref(),
+            Some("gpt4-prod-deployment")
+        );
+        assert_eq!(openai.calls(), 1);
+
+        // Without a compatible alternative the request fails fast
+        let err = handle_chat_completions(
+            State(state(families())),
+            HeaderMap::new(),
+            None,
+            Json(request_for("gpt-4", Some(32))),
+        )
+        .await
+        .expect_err("no other provider serves gpt-4");
+        assert!(matches!(err, ProxyError::RateLimited { .. }));
+        assert_eq!(openai.calls(), 2);
+        assert_eq!(anthropic.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_stop_reasons_are_normalized() {
+        for (native, canonical) in [("end_turn", "stop"), ("max_tokens", "length")] {
+            let anthropic = PinProbe::finishing_with("anthropic", HealthStatus::Healthy, native);
+            let state = pin_state(PinProbe::new("openai", HealthStatus::Healthy), anthropic);
+
+            let Json(response) = handle_chat_completions(
+                State(state),
+                HeaderMap::new(),
+                None,
+                Json(request_for("claude-3-opus", Some(16))),
+            )
+            .await
+            .unwrap();
+
+            let body = serde_json::to_value(&response).unwrap();
+            assert_eq!(body["choices"][0]["finish_reason"], canonical);
+            assert_eq!(body["metadata"]["native_finish_reason"], native);
+        }
+    }
+
+    /// Response as the Anthropic adapter produces it
+    fn anthropic_response() -> UnifiedResponse {
+        UnifiedResponse {
+            id: "msg_01XFDUDYJgAACzvnptvVoYEL".to_string(),
+            model: "claude-3-opus-20240229".to_string(),
+            choices: vec![llm_edge_providers::types::Choice {
+                index: 0,
+                message: llm_edge_providers::Message {
+                    role: "assistant".to_string(),
+                    content: "Hello!".to_string(),
+                },
+                finish_reason: Some(FinishReason::from_native("end_turn")),
+                native_finish_reason: Some("end_turn".to_string()),
+                logprobs: None,
+                tool_calls: Vec::new(),
+            }],
+            usage: llm_edge_providers::Usage {
+                prompt_tokens: 10,
+                completion_tokens: 3,
+                total_tokens: 13,
+            },
+            metadata: llm_edge_providers::types::ResponseMetadata {
+                provider: "anthropic".to_string(),
+                cached: false,
+                latency_ms: 420,
+                cost_usd: Some(0.0003),
+                usage_estimated: false,
+            },
+        }
+    }
+
+    /// Field names of a JSON object, sorted
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn test_anthropic_response_has_only_openai_fields() {
+        let response = build_response_from_provider(
+            &request_for("claude-3-opus", Some(16)),
+            anthropic_response(),
+            "anthropic",
+            420,
+            Some(CostBreakdown {
+                input: 0.0001,
+                output: 0.0002,
+            }),
+            Some(16),
+            0,
+        );
+        let body = serde_json::to_value(&response).unwrap();
+
+        // `metadata` is the one extension, and holds everything provider-specific
+        for key in keys(&body) {
+            assert!(
+                ["id", "object", "created", "model", "choices", "usage", "metadata"].contains(&key),
+                "unexpected top-level field {key}"
+            );
+        }
+        for key in keys(&body["choices"][0]) {
+            assert!(
+                ["index", "message", "finish_reason", "logprobs"].contains(&key),
+                "unexpected choice field {key}"
+            );
+        }
+        assert_eq!(
+            keys(&body["choices"][0]["message"]),
+            vec!["content", "role"]
+        );
+        assert_eq!(
+            keys(&body["usage"]),
+            vec!["completion_tokens", "prompt_tokens", "total_tokens"]
+        );
+
+        assert_eq!(body["object"], "chat.completion");
+        assert_eq!(body["choices"][0]["finish_reason"], "stop");
+        assert_eq!(body["metadata"]["provider"], "anthropic");
+        assert_eq!(body["metadata"]["native_finish_reason"], "end_turn");
+        assert!(body["metadata"].get("provider_extras").is_none());
+    }
+
+    #[test]
+    fn test_cost_split_by_token_type() {
+        let provider: Arc<dyn LLMProvider> = Arc::new(
+            llm_edge_providers::anthropic::AnthropicAdapter::new("test".to_string()),
+        );
+        let mut provider_response = anthropic_response();
+        provider_response.usage.prompt_tokens = 2000;
+        provider_response.usage.completion_tokens = 400;
+
+        // $0.015 per 1k input and $0.075 per 1k output tokens
+        let cost = calculate_cost(
+            &provider,
+            "claude-3-opus-20240229",
+            &provider_response.usage,
+        )
+        .unwrap();
+        assert!((cost.input - 0.03).abs() < 1e-9);
+        assert!((cost.output - 0.03).abs() < 1e-9);
+
+        let response = build_response_from_provider(
+            &request_for("claude-3-opus-20240229", Some(16)),
+            provider_response,
+            "anthropic",
+            420,
+            Some(cost),
+            Some(16),
+            0,
+        );
+        let metadata = serde_json::to_value(response.metadata.unwrap()).unwrap();
+        assert_eq!(metadata["input_cost_usd"], cost.input);
+        assert_eq!(metadata["output_cost_usd"], cost.output);
+        assert_eq!(metadata["cost_usd"], cost.total());
+        assert!((cost.total() - 0.06).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_non_assistant_role_moves_to_metadata() {
+        let mut provider_response = anthropic_response();
+        provider_response.choices[0].message.role = "model".to_string();
+
+        let response = build_response_from_provider(
+            &request_for("claude-3-opus", Some(16)),
+            provider_response,
+            "anthropic",
+            0,
+            None,
+            None,
+            0,
+        );
+        let body = serde_json::to_value(&response).unwrap();
+
+        assert_eq!(body["choices"][0]["message"]["role"], "assistant");
+        assert_eq!(
+            body["metadata"]["provider_extras"]["native_roles"],
+            serde_json::json!([{ "index": 0, "role": "model" }])
+        );
+    }
+
+    #[test]
+    fn test_provider_override_parsing() {
+        // Unknown prefixes are part of the model name
+        let mut request = request_for("meta-llama/llama-3-70b", None);
+        assert_eq!(
+            resolve_provider_override(&HeaderMap::new(), &mut request).unwrap(),
+            None
+        );
+        assert_eq!(request.model, "meta-llama/llama-3-70b");
+
+        // Unknown header value is a client error
+        let mut headers = HeaderMap::new();
+        headers.insert(PROVIDER_HEADER, "nope".parse().unwrap());
+        let mut request = request_for("gpt-4", None);
+        assert!(matches!(
+            resolve_provider_override(&headers, &mut request),
+            Err(ProxyError::ValidationError { .. })
+        ));
+
+        // Prefix and header must agree
+        headers.insert(PROVIDER_HEADER, "openai".parse().unwrap());
+        let mut request = request_for("anthropic/claude-3-haiku", None);
+        assert!(matches!(
+            resolve_provider_override(&headers, &mut request),
+            Err(ProxyError::ValidationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cache_ttl_header_parsing() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CACHE_TTL_HEADER, value.parse().unwrap());
+            headers
+        };
+
+        assert_eq!(parse_cache_ttl(&HeaderMap::new(), 3600).unwrap(), None);
+        assert_eq!(parse_cache_ttl(&headers("60"), 3600).unwrap(), Some(60));
+        // Clamped to the configured maximum
+        assert_eq!(
+            parse_cache_ttl(&headers("999999"), 3600).unwrap(),
+            Some(3600)
+        );
+
+        for invalid in ["0", "-5", "1.5", "soon", ""] {
+            let err = parse_cache_ttl(&headers(invalid), 3600).unwrap_err();
+            assert_eq!(err.into_parts().0, StatusCode::BAD_REQUEST, "{:?}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_cache_ttl_header_rejected_before_provider_call() {
+        let probe = PinProbe::new("openai", HealthStatus::Healthy);
+        let state = pin_state(
+            probe.clone(),
+            PinProbe::new("anthropic", HealthStatus::Healthy),
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_TTL_HEADER, "forever".parse().unwrap());
+        let err = handle_chat_completions(
+            State(state),
+            headers,
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .expect_err("invalid TTL must be rejected");
+
+        assert!(matches!(err, ProxyError::ValidationError { .. }));
+        assert_eq!(probe.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_cache_ttl_from_body_unless_header_set() {
+        let mut request = request_for("gpt-4", Some(16));
+        request.cache = Some(CacheDirectives {
+            ttl_seconds: Some(120),
+            ..CacheDirectives::default()
+        });
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(
+            resolve_cache_ttl(&headers, &request, 3600).unwrap(),
+            Some(120)
+        );
+        assert_eq!(resolve_cache_ttl(&headers, &request, 60).unwrap(), Some(60));
+
+        // The header wins over the body
+        headers.insert(CACHE_TTL_HEADER, "30".parse().unwrap());
+        assert_eq!(
+            resolve_cache_ttl(&headers, &request, 3600).unwrap(),
+            Some(30)
+        );
+
+        request.cache.as_mut().unwrap().ttl_seconds = Some(0);
+        let (status, error) = resolve_cache_ttl(&HeaderMap::new(), &request, 3600)
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["param"], "cache.ttl_seconds");
+    }
+
+    #[test]
+    fn test_cache_directives_not_forwarded_or_keyed() {
+        use llm_edge_cache::key::generate_cache_key;
+        use llm_edge_providers::openai::OpenAIAdapter;
+
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "cache": {"no_store": true, "ttl_seconds": 60}
+        }))
+        .unwrap();
+        // Fields left out of the object keep their defaults
+        assert_eq!(
+            request.cache,
+            Some(CacheDirectives {
+                enabled: true,
+                ttl_seconds: Some(60),
+                no_store: true,
+            })
+        );
+        let mut plain = request.clone();
+        plain.cache = None;
+
+        assert_eq!(
+            generate_cache_key(&convert_to_cacheable(&request)),
+            generate_cache_key(&convert_to_cacheable(&plain))
+        );
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let body = serde_json::to_value(adapter.build_request_body(&convert_to_unified(&request)))
+            .unwrap();
+        assert!(body.get("cache").is_none());
+    }
+
+    /// Send `request` once and report whether the provider was called and
+    /// whether the response ended up in the cache
+    async fn send_with_cache_directives(
+        state: &Arc<AppState>,
+        probe: &PinProbe,
+        request: ChatCompletionRequest,
+    ) -> (bool, bool) {
+        let calls_before = probe.calls.load(std::sync::atomic::Ordering::SeqCst);
+        let _ = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            None,
+            Json(request.clone()),
+        )
+        .await
+        .unwrap();
+        let called = probe.calls.load(std::sync::atomic::Ordering::SeqCst) > calls_before;
+
+        // Responses are stored in the background
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let cached = !matches!(
+            state
+                .cache_manager
+                .lookup(&convert_to_cacheable(&request))
+                .await,
+            CacheLookupResult::Miss
+        );
+        (called, cached)
+    }
+
+    #[tokio::test]
+    async fn test_cache_directives_in_body_control_lookup_and_store() {
+        let probe = PinProbe::new("openai", HealthStatus::Healthy);
+        let state = pin_state(
+            probe.clone(),
+            PinProbe::new("anthropic", HealthStatus::Healthy),
+        );
+        let with_cache = |content: &str, cache: CacheDirectives| {
+            let mut request = request_for("gpt-4", Some(16));
+            request.messages[0].content = content.to_string();
+            request.cache = Some(cache);
+            request
+        };
+
+        // no_store: answered by the provider, nothing cached
+        let no_store = CacheDirectives {
+            no_store: true,
+            ..CacheDirectives::default()
+        };
+        assert_eq!(
+            send_with_cache_directives(&state, &probe, with_cache("a", no_store)).await,
+            (true, false)
+        );
+
+        // Enabled: stored, and the next identical request is a hit
+        let enabled = with_cache("b", CacheDirectives::default());
+        assert_eq!(
+            send_with_cache_directives(&state, &probe, enabled.clone()).await,
+            (true, true)
+        );
+        assert_eq!(
+            send_with_cache_directives(&state, &probe, enabled).await,
+            (false, true)
+        );
+
+        // Disabled: the cached entry is ignored and the provider called again
+        let disabled = CacheDirectives {
+            enabled: false,
+            ..CacheDirectives::default()
+        };
+        assert_eq!(
+            send_with_cache_directives(&state, &probe, with_cache("b", disabled.clone())).await,
+            (true, true)
+        );
+        assert_eq!(
+            send_with_cache_directives(&state, &probe, with_cache("c", disabled)).await,
+            (true, false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_hit_served_then_refreshed_from_provider() {
+        use std::sync::atomic::{AtomicI64, Ordering};
+
+        let now = Arc::new(AtomicI64::new(chrono::Utc::now().timestamp()));
+        let clock = {
+            let now = now.clone();
+            Arc::new(move || now.load(Ordering::SeqCst))
+        };
+        let openai = PinProbe::new("openai", HealthStatus::Healthy);
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig::default())
+                .with_cache_manager(
+                    llm_edge_cache::CacheManager::new()
+                        .with_stale_while_revalidate(llm_edge_cache::StaleWhileRevalidate {
+                            soft_ttl_seconds: 60,
+                            hard_ttl_seconds: 600,
+                        })
+                        .with_clock(clock),
+                )
+                .with_openai(openai.clone()),
+        );
+
+        let request = request_for("gpt-4", Some(16));
+        state
+            .cache_manager
+            .store(
+                &convert_to_cacheable(&request),
+                llm_edge_cache::l1::CachedResponse {
+                    content: "From cache".to_string(),
+                    tokens: None,
+                    model: "gpt-4".to_string(),
+                    cached_at: now.load(Ordering::SeqCst),
+                },
+            )
+            .await;
+        now.fetch_add(120, Ordering::SeqCst);
+
+        let Json(response) =
+            handle_chat_completions(State(state.clone()), HeaderMap::new(), None, Json(request))
+                .await
+                .unwrap();
+        assert_eq!(response.choices[0].message.content, "From cache");
+        assert!(response.metadata.unwrap().cached);
+
+        // The provider is called in the background to refresh the entry
+        for _ in 0..50 {
+            if openai.calls.load(Ordering::SeqCst) == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(openai.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_reports_cache_key_version() {
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig::default())
+                .with_cache_manager(llm_edge_cache::CacheManager::new().with_key_version(2))
+                .with_openai(PinProbe::new("openai", HealthStatus::Healthy)),
+        );
+
+        let request = request_for("gpt-4", Some(16));
+        let cacheable = convert_to_cacheable(&request);
+        for cached in [false, true] {
+            let Json(response) = handle_chat_completions(
+                State(state.clone()),
+                HeaderMap::new(),
+                None,
+                Json(request.clone()),
+            )
+            .await
+            .unwrap();
+            let metadata = response.metadata.unwrap();
+            assert_eq!(metadata.cached, cached);
+            assert_eq!(metadata.cache_key_version, Some(2));
+
+            // The response is cached in the background
+            for _ in 0..50 {
+                if state.cache_manager.lookup(&cacheable).await.is_hit() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_cache_ttl_header_expires_l2_entry_early() {
+        use llm_edge_cache::{l2::L2Config, CacheManager};
+
+        let l2_config = L2Config {
+            key_prefix: format!("ttl-test-{}:", Uuid::new_v4()),
+            ..L2Config::default()
+        };
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig::default())
+                .with_cache_manager(CacheManager::with_l2(l2_config.clone()).await)
+                .with_openai(PinProbe::new("openai", HealthStatus::Healthy)),
+        );
+        assert!(state.cache_manager.has_l2(), "Redis not available");
+
+        let mut short = request_for("gpt-4", Some(16));
+        short.messages[0].content = "short-lived".to_string();
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_TTL_HEADER, "1".parse().unwrap());
+        let _ = handle_chat_completions(State(state.clone()), headers, None, Json(short.clone()))
+            .await
+            .unwrap();
+
+        let default = request_for("gpt-4", Some(16));
+        let _ =
+            handle_chat_completions(State(state), HeaderMap::new(), None, Json(default.clone()))
+                .await
+                .unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+
+        // A fresh manager has an empty L1, so lookups go to Redis
+        let fresh = CacheManager::with_l2(l2_config).await;
+        assert!(matches!(
+            fresh.lookup(&convert_to_cacheable(&short)).await,
+            CacheLookupResult::Miss
+        ));
+        assert!(matches!(
+            fresh.lookup(&convert_to_cacheable(&default)).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+
+        fresh.clear_all().await;
+    }
+
+    /// Collects `(kind, body)` from debug body trace events
+    #[derive(Clone, Default)]
+    struct BodyCapture(Arc<parking_lot::Mutex<Vec<(String, String)>>>);
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for BodyCapture {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            #[derive(Default)]
+            struct Fields {
+                kind: Option<String>,
+                body: Option<String>,
+            }
+
+            impl tracing::field::Visit for Fields {
+                fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+                    if field.name() == "kind" {
+                        self.kind = Some(value.to_string());
+                    }
+                }
+
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    if field.name() == "body" {
+                        self.body = Some(format!("{:?}", value));
+                    }
+                }
+            }
+
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            if let (Some(kind), Some(body)) = (fields.kind, fields.body) {
+                self.0.lock().push((kind, body));
+            }
+        }
+    }
+
+    fn traced_bodies(debug_body_logging: bool) -> (Arc<AppState>, ChatCompletionRequest) {
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig {
+                debug_body_logging,
+                ..crate::integration::AppConfig::default()
+            })
+            .with_openai(PinProbe::new("openai", HealthStatus::Healthy)),
+        );
+
+        let mut request = request_for("gpt-4", Some(16));
+        request.messages[0].content =
+            "Reach me at jane.doe@example.com, key sk-abcdefghijklmnopqrstuv".to_string();
+        (state, request)
+    }
+
+    #[tokio::test]
+    async fn test_bodies_not_logged_by_default() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = BodyCapture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let (state, request) = traced_bodies(false);
+        let _ = handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+            .await
+            .unwrap();
+
+        assert!(capture.0.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_enabled_body_logging_is_redacted() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = BodyCapture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let (state, request) = traced_bodies(true);
+        let _ = handle_chat_completions(State(state), HeaderMap::new(), None, Json(request))
+            .await
+            .unwrap();
+
+        let events = capture.0.lock();
+        let kinds: Vec<_> = events.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, vec!["request", "response"]);
+
+        let (_, request_body) = &events[0];
+        assert!(request_body.contains("[EMAIL_REDACTED]"));
+        assert!(request_body.contains("[API_KEY_REDACTED]"));
+        assert!(!request_body.contains("jane.doe@example.com"));
+        assert!(!request_body.contains("sk-abcdefghijklmnopqrstuv"));
+        assert!(events[1].1.contains("chat.completion"));
+    }
+
+    #[tokio::test]
+    async fn test_debug_trace_header_requires_admin_scope() {
+        use axum::{body::Body, routing::get, Extension, Router};
+        use std::collections::HashSet;
+        use tower::ServiceExt;
+
+        async fn sees_header(scopes: Option<GrantedScopes>) -> bool {
+            let app = Router::new()
+                .route(
+                    "/",
+                    get(|headers: HeaderMap| async move {
+                        headers.contains_key(DEBUG_TRACE_HEADER).to_string()
+                    }),
+                )
+                .route_layer(axum::middleware::from_fn(debug_trace_gate));
+            let app = match scopes {
+                Some(scopes) => app.layer(Extension(scopes)),
+                None => app,
+            };
+
+            let response = app
+                .oneshot(
+                    axum::http::Request::builder()
+                        .uri("/")
+                        .header(DEBUG_TRACE_HEADER, "1")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            body.as_ref() == b"true"
+        }
+
+        let only = |scope: &str| GrantedScopes::Only(HashSet::from([scope.to_string()]));
+
+        assert!(sees_header(Some(only(SCOPE_ADMIN))).await);
+        assert!(sees_header(Some(GrantedScopes::All)).await);
+        assert!(!sees_header(Some(only("inference"))).await);
+        assert!(!sees_header(None).await);
+    }
+
+    #[tokio::test]
+    async fn test_debug_trace_honoured_on_chat_route_for_admin_key() {
+        use axum::body::Body;
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+        use std::collections::HashMap;
+        use tower::ServiceExt;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let auth_config = llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: Vec::new(),
+                require_auth_for_health: false,
+                key_scopes: HashMap::from([
+                    (
+                        "ops-key".to_string(),
+                        vec![SCOPE_ADMIN.to_string(), SCOPE_INFERENCE.to_string()],
+                    ),
+                    ("app-key".to_string(), vec![SCOPE_INFERENCE.to_string()]),
+                ]),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        };
+
+        let traced_kinds = |key: &'static str| {
+            let auth_config = auth_config.clone();
+            async move {
+                let capture = BodyCapture::default();
+                let _guard = tracing::subscriber::set_default(
+                    tracing_subscriber::registry().with(capture.clone()),
+                );
+
+                let (state, request) = traced_bodies(false);
+                let limiter = RateLimiter::from_config(&auth_config.rate_limit);
+                let response = chat_routes(auth_config, limiter)
+                    .with_state(state)
+                    .oneshot(
+                        axum::http::Request::builder()
+                            .method("POST")
+                            .uri("/v1/chat/completions")
+                            .header("content-type", "application/json")
+                            .header("x-api-key", key)
+                            .header(DEBUG_TRACE_HEADER, "1")
+                            .body(Body::from(serde_json::to_vec(&request).unwrap()))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+
+                let kinds: Vec<String> = capture.0.lock().iter().map(|(k, _)| k.clone()).collect();
+                kinds
+            }
+        };
+
+        assert_eq!(traced_kinds("ops-key").await, ["request", "response"]);
+        assert!(traced_kinds("app-key").await.is_empty());
+    }
+
+    /// Span exporter keeping finished spans in memory
+    #[derive(Debug, Clone, Default)]
+    struct SpanCapture(Arc<parking_lot::Mutex<Vec<opentelemetry_sdk::export::trace::SpanData>>>);
+
+    impl opentelemetry_sdk::export::trace::SpanExporter for SpanCapture {
+        fn export(
+            &mut self,
+            batch: Vec<opentelemetry_sdk::export::trace::SpanData>,
+        ) -> futures::future::BoxFuture<'static, opentelemetry_sdk::export::trace::ExportResult>
+        {
+            self.0.lock().extend(batch);
+            Box::pin(std::future::ready(Ok(())))
+        }
+    }
+
+    /// Provider that sends a real OpenAI request upstream before answering
+    struct TracedUpstream {
+        adapter: llm_edge_providers::openai::OpenAIAdapter,
+        probe: Arc<PinProbe>,
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for TracedUpstream {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(
+            &self,
+            request: UnifiedRequest,
+        ) -> llm_edge_providers::ProviderResult<UnifiedResponse> {
+            self.adapter
+                .post("/chat/completions")
+                .json(&serde_json::json!({ "model": request.model }))
+                .send()
+                .await?;
+            self.probe.send(request).await
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<llm_edge_providers::adapter::PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    #[tokio::test]
+    async fn test_traceparent_parents_proxy_span_and_reaches_upstream() {
+        use tracing_subscriber::layer::SubscriberExt;
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+        const CALLER_SPAN_ID: &str = "00f067aa0ba902b7";
+
+        llm_edge_monitoring::tracing::init_propagation();
+        let spans = SpanCapture::default();
+        let tracer_provider = opentelemetry_sdk::trace::TracerProvider::builder()
+            .with_simple_exporter(spans.clone())
+            .build();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(
+            llm_edge_monitoring::tracing::context_layer(Some(&tracer_provider)),
+        ));
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let state = Arc::new(
+            AppState::new(crate::integration::AppConfig::default()).with_openai(Arc::new(
+                TracedUpstream {
+                    adapter: llm_edge_providers::openai::OpenAIAdapter::new("sk-test".to_string())
+                        .with_base_url(server.uri()),
+                    probe: PinProbe::new("openai", HealthStatus::Healthy),
+                },
+            )),
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TRACEPARENT_HEADER,
+            format!("00-{}-{}-01", TRACE_ID, CALLER_SPAN_ID)
+                .parse()
+                .unwrap(),
+        );
+        let _ = handle_chat_completions(
+            State(state),
+            headers,
+            None,
+            Json(request_for("gpt-4", Some(16))),
+        )
+        .await
+        .unwrap();
+
+        let proxy_span = spans
+            .0
+            .lock()
+            .iter()
+            .find(|span| span.name == "proxy_chat_completions")
+            .cloned()
+            .expect("proxy span exported");
+        assert_eq!(proxy_span.span_context.trace_id().to_string(), TRACE_ID);
+        assert_eq!(proxy_span.parent_span_id.to_string(), CALLER_SPAN_ID);
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0].headers.get(TRACEPARENT_HEADER).unwrap(),
+            format!("00-{}-{}-01", TRACE_ID, proxy_span.span_context.span_id()).as_str()
+        );
+    }
 }
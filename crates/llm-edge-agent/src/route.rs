@@ -0,0 +1,780 @@
+//! Provider selection
+//!
+//! [`explain_route`] is the only place that decides which provider serves a
+//! request. The proxy handler acts on its decision and
+//! `POST /admin/route/explain` returns it unchanged, so the explanation always
+//! matches what a real request would do.
+//!
+//! Providers whose rate-limit headers say their request quota is nearly used
+//! up are moved behind the other candidates until the quota resets, so
+//! traffic shifts away before they start answering `429`.
+//!
+//! When [`AppState::model_families`] knows the requested model, only the
+//! providers serving its family are candidates, each sent the model id it
+//! knows the family by (e.g. an Azure deployment name). A request never fails
+//! over to a provider of another family; with no compatible alternative it
+//! fails fast.
+//!
+//! The configured [`RouteStrategy`] decides the preference order. With
+//! `ALLOW_ROUTING_STRATEGY_HEADER`, a request can pick another one with
+//! `X-Routing-Strategy` for experiments.
+
+use axum::http::HeaderMap;
+use llm_edge_providers::{adapter::HealthStatus, LLMProvider};
+use llm_edge_routing::TranslatedModel;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, warn};
+
+use crate::integration::AppState;
+use crate::proxy::ProxyError;
+
+/// Candidate exclusion reason: the provider has no API key configured
+pub const EXCLUDED_NOT_CONFIGURED: &str = "not_configured";
+
+/// Candidate exclusion reason: the provider reported itself unhealthy
+pub const EXCLUDED_UNHEALTHY: &str = "unhealthy";
+
+/// Default remaining-request count at or below which a provider is tried last
+pub const DEFAULT_RATELIMIT_MIN_REMAINING: u64 = 1;
+
+/// Routing strategy used when the client pinned a provider
+pub const STRATEGY_PINNED: &str = "pinned";
+
+/// Routing strategy matching the model family, then falling back in order
+pub const STRATEGY_MODEL_FAMILY: &str = "model_family";
+
+/// Routing strategy preferring the provider with the cheapest pricing
+pub const STRATEGY_COST_OPTIMIZED: &str = "cost_optimized";
+
+/// Header choosing the routing strategy for this request
+///
+/// Only honoured when `ALLOW_ROUTING_STRATEGY_HEADER` is set, since it can
+/// change cost and behaviour.
+pub const ROUTING_STRATEGY_HEADER: &str = "x-routing-strategy";
+
+/// How candidates are ordered when no provider is pinned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RouteStrategy {
+    /// The provider matching the model family first, then the rest in order
+    #[default]
+    ModelFamily,
+    /// Cheapest input plus output price first; unpriced providers last
+    CostOptimized,
+}
+
+impl RouteStrategy {
+    /// Parse a strategy name; `-` and `_` are interchangeable
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            STRATEGY_MODEL_FAMILY => Some(RouteStrategy::ModelFamily),
+            STRATEGY_COST_OPTIMIZED => Some(RouteStrategy::CostOptimized),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RouteStrategy::ModelFamily => STRATEGY_MODEL_FAMILY,
+            RouteStrategy::CostOptimized => STRATEGY_COST_OPTIMIZED,
+        }
+    }
+
+    /// Load the default strategy from `ROUTING_STRATEGY`
+    pub fn from_env() -> Self {
+        let Ok(raw) = std::env::var("ROUTING_STRATEGY") else {
+            return Self::default();
+        };
+        Self::parse(&raw).unwrap_or_else(|| {
+            warn!(value = %raw, "Invalid ROUTING_STRATEGY, using model_family");
+            Self::default()
+        })
+    }
+}
+
+/// Strategy for a request: the `X-Routing-Strategy` override if allowed,
+/// otherwise the configured default
+///
+/// An unknown strategy name is rejected with `400`. Without
+/// `ALLOW_ROUTING_STRATEGY_HEADER` the header is ignored.
+pub fn resolve_strategy(
+    headers: &HeaderMap,
+    state: &AppState,
+) -> Result<RouteStrategy, ProxyError> {
+    let Some(value) = headers.get(ROUTING_STRATEGY_HEADER) else {
+        return Ok(state.config.routing_strategy);
+    };
+    if !state.config.allow_routing_strategy_header {
+        warn!("Ignoring X-Routing-Strategy: ALLOW_ROUTING_STRATEGY_HEADER is not set");
+        return Ok(state.config.routing_strategy);
+    }
+
+    let value = value.to_str().unwrap_or_default();
+    RouteStrategy::parse(value).ok_or_else(|| {
+        ProxyError::validation(format!(
+            "Unknown routing strategy '{}': expected {} or {}",
+            value.trim(),
+            STRATEGY_MODEL_FAMILY,
+            STRATEGY_COST_OPTIMIZED
+        ))
+    })
+}
+
+/// A provider considered for a request
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteCandidate {
+    pub provider: String,
+    /// Model id the provider is sent, if not the requested one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// `healthy`, `degraded` or `unhealthy`; absent if not configured
+    pub health: Option<&'static str>,
+    /// Time the health check took
+    pub health_check_latency_ms: Option<u64>,
+    pub input_cost_per_1k: Option<f64>,
+    pub output_cost_per_1k: Option<f64>,
+    /// Why the candidate was skipped, if it was
+    pub excluded: Option<&'static str>,
+    /// Requests left according to the provider's last rate-limit headers
+    pub ratelimit_remaining: Option<u64>,
+    /// Quota nearly used up until it resets, so the candidate is tried last
+    pub near_rate_limit: bool,
+}
+
+/// The routing decision for a request and how it was reached
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteExplanation {
+    pub model: String,
+    pub strategy: &'static str,
+    pub selected: Option<String>,
+    pub reason: String,
+    /// Candidates in preference order
+    pub candidates: Vec<RouteCandidate>,
+}
+
+impl RouteExplanation {
+    /// Eligible candidates other than the selected one, in the order they
+    /// would be tried if it is rate limiting
+    ///
+    /// Empty for a pinned provider, which is never replaced.
+    pub fn fallbacks(&self, state: &AppState) -> Vec<(Arc<dyn LLMProvider>, String)> {
+        if self.strategy == STRATEGY_PINNED {
+            return Vec::new();
+        }
+
+        let eligible = self
+            .candidates
+            .iter()
+            .filter(|c| c.excluded.is_none() && Some(&c.provider) != self.selected.as_ref());
+        eligible
+            .clone()
+            .filter(|c| !c.near_rate_limit)
+            .chain(eligible.filter(|c| c.near_rate_limit))
+            .filter_map(|c| Some((c.provider_for(state)?, c.provider.clone())))
+            .collect()
+    }
+
+    /// The selected provider, or the error a request would fail with
+    pub fn into_provider(
+        self,
+        state: &AppState,
+    ) -> Result<(Arc<dyn LLMProvider>, String), ProxyError> {
+        if let Some(name) = self.selected {
+            let selected = self.candidates.iter().find(|c| c.provider == name);
+            if let Some(provider) = selected.and_then(|c| c.provider_for(state)) {
+                return Ok((provider, name));
+            }
+        }
+
+        if self.strategy == STRATEGY_PINNED {
+            let candidate = &self.candidates[0];
+            let problem = match candidate.excluded {
+                Some(EXCLUDED_NOT_CONFIGURED) => "is not configured",
+                _ => "is unavailable",
+            };
+            return Err(ProxyError::ServiceUnavailable(format!(
+                "Requested provider '{}' {}",
+                candidate.provider, problem
+            )));
+        }
+
+        if self
+            .candidates
+            .iter()
+            .all(|c| c.excluded == Some(EXCLUDED_NOT_CONFIGURED))
+        {
+            return Err(ProxyError::InternalError(
+                "No providers configured".to_string(),
+            ));
+        }
+
+        Err(ProxyError::ServiceUnavailable(format!(
+            "No healthy provider available for model '{}'",
+            self.model
+        )))
+    }
+}
+
+impl RouteCandidate {
+    /// The provider, sending the candidate's model id if it has one
+    fn provider_for(&self, state: &AppState) -> Option<Arc<dyn LLMProvider>> {
+        let provider = provider_for(state, &self.provider)?;
+        Some(match &self.model {
+            Some(model) => TranslatedModel::wrap(provider, model.clone()),
+            None => provider,
+        })
+    }
+}
+
+/// Provider family implied by the model name
+fn model_family(model: &str) -> Option<&'static str> {
+    let model = model.to_lowercase();
+    if model.contains("gpt") || model.contains("openai") {
+        Some("openai")
+    } else if model.contains("claude") || model.contains("anthropic") {
+        Some("anthropic")
+    } else {
+        None
+    }
+}
+
+/// Configured provider registered under `name`
+pub fn provider_for(state: &AppState, name: &str) -> Option<Arc<dyn LLMProvider>> {
+    match name {
+        "openai" => state.openai_provider.clone(),
+        "anthropic" => state.anthropic_provider.clone(),
+        _ => state.model_families.provider(name),
+    }
+}
+
+/// Assess `name` for a request for `model`, sent to it as `model_id`
+async fn assess(state: &AppState, name: &str, model: &str, model_id: &str) -> RouteCandidate {
+    let translated = (model_id != model).then(|| model_id.to_string());
+    let Some(provider) = provider_for(state, name) else {
+        return RouteCandidate {
+            provider: name.to_string(),
+            model: translated,
+            health: None,
+            health_check_latency_ms: None,
+            input_cost_per_1k: None,
+            output_cost_per_1k: None,
+            excluded: Some(EXCLUDED_NOT_CONFIGURED),
+            ratelimit_remaining: None,
+            near_rate_limit: false,
+        };
+    };
+
+    let start = Instant::now();
+    let status = provider.health().await;
+    let health_check_latency_ms = start.elapsed().as_millis() as u64;
+    let pricing = provider.get_pricing(model_id);
+    let rate_limit = provider.rate_limit_state();
+    let near_rate_limit = rate_limit.is_some_and(|limit| {
+        limit.is_exhausted(state.config.ratelimit_min_remaining, Instant::now())
+    });
+
+    let (health, excluded) = match status {
+        HealthStatus::Healthy => ("healthy", None),
+        HealthStatus::Degraded => ("degraded", None),
+        HealthStatus::Unhealthy => ("unhealthy", Some(EXCLUDED_UNHEALTHY)),
+    };
+
+    RouteCandidate {
+        provider: name.to_string(),
+        model: translated,
+        health: Some(health),
+        health_check_latency_ms: Some(health_check_latency_ms),
+        input_cost_per_1k: pricing.as_ref().map(|p| p.input_cost_per_1k),
+        output_cost_per_1k: pricing.as_ref().map(|p| p.output_cost_per_1k),
+        excluded,
+        ratelimit_remaining: rate_limit.map(|limit| limit.remaining),
+        near_rate_limit,
+    }
+}
+
+/// Combined input and output price, if the provider has pricing for the model
+fn price_per_1k(candidate: &RouteCandidate) -> Option<f64> {
+    Some(candidate.input_cost_per_1k? + candidate.output_cost_per_1k?)
+}
+
+/// Decide which provider serves `model`
+///
+/// A pinned provider is the only candidate. Otherwise the candidates are the
+/// providers [`AppState::model_families`] registers for the model's family,
+/// in registration order; a model it does not know goes to the provider its
+/// name implies, or to every provider if it implies none. `strategy` then
+/// orders the candidates: by default the family order is kept. Degraded
+/// providers stay
+/// eligible, unhealthy or unconfigured ones are skipped. Providers near their
+/// rate limit are only chosen if nothing else is eligible.
+pub async fn explain_route(
+    state: &AppState,
+    model: &str,
+    pinned: Option<&'static str>,
+    strategy: RouteStrategy,
+) -> RouteExplanation {
+    let family_candidates = state.model_families.candidates(model);
+    let heuristic = model_family(model);
+    let family = family_candidates
+        .first()
+        .map(|(name, _)| *name)
+        .or(heuristic);
+    let order: Vec<(&str, &str)> = match (pinned, heuristic) {
+        (Some(name), _) => vec![(name, model)],
+        (None, _) if !family_candidates.is_empty() => family_candidates,
+        (None, Some(name)) if !state.model_families.is_empty() => vec![(name, model)],
+        (None, Some("anthropic")) => vec![("anthropic", model), ("openai", model)],
+        (None, _) => vec![("openai", model), ("anthropic", model)],
+    };
+
+    // Health checks run side by side, so a slow one does not add up
+    let mut candidates = futures::future::join_all(
+        order
+            .into_iter()
+            .map(|(name, model_id)| assess(state, name, model, model_id)),
+    )
+    .await;
+    if pinned.is_none() && strategy == RouteStrategy::CostOptimized {
+        candidates.sort_by(|a, b| match (price_per_1k(a), price_per_1k(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+    }
+
+    let eligible = || candidates.iter().filter(|c| c.excluded.is_none());
+    let selected = eligible()
+        .find(|c| !c.near_rate_limit)
+        .or_else(|| eligible().next())
+        .map(|c| c.provider.clone());
+    let preferred_near_rate_limit = candidates
+        .first()
+        .is_some_and(|c| c.excluded.is_none() && c.near_rate_limit);
+    if preferred_near_rate_limit {
+        debug!(
+            provider = %candidates[0].provider,
+            remaining = ?candidates[0].ratelimit_remaining,
+            "Provider near its rate limit; trying it last"
+        );
+    }
+
+    let reason = match (&selected, pinned) {
+        (Some(name), Some(_)) => format!("Provider '{}' pinned by the request", name),
+        (Some(name), None)
+            if strategy == RouteStrategy::CostOptimized && candidates[0].provider == *name =>
+        {
+            format!("Provider '{}' is the cheapest for model '{}'", name, model)
+        }
+        (Some(name), None) if preferred_near_rate_limit && candidates[0].provider != *name => {
+            format!(
+                "Preferred provider '{}' is near its rate limit; falling back to '{}'",
+                candidates[0].provider, name
+            )
+        }
+        (Some(name), None) if strategy == RouteStrategy::CostOptimized => format!(
+            "Cheaper provider '{}' was excluded; falling back to '{}'",
+            candidates[0].provider, name
+        ),
+        (Some(name), None) if family == Some(name.as_str()) => {
+            format!("Model '{}' belongs to provider '{}'", model, name)
+        }
+        (Some(name), None) => match family {
+            Some(preferred) => format!(
+                "Preferred provider '{}' was excluded; falling back to '{}'",
+                preferred, name
+            ),
+            None => format!(
+                "No provider matches model '{}'; falling back to '{}'",
+                model, name
+            ),
+        },
+        (None, _) => "Every candidate was excluded".to_string(),
+    };
+
+    RouteExplanation {
+        model: model.to_string(),
+        strategy: if pinned.is_some() {
+            STRATEGY_PINNED
+        } else {
+            strategy.name()
+        },
+        selected,
+        reason,
+        candidates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use async_trait::async_trait;
+    use llm_edge_providers::{
+        adapter::PricingInfo, ProviderResult, RateLimitState, RateLimitTracker, UnifiedRequest,
+        UnifiedResponse,
+    };
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    /// Provider with a fixed health status
+    struct StatusProbe(HealthStatus);
+
+    #[async_trait]
+    impl LLMProvider for StatusProbe {
+        fn name(&self) -> &str {
+            "probe"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            unimplemented!("routing never sends")
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            Some(PricingInfo {
+                input_cost_per_1k: 0.01,
+                output_cost_per_1k: 0.03,
+            })
+        }
+
+        async fn health(&self) -> HealthStatus {
+            self.0.clone()
+        }
+    }
+
+    /// Healthy provider whose health check takes this long to answer
+    struct SlowProbe(std::time::Duration);
+
+    #[async_trait]
+    impl LLMProvider for SlowProbe {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            unimplemented!("routing never sends")
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            tokio::time::sleep(self.0).await;
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Healthy provider reporting whatever rate-limit headers it was fed
+    #[derive(Default)]
+    struct QuotaProbe(RateLimitTracker);
+
+    impl QuotaProbe {
+        fn respond_with(&self, remaining: &'static str, reset: &'static str) {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-remaining", HeaderValue::from_static(remaining));
+            headers.insert("x-ratelimit-reset", HeaderValue::from_static(reset));
+            self.0.observe("openai", &headers);
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for QuotaProbe {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            unimplemented!("routing never sends")
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+
+        fn rate_limit_state(&self) -> Option<RateLimitState> {
+            self.0.current()
+        }
+    }
+
+    fn state(openai: Option<HealthStatus>, anthropic: Option<HealthStatus>) -> AppState {
+        let probe = |status: HealthStatus| Arc::new(StatusProbe(status)) as Arc<dyn LLMProvider>;
+        AppState {
+            openai_provider: openai.map(probe),
+            anthropic_provider: anthropic.map(probe),
+            ..AppState::new(AppConfig::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_family_preferred() {
+        let state = state(Some(HealthStatus::Healthy), Some(HealthStatus::Healthy));
+
+        let route = explain_route(
+            &state,
+            "claude-3-5-sonnet",
+            None,
+            RouteStrategy::ModelFamily,
+        )
+        .await;
+
+        assert_eq!(route.strategy, STRATEGY_MODEL_FAMILY);
+        assert_eq!(route.selected.as_deref(), Some("anthropic"));
+        assert_eq!(route.candidates.len(), 2);
+        assert!(route.candidates.iter().all(|c| c.excluded.is_none()));
+        assert_eq!(route.candidates[0].input_cost_per_1k, Some(0.01));
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_provider_skipped() {
+        let state = state(Some(HealthStatus::Unhealthy), Some(HealthStatus::Degraded));
+
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+
+        assert_eq!(route.selected.as_deref(), Some("anthropic"));
+        assert_eq!(route.candidates[0].excluded, Some(EXCLUDED_UNHEALTHY));
+        assert!(route.reason.contains("falling back"));
+        assert_eq!(route.into_provider(&state).unwrap().1, "anthropic");
+    }
+
+    #[tokio::test]
+    async fn test_no_eligible_provider_errors() {
+        let none = state(None, None);
+        let err = explain_route(&none, "gpt-4", None, RouteStrategy::ModelFamily)
+            .await
+            .into_provider(&none)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProxyError::InternalError(_)));
+
+        let unhealthy = state(Some(HealthStatus::Unhealthy), None);
+        let route = explain_route(&unhealthy, "gpt-4", None, RouteStrategy::ModelFamily).await;
+        assert_eq!(route.candidates[1].excluded, Some(EXCLUDED_NOT_CONFIGURED));
+        let err = route.into_provider(&unhealthy).err().unwrap();
+        assert!(matches!(err, ProxyError::ServiceUnavailable(_)));
+
+        let route = explain_route(
+            &unhealthy,
+            "gpt-4",
+            Some("anthropic"),
+            RouteStrategy::ModelFamily,
+        )
+        .await;
+        assert_eq!(route.strategy, STRATEGY_PINNED);
+        assert_eq!(route.candidates.len(), 1);
+        match route.into_provider(&unhealthy).err().unwrap() {
+            ProxyError::ServiceUnavailable(message) => assert!(message.contains("not configured")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_near_rate_limit_shifts_traffic_until_reset() {
+        let openai = Arc::new(QuotaProbe::default());
+        let mut state = state(None, Some(HealthStatus::Healthy));
+        state.openai_provider = Some(openai.clone());
+
+        openai.respond_with("100", "0.2");
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+        assert_eq!(route.candidates[0].ratelimit_remaining, Some(100));
+
+        openai.respond_with("0", "0.2");
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+        assert_eq!(route.selected.as_deref(), Some("anthropic"));
+        assert!(route.candidates[0].near_rate_limit);
+        assert!(route.candidates[0].excluded.is_none());
+        assert!(route.reason.contains("near its rate limit"));
+
+        // Still the only choice when nothing else is eligible
+        let route =
+            explain_route(&state, "gpt-4", Some("openai"), RouteStrategy::ModelFamily).await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+        assert!(!route.candidates[0].near_rate_limit);
+    }
+
+    /// Healthy provider answering with its own name at a fixed price
+    struct PricedProbe {
+        name: &'static str,
+        cost_per_1k: f64,
+    }
+
+    #[async_trait]
+    impl LLMProvider for PricedProbe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: Vec::new(),
+                usage: llm_edge_providers::Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: llm_edge_providers::types::ResponseMetadata {
+                    provider: self.name.to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            Some(PricingInfo {
+                input_cost_per_1k: self.cost_per_1k,
+                output_cost_per_1k: self.cost_per_1k,
+            })
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// OpenAI serving `gpt-4` at three times Anthropic's price
+    fn priced_state(config: AppConfig) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(config)
+                .with_openai(Arc::new(PricedProbe {
+                    name: "openai",
+                    cost_per_1k: 0.03,
+                }))
+                .with_anthropic(Arc::new(PricedProbe {
+                    name: "anthropic",
+                    cost_per_1k: 0.01,
+                })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_cost_optimized_prefers_cheapest_provider() {
+        let state = priced_state(AppConfig::default());
+
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::CostOptimized).await;
+        assert_eq!(route.strategy, STRATEGY_COST_OPTIMIZED);
+        assert_eq!(route.selected.as_deref(), Some("anthropic"));
+        assert_eq!(route.candidates[1].provider, "openai");
+        assert!(route.reason.contains("cheapest"));
+
+        // A pinned provider still wins
+        let route = explain_route(
+            &state,
+            "gpt-4",
+            Some("openai"),
+            RouteStrategy::CostOptimized,
+        )
+        .await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_health_checks_run_concurrently() {
+        let probe = || Arc::new(SlowProbe(std::time::Duration::from_secs(1)));
+        let state = AppState::new(AppConfig::default())
+            .with_openai(probe())
+            .with_anthropic(probe());
+
+        let start = tokio::time::Instant::now();
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::ModelFamily).await;
+
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+        // Both checks waited out the same second
+        assert_eq!(start.elapsed(), std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_cost_optimized_prices_adapters_by_the_model_they_run() {
+        use llm_edge_providers::{anthropic::AnthropicAdapter, openai::OpenAIAdapter};
+        use std::collections::HashMap;
+
+        // Anthropic serves `gpt-4` requests with Claude 3 Haiku
+        let state = AppState::new(AppConfig::default())
+            .with_openai(Arc::new(OpenAIAdapter::new("sk-test".to_string())))
+            .with_anthropic(Arc::new(
+                AnthropicAdapter::new("sk-ant-test".to_string()).with_model_map(HashMap::from([(
+                    "gpt-4".to_string(),
+                    "claude-3-haiku-20240307".to_string(),
+                )])),
+            ));
+
+        let route = explain_route(&state, "gpt-4", None, RouteStrategy::CostOptimized).await;
+        assert_eq!(route.selected.as_deref(), Some("anthropic"));
+        assert_eq!(route.candidates[0].input_cost_per_1k, Some(0.00025));
+        assert_eq!(route.candidates[1].provider, "openai");
+        assert_eq!(route.candidates[1].input_cost_per_1k, Some(0.03));
+
+        // Unmapped, both would run `gpt-3.5-turbo` at the same price, so
+        // the model family decides
+        let route =
+            explain_route(&state, "gpt-3.5-turbo", None, RouteStrategy::CostOptimized).await;
+        assert_eq!(route.selected.as_deref(), Some("openai"));
+        assert_eq!(
+            route.candidates[0].input_cost_per_1k,
+            route.candidates[1].input_cost_per_1k
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strategy_header_overrides_default_when_allowed() {
+        use crate::proxy::{handle_chat_completions, ChatCompletionRequest};
+        use axum::{extract::State, Json};
+
+        let provider_for = |state: Arc<AppState>, header: Option<&'static str>| async move {
+            let mut headers = HeaderMap::new();
+            if let Some(value) = header {
+                headers.insert(ROUTING_STRATEGY_HEADER, HeaderValue::from_static(value));
+            }
+            let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+                "model": "gpt-4",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "max_tokens": 16
+            }))
+            .unwrap();
+
+            handle_chat_completions(State(state), headers, None, Json(request))
+                .await
+                .map(|Json(response)| response.metadata.unwrap().provider)
+        };
+
+        let allowed = priced_state(AppConfig {
+            allow_routing_strategy_header: true,
+            ..AppConfig::default()
+        });
+        assert_eq!(provider_for(allowed.clone(), None).await.unwrap(), "openai");
+        assert_eq!(
+            provider_for(allowed.clone(), Some("cost-optimized"))
+                .await
+                .unwrap(),
+            "anthropic"
+        );
+
+        let (status, error) = provider_for(allowed, Some("cheapest"))
+            .await
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert!(error["message"].as_str().unwrap().contains("cheapest"));
+
+        // Ignored unless enabled
+        let default = priced_state(AppConfig::default());
+        assert_eq!(
+            provider_for(default, Some("cost-optimized")).await.unwrap(),
+            "openai"
+        );
+    }
+}
@@ -0,0 +1,596 @@
+//! HTTP router served by the binary
+//!
+//! Health and metrics endpoints are public (unless `AUTH_HEALTH_CHECK` is
+//! set for `/health/integrations`). `/v1/*` needs an API key with the
+//! `inference` scope and `/admin/*` one with the `admin` scope. CORS
+//! preflights are answered for every route, and a known path hit with an
+//! unsupported method gets a JSON `405`.
+
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+use tower_http::{decompression::RequestDecompressionLayer, limit::RequestBodyLimitLayer};
+
+use crate::admin::admin_routes;
+use crate::batch::batch_routes;
+use crate::concurrency::{limit_concurrency, ConcurrencyLimit};
+use crate::drain::{drain_routes, reject_while_draining, DrainSwitch};
+use crate::integration::{check_system_health, AppState, DetailedHealth};
+use crate::models::models_routes;
+use crate::proxy::chat_routes;
+use crate::upstream::{integration_health_routes, IntegrationHealthSource};
+
+/// Build the full application router
+///
+/// `drain` is flipped by `/admin/drain` and `/admin/undrain`.
+pub fn build_router(
+    state: Arc<AppState>,
+    integrations: Arc<dyn IntegrationHealthSource>,
+    proxy_config: &llm_edge_proxy::Config,
+    drain: DrainSwitch,
+) -> Router {
+    // /v1/* endpoints; new requests get 503 while draining. Chat and batch
+    // requests share each API key's rate limit bucket.
+    let limiter = llm_edge_proxy::middleware::create_rate_limiter(proxy_config);
+    let api = Router::new()
+        .merge(chat_routes(proxy_config.clone(), limiter.clone()))
+        .merge(batch_routes(proxy_config.clone(), limiter))
+        .merge(models_routes(proxy_config.clone()))
+        // Instant backpressure: 503 once MAX_CONCURRENT_REQUESTS are in flight
+        .layer(axum::middleware::from_fn_with_state(
+            ConcurrencyLimit::new(state.config.max_concurrent_requests),
+            limit_concurrency,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            drain.clone(),
+            reject_while_draining,
+        ));
+
+    Router::new()
+        // Health check endpoints
+        .route("/health", get(health_handler))
+        .route("/health/detailed", get(detailed_health_handler))
+        .route("/health/ready", get(readiness_handler))
+        .route("/health/live", get(liveness_handler))
+        // Upstream integration health (API key required if AUTH_HEALTH_CHECK is set)
+        .merge(integration_health_routes(
+            integrations,
+            proxy_config.clone(),
+        ))
+        // Metrics endpoint
+        .route("/metrics", get(metrics_handler))
+        // Main proxy endpoints (OpenAI-compatible)
+        .merge(api)
+        // Admin endpoints (API keys with the `admin` scope)
+        .merge(admin_routes(proxy_config.clone()))
+        .merge(drain_routes(drain, proxy_config.clone()))
+        // JSON 405 for known paths hit with an unsupported method
+        .method_not_allowed_fallback(llm_edge_proxy::server::routes::method_not_allowed)
+        // MAX_REQUEST_SIZE instead of axum's 2 MB default, which would
+        // otherwise cut requests off well before MAX_TOTAL_PROMPT_CHARS. The
+        // cap applies *after* decompression so a small gzip/deflate payload
+        // can't expand past it
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(
+            proxy_config.server.max_request_size,
+        ))
+        .layer(RequestDecompressionLayer::new())
+        // In-flight requests per matched route (llm_active_requests)
+        .layer(axum::middleware::from_fn(
+            llm_edge_proxy::middleware::track_active_requests,
+        ))
+        // Access log for sampled requests and every error
+        .layer(axum::middleware::from_fn_with_state(
+            llm_edge_proxy::middleware::AccessLogSampler::from_config(proxy_config),
+            llm_edge_proxy::middleware::access_log_middleware,
+        ))
+        // One request id (inbound X-Request-Id if trusted) for spans, logs and the response
+        .layer(axum::middleware::from_fn_with_state(
+            llm_edge_proxy::middleware::RequestIdPolicy::from_config(proxy_config),
+            llm_edge_proxy::middleware::request_id_middleware,
+        ))
+        // Outermost so OPTIONS preflights are answered before auth
+        .layer(llm_edge_proxy::server::cors_layer())
+        // Share application state with handlers
+        .with_state(state)
+}
+
+/// Health check handler
+async fn health_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let health = check_system_health(&state).await;
+
+    let providers: serde_json::Map<String, serde_json::Value> = health
+        .providers
+        .iter()
+        .map(|p| {
+            (
+                p.name.clone(),
+                serde_json::json!({ "configured": true, "healthy": p.healthy }),
+            )
+        })
+        .collect();
+
+    Json(serde_json::json!({
+        "status": health.status_string(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "cache": {
+            "l1_healthy": health.cache_l1_healthy,
+            "l2_healthy": health.cache_l2_healthy,
+            "l2_configured": health.cache_l2_configured,
+        },
+        "providers": providers,
+    }))
+}
+
+/// Detailed health handler
+async fn detailed_health_handler(State(state): State<Arc<AppState>>) -> Json<DetailedHealth> {
+    let health = check_system_health(&state).await;
+    Json(health.detailed())
+}
+
+/// Readiness check handler
+async fn readiness_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let health = check_system_health(&state).await;
+
+    // Degraded (e.g. L2 down) still serves traffic from L1 + providers
+    let ready = health.is_operational();
+
+    Json(serde_json::json!({
+        "ready": ready,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Liveness check handler
+async fn liveness_handler() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "alive": true,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Prometheus metrics handler
+async fn metrics_handler() -> String {
+    // Get the metrics handle from the global registry
+    // In a real implementation, we'd store this in the app state
+    // For now, return a basic response
+    "# Prometheus metrics\n# See /health for detailed status\n".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use async_trait::async_trait;
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use llm_edge_proxy::middleware::{SCOPE_ADMIN, SCOPE_INFERENCE};
+    use std::collections::{BTreeMap, HashMap};
+    use tower::ServiceExt;
+
+    /// Provider that answers every request with "ok"
+    struct OkProvider;
+
+    #[async_trait]
+    impl LLMProvider for OkProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "ok".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    struct NoIntegrations;
+
+    #[async_trait]
+    impl IntegrationHealthSource for NoIntegrations {
+        async fn integration_health(&self) -> BTreeMap<&'static str, bool> {
+            BTreeMap::new()
+        }
+    }
+
+    /// `legacy-key` has no scope mapping; `ops-key` has every scope;
+    /// `cheap-key` may only use `gpt-3.5-turbo*`
+    fn proxy_config() -> llm_edge_proxy::Config {
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+
+        llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: vec!["legacy-key".to_string(), "cheap-key".to_string()],
+                require_auth_for_health: false,
+                key_scopes: HashMap::from([(
+                    "ops-key".to_string(),
+                    vec![SCOPE_ADMIN.to_string(), SCOPE_INFERENCE.to_string()],
+                )]),
+                key_models: HashMap::from([(
+                    "cheap-key".to_string(),
+                    vec!["gpt-3.5-turbo*".to_string()],
+                )]),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    fn app(config: &llm_edge_proxy::Config) -> Router {
+        let state = Arc::new(AppState::new(AppConfig::default()).with_openai(Arc::new(OkProvider)));
+        build_router(state, Arc::new(NoIntegrations), config, DrainSwitch::new())
+    }
+
+    fn chat(key: Option<&str>, model: &str) -> Request<Body> {
+        let mut builder = Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json");
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        let body = serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "hi"}],
+        });
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    /// Chat request from `legacy-key` with one message of `chars` characters
+    fn chat_of_size(chars: usize) -> Request<Body> {
+        let body = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "x".repeat(chars)}],
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json")
+            .header("x-api-key", "legacy-key")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn keyed(method: &str, path: &str, key: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_prompt_cap_applies_before_the_body_limit() {
+        use crate::integration::DEFAULT_MAX_TOTAL_PROMPT_CHARS;
+
+        let router = app(&proxy_config());
+
+        // Over axum's 2 MB default body limit, within the prompt cap
+        let response = router
+            .clone()
+            .oneshot(chat_of_size(3 * 1024 * 1024))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Over the prompt cap, within MAX_REQUEST_SIZE
+        let response = router
+            .oneshot(chat_of_size(DEFAULT_MAX_TOTAL_PROMPT_CHARS + 1))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["param"], "messages");
+
+        // Bodies over MAX_REQUEST_SIZE never reach validation
+        let mut small = proxy_config();
+        small.server.max_request_size = 1024;
+        let response = app(&small).oneshot(chat_of_size(2048)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Chat request from `legacy-key` with a gzip-compressed `body`
+    fn gzip_chat(body: &[u8]) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .header("x-api-key", "legacy-key")
+            .body(Body::from(gzip(body)))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gzip_request_body_is_decompressed() {
+        let body = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "hi"}],
+        });
+
+        let response = app(&proxy_config())
+            .oneshot(gzip_chat(body.to_string().as_bytes()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_decompression_bomb_is_rejected() {
+        let mut config = proxy_config();
+        config.server.max_request_size = 64 * 1024;
+
+        // ~8MB of whitespace compresses to a few KB but expands far past the limit
+        let mut payload = serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "hi"}],
+        })
+        .to_string()
+        .into_bytes();
+        payload.extend(std::iter::repeat(b' ').take(8 * 1024 * 1024));
+        let request = gzip_chat(&payload);
+
+        let response = app(&config).oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_chat_route_requires_api_key() {
+        let app = app(&proxy_config());
+
+        let response = app.clone().oneshot(chat(None, "gpt-4")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app.oneshot(chat(Some("wrong-key"), "gpt-4")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_unmapped_key_is_limited_to_inference() {
+        let app = app(&proxy_config());
+
+        let response = app
+            .clone()
+            .oneshot(chat(Some("legacy-key"), "gpt-4"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for (method, path) in [
+            ("GET", "/admin/failures"),
+            ("GET", "/admin/cache/stats"),
+            ("POST", "/admin/drain"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(keyed(method, path, "legacy-key"))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", path);
+        }
+
+        let response = app
+            .oneshot(keyed("GET", "/admin/failures", "ops-key"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_model_allowlist_applied_on_chat_route() {
+        let app = app(&proxy_config());
+
+        let response = app
+            .clone()
+            .oneshot(chat(Some("cheap-key"), "gpt-3.5-turbo"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(chat(Some("cheap-key"), "gpt-4")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "model_not_allowed");
+    }
+
+    #[tokio::test]
+    async fn test_models_route_lists_only_allowed_models() {
+        let state = Arc::new(AppState::new(AppConfig::default()).with_openai(Arc::new(
+            llm_edge_providers::openai::OpenAIAdapter::new("sk-test".to_string()),
+        )));
+        let router = build_router(
+            state,
+            Arc::new(NoIntegrations),
+            &proxy_config(),
+            DrainSwitch::new(),
+        );
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/v1/models").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router
+            .oneshot(keyed("GET", "/v1/models", "cheap-key"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let ids: Vec<&str> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["gpt-3.5-turbo"]);
+    }
+
+    #[tokio::test]
+    async fn test_preflight_answered_without_api_key() {
+        use axum::http::{header, Method};
+
+        let response = app(&proxy_config())
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/v1/chat/completions")
+                    .header(header::ORIGIN, "https://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(
+                        header::ACCESS_CONTROL_REQUEST_HEADERS,
+                        "authorization,content-type,x-provider",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("POST"));
+        let allowed_headers = headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap();
+        assert!(allowed_headers.contains("authorization"));
+        assert!(allowed_headers.contains("x-provider"));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_method_returns_json_405() {
+        let response = app(&proxy_config())
+            .oneshot(keyed("GET", "/v1/chat/completions", "legacy-key"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "METHOD_NOT_ALLOWED");
+    }
+
+    #[tokio::test]
+    async fn test_chat_route_rate_limited_per_api_key() {
+        let mut config = proxy_config();
+        config.rate_limit.enabled = true;
+        config.rate_limit.requests_per_minute = 1;
+        let app = app(&config);
+
+        let first = app
+            .clone()
+            .oneshot(chat(Some("legacy-key"), "gpt-4"))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers()["x-ratelimit-remaining"], "0");
+
+        let limited = app
+            .clone()
+            .oneshot(chat(Some("legacy-key"), "gpt-4"))
+            .await
+            .unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers().contains_key("retry-after"));
+
+        // Another key still has its whole quota
+        let other = app
+            .oneshot(chat(Some("cheap-key"), "gpt-3.5-turbo"))
+            .await
+            .unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+}
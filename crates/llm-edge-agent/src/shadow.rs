@@ -0,0 +1,448 @@
+//! Shadow traffic for model migrations
+//!
+//! With `SHADOW_PROVIDER` set, a sampled fraction of successful requests is
+//! sent a second time to a candidate provider (and optionally model) in the
+//! background, after the client already has the primary response. Both
+//! responses are logged PII-redacted under the request id, and appended to
+//! `SHADOW_OUTPUT_PATH` as JSONL for offline quality comparison.
+//!
+//! The client never waits on or sees the shadow request. Its cost goes to
+//! `llm_edge_shadow_cost_micro_usd_total` rather than the regular cost metric.
+
+use llm_edge_monitoring::metrics;
+use llm_edge_providers::{UnifiedRequest, UnifiedResponse};
+use llm_edge_security::sanitize_log_data;
+use rand::Rng;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+use crate::integration::AppState;
+use crate::proxy::calculate_cost;
+use crate::route::provider_for;
+
+/// Longest response content kept in a comparison, in bytes
+const SHADOW_CONTENT_MAX_LENGTH: usize = 64 * 1024;
+
+/// Where shadow traffic goes and how much of it there is
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowConfig {
+    /// Candidate provider (`openai` or `anthropic`)
+    pub provider: String,
+
+    /// Model sent to the candidate; the primary request's model if `None`
+    pub model: Option<String>,
+
+    /// Fraction of successful requests shadowed, 0.0 to 1.0
+    pub sample_rate: f64,
+
+    /// JSONL file comparisons are appended to, if any
+    pub output_path: Option<PathBuf>,
+}
+
+impl ShadowConfig {
+    /// Load from `SHADOW_PROVIDER`, `SHADOW_MODEL`, `SHADOW_SAMPLE_RATE` and
+    /// `SHADOW_OUTPUT_PATH`; `None` unless a provider is set
+    pub fn from_env() -> Option<Self> {
+        let provider = std::env::var("SHADOW_PROVIDER")
+            .ok()
+            .map(|p| p.trim().to_ascii_lowercase())
+            .filter(|p| !p.is_empty())?;
+
+        Some(Self {
+            provider,
+            model: std::env::var("SHADOW_MODEL")
+                .ok()
+                .filter(|m| !m.trim().is_empty()),
+            sample_rate: std::env::var("SHADOW_SAMPLE_RATE")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .map_or(0.01, |rate| rate.clamp(0.0, 1.0)),
+            output_path: std::env::var("SHADOW_OUTPUT_PATH").ok().map(PathBuf::from),
+        })
+    }
+
+    /// Whether to shadow the current request
+    pub fn sample(&self) -> bool {
+        self.sample_rate >= 1.0
+            || (self.sample_rate > 0.0 && rand::thread_rng().gen::<f64>() < self.sample_rate)
+    }
+}
+
+/// One side of a shadowed request
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowOutcome {
+    pub provider: String,
+    pub model: String,
+    /// First choice's content, PII-redacted
+    pub content: Option<String>,
+    /// Why the provider gave no response
+    pub error: Option<String>,
+    pub latency_ms: u64,
+    pub cost_usd: Option<f64>,
+}
+
+impl ShadowOutcome {
+    pub fn response(
+        provider: &str,
+        model: &str,
+        response: &UnifiedResponse,
+        latency_ms: u64,
+        cost_usd: Option<f64>,
+    ) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            content: response
+                .choices
+                .first()
+                .map(|c| sanitize_log_data(&c.message.content, SHADOW_CONTENT_MAX_LENGTH)),
+            error: None,
+            latency_ms,
+            cost_usd,
+        }
+    }
+
+    fn failure(provider: &str, model: &str, error: String, latency_ms: u64) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            content: None,
+            error: Some(error),
+            latency_ms,
+            cost_usd: None,
+        }
+    }
+}
+
+/// Primary and shadow responses to one request
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowComparison {
+    pub request_id: String,
+    pub recorded_at: String,
+    pub primary: ShadowOutcome,
+    pub shadow: ShadowOutcome,
+}
+
+/// Send `request` to the shadow provider in the background and record it
+/// next to the `primary` outcome
+///
+/// Does nothing if shadow traffic is off or its provider is not configured.
+pub fn spawn_shadow(
+    state: &Arc<AppState>,
+    request_id: &str,
+    mut request: UnifiedRequest,
+    primary: ShadowOutcome,
+) {
+    let Some(config) = state.config.shadow.clone() else {
+        return;
+    };
+    let Some(provider) = provider_for(state, &config.provider) else {
+        warn!(
+            provider = %config.provider,
+            "Shadow provider is not configured, skipping shadow request"
+        );
+        return;
+    };
+    if let Some(model) = &config.model {
+        request.model = model.clone();
+    }
+    let timeout = state.config.request_timeout();
+    let request_id = request_id.to_string();
+
+    tokio::spawn(async move {
+        let model = request.model.clone();
+        let start = Instant::now();
+        let result = match provider.check_request(&request) {
+            Ok(()) => tokio::time::timeout(timeout, provider.send(request))
+                .await
+                .map_err(|_| "Shadow request timed out".to_string())
+                .and_then(|result| result.map_err(|e| e.to_string())),
+            Err(e) => Err(e.to_string()),
+        };
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let shadow = match result {
+            Ok(response) => {
+                let cost_usd =
+                    calculate_cost(&provider, &model, &response.usage).map(|c| c.total());
+                metrics::record_shadow_request(&config.provider, &model, "success");
+                if let Some(cost) = cost_usd {
+                    metrics::record_shadow_cost(&config.provider, &model, cost);
+                }
+                ShadowOutcome::response(&config.provider, &model, &response, latency_ms, cost_usd)
+            }
+            Err(error) => {
+                metrics::record_shadow_request(&config.provider, &model, "error");
+                ShadowOutcome::failure(&config.provider, &model, error, latency_ms)
+            }
+        };
+
+        let comparison = ShadowComparison {
+            request_id,
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            primary,
+            shadow,
+        };
+        info!(
+            request_id = %comparison.request_id,
+            primary_provider = %comparison.primary.provider,
+            shadow_provider = %comparison.shadow.provider,
+            shadow_model = %comparison.shadow.model,
+            shadow_latency_ms = comparison.shadow.latency_ms,
+            shadow_error = ?comparison.shadow.error,
+            "Shadow request completed"
+        );
+        if let Some(path) = &config.output_path {
+            append_jsonl(path, &comparison).await;
+        }
+    });
+}
+
+/// Append `record` to the JSONL file at `path`, logging any failure
+pub(crate) async fn append_jsonl<T: Serialize>(path: &Path, record: &T) {
+    let mut line = match serde_json::to_string(record) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!(error = %e, "Failed to serialize JSONL record");
+            return;
+        }
+    };
+    line.push('\n');
+
+    let written = async {
+        tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?
+            .write_all(line.as_bytes())
+            .await
+    };
+    if let Err(e) = written.await {
+        warn!(path = %path.display(), error = %e, "Failed to write JSONL record");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use crate::proxy::{handle_chat_completions, ChatCompletionRequest, ChatMessage};
+    use async_trait::async_trait;
+    use axum::{extract::State, http::HeaderMap, Json};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, Usage,
+    };
+    use metrics_util::debugging::DebuggingRecorder;
+    use std::time::Duration;
+
+    /// Priced provider answering `content` after `delay`, recording the
+    /// models it was sent
+    struct Probe {
+        name: &'static str,
+        content: &'static str,
+        delay: Duration,
+        models: parking_lot::Mutex<Vec<String>>,
+    }
+
+    impl Probe {
+        fn new(name: &'static str, content: &'static str, delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                content,
+                delay,
+                models: parking_lot::Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Probe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.models.lock().push(request.model.clone());
+            tokio::time::sleep(self.delay).await;
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: self.content.to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1000,
+                    completion_tokens: 1000,
+                    total_tokens: 2000,
+                },
+                metadata: ResponseMetadata {
+                    provider: self.name.to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            Some(PricingInfo {
+                input_cost_per_1k: 0.001,
+                output_cost_per_1k: 0.003,
+            })
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    fn state(
+        primary: &Arc<Probe>,
+        shadow: &Arc<Probe>,
+        sample_rate: f64,
+        output_path: &Path,
+    ) -> Arc<AppState> {
+        Arc::new(
+            AppState::new(AppConfig {
+                shadow: Some(ShadowConfig {
+                    provider: "anthropic".to_string(),
+                    model: Some("claude-3-5-sonnet".to_string()),
+                    sample_rate,
+                    output_path: Some(output_path.to_path_buf()),
+                }),
+                ..AppConfig::default()
+            })
+            .with_openai(primary.clone())
+            .with_anthropic(shadow.clone()),
+        )
+    }
+
+    fn headers(request_id: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", request_id.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_sampled_request_shadowed_without_affecting_client() {
+        let primary = Probe::new("openai", "Mail me at jane@example.com", Duration::ZERO);
+        let shadow = Probe::new("anthropic", "shadow answer", Duration::from_millis(300));
+        let path = std::env::temp_dir().join(format!("shadow-{}.jsonl", uuid::Uuid::new_v4()));
+        let state = state(&primary, &shadow, 1.0, &path);
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let (response, elapsed) = ::metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    let start = Instant::now();
+                    let Json(response) = handle_chat_completions(
+                        State(state),
+                        headers("req-1"),
+                        None,
+                        Json(request()),
+                    )
+                    .await
+                    .unwrap();
+                    let elapsed = start.elapsed();
+
+                    while !std::fs::read_to_string(&path).is_ok_and(|c| c.ends_with('\n')) {
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                    (response, elapsed)
+                })
+        });
+
+        // The client got the primary answer without waiting for the shadow
+        assert_eq!(
+            response.choices[0].message.content,
+            "Mail me at jane@example.com"
+        );
+        assert_eq!(response.metadata.unwrap().provider, "openai");
+        assert!(elapsed < shadow.delay, "{:?}", elapsed);
+        assert_eq!(*primary.models.lock(), vec!["gpt-4"]);
+        assert_eq!(*shadow.models.lock(), vec!["claude-3-5-sonnet"]);
+
+        let comparison: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(comparison["request_id"], "req-1");
+        assert_eq!(comparison["shadow"]["content"], "shadow answer");
+        let primary_content = comparison["primary"]["content"].as_str().unwrap();
+        assert!(
+            !primary_content.contains("jane@example.com"),
+            "{primary_content}"
+        );
+
+        let shadow_cost = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .find(|(key, _, _, _)| key.key().name() == "llm_edge_shadow_cost_micro_usd_total")
+            .map(|(_, _, _, value)| value);
+        assert!(matches!(
+            shadow_cost,
+            // $0.004, which a whole-dollar counter would have dropped
+            Some(metrics_util::debugging::DebugValue::Counter(4000))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unsampled_request_not_shadowed() {
+        let primary = Probe::new("openai", "primary answer", Duration::ZERO);
+        let shadow = Probe::new("anthropic", "shadow answer", Duration::ZERO);
+        let path = std::env::temp_dir().join(format!("shadow-{}.jsonl", uuid::Uuid::new_v4()));
+
+        let Json(response) = handle_chat_completions(
+            State(state(&primary, &shadow, 0.0, &path)),
+            headers("req-2"),
+            None,
+            Json(request()),
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(response.choices[0].message.content, "primary answer");
+        assert!(shadow.models.lock().is_empty());
+        assert!(!path.exists());
+    }
+}
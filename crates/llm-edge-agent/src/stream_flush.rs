@@ -0,0 +1,198 @@
+//! Flush policy for streamed responses
+//!
+//! Forwarding every upstream token chunk as its own SSE frame costs a frame
+//! and a write per token. [`StreamFlushPolicy`] lets high-throughput
+//! deployments buffer chunks and flush them together instead:
+//!
+//! - `immediate` (the default) sends each chunk as soon as it arrives
+//! - `tokens:N` sends a frame once N chunks are buffered
+//! - `ms:M` sends what has been buffered M milliseconds after the first
+//!   chunk of the frame arrived
+//!
+//! Whatever the policy, the buffer is flushed when the upstream stream ends.
+//! See [`crate::streaming`] for the handler that runs its tokens through
+//! [`sse_frames`].
+
+use axum::response::sse::Event;
+use futures::{stream, Stream, StreamExt};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// When buffered stream chunks are sent to the client
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamFlushPolicy {
+    /// One frame per chunk, for the lowest latency
+    #[default]
+    Immediate,
+    /// One frame per this many chunks
+    EveryTokens(usize),
+    /// One frame per this long after a frame's first chunk
+    Interval(Duration),
+}
+
+impl StreamFlushPolicy {
+    /// Parse `immediate`, `tokens:<n>` or `ms:<m>`
+    ///
+    /// A count or interval of zero means `immediate`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        let policy = match value.split_once(':') {
+            None if value.is_empty() || value == "immediate" => StreamFlushPolicy::Immediate,
+            Some(("tokens", n)) => match n.trim().parse().ok()? {
+                0 | 1 => StreamFlushPolicy::Immediate,
+                n => StreamFlushPolicy::EveryTokens(n),
+            },
+            Some(("ms", ms)) => match ms.trim().parse().ok()? {
+                0 => StreamFlushPolicy::Immediate,
+                ms => StreamFlushPolicy::Interval(Duration::from_millis(ms)),
+            },
+            _ => return None,
+        };
+        Some(policy)
+    }
+
+    /// Load the policy from `STREAM_FLUSH_POLICY`
+    pub fn from_env() -> Self {
+        let raw = std::env::var("STREAM_FLUSH_POLICY").unwrap_or_default();
+        Self::parse(&raw).unwrap_or_else(|| {
+            warn!(value = %raw, "Invalid STREAM_FLUSH_POLICY, flushing immediately");
+            StreamFlushPolicy::Immediate
+        })
+    }
+}
+
+/// Join `chunks` into frames as `policy` allows
+pub fn buffer_chunks<S>(chunks: S, policy: StreamFlushPolicy) -> impl Stream<Item = String>
+where
+    S: Stream<Item = String> + Unpin,
+{
+    stream::unfold(chunks.fuse(), move |mut chunks| async move {
+        let mut frame = chunks.next().await?;
+        let mut buffered = 1;
+        let deadline = match policy {
+            StreamFlushPolicy::Interval(interval) => Some(Instant::now() + interval),
+            _ => None,
+        };
+
+        loop {
+            if let StreamFlushPolicy::EveryTokens(n) = policy {
+                if buffered >= n {
+                    break;
+                }
+            }
+
+            let next = match (policy, deadline) {
+                (StreamFlushPolicy::Immediate, _) => break,
+                (_, Some(deadline)) => {
+                    match tokio::time::timeout_at(deadline, chunks.next()).await {
+                        Ok(next) => next,
+                        Err(_) => break,
+                    }
+                }
+                (_, None) => chunks.next().await,
+            };
+            let Some(chunk) = next else {
+                break;
+            };
+            frame.push_str(&chunk);
+            buffered += 1;
+        }
+
+        Some((frame, chunks))
+    })
+}
+
+/// SSE events for a stream of token chunks, buffered per `policy` and
+/// turned into events by `frame_event`
+pub fn sse_frames<S, F>(
+    chunks: S,
+    policy: StreamFlushPolicy,
+    mut frame_event: F,
+) -> impl Stream<Item = Result<Event, Infallible>>
+where
+    S: Stream<Item = String> + Unpin,
+    F: FnMut(String) -> Event,
+{
+    buffer_chunks(chunks, policy).map(move |frame| Ok(frame_event(frame)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::{sse::Sse, IntoResponse};
+
+    /// Data of every SSE frame sent for `tokens` under `policy`
+    async fn frames_sent(tokens: &[&str], policy: StreamFlushPolicy) -> Vec<String> {
+        let tokens: Vec<String> = tokens.iter().map(|t| t.to_string()).collect();
+        let chunks = stream::iter(tokens);
+        let response = Sse::new(sse_frames(chunks, policy, |frame| {
+            Event::default().data(frame)
+        }))
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(body.to_vec())
+            .unwrap()
+            .split("\n\n")
+            .filter(|frame| !frame.is_empty())
+            .map(|frame| frame.strip_prefix("data: ").unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_batching_sends_several_tokens_per_frame() {
+        let tokens = ["The", " quick", " brown", " fox", " jumps"];
+
+        assert_eq!(
+            frames_sent(&tokens, StreamFlushPolicy::Immediate).await,
+            tokens
+        );
+        // The last frame is flushed short when the stream ends
+        assert_eq!(
+            frames_sent(&tokens, StreamFlushPolicy::EveryTokens(2)).await,
+            ["The quick", " brown fox", " jumps"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interval_flushes_what_arrived_in_time() {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let policy = StreamFlushPolicy::Interval(Duration::from_millis(50));
+        let frames = tokio::spawn(buffer_chunks(rx, policy).collect::<Vec<_>>());
+
+        for (token, delay_ms) in [("a", 0), ("b", 10), ("c", 30), ("d", 20), ("e", 5)] {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            tx.unbounded_send(token.to_string()).unwrap();
+        }
+        drop(tx);
+
+        // a, b and c arrive within 50ms of a; d starts the next frame
+        assert_eq!(frames.await.unwrap(), ["abc", "de"]);
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            StreamFlushPolicy::parse(""),
+            Some(StreamFlushPolicy::Immediate)
+        );
+        assert_eq!(
+            StreamFlushPolicy::parse("tokens:8"),
+            Some(StreamFlushPolicy::EveryTokens(8))
+        );
+        assert_eq!(
+            StreamFlushPolicy::parse("ms:25"),
+            Some(StreamFlushPolicy::Interval(Duration::from_millis(25)))
+        );
+        assert_eq!(
+            StreamFlushPolicy::parse("tokens:0"),
+            Some(StreamFlushPolicy::Immediate)
+        );
+        assert_eq!(StreamFlushPolicy::parse("tokens:many"), None);
+        assert_eq!(StreamFlushPolicy::parse("bytes:512"), None);
+    }
+}
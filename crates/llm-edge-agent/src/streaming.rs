@@ -0,0 +1,710 @@
+//! Streamed chat completions (`"stream": true`)
+//!
+//! The completion is forwarded as OpenAI `chat.completion.chunk` events,
+//! grouped into SSE frames by the configured
+//! [`StreamFlushPolicy`](crate::StreamFlushPolicy), and closed by a chunk
+//! carrying the finish reason and usage, then `[DONE]`.
+//!
+//! Until the provider has produced content nothing has reached the client,
+//! so a stream that fails or ends early is retried, on the next candidate
+//! provider or the same one, up to `STREAM_RETRIES` times. Once content has
+//! been sent an abnormal end can no longer be retried; the client gets an
+//! error event with code [`STREAM_TRUNCATED_CODE`] instead of `[DONE]`, so a
+//! cut-off answer is never mistaken for a complete one.
+//!
+//! The request deadline keeps running while tokens are forwarded. When it
+//! passes, the provider stream is dropped and the client gets a final chunk
+//! with finish reason [`STREAM_TIMEOUT_FINISH_REASON`], then `[DONE]`; the
+//! tokens sent so far are estimated and recorded in the usage and cost
+//! metrics.
+//!
+//! Streams are neither served from nor written to the cache.
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
+};
+use futures::{future, stream, StreamExt};
+use llm_edge_monitoring::metrics;
+use llm_edge_providers::{
+    adapter::filter_passthrough_headers, ChunkStream, FinishReason, LLMProvider, ProviderError,
+    StreamChunk, UnifiedRequest, Usage,
+};
+use llm_edge_proxy::middleware::{request_id_from_headers, AllowedModels};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::cost_ceiling::{estimate_prompt_tokens, CHARS_PER_TOKEN};
+use crate::deadline::RequestDeadline;
+use crate::integration::AppState;
+use crate::proxy::{
+    calculate_cost, convert_to_unified, prepare_request, request_for_provider,
+    ChatCompletionRequest, ProxyError,
+};
+use crate::route::explain_route;
+use crate::stream_flush::sse_frames;
+
+/// Error code of the event ending a stream cut short after content was sent
+pub const STREAM_TRUNCATED_CODE: &str = "stream_truncated";
+
+/// Finish reason of a stream ended by the request deadline
+pub const STREAM_TIMEOUT_FINISH_REASON: &str = "timeout";
+
+/// Streamed chat completions handler
+///
+/// Errors before the first token are answered like any other request; see
+/// the module docs for what happens after.
+#[instrument(name = "proxy_chat_completions_stream", skip(state, headers, allowed_models, request), fields(
+    request_id = tracing::field::Empty,
+    model = %request.model,
+    message_count = request.messages.len(),
+))]
+pub async fn handle_chat_completions_stream(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    allowed_models: Option<Extension<AllowedModels>>,
+    Json(mut request): Json<ChatCompletionRequest>,
+) -> Result<Response, ProxyError> {
+    let request_id = request_id_from_headers(&headers);
+    tracing::Span::current().record("request_id", request_id.as_str());
+    let tags = state.config.request_tags.extract(&headers);
+    let deadline = RequestDeadline::from_headers(&headers, state.config.request_timeout());
+
+    info!(
+        request_id = %request_id,
+        model = %request.model,
+        "Processing streamed chat completion request"
+    );
+
+    let (pinned_provider, strategy) = prepare_request(
+        &state,
+        &headers,
+        allowed_models.as_ref(),
+        &mut request,
+        &request_id,
+    )
+    .await?;
+
+    let route = explain_route(&state, &request.model, pinned_provider, strategy).await;
+    debug!(request_id = %request_id, reason = %route.reason, "Routing decision");
+    let fallbacks = route.fallbacks(&state);
+    let candidates: Vec<_> = std::iter::once(route.into_provider(&state)?)
+        .chain(fallbacks)
+        .collect();
+
+    let mut base_request = convert_to_unified(&request);
+    base_request.extra_headers =
+        filter_passthrough_headers(&headers, &state.config.passthrough_headers);
+
+    let attempts = 1 + state.config.stream_retries;
+    let mut last_error = None;
+    for (attempt, (provider, provider_name)) in candidates.iter().cycle().take(attempts).enumerate()
+    {
+        let unified_request = match request_for_provider(
+            &state,
+            &headers,
+            &request,
+            &base_request,
+            provider,
+            provider_name,
+            &request_id,
+        ) {
+            Ok((unified_request, _)) => unified_request,
+            // A retry candidate that cannot take the request is skipped
+            Err(_) if attempt > 0 => {
+                debug!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    "Skipping retry candidate that cannot take the request"
+                );
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        info!(
+            request_id = %request_id,
+            provider = %provider_name,
+            attempt,
+            "Opening stream from provider"
+        );
+        let provider_start = Instant::now();
+        let opened = deadline
+            .run(open_stream(provider.as_ref(), unified_request))
+            .await
+            .map_err(|e| {
+                warn!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    "Request deadline exceeded waiting for the first token"
+                );
+                metrics::record_request_failure(provider_name, &request.model, "timeout");
+                e
+            })?;
+
+        match opened {
+            Ok(opened) => {
+                debug!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    first_token_ms = provider_start.elapsed().as_millis() as u64,
+                    "Stream started"
+                );
+                let forward = ForwardedStream {
+                    state: state.clone(),
+                    provider: provider.clone(),
+                    provider_name: provider_name.clone(),
+                    model: request.model.clone(),
+                    request_id,
+                    tags: tags.labels().to_vec(),
+                    provider_start,
+                    deadline,
+                    prompt_tokens: estimate_prompt_tokens(&request),
+                };
+                return Ok(forward.into_response(opened));
+            }
+            Err(e) => {
+                warn!(
+                    request_id = %request_id,
+                    provider = %provider_name,
+                    attempt,
+                    error = %e,
+                    "Provider stream failed before the first token"
+                );
+                metrics::record_request_failure(provider_name, &request.model, e.error_type());
+                if !e.is_retryable() {
+                    return Err(e.into());
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.map(ProxyError::from).unwrap_or_else(|| {
+        ProxyError::ServiceUnavailable("No provider could take the request".into())
+    }))
+}
+
+/// A provider stream that has produced its first content
+struct OpenedStream {
+    /// Chunks read while waiting for content, the content chunk last
+    head: Vec<StreamChunk>,
+    rest: ChunkStream,
+}
+
+/// Why a provider stream could not be opened
+#[derive(Debug)]
+enum OpenError {
+    /// The provider refused the request
+    Rejected(ProviderError),
+    /// The stream failed, or ended, before producing any content
+    Truncated(Option<ProviderError>),
+}
+
+impl OpenError {
+    /// Nothing has reached the client yet, so any failure mid-stream may be
+    /// retried; refusals only when the provider says so
+    fn is_retryable(&self) -> bool {
+        match self {
+            OpenError::Rejected(e) => e.is_retryable(),
+            OpenError::Truncated(_) => true,
+        }
+    }
+
+    fn error_type(&self) -> &'static str {
+        match self {
+            OpenError::Rejected(e) if e.is_rate_limited() => "rate_limited",
+            OpenError::Rejected(_) => "provider_error",
+            OpenError::Truncated(_) => STREAM_TRUNCATED_CODE,
+        }
+    }
+}
+
+impl std::fmt::Display for OpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenError::Rejected(e) | OpenError::Truncated(Some(e)) => e.fmt(f),
+            OpenError::Truncated(None) => f.write_str("stream ended before any content"),
+        }
+    }
+}
+
+impl From<OpenError> for ProxyError {
+    fn from(e: OpenError) -> Self {
+        match e {
+            OpenError::Rejected(e) => e.into(),
+            e @ OpenError::Truncated(_) => {
+                ProxyError::ProviderError(format!("Provider stream failed: {}", e))
+            }
+        }
+    }
+}
+
+/// Whether `chunk` commits the answer: it carries content or ends a choice
+fn starts_answer(chunk: &StreamChunk) -> bool {
+    chunk.choices.iter().any(|choice| {
+        choice.content.as_deref().is_some_and(|c| !c.is_empty()) || choice.finish_reason.is_some()
+    })
+}
+
+/// Start streaming from `provider`, reading up to its first content
+async fn open_stream(
+    provider: &dyn LLMProvider,
+    request: UnifiedRequest,
+) -> Result<OpenedStream, OpenError> {
+    let mut chunks = provider
+        .send_stream(request)
+        .await
+        .map_err(OpenError::Rejected)?;
+
+    let mut head = Vec::new();
+    loop {
+        match chunks.next().await {
+            Some(Ok(chunk)) => {
+                let started = starts_answer(&chunk);
+                head.push(chunk);
+                if started {
+                    return Ok(OpenedStream { head, rest: chunks });
+                }
+            }
+            Some(Err(e)) => return Err(OpenError::Truncated(Some(e))),
+            None => return Err(OpenError::Truncated(None)),
+        }
+    }
+}
+
+/// How the forwarded stream ended
+#[derive(Default)]
+struct StreamEnd {
+    finish_reason: Option<String>,
+    usage: Option<Usage>,
+    error: Option<ProviderError>,
+    /// The request deadline passed before the provider finished
+    timed_out: bool,
+    /// Characters of content forwarded so far
+    content_chars: usize,
+}
+
+/// A stream being forwarded to the client from `provider`
+struct ForwardedStream {
+    state: Arc<AppState>,
+    provider: Arc<dyn LLMProvider>,
+    provider_name: String,
+    model: String,
+    request_id: String,
+    tags: Vec<(String, String)>,
+    provider_start: Instant,
+    deadline: RequestDeadline,
+    /// Estimated prompt size, for usage of a stream the deadline cut short
+    prompt_tokens: u32,
+}
+
+impl ForwardedStream {
+    fn into_response(self, opened: OpenedStream) -> Response {
+        let id = format!("chatcmpl-{}", Uuid::new_v4());
+        let created = chrono::Utc::now().timestamp();
+        let end = Arc::new(Mutex::new(StreamEnd::default()));
+
+        // Content of the first choice, ending at the first error or the
+        // request deadline
+        let deadline = {
+            let (end, at) = (end.clone(), self.deadline.instant());
+            async move {
+                tokio::time::sleep_until(at).await;
+                end.lock().unwrap().timed_out = true;
+            }
+        };
+        let tokens = stream::iter(opened.head.into_iter().map(Ok))
+            .chain(opened.rest)
+            .take_until(deadline)
+            .scan(end.clone(), |end, item| {
+                let mut end = end.lock().unwrap();
+                let token = match item {
+                    Ok(chunk) => {
+                        if chunk.usage.is_some() {
+                            end.usage = chunk.usage;
+                        }
+                        let mut token = String::new();
+                        for choice in chunk.choices.into_iter().filter(|c| c.index == 0) {
+                            if let Some(reason) = choice.finish_reason {
+                                end.finish_reason = Some(reason);
+                            }
+                            token.extend(choice.content);
+                        }
+                        end.content_chars += token.chars().count();
+                        Some(token)
+                    }
+                    Err(e) => {
+                        end.error = Some(e);
+                        None
+                    }
+                };
+                future::ready(token)
+            })
+            .filter(|token| future::ready(!token.is_empty()))
+            .boxed();
+
+        let role = chunk_event(
+            &id,
+            created,
+            &self.model,
+            serde_json::json!({"role": "assistant", "content": ""}),
+            None,
+            None,
+        );
+        let content = sse_frames(tokens, self.state.config.stream_flush, {
+            let (id, model) = (id.clone(), self.model.clone());
+            move |frame| {
+                chunk_event(
+                    &id,
+                    created,
+                    &model,
+                    serde_json::json!({"content": frame}),
+                    None,
+                    None,
+                )
+            }
+        });
+        let tail = stream::once(async move {
+            let end = std::mem::take(&mut *end.lock().unwrap());
+            self.finish(end, &id, created)
+        })
+        .flat_map(|events| stream::iter(events.into_iter().map(Ok)));
+
+        Sse::new(
+            stream::once(future::ready(Ok(role)))
+                .chain(content)
+                .chain(tail),
+        )
+        .into_response()
+    }
+
+    /// The events closing the stream, recording its outcome
+    fn finish(self, mut end: StreamEnd, id: &str, created: i64) -> Vec<Event> {
+        if end.timed_out {
+            warn!(
+                request_id = %self.request_id,
+                provider = %self.provider_name,
+                "Request deadline exceeded mid-stream"
+            );
+            metrics::record_request_failure(&self.provider_name, &self.model, "timeout");
+            let completion_tokens = end.content_chars.div_ceil(CHARS_PER_TOKEN);
+            let usage = Usage {
+                prompt_tokens: self.prompt_tokens as usize,
+                completion_tokens,
+                total_tokens: self.prompt_tokens as usize + completion_tokens,
+            };
+            self.record_usage(&usage);
+            return vec![
+                chunk_event(
+                    id,
+                    created,
+                    &self.model,
+                    serde_json::json!({}),
+                    Some(STREAM_TIMEOUT_FINISH_REASON),
+                    None,
+                ),
+                Event::default().data("[DONE]"),
+            ];
+        }
+
+        let (finish_reason, error) = match (end.finish_reason.take(), end.error.take()) {
+            (Some(reason), None) => (reason, None),
+            (_, error) => {
+                let message = match error {
+                    Some(e) => format!("Provider stream failed mid-response: {}", e),
+                    None => "Provider stream ended before the response was complete".to_string(),
+                };
+                (String::new(), Some(message))
+            }
+        };
+
+        if let Some(message) = error {
+            warn!(
+                request_id = %self.request_id,
+                provider = %self.provider_name,
+                error = %message,
+                "Stream cut short after content was sent"
+            );
+            metrics::record_request_failure(
+                &self.provider_name,
+                &self.model,
+                STREAM_TRUNCATED_CODE,
+            );
+            let error = serde_json::json!({
+                "error": {
+                    "message": message,
+                    "type": "proxy_error",
+                    "param": null,
+                    "code": STREAM_TRUNCATED_CODE,
+                }
+            });
+            return vec![Event::default().data(error.to_string())];
+        }
+
+        let latency = self.provider_start.elapsed().as_millis() as u64;
+        metrics::record_request_success(&self.provider_name, &self.model, latency);
+        if let Some(usage) = &end.usage {
+            self.record_usage(usage);
+        }
+        info!(
+            request_id = %self.request_id,
+            provider = %self.provider_name,
+            latency_ms = latency,
+            "Stream completed"
+        );
+
+        vec![
+            chunk_event(
+                id,
+                created,
+                &self.model,
+                serde_json::json!({}),
+                Some(FinishReason::from_native(&finish_reason).as_str()),
+                end.usage.as_ref(),
+            ),
+            Event::default().data("[DONE]"),
+        ]
+    }
+
+    /// Record the tokens the stream used and what they cost
+    fn record_usage(&self, usage: &Usage) {
+        metrics::record_token_usage(
+            &self.provider_name,
+            &self.model,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            &self.tags,
+        );
+        if let Some(cost) = calculate_cost(&self.provider, &self.model, usage) {
+            metrics::record_cost(&self.provider_name, &self.model, cost.total(), &self.tags);
+        }
+    }
+}
+
+/// One `chat.completion.chunk` event
+fn chunk_event(
+    id: &str,
+    created: i64,
+    model: &str,
+    delta: serde_json::Value,
+    finish_reason: Option<&str>,
+    usage: Option<&Usage>,
+) -> Event {
+    let mut chunk = serde_json::json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "delta": delta,
+            "finish_reason": finish_reason,
+        }],
+    });
+    if let Some(usage) = usage {
+        chunk["usage"] = serde_json::json!(usage);
+    }
+    Event::default().data(chunk.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::AppConfig;
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        ChoiceDelta, UnifiedResponse,
+    };
+    use std::collections::VecDeque;
+
+    /// Provider answering each stream with the next scripted list of chunks;
+    /// `None` stands for the connection dropping, `[HANG]` for the provider
+    /// going quiet without closing it
+    struct Scripted {
+        streams: Mutex<VecDeque<Vec<Option<&'static str>>>>,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Scripted {
+        fn new(streams: Vec<Vec<Option<&'static str>>>) -> Arc<Self> {
+            Arc::new(Self {
+                streams: Mutex::new(streams.into()),
+                calls: Default::default(),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    fn content_chunk(content: &str) -> StreamChunk {
+        StreamChunk {
+            choices: vec![ChoiceDelta {
+                content: Some(content.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for Scripted {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(
+            &self,
+            _request: UnifiedRequest,
+        ) -> llm_edge_providers::ProviderResult<UnifiedResponse> {
+            unreachable!("streamed requests are sent with send_stream")
+        }
+
+        async fn send_stream(
+            &self,
+            _request: UnifiedRequest,
+        ) -> llm_edge_providers::ProviderResult<ChunkStream> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let script = self.streams.lock().unwrap().pop_front().unwrap();
+            let hangs = script.last() == Some(&Some("[HANG]"));
+            let chunks = script.into_iter().filter(|step| *step != Some("[HANG]"));
+            let chunks = chunks.map(|step| match step {
+                Some("[STOP]") => Ok(StreamChunk {
+                    choices: vec![ChoiceDelta {
+                        finish_reason: Some("stop".to_string()),
+                        ..Default::default()
+                    }],
+                    usage: Some(Usage {
+                        prompt_tokens: 5,
+                        completion_tokens: 3,
+                        total_tokens: 8,
+                    }),
+                    ..Default::default()
+                }),
+                Some(content) => Ok(content_chunk(content)),
+                None => Err(ProviderError::Timeout),
+            });
+            let silence = if hangs {
+                stream::pending().boxed()
+            } else {
+                stream::empty().boxed()
+            };
+            Ok(stream::iter(chunks).chain(silence).boxed())
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn stream_request() -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "stream": true,
+        }))
+        .unwrap()
+    }
+
+    /// Data of every SSE event the client receives
+    async fn events_received(provider: Arc<Scripted>) -> Vec<serde_json::Value> {
+        events_received_with(provider, HeaderMap::new()).await
+    }
+
+    async fn events_received_with(
+        provider: Arc<Scripted>,
+        headers: HeaderMap,
+    ) -> Vec<serde_json::Value> {
+        let state = Arc::new(AppState::new(AppConfig::default()).with_openai(provider));
+        let response =
+            handle_chat_completions_stream(State(state), headers, None, Json(stream_request()))
+                .await
+                .expect("stream starts");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        String::from_utf8(body.to_vec())
+            .unwrap()
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let data = event.strip_prefix("data: ").unwrap();
+                serde_json::from_str(data).unwrap_or_else(|_| serde_json::json!(data))
+            })
+            .collect()
+    }
+
+    fn content_of(events: &[serde_json::Value]) -> String {
+        events
+            .iter()
+            .filter_map(|e| e["choices"][0]["delta"]["content"].as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_stream_dropped_before_first_token_is_retried() {
+        let provider = Scripted::new(vec![
+            vec![None],
+            vec![Some("Hel"), Some("lo"), Some("[STOP]")],
+        ]);
+
+        let events = events_received(provider.clone()).await;
+
+        assert_eq!(provider.calls(), 2);
+        assert_eq!(events[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(content_of(&events), "Hello");
+        let last = &events[events.len() - 2];
+        assert_eq!(last["choices"][0]["finish_reason"], "stop");
+        assert_eq!(last["usage"]["completion_tokens"], 3);
+        assert_eq!(events.last().unwrap(), "[DONE]");
+    }
+
+    #[tokio::test]
+    async fn test_stream_dropped_after_tokens_ends_with_an_error_event() {
+        let provider = Scripted::new(vec![
+            vec![Some("Hel"), Some("lo"), None],
+            vec![Some("never"), Some("[STOP]")],
+        ]);
+
+        let events = events_received(provider.clone()).await;
+
+        // Content already sent cannot be taken back, so there is no retry
+        assert_eq!(provider.calls(), 1);
+        assert_eq!(content_of(&events), "Hello");
+        let last = events.last().unwrap();
+        assert_eq!(last["error"]["code"], STREAM_TRUNCATED_CODE);
+        assert!(!events.iter().any(|e| e == "[DONE]"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_ends_a_hanging_stream_with_a_timeout_chunk() {
+        let provider = Scripted::new(vec![vec![Some("Hel"), Some("lo"), Some("[HANG]")]]);
+        let mut headers = HeaderMap::new();
+        headers.insert(crate::deadline::DEADLINE_HEADER, "500".parse().unwrap());
+
+        let events = events_received_with(provider.clone(), headers).await;
+
+        assert_eq!(provider.calls(), 1);
+        assert_eq!(content_of(&events), "Hello");
+        let last = &events[events.len() - 2];
+        assert_eq!(
+            last["choices"][0]["finish_reason"],
+            STREAM_TIMEOUT_FINISH_REASON
+        );
+        assert_eq!(events.last().unwrap(), "[DONE]");
+    }
+}
@@ -0,0 +1,344 @@
+//! Request tags for usage attribution
+//!
+//! Clients attribute spend to teams or projects with headers such as
+//! `X-Cost-Center`. Only headers listed in `REQUEST_TAGS` are read, and only
+//! their listed values are kept; any other value is reported as
+//! [`OTHER_TAG_VALUE`]. That keeps the tag labels on the cost and token
+//! metrics to a bounded set. Tags are also recorded on the request span, so
+//! every log line of the request carries them.
+
+use axum::http::HeaderMap;
+use std::fmt;
+use tracing::warn;
+
+/// Value reported for a tag header whose value is not allowlisted
+pub const OTHER_TAG_VALUE: &str = "other";
+
+/// Labels the tagged metrics already use
+const RESERVED_LABELS: &[&str] = &["provider", "model", "type", "status", "error_type"];
+
+/// An allowlisted tag header and its allowed values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagSpec {
+    /// Inbound header name (lowercase)
+    pub header: String,
+
+    /// Metric label and log field name, e.g. `cost_center` for `x-cost-center`
+    pub label: String,
+
+    /// Values reported as-is
+    pub values: Vec<String>,
+}
+
+/// Which request tags are read, and which of their values are kept
+///
+/// The default reads no tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestTagPolicy {
+    pub tags: Vec<TagSpec>,
+}
+
+impl RequestTagPolicy {
+    /// Load the allowlist from `REQUEST_TAGS`
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var("REQUEST_TAGS").unwrap_or_default())
+    }
+
+    /// Parse `header=value|value,...`, e.g. `x-cost-center=eng|sales`
+    ///
+    /// Entries without values, or whose label clashes with an existing
+    /// metric label, are skipped with a warning.
+    pub fn parse(raw: &str) -> Self {
+        let tags = raw
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| {
+                let spec = entry.split_once('=').and_then(|(header, values)| {
+                    let header = header.trim().to_ascii_lowercase();
+                    let label = header
+                        .strip_prefix("x-")
+                        .unwrap_or(&header)
+                        .replace('-', "_");
+                    let values: Vec<String> = values
+                        .split('|')
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty())
+                        .collect();
+
+                    let valid = !label.is_empty()
+                        && !values.is_empty()
+                        && !RESERVED_LABELS.contains(&label.as_str());
+                    valid.then_some(TagSpec {
+                        header,
+                        label,
+                        values,
+                    })
+                });
+                if spec.is_none() {
+                    warn!(entry = %entry.trim(), "Ignoring invalid REQUEST_TAGS entry");
+                }
+                spec
+            })
+            .collect();
+
+        Self { tags }
+    }
+
+    /// Tags sent with a request
+    ///
+    /// Headers that are not allowlisted are ignored; allowlisted headers
+    /// with an unlisted value are tagged [`OTHER_TAG_VALUE`].
+    pub fn extract(&self, headers: &HeaderMap) -> RequestTags {
+        let tags = self
+            .tags
+            .iter()
+            .filter_map(|spec| {
+                let sent = headers.get(&spec.header)?.to_str().ok()?.trim();
+                let value = spec
+                    .values
+                    .iter()
+                    .find(|allowed| allowed.eq_ignore_ascii_case(sent))
+                    .map_or(OTHER_TAG_VALUE, String::as_str);
+                Some((spec.label.clone(), value.to_string()))
+            })
+            .collect();
+
+        RequestTags(tags)
+    }
+}
+
+/// Tags attached to one request as `(label, value)` pairs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestTags(Vec<(String, String)>);
+
+impl RequestTags {
+    /// `(label, value)` pairs in allowlist order
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Formats as `label=value` pairs separated by spaces
+impl fmt::Display for RequestTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (label, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}={}", label, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{handle_chat_completions, ChatCompletionRequest, ChatMessage};
+    use async_trait::async_trait;
+    use axum::{extract::State, http::HeaderValue, Json};
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use metrics_util::debugging::DebuggingRecorder;
+    use std::sync::Arc;
+
+    fn policy() -> RequestTagPolicy {
+        RequestTagPolicy::parse("X-Cost-Center=eng|sales, x-project=alpha")
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_skips_invalid_entries() {
+        let policy = RequestTagPolicy::parse("x-cost-center=eng|sales,x-team=,model=a,=b,junk");
+
+        assert_eq!(
+            policy.tags,
+            vec![TagSpec {
+                header: "x-cost-center".to_string(),
+                label: "cost_center".to_string(),
+                values: vec!["eng".to_string(), "sales".to_string()],
+            }]
+        );
+        assert!(RequestTagPolicy::parse("").tags.is_empty());
+    }
+
+    #[test]
+    fn test_only_allowlisted_tags_and_values_kept() {
+        let tags = policy().extract(&headers(&[
+            ("x-cost-center", "ENG"),
+            ("x-project", "gamma"),
+            ("x-tenant", "acme"),
+        ]));
+
+        assert_eq!(
+            tags.labels(),
+            [
+                ("cost_center".to_string(), "eng".to_string()),
+                ("project".to_string(), OTHER_TAG_VALUE.to_string()),
+            ]
+        );
+        assert_eq!(tags.to_string(), "cost_center=eng project=other");
+        assert!(policy().extract(&HeaderMap::new()).is_empty());
+    }
+
+    /// Priced provider, so every response records a cost
+    struct PricedProvider;
+
+    #[async_trait]
+    impl LLMProvider for PricedProvider {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "ok".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1000,
+                    completion_tokens: 1000,
+                    total_tokens: 2000,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            Some(PricingInfo {
+                input_cost_per_1k: 0.0005,
+                output_cost_per_1k: 0.0015,
+            })
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Collects the `request_tags` values recorded on spans
+    #[derive(Clone, Default)]
+    struct TagCapture(Arc<parking_lot::Mutex<Vec<String>>>);
+
+    struct TagField<'a>(&'a TagCapture);
+
+    impl tracing::field::Visit for TagField<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "request_tags" {
+                self.0 .0.lock().push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for TagCapture {
+        fn on_record(
+            &self,
+            _span: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            values.record(&mut TagField(self));
+        }
+    }
+
+    #[test]
+    fn test_cost_center_tags_cost_metric_and_span() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let state = Arc::new(
+            AppState::new(AppConfig {
+                request_tags: policy(),
+                ..AppConfig::default()
+            })
+            .with_openai(Arc::new(PricedProvider)),
+        );
+        let request = ChatCompletionRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        };
+
+        let capture = TagCapture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let Json(response) = metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(handle_chat_completions(
+                    State(state),
+                    headers(&[("x-cost-center", "eng"), ("x-tenant", "acme")]),
+                    None,
+                    Json(request),
+                ))
+                .unwrap()
+        });
+        assert_eq!(response.choices[0].message.content, "ok");
+
+        let (cost_key, cost) = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| (key.key().clone(), value))
+            .find(|(key, _)| key.name() == "llm_edge_cost_micro_usd_total")
+            .expect("cost recorded");
+        // $0.002 is counted in micro-dollars rather than truncated to 0
+        assert!(matches!(
+            cost,
+            metrics_util::debugging::DebugValue::Counter(2000)
+        ));
+        let cost_labels: Vec<(String, String)> = cost_key
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect();
+        assert!(cost_labels.contains(&("cost_center".to_string(), "eng".to_string())));
+        assert!(cost_labels.iter().all(|(label, _)| label != "tenant"));
+
+        assert_eq!(*capture.0.lock(), vec!["cost_center=eng".to_string()]);
+    }
+}
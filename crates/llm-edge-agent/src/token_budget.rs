@@ -0,0 +1,392 @@
+//! Model selection by prompt size
+//!
+//! Requests sent to a model alias (by default `auto`) are rewritten to a
+//! concrete model chosen from the estimated prompt size, so short prompts go
+//! to a cheap model and long ones to a larger one. [`TokenBudgetProcessor`]
+//! runs with the other request processors before the cache lookup, so the
+//! chosen model is part of the cache key and decides which provider is used.
+
+use async_trait::async_trait;
+use tracing::debug;
+
+use crate::cost_ceiling::estimate_prompt_tokens;
+use crate::processor::RequestProcessor;
+use crate::proxy::{ChatCompletionRequest, ProxyError};
+
+/// Alias rewritten when `TOKEN_BUDGET_MODELS` is not set
+pub const DEFAULT_TOKEN_BUDGET_ALIAS: &str = "auto";
+
+/// Model used for prompts under a token threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBudgetTier {
+    /// Prompts estimated below this many tokens use `model` (`None` matches any size)
+    pub below_tokens: Option<u32>,
+
+    /// Model the request is rewritten to
+    pub model: String,
+}
+
+/// Threshold table mapping prompt size to a model
+///
+/// The default has no tiers and rewrites nothing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBudget {
+    /// Requested model names that are rewritten
+    pub aliases: Vec<String>,
+
+    /// Tiers by ascending threshold; the first match wins
+    pub tiers: Vec<TokenBudgetTier>,
+}
+
+impl Default for TokenBudget {
+    fn default() -> Self {
+        Self {
+            aliases: vec![DEFAULT_TOKEN_BUDGET_ALIAS.to_string()],
+            tiers: Vec::new(),
+        }
+    }
+}
+
+impl TokenBudget {
+    /// Load the table from environment variables
+    pub fn from_env() -> Self {
+        let aliases: Vec<String> = std::env::var("TOKEN_BUDGET_MODELS")
+            .map(|v| {
+                v.split(',')
+                    .map(|m| m.trim().to_string())
+                    .filter(|m| !m.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            aliases: if aliases.is_empty() {
+                Self::default().aliases
+            } else {
+                aliases
+            },
+            tiers: parse_tiers(&std::env::var("TOKEN_BUDGET_TIERS").unwrap_or_default()),
+        }
+    }
+
+    /// Whether any tiers are configured
+    pub fn is_enabled(&self) -> bool {
+        !self.tiers.is_empty()
+    }
+
+    /// Model for a prompt of `prompt_tokens` estimated tokens
+    pub fn model_for(&self, prompt_tokens: u32) -> Option<&str> {
+        self.tiers
+            .iter()
+            .find(|tier| {
+                tier.below_tokens
+                    .map_or(true, |limit| prompt_tokens < limit)
+            })
+            .map(|tier| tier.model.as_str())
+    }
+}
+
+/// Rewrites alias models to the tier matching the prompt size
+pub struct TokenBudgetProcessor {
+    budget: TokenBudget,
+}
+
+impl TokenBudgetProcessor {
+    pub fn new(budget: TokenBudget) -> Self {
+        Self { budget }
+    }
+}
+
+#[async_trait]
+impl RequestProcessor for TokenBudgetProcessor {
+    fn name(&self) -> &str {
+        "token_budget"
+    }
+
+    async fn process(&self, request: &mut ChatCompletionRequest) -> Result<(), ProxyError> {
+        if !self.budget.aliases.contains(&request.model) {
+            return Ok(());
+        }
+
+        let prompt_tokens = estimate_prompt_tokens(request);
+        let model = self.budget.model_for(prompt_tokens).ok_or_else(|| {
+            ProxyError::invalid_param(
+                "model",
+                format!(
+                    "No model configured for '{}' with a {}-token prompt",
+                    request.model, prompt_tokens
+                ),
+            )
+        })?;
+
+        debug!(
+            alias = %request.model,
+            model = %model,
+            prompt_tokens,
+            "Selected model by prompt size"
+        );
+        request.model = model.to_string();
+        Ok(())
+    }
+}
+
+/// Parse `TOKEN_BUDGET_TIERS` (`tokens:model,...,*:model`)
+///
+/// Tiers are sorted by threshold with the catch-all `*` last; malformed
+/// entries are skipped.
+fn parse_tiers(raw: &str) -> Vec<TokenBudgetTier> {
+    let mut tiers: Vec<TokenBudgetTier> = raw
+        .split(',')
+        .filter_map(|entry| entry.trim().split_once(':'))
+        .filter_map(|(limit, model)| {
+            let model = model.trim();
+            if model.is_empty() {
+                return None;
+            }
+            let below_tokens = match limit.trim() {
+                "*" => None,
+                n => Some(n.parse().ok()?),
+            };
+            Some(TokenBudgetTier {
+                below_tokens,
+                model: model.to_string(),
+            })
+        })
+        .collect();
+    tiers.sort_by_key(|tier| tier.below_tokens.unwrap_or(u32::MAX));
+    tiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::{AppConfig, AppState};
+    use crate::proxy::{convert_to_cacheable, handle_chat_completions, ChatMessage};
+    use axum::{extract::State, http::HeaderMap, Json};
+    use llm_edge_cache::CacheLookupResult;
+    use llm_edge_providers::{
+        adapter::{HealthStatus, PricingInfo},
+        types::{Choice, FinishReason, ResponseMetadata},
+        LLMProvider, Message, ProviderResult, UnifiedRequest, UnifiedResponse, Usage,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Provider that records the models it was asked for
+    #[derive(Default)]
+    struct ModelRecorder {
+        models: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ModelRecorder {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.models.lock().push(request.model.clone());
+
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "ok".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Under 500 tokens -> gpt-4o-mini, otherwise gpt-4o
+    fn budget() -> TokenBudget {
+        TokenBudget {
+            tiers: parse_tiers("*:gpt-4o, 500:gpt-4o-mini"),
+            ..TokenBudget::default()
+        }
+    }
+
+    fn request(model: &str, prompt_chars: usize) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "x".repeat(prompt_chars),
+            }],
+            temperature: None,
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            user: None,
+            cache: None,
+            stream: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_tiers_sorts_catch_all_last() {
+        assert_eq!(
+            budget().tiers,
+            vec![
+                TokenBudgetTier {
+                    below_tokens: Some(500),
+                    model: "gpt-4o-mini".to_string(),
+                },
+                TokenBudgetTier {
+                    below_tokens: None,
+                    model: "gpt-4o".to_string(),
+                },
+            ]
+        );
+        assert!(parse_tiers("many:gpt-4o,500:,").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_size_selects_tier() {
+        let processor = TokenBudgetProcessor::new(budget());
+
+        // 499 tokens at four characters per token
+        let mut short = request("auto", 499 * 4);
+        processor.process(&mut short).await.unwrap();
+        assert_eq!(short.model, "gpt-4o-mini");
+
+        let mut long = request("auto", 500 * 4);
+        processor.process(&mut long).await.unwrap();
+        assert_eq!(long.model, "gpt-4o");
+
+        // Explicit models are left alone
+        let mut explicit = request("gpt-4", 10);
+        processor.process(&mut explicit).await.unwrap();
+        assert_eq!(explicit.model, "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_no_matching_tier_is_rejected() {
+        let processor = TokenBudgetProcessor::new(TokenBudget {
+            tiers: parse_tiers("500:gpt-4o-mini"),
+            ..TokenBudget::default()
+        });
+
+        let (_, error) = processor
+            .process(&mut request("auto", 4000))
+            .await
+            .unwrap_err()
+            .into_parts();
+        assert_eq!(error["param"], "model");
+    }
+
+    #[tokio::test]
+    async fn test_selected_model_is_sent_and_cached() {
+        let provider = Arc::new(ModelRecorder::default());
+        let state = Arc::new(
+            AppState::new(AppConfig::default())
+                .with_openai(provider.clone())
+                .with_request_processors(vec![Arc::new(TokenBudgetProcessor::new(budget()))]),
+        );
+
+        for prompt_chars in [40, 4000] {
+            let Json(response) = handle_chat_completions(
+                State(state.clone()),
+                HeaderMap::new(),
+                None,
+                Json(request("auto", prompt_chars)),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.choices[0].message.content, "ok");
+        }
+        assert_eq!(*provider.models.lock(), vec!["gpt-4o-mini", "gpt-4o"]);
+
+        // Cache write is async; give it a moment to land
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        // Keyed by the selected model, not the alias
+        assert!(matches!(
+            state
+                .cache_manager
+                .lookup(&convert_to_cacheable(&request("gpt-4o-mini", 40)))
+                .await,
+            CacheLookupResult::L1Hit(_)
+        ));
+        assert!(matches!(
+            state
+                .cache_manager
+                .lookup(&convert_to_cacheable(&request("auto", 40)))
+                .await,
+            CacheLookupResult::Miss
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_model_allowlist_checked_after_alias_resolution() {
+        use crate::proxy::{ProxyError, CODE_MODEL_NOT_ALLOWED};
+        use axum::Extension;
+        use llm_edge_proxy::middleware::AllowedModels;
+
+        let provider = Arc::new(ModelRecorder::default());
+        let state = Arc::new(
+            AppState::new(AppConfig::default())
+                .with_openai(provider.clone())
+                .with_request_processors(vec![Arc::new(TokenBudgetProcessor::new(budget()))]),
+        );
+        let cheap_key = || {
+            Some(Extension(AllowedModels::Only(vec![
+                "gpt-4o-mini*".to_string()
+            ])))
+        };
+
+        // `auto` is not on the allowlist, but the tier it resolves to is
+        let Json(response) = handle_chat_completions(
+            State(state.clone()),
+            HeaderMap::new(),
+            cheap_key(),
+            Json(request("auto", 40)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.model, "gpt-4o-mini");
+
+        let err = handle_chat_completions(
+            State(state),
+            HeaderMap::new(),
+            cheap_key(),
+            Json(request("auto", 4000)),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(&err, ProxyError::ModelNotAllowed(model) if model == "gpt-4o"));
+        let (status, error) = err.into_parts();
+        assert_eq!(status, axum::http::StatusCode::FORBIDDEN);
+        assert_eq!(error["code"], CODE_MODEL_NOT_ALLOWED);
+        assert_eq!(*provider.models.lock(), vec!["gpt-4o-mini"]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Upstream integration status
+//!
+//! `GET /health/integrations` reports the health of each upstream integration
+//! compiled into this build (shield, sentinel, connector_hub, cost_ops,
+//! observatory, policy_engine). Integrations whose cargo feature is disabled
+//! are omitted; enabled ones that failed to initialize report unhealthy.
+
+use async_trait::async_trait;
+use axum::{extract::State, routing::get, Json, Router};
+use llm_edge_integrations::IntegrationManager;
+use llm_edge_proxy::middleware::auth_middleware;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Source of upstream integration health
+#[async_trait]
+pub trait IntegrationHealthSource: Send + Sync {
+    /// Health of each compiled-in integration, keyed by integration name
+    async fn integration_health(&self) -> BTreeMap<&'static str, bool>;
+}
+
+#[async_trait]
+impl IntegrationHealthSource for IntegrationManager {
+    async fn integration_health(&self) -> BTreeMap<&'static str, bool> {
+        self.health_check().await.statuses()
+    }
+}
+
+/// Integration health route
+///
+/// Public like the other `/health` endpoints unless `AUTH_HEALTH_CHECK`
+/// requires an API key for them.
+pub fn integration_health_routes<S>(
+    source: Arc<dyn IntegrationHealthSource>,
+    auth_config: llm_edge_proxy::Config,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/health/integrations", get(handle_integration_health))
+        .route_layer(axum::middleware::from_fn_with_state(
+            auth_config,
+            auth_middleware,
+        ))
+        .with_state(source)
+}
+
+/// Report the health of every compiled-in integration
+pub async fn handle_integration_health(
+    State(source): State<Arc<dyn IntegrationHealthSource>>,
+) -> Json<serde_json::Value> {
+    let statuses = source.integration_health().await;
+    let healthy = statuses.values().all(|healthy| *healthy);
+
+    let integrations: serde_json::Map<String, serde_json::Value> = statuses
+        .into_iter()
+        .map(|(name, healthy)| (name.to_string(), serde_json::json!({ "healthy": healthy })))
+        .collect();
+
+    Json(serde_json::json!({
+        "status": if healthy { "healthy" } else { "degraded" },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "integrations": integrations,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use tower::ServiceExt;
+
+    /// Reports a fixed set of integrations
+    struct StubIntegrations(BTreeMap<&'static str, bool>);
+
+    #[async_trait]
+    impl IntegrationHealthSource for StubIntegrations {
+        async fn integration_health(&self) -> BTreeMap<&'static str, bool> {
+            self.0.clone()
+        }
+    }
+
+    fn auth_config(require_auth_for_health: bool) -> llm_edge_proxy::Config {
+        use llm_edge_proxy::config::{
+            AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig,
+        };
+
+        llm_edge_proxy::Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: vec!["ops-key".to_string()],
+                require_auth_for_health,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    fn app(require_auth_for_health: bool) -> Router {
+        let source = Arc::new(StubIntegrations(BTreeMap::from([("shield", true)])));
+        integration_health_routes(source, auth_config(require_auth_for_health))
+    }
+
+    fn get_integrations(api_key: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder().uri("/health/integrations");
+        if let Some(key) = api_key {
+            request = request.header("x-api-key", key);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_healthy_integration_is_reported() {
+        let response = app(false).oneshot(get_integrations(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(health["status"], "healthy");
+        assert_eq!(health["integrations"]["shield"]["healthy"], true);
+        assert!(health["integrations"].get("sentinel").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_integration_health_requires_auth_when_configured() {
+        let response = app(true).oneshot(get_integrations(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app(true)
+            .oneshot(get_integrations(Some("ops-key")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
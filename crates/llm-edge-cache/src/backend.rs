@@ -0,0 +1,60 @@
+//! Pluggable L2 cache backends
+//!
+//! [`CacheManager`](crate::CacheManager) talks to its L2 tier only through
+//! [`DistributedCache`]. Redis ([`L2Cache`](crate::l2::L2Cache)) is the default
+//! backend; others (Memcached, an in-process distributed cache, ...) can be
+//! supplied with [`CacheManager::with_backend`](crate::CacheManager::with_backend).
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+
+use crate::l1::CachedResponse;
+use crate::l2::L2Error;
+
+/// Shared cache tier behind L1
+///
+/// `model` labels the hit/miss/write metrics where a backend records them.
+/// Backends handle their own key namespacing and default TTL.
+#[async_trait]
+pub trait DistributedCache: Send + Sync {
+    /// Get a value
+    async fn get(&self, key: &str, model: Option<&str>) -> Result<Option<CachedResponse>, L2Error>;
+
+    /// Set a value with the backend's default TTL
+    async fn set(
+        &self,
+        key: String,
+        value: CachedResponse,
+        model: Option<&str>,
+    ) -> Result<(), L2Error>;
+
+    /// Set a value with a custom TTL
+    async fn set_with_ttl(
+        &self,
+        key: String,
+        value: CachedResponse,
+        ttl_seconds: u64,
+        model: Option<&str>,
+    ) -> Result<(), L2Error>;
+
+    /// Remove a value
+    async fn remove(&self, key: &str) -> Result<(), L2Error>;
+
+    /// Remove every value owned by this cache
+    async fn clear(&self) -> Result<(), L2Error>;
+
+    /// Whether the backend is reachable
+    async fn health_check(&self) -> bool;
+
+    /// Approximate number of stored entries
+    async fn approximate_size(&self) -> Result<usize, L2Error>;
+
+    /// Run a write in the background
+    ///
+    /// Returns `false` if the write was dropped. The default spawns every
+    /// write; backends with limited capacity should bound them.
+    async fn spawn_write(&self, write: BoxFuture<'static, ()>) -> bool {
+        tokio::spawn(write);
+        true
+    }
+}
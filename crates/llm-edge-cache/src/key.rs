@@ -55,7 +55,15 @@ impl CacheableRequest {
 
 /// Generate a cache key from a request using SHA-256
 ///
+/// Same as [`generate_versioned_cache_key`] with key version 0.
+pub fn generate_cache_key(request: &CacheableRequest) -> String {
+    generate_versioned_cache_key(request, 0)
+}
+
+/// Generate a cache key for a request under a key version
+///
 /// The key includes:
+/// - Key version (omitted for version 0, so unversioned keys stay valid)
 /// - Model name
 /// - Prompt content
 /// - Temperature (normalized to 2 decimal places)
@@ -65,9 +73,14 @@ impl CacheableRequest {
 /// # Performance
 /// - Target: <100μs for typical requests
 /// - SHA-256 is hardware-accelerated on most modern CPUs
-pub fn generate_cache_key(request: &CacheableRequest) -> String {
+pub fn generate_versioned_cache_key(request: &CacheableRequest, version: u32) -> String {
     let mut hasher = Sha256::new();
 
+    // Bumping the version makes every existing entry unreachable
+    if version != 0 {
+        hasher.update(format!("v{}|", version).as_bytes());
+    }
+
     // Add model name
     hasher.update(request.model.as_bytes());
     hasher.update(b"|");
@@ -197,6 +210,26 @@ mod tests {
         assert_eq!(key1, key2, "Parameter order should not affect cache key");
     }
 
+    #[test]
+    fn test_cache_key_versions() {
+        let req = CacheableRequest::new("gpt-4", "Hello").with_temperature(0.7);
+
+        assert_eq!(
+            generate_versioned_cache_key(&req, 0),
+            generate_cache_key(&req),
+            "Version 0 should keep unversioned keys"
+        );
+        assert_ne!(
+            generate_versioned_cache_key(&req, 1),
+            generate_versioned_cache_key(&req, 2),
+            "Different versions should produce different keys"
+        );
+        assert_ne!(
+            generate_versioned_cache_key(&req, 1),
+            generate_cache_key(&req)
+        );
+    }
+
     #[test]
     fn test_short_key_length() {
         let req = CacheableRequest::new("gpt-4", "Test prompt");
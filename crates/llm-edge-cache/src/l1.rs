@@ -3,8 +3,10 @@
 //! High-performance in-process cache with TinyLFU eviction policy.
 //! Target latency: <1ms for get/set operations.
 
-use crate::metrics::{CacheMetrics, CacheOperation, CacheTier, LatencyTimer};
+use crate::metrics::{CacheMetrics, CacheOperation, CacheTier, EvictionCause, LatencyTimer};
 use moka::future::Cache;
+use moka::notification::RemovalCause;
+use moka::ops::compute::{CompResult, Op};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
@@ -15,6 +17,11 @@ use tracing::{debug, info};
 pub struct L1Config {
     /// Maximum number of entries (default: 1000)
     pub max_capacity: u64,
+    /// Maximum approximate size of all entries in bytes
+    ///
+    /// When set, entries are weighed by size and this replaces the
+    /// `max_capacity` entry limit.
+    pub max_weighted_size: Option<u64>,
     /// Time to live in seconds (default: 300 = 5 minutes)
     pub ttl_seconds: u64,
     /// Time to idle in seconds (default: 120 = 2 minutes)
@@ -25,6 +32,7 @@ impl Default for L1Config {
     fn default() -> Self {
         Self {
             max_capacity: 1000,
+            max_weighted_size: None,
             ttl_seconds: 300,
             tti_seconds: 120,
         }
@@ -44,6 +52,13 @@ pub struct CachedResponse {
     pub cached_at: i64,
 }
 
+impl CachedResponse {
+    /// Approximate memory held by the entry in bytes
+    pub fn approximate_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.content.len() + self.model.len()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub prompt_tokens: u32,
@@ -67,15 +82,33 @@ impl L1Cache {
 
     /// Create a new L1 cache with custom configuration
     pub fn with_config(config: L1Config, metrics: CacheMetrics) -> Self {
-        info!(
-            "Initializing L1 cache: capacity={}, ttl={}s, tti={}s",
-            config.max_capacity, config.ttl_seconds, config.tti_seconds
+        debug!(
+            "Initializing L1 cache: capacity={}, max_bytes={:?}, ttl={}s, tti={}s",
+            config.max_capacity, config.max_weighted_size, config.ttl_seconds, config.tti_seconds
         );
 
-        let cache = Cache::builder()
-            .max_capacity(config.max_capacity)
+        let listener_metrics = metrics.clone();
+        let builder = match config.max_weighted_size {
+            Some(max_bytes) => Cache::builder()
+                .weigher(|key: &String, value: &Arc<CachedResponse>| {
+                    u32::try_from(key.len() + value.approximate_size()).unwrap_or(u32::MAX)
+                })
+                .max_capacity(max_bytes),
+            None => Cache::builder().max_capacity(config.max_capacity),
+        };
+        let cache = builder
             .time_to_live(Duration::from_secs(config.ttl_seconds))
             .time_to_idle(Duration::from_secs(config.tti_seconds))
+            .eviction_listener(move |_key, _value, cause| {
+                let cause = match cause {
+                    RemovalCause::Expired => EvictionCause::Expired,
+                    RemovalCause::Size => EvictionCause::Size,
+                    RemovalCause::Explicit => EvictionCause::Explicit,
+                    // Overwriting a key is not an eviction
+                    RemovalCause::Replaced => return,
+                };
+                listener_metrics.record_l1_eviction(cause);
+            })
             .build();
 
         Self {
@@ -90,6 +123,15 @@ impl L1Cache {
     /// # Performance
     /// Target: <1ms (typically <100μs)
     pub async fn get(&self, key: &str) -> Option<Arc<CachedResponse>> {
+        self.get_for_model(key, None).await
+    }
+
+    /// Get a value, labeling the hit/miss metric with the request model
+    pub async fn get_for_model(
+        &self,
+        key: &str,
+        model: Option<&str>,
+    ) -> Option<Arc<CachedResponse>> {
         let _timer = LatencyTimer::new(CacheTier::L1, self.metrics.clone());
 
         let result = self.cache.get(key).await;
@@ -97,11 +139,11 @@ impl L1Cache {
         if result.is_some() {
             debug!("L1 cache HIT: key={}", &key[..16.min(key.len())]);
             self.metrics
-                .record_operation(CacheTier::L1, CacheOperation::Hit);
+                .record_operation(CacheTier::L1, CacheOperation::Hit, model);
         } else {
             debug!("L1 cache MISS: key={}", &key[..16.min(key.len())]);
             self.metrics
-                .record_operation(CacheTier::L1, CacheOperation::Miss);
+                .record_operation(CacheTier::L1, CacheOperation::Miss, model);
         }
 
         result
@@ -112,13 +154,70 @@ impl L1Cache {
     /// # Performance
     /// Target: <1ms (non-blocking, async write)
     pub async fn set(&self, key: String, value: CachedResponse) {
+        self.set_for_model(key, value, None).await
+    }
+
+    /// Set a value, labeling the write metric with the request model
+    ///
+    /// An existing entry with a newer `cached_at` is kept, so a write that
+    /// lost a race cannot clobber a fresher one.
+    pub async fn set_for_model(&self, key: String, value: CachedResponse, model: Option<&str>) {
+        self.write(key, value, model, false).await
+    }
+
+    /// Populate L1 from a lower tier (e.g. an L2 hit)
+    ///
+    /// Only fills an empty slot or replaces a strictly older entry: a
+    /// promotion that arrives late never overwrites a direct write, even one
+    /// with the same `cached_at`.
+    pub async fn promote(&self, key: String, value: CachedResponse) {
+        self.write(key, value, None, true).await
+    }
+
+    /// Atomically insert `value` unless the existing entry is newer
+    ///
+    /// With `keep_equal`, an existing entry of the same age is kept too.
+    async fn write(
+        &self,
+        key: String,
+        value: CachedResponse,
+        model: Option<&str>,
+        keep_equal: bool,
+    ) {
         let _timer = LatencyTimer::new(CacheTier::L1, self.metrics.clone());
 
-        debug!("L1 cache WRITE: key={}", &key[..16.min(key.len())]);
+        let short_key = key[..16.min(key.len())].to_string();
+        let incoming = value.cached_at;
+        let result = self
+            .cache
+            .entry(key)
+            .and_compute_with(|existing| {
+                let op = match existing {
+                    Some(entry)
+                        if entry.value().cached_at > incoming
+                            || (keep_equal && entry.value().cached_at == incoming) =>
+                    {
+                        Op::Nop
+                    }
+                    _ => Op::Put(Arc::new(value)),
+                };
+                std::future::ready(op)
+            })
+            .await;
+
+        if let CompResult::Unchanged(entry) = result {
+            debug!(
+                "L1 cache WRITE skipped: key={}, existing entry is newer ({} >= {})",
+                short_key,
+                entry.value().cached_at,
+                incoming
+            );
+            return;
+        }
 
-        self.cache.insert(key, Arc::new(value)).await;
+        debug!("L1 cache WRITE: key={}", short_key);
         self.metrics
-            .record_operation(CacheTier::L1, CacheOperation::Write);
+            .record_operation(CacheTier::L1, CacheOperation::Write, model);
 
         // Update size metrics
         let size = self.cache.entry_count();
@@ -129,7 +228,7 @@ impl L1Cache {
     pub async fn remove(&self, key: &str) {
         self.cache.invalidate(key).await;
         self.metrics
-            .record_operation(CacheTier::L1, CacheOperation::Delete);
+            .record_operation(CacheTier::L1, CacheOperation::Delete, None);
     }
 
     /// Clear all entries from the cache
@@ -217,11 +316,66 @@ mod tests {
         assert_eq!(cached.unwrap().content, "Hello, world!");
     }
 
+    fn response_at(content: &str, cached_at: i64) -> CachedResponse {
+        CachedResponse {
+            cached_at,
+            ..create_test_response(content)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_l1_older_write_does_not_clobber_newer() {
+        let cache = L1Cache::new(CacheMetrics::new());
+        let key = "test_key".to_string();
+
+        cache.set(key.clone(), response_at("fresh", 200)).await;
+        cache.set(key.clone(), response_at("stale", 100)).await;
+        assert_eq!(cache.get(&key).await.unwrap().content, "fresh");
+
+        cache.set(key.clone(), response_at("fresher", 300)).await;
+        assert_eq!(cache.get(&key).await.unwrap().content, "fresher");
+    }
+
+    #[tokio::test]
+    async fn test_l1_slow_promotion_loses_to_fresh_store() {
+        let cache = L1Cache::new(CacheMetrics::new());
+        let key = "test_key".to_string();
+        let now = Utc::now().timestamp();
+
+        for promoted_at in [now - 60, now] {
+            cache.remove(&key).await;
+
+            // L2 hit whose promotion is delayed past a direct store
+            let promotion = tokio::spawn({
+                let cache = cache.clone();
+                let key = key.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    cache
+                        .promote(key, response_at("promoted", promoted_at))
+                        .await;
+                }
+            });
+            cache.set(key.clone(), response_at("fresh", now)).await;
+            promotion.await.unwrap();
+
+            assert_eq!(cache.get(&key).await.unwrap().content, "fresh");
+        }
+
+        // Promotion still fills an empty slot
+        cache.remove(&key).await;
+        cache
+            .promote(key.clone(), response_at("promoted", now))
+            .await;
+        assert_eq!(cache.get(&key).await.unwrap().content, "promoted");
+    }
+
     #[tokio::test]
     async fn test_l1_eviction_by_capacity() {
         let metrics = CacheMetrics::new();
         let config = L1Config {
             max_capacity: 2,
+            max_weighted_size: None,
             ttl_seconds: 300,
             tti_seconds: 120,
         };
@@ -245,6 +399,56 @@ mod tests {
         assert!(cache.entry_count() <= 2);
     }
 
+    #[test]
+    fn test_l1_eviction_counter_by_size() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let config = L1Config {
+                    max_capacity: 2,
+                    max_weighted_size: None,
+                    ttl_seconds: 300,
+                    tti_seconds: 120,
+                };
+                let cache = L1Cache::with_config(config, CacheMetrics::new());
+
+                for i in 0..10 {
+                    cache
+                        .set(format!("key{}", i), create_test_response("value"))
+                        .await;
+                    cache.cache.run_pending_tasks().await;
+                }
+            });
+        });
+
+        let size_evictions =
+            snapshotter
+                .snapshot()
+                .into_vec()
+                .into_iter()
+                .find_map(|(key, _, _, value)| {
+                    let key = key.key();
+                    let matches = key.name() == "llm_edge_cache_l1_evictions_total"
+                        && key
+                            .labels()
+                            .any(|l| l.key() == "cause" && l.value() == "size");
+                    match value {
+                        DebugValue::Counter(v) if matches => Some(v),
+                        _ => None,
+                    }
+                });
+
+        assert!(size_evictions.unwrap_or(0) >= 8);
+    }
+
     #[tokio::test]
     async fn test_l1_remove() {
         let metrics = CacheMetrics::new();
@@ -287,6 +491,7 @@ mod tests {
         let metrics = CacheMetrics::new();
         let config = L1Config {
             max_capacity: 100,
+            max_weighted_size: None,
             ttl_seconds: 300,
             tti_seconds: 120,
         };
@@ -324,4 +529,68 @@ mod tests {
         cache.get(&key).await;
         assert_eq!(metrics.snapshot().l1_hits, 1);
     }
+
+    fn small_cache(max_capacity: u64, max_weighted_size: Option<u64>) -> L1Cache {
+        L1Cache::with_config(
+            L1Config {
+                max_capacity,
+                max_weighted_size,
+                ..L1Config::default()
+            },
+            CacheMetrics::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_l1_configured_capacity_evicts_at_limit() {
+        let cache = small_cache(3, None);
+
+        for i in 0..3 {
+            cache
+                .set(format!("key{}", i), create_test_response("value"))
+                .await;
+            cache.cache.run_pending_tasks().await;
+        }
+        assert_eq!(cache.entry_count(), 3);
+
+        for i in 3..8 {
+            cache
+                .set(format!("key{}", i), create_test_response("value"))
+                .await;
+            cache.cache.run_pending_tasks().await;
+        }
+        assert_eq!(cache.entry_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_l1_max_weighted_size_replaces_entry_limit() {
+        let entry = create_test_response(&"x".repeat(1000));
+        let entry_size = ("key0".len() + entry.approximate_size()) as u64;
+        let cache = small_cache(1000, Some(entry_size * 2));
+
+        for i in 0..5 {
+            cache.set(format!("key{}", i), entry.clone()).await;
+            cache.cache.run_pending_tasks().await;
+        }
+
+        assert_eq!(cache.entry_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_l1_ttl_and_tti_take_effect() {
+        let cache = L1Cache::with_config(
+            L1Config {
+                ttl_seconds: 2,
+                tti_seconds: 1,
+                ..L1Config::default()
+            },
+            CacheMetrics::new(),
+        );
+
+        // Expiry runs on moka's own clock, which tokio cannot pause, so
+        // check the policy it enforces rather than waiting it out
+        let policy = cache.cache.policy();
+        assert_eq!(policy.time_to_live(), Some(Duration::from_secs(2)));
+        assert_eq!(policy.time_to_idle(), Some(Duration::from_secs(1)));
+    }
 }
@@ -3,21 +3,41 @@
 //! Distributed cache layer with persistence and multi-instance sharing.
 //! Target latency: 1-2ms for get/set operations.
 
+use crate::backend::DistributedCache;
 use crate::l1::CachedResponse;
 use crate::metrics::{CacheMetrics, CacheOperation, CacheTier, LatencyTimer};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
 use redis::{AsyncCommands, RedisError};
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
+/// Keys requested per `SCAN` page
+const SCAN_PAGE_SIZE: usize = 500;
+
+/// First byte of a MessagePack entry
+///
+/// `0xC1` is never used by MessagePack and cannot start a JSON document, so
+/// entries without it are read as JSON.
+const MESSAGE_PACK_MARKER: u8 = 0xC1;
+
 /// L2 cache errors
 #[derive(Debug, Error)]
 pub enum L2Error {
     #[error("Redis connection error: {0}")]
-    Connection(#[from] RedisError),
+    Connection(RedisError),
 
     #[error("Serialization error: {0}")]
-    Serialization(#[from] serde_json::Error),
+    Serialization(String),
+
+    #[error("Deserialization error: {0}")]
+    Deserialization(String),
 
     #[error("Cache operation timeout")]
     Timeout,
@@ -26,6 +46,18 @@ pub enum L2Error {
     Unavailable,
 }
 
+impl From<RedisError> for L2Error {
+    fn from(e: RedisError) -> Self {
+        // A lost connection is reported as unavailability; the connection
+        // manager reconnects in the background on the next operation
+        if e.is_connection_dropped() || e.is_connection_refusal() || e.is_io_error() {
+            L2Error::Unavailable
+        } else {
+            L2Error::Connection(e)
+        }
+    }
+}
+
 /// Configuration for L2 cache
 #[derive(Debug, Clone)]
 pub struct L2Config {
@@ -39,6 +71,59 @@ pub struct L2Config {
     pub operation_timeout_ms: u64,
     /// Key prefix for namespacing (default: "llm_cache:")
     pub key_prefix: String,
+    /// Interval for the background entry-count task in seconds (0 disables, default: 60)
+    pub maintenance_interval_seconds: u64,
+    /// Maximum background writes in flight at once (default: 64)
+    pub max_concurrent_writes: usize,
+    /// What a write does when `max_concurrent_writes` is reached (default: drop)
+    pub write_overflow: WriteOverflowPolicy,
+    /// Format new entries are written in (default: JSON)
+    pub serialization: Serialization,
+}
+
+/// Format of the entries written to Redis
+///
+/// Entries are read in either format whatever is configured, so switching
+/// formats doesn't require flushing the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Serialization {
+    /// Plain JSON, readable by instances without this setting
+    #[default]
+    Json,
+    /// MessagePack behind a marker byte; smaller and faster to (de)serialize
+    MessagePack,
+}
+
+impl Serialization {
+    /// Parse `json` or `messagepack` (`msgpack`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "json" => Some(Serialization::Json),
+            "messagepack" | "msgpack" => Some(Serialization::MessagePack),
+            _ => None,
+        }
+    }
+
+    /// Load the format from `L2_SERIALIZATION`
+    pub fn from_env() -> Self {
+        let Ok(raw) = std::env::var("L2_SERIALIZATION") else {
+            return Self::default();
+        };
+        Self::parse(&raw).unwrap_or_else(|| {
+            warn!(value = %raw, "Invalid L2_SERIALIZATION, using json");
+            Self::default()
+        })
+    }
+}
+
+/// Behavior of a background L2 write when all write slots are busy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteOverflowPolicy {
+    /// Skip the L2 write; the response is still cached in L1
+    #[default]
+    Drop,
+    /// Wait up to the given duration for a free slot, then drop
+    Wait(Duration),
 }
 
 impl Default for L2Config {
@@ -49,16 +134,115 @@ impl Default for L2Config {
             connection_timeout_ms: 1000,
             operation_timeout_ms: 100,
             key_prefix: "llm_cache:".to_string(),
+            maintenance_interval_seconds: 60,
+            max_concurrent_writes: 64,
+            write_overflow: WriteOverflowPolicy::Drop,
+            serialization: Serialization::Json,
+        }
+    }
+}
+
+/// Bounds the number of background L2 writes in flight
+///
+/// Clones share the same slots.
+#[derive(Clone)]
+pub(crate) struct WriteLimiter {
+    slots: Arc<Semaphore>,
+    overflow: WriteOverflowPolicy,
+    metrics: CacheMetrics,
+}
+
+impl WriteLimiter {
+    pub(crate) fn new(
+        max_concurrent_writes: usize,
+        overflow: WriteOverflowPolicy,
+        metrics: CacheMetrics,
+    ) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent_writes.max(1))),
+            overflow,
+            metrics,
         }
     }
+
+    /// Spawn `write` once a slot is free
+    ///
+    /// Returns `false` if the write was dropped under the overflow policy.
+    pub(crate) async fn spawn<F>(&self, write: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let permit = match self.overflow {
+            WriteOverflowPolicy::Drop => self.slots.clone().try_acquire_owned().ok(),
+            WriteOverflowPolicy::Wait(max_wait) => {
+                tokio::time::timeout(max_wait, self.slots.clone().acquire_owned())
+                    .await
+                    .ok()
+                    .and_then(Result::ok)
+            }
+        };
+
+        let Some(permit) = permit else {
+            debug!("L2 write slots exhausted, dropping write");
+            self.metrics.record_l2_write_dropped();
+            return false;
+        };
+
+        tokio::spawn(async move {
+            write.await;
+            drop(permit);
+        });
+        true
+    }
+}
+
+/// Encode a value for storage in `format`, counting failures
+fn encode_entry<T: serde::Serialize>(
+    value: &T,
+    format: Serialization,
+    metrics: &CacheMetrics,
+) -> Result<Vec<u8>, L2Error> {
+    let encoded = match format {
+        Serialization::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+        Serialization::MessagePack => {
+            let mut entry = vec![MESSAGE_PACK_MARKER];
+            rmp_serde::encode::write_named(&mut entry, value)
+                .map(|()| entry)
+                .map_err(|e| e.to_string())
+        }
+    };
+
+    encoded.map_err(|e| {
+        metrics.record_l2_serialization_error();
+        L2Error::Serialization(e)
+    })
+}
+
+/// Decode a stored value in whichever format it was written, counting failures
+fn decode_entry(data: &[u8], metrics: &CacheMetrics) -> Result<CachedResponse, L2Error> {
+    let decoded = match data.split_first() {
+        Some((&MESSAGE_PACK_MARKER, packed)) => {
+            rmp_serde::from_slice(packed).map_err(|e| e.to_string())
+        }
+        _ => serde_json::from_slice(data).map_err(|e| e.to_string()),
+    };
+
+    decoded.map_err(|e| {
+        metrics.record_l2_deserialization_error();
+        L2Error::Deserialization(e)
+    })
 }
 
 /// L2 cache implementation using Redis
+///
+/// Holds a single auto-reconnecting multiplexed connection created at
+/// construction; clones share it.
 #[derive(Clone)]
 pub struct L2Cache {
-    client: redis::Client,
+    conn: ConnectionManager,
     config: L2Config,
     metrics: CacheMetrics,
+    writes: WriteLimiter,
 }
 
 impl L2Cache {
@@ -76,16 +260,27 @@ impl L2Cache {
 
         let client = redis::Client::open(config.redis_url.as_str())?;
 
+        let manager_config = ConnectionManagerConfig::new()
+            .set_connection_timeout(Duration::from_millis(config.connection_timeout_ms))
+            .set_response_timeout(Duration::from_millis(config.operation_timeout_ms));
+        let mut conn = ConnectionManager::new_with_config(client, manager_config).await?;
+
         // Test connection
-        let mut conn = client.get_multiplexed_async_connection().await?;
         let _: () = redis::cmd("PING").query_async(&mut conn).await?;
 
         info!("L2 cache connected to Redis successfully");
 
+        let writes = WriteLimiter::new(
+            config.max_concurrent_writes,
+            config.write_overflow,
+            metrics.clone(),
+        );
+
         Ok(Self {
-            client,
+            conn,
             config,
             metrics,
+            writes,
         })
     }
 
@@ -94,6 +289,15 @@ impl L2Cache {
     /// # Performance
     /// Target: 1-2ms (network round-trip)
     pub async fn get(&self, key: &str) -> Result<Option<CachedResponse>, L2Error> {
+        self.get_for_model(key, None).await
+    }
+
+    /// Get a value, labeling the hit/miss metric with the request model
+    pub async fn get_for_model(
+        &self,
+        key: &str,
+        model: Option<&str>,
+    ) -> Result<Option<CachedResponse>, L2Error> {
         let _timer = LatencyTimer::new(CacheTier::L2, self.metrics.clone());
 
         let prefixed_key = self.prefixed_key(key);
@@ -109,41 +313,49 @@ impl L2Cache {
             Ok(Ok(Some(value))) => {
                 debug!("L2 cache HIT: key={}", &key[..16.min(key.len())]);
                 self.metrics
-                    .record_operation(CacheTier::L2, CacheOperation::Hit);
+                    .record_operation(CacheTier::L2, CacheOperation::Hit, model);
                 Ok(Some(value))
             }
             Ok(Ok(None)) => {
                 debug!("L2 cache MISS: key={}", &key[..16.min(key.len())]);
                 self.metrics
-                    .record_operation(CacheTier::L2, CacheOperation::Miss);
+                    .record_operation(CacheTier::L2, CacheOperation::Miss, model);
                 Ok(None)
             }
             Ok(Err(e)) => {
                 warn!("L2 cache GET error: {}", e);
                 self.metrics
-                    .record_operation(CacheTier::L2, CacheOperation::Miss);
+                    .record_operation(CacheTier::L2, CacheOperation::Miss, model);
                 Err(e)
             }
             Err(_) => {
                 warn!("L2 cache GET timeout");
                 self.metrics
-                    .record_operation(CacheTier::L2, CacheOperation::Miss);
+                    .record_operation(CacheTier::L2, CacheOperation::Miss, model);
                 Err(L2Error::Timeout)
             }
         }
     }
 
     /// Internal get implementation
+    ///
+    /// A corrupt entry is deleted and reported as a miss so it can't poison
+    /// later reads.
     async fn get_internal(&self, key: &str) -> Result<Option<CachedResponse>, L2Error> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let data: Option<String> = conn.get(key).await?;
+        let mut conn = self.conn.clone();
+        let data: Option<Vec<u8>> = conn.get(key).await?;
 
-        match data {
-            Some(json) => {
-                let response: CachedResponse = serde_json::from_str(&json)?;
-                Ok(Some(response))
+        let Some(data) = data else {
+            return Ok(None);
+        };
+
+        match decode_entry(&data, &self.metrics) {
+            Ok(response) => Ok(Some(response)),
+            Err(e) => {
+                warn!("Deleting corrupt L2 cache entry: {}", e);
+                let _: () = conn.del(key).await?;
+                Ok(None)
             }
-            None => Ok(None),
         }
     }
 
@@ -156,12 +368,35 @@ impl L2Cache {
         self.set_with_ttl(key, value, self.config.ttl_seconds).await
     }
 
+    /// Set a value, labeling the write metric with the request model
+    pub async fn set_for_model(
+        &self,
+        key: String,
+        value: CachedResponse,
+        model: Option<&str>,
+    ) -> Result<(), L2Error> {
+        self.set_with_ttl_for_model(key, value, self.config.ttl_seconds, model)
+            .await
+    }
+
     /// Set a value in the cache with custom TTL
     pub async fn set_with_ttl(
         &self,
         key: String,
         value: CachedResponse,
         ttl_seconds: u64,
+    ) -> Result<(), L2Error> {
+        self.set_with_ttl_for_model(key, value, ttl_seconds, None)
+            .await
+    }
+
+    /// Set a value with custom TTL, labeling the write metric with the request model
+    pub async fn set_with_ttl_for_model(
+        &self,
+        key: String,
+        value: CachedResponse,
+        ttl_seconds: u64,
+        model: Option<&str>,
     ) -> Result<(), L2Error> {
         let _timer = LatencyTimer::new(CacheTier::L2, self.metrics.clone());
 
@@ -178,7 +413,7 @@ impl L2Cache {
             Ok(Ok(())) => {
                 debug!("L2 cache WRITE: key={}", &key[..16.min(key.len())]);
                 self.metrics
-                    .record_operation(CacheTier::L2, CacheOperation::Write);
+                    .record_operation(CacheTier::L2, CacheOperation::Write, model);
                 Ok(())
             }
             Ok(Err(e)) => {
@@ -199,11 +434,11 @@ impl L2Cache {
         value: CachedResponse,
         ttl_seconds: u64,
     ) -> Result<(), L2Error> {
-        let json = serde_json::to_string(&value)?;
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let entry = encode_entry(&value, self.config.serialization, &self.metrics)?;
+        let mut conn = self.conn.clone();
 
         // Use SETEX to set value with expiration atomically
-        let _: () = conn.set_ex(&key, json, ttl_seconds).await?;
+        let _: () = conn.set_ex(&key, entry, ttl_seconds).await?;
 
         Ok(())
     }
@@ -211,52 +446,128 @@ impl L2Cache {
     /// Remove a value from the cache
     pub async fn remove(&self, key: &str) -> Result<(), L2Error> {
         let prefixed_key = self.prefixed_key(key);
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.conn.clone();
 
         let _: () = conn.del(&prefixed_key).await?;
         self.metrics
-            .record_operation(CacheTier::L2, CacheOperation::Delete);
+            .record_operation(CacheTier::L2, CacheOperation::Delete, None);
 
         Ok(())
     }
 
     /// Clear all cache entries (use with caution!)
+    ///
+    /// Walks the keyspace with `SCAN` and deletes page by page, so large
+    /// datasets don't block Redis the way `KEYS` would.
     pub async fn clear(&self) -> Result<(), L2Error> {
         info!("Clearing L2 cache with prefix: {}", self.config.key_prefix);
 
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let pattern = format!("{}*", self.config.key_prefix);
+        let mut conn = self.conn.clone();
+        let mut cursor = 0;
+        let mut cleared = 0;
+
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor).await?;
 
-        // Get all keys matching the pattern
-        let keys: Vec<String> = conn.keys(&pattern).await?;
+            if !keys.is_empty() {
+                let _: () = conn.del(&keys).await?;
+                cleared += keys.len();
+            }
 
-        if !keys.is_empty() {
-            let _: () = conn.del(&keys).await?;
-            info!("Cleared {} keys from L2 cache", keys.len());
+            if next == 0 {
+                break;
+            }
+            cursor = next;
         }
 
+        info!("Cleared {} keys from L2 cache", cleared);
         Ok(())
     }
 
     /// Check if Redis connection is healthy
     pub async fn health_check(&self) -> bool {
-        match self.client.get_multiplexed_async_connection().await {
-            Ok(mut conn) => {
-                let result: Result<String, RedisError> =
-                    redis::cmd("PING").query_async(&mut conn).await;
-                result.is_ok()
-            }
-            Err(_) => false,
-        }
+        let mut conn = self.conn.clone();
+        let result: Result<String, RedisError> = redis::cmd("PING").query_async(&mut conn).await;
+        result.is_ok()
     }
 
     /// Get the current size of the cache (approximate)
+    ///
+    /// Counts prefixed keys with a `SCAN` loop; entries may be counted twice
+    /// or missed if the keyspace changes mid-scan.
     pub async fn approximate_size(&self) -> Result<usize, L2Error> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        let mut conn = self.conn.clone();
+        let mut cursor = 0;
+        let mut count = 0;
+
+        loop {
+            let (next, keys) = self.scan_page(&mut conn, cursor).await?;
+            count += keys.len();
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        Ok(count)
+    }
+
+    /// Fetch one `SCAN` page of prefixed keys starting at `cursor`
+    async fn scan_page(
+        &self,
+        conn: &mut ConnectionManager,
+        cursor: u64,
+    ) -> Result<(u64, Vec<String>), L2Error> {
         let pattern = format!("{}*", self.config.key_prefix);
-        let keys: Vec<String> = conn.keys(&pattern).await?;
+        let page = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(&pattern)
+            .arg("COUNT")
+            .arg(SCAN_PAGE_SIZE)
+            .query_async(conn)
+            .await?;
+
+        Ok(page)
+    }
+
+    /// Spawn the background maintenance task
+    ///
+    /// Periodically reports the L2 entry count as the
+    /// `llm_edge_cache_size_entries{tier="l2"}` gauge. Returns `None` when
+    /// `maintenance_interval_seconds` is 0.
+    pub fn spawn_maintenance(&self) -> Option<JoinHandle<()>> {
+        if self.config.maintenance_interval_seconds == 0 {
+            return None;
+        }
 
-        Ok(keys.len())
+        let cache = self.clone();
+        let period = Duration::from_secs(self.config.maintenance_interval_seconds);
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                match cache.approximate_size().await {
+                    Ok(count) => {
+                        debug!("L2 cache entry count: {}", count);
+                        cache.metrics.update_cache_size(CacheTier::L2, count as u64);
+                    }
+                    Err(e) => warn!("L2 maintenance scan failed: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Run a write in the background, bounded by `max_concurrent_writes`
+    ///
+    /// Returns `false` if the write was dropped because all slots were busy.
+    pub async fn spawn_write<F>(&self, write: F) -> bool
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.writes.spawn(write).await
     }
 
     /// Add key prefix for namespacing
@@ -270,6 +581,53 @@ impl L2Cache {
     }
 }
 
+#[async_trait]
+impl DistributedCache for L2Cache {
+    async fn get(&self, key: &str, model: Option<&str>) -> Result<Option<CachedResponse>, L2Error> {
+        self.get_for_model(key, model).await
+    }
+
+    async fn set(
+        &self,
+        key: String,
+        value: CachedResponse,
+        model: Option<&str>,
+    ) -> Result<(), L2Error> {
+        self.set_for_model(key, value, model).await
+    }
+
+    async fn set_with_ttl(
+        &self,
+        key: String,
+        value: CachedResponse,
+        ttl_seconds: u64,
+        model: Option<&str>,
+    ) -> Result<(), L2Error> {
+        self.set_with_ttl_for_model(key, value, ttl_seconds, model)
+            .await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), L2Error> {
+        L2Cache::remove(self, key).await
+    }
+
+    async fn clear(&self) -> Result<(), L2Error> {
+        L2Cache::clear(self).await
+    }
+
+    async fn health_check(&self) -> bool {
+        L2Cache::health_check(self).await
+    }
+
+    async fn approximate_size(&self) -> Result<usize, L2Error> {
+        L2Cache::approximate_size(self).await
+    }
+
+    async fn spawn_write(&self, write: BoxFuture<'static, ()>) -> bool {
+        L2Cache::spawn_write(self, write).await
+    }
+}
+
 /// Helper function to create L2 cache with graceful fallback
 ///
 /// If Redis is unavailable, returns None and logs a warning.
@@ -417,4 +775,310 @@ mod tests {
         // Cleanup
         cache.remove(&key).await.unwrap();
     }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_l2_corrupt_entry_is_miss_and_removed() {
+        let metrics = CacheMetrics::new();
+        let cache = L2Cache::new(metrics.clone())
+            .await
+            .expect("Redis not available");
+
+        let key = "test_corrupt_key".to_string();
+        cache
+            .set(key.clone(), create_test_response("fine"))
+            .await
+            .unwrap();
+
+        // Overwrite the stored JSON with garbage
+        let prefixed = cache.prefixed_key(&key);
+        let mut conn = cache.conn.clone();
+        let _: () = conn.set(&prefixed, "{not json").await.unwrap();
+
+        assert!(cache.get(&key).await.unwrap().is_none());
+        assert_eq!(metrics.snapshot().l2_deserialization_errors, 1);
+
+        let exists: bool = conn.exists(&prefixed).await.unwrap();
+        assert!(!exists, "corrupt entry should be deleted");
+    }
+
+    #[test]
+    fn test_serialization_errors_are_counted() {
+        let metrics = CacheMetrics::new();
+
+        // JSON object keys must be strings
+        let unencodable = std::collections::HashMap::from([((1, 2), "value")]);
+        let err = encode_entry(&unencodable, Serialization::Json, &metrics).unwrap_err();
+        assert!(matches!(err, L2Error::Serialization(_)));
+
+        let err = decode_entry(b"{not json", &metrics).unwrap_err();
+        assert!(matches!(err, L2Error::Deserialization(_)));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.l2_serialization_errors, 1);
+        assert_eq!(snapshot.l2_deserialization_errors, 1);
+
+        assert!(encode_entry(&create_test_response("ok"), Serialization::Json, &metrics).is_ok());
+        assert_eq!(metrics.snapshot().l2_serialization_errors, 1);
+
+        // A MessagePack marker followed by garbage is corrupt, not JSON
+        let err = decode_entry(&[MESSAGE_PACK_MARKER, 0xFF], &metrics).unwrap_err();
+        assert!(matches!(err, L2Error::Deserialization(_)));
+    }
+
+    #[test]
+    fn test_entries_round_trip_in_both_formats() {
+        let metrics = CacheMetrics::new();
+        let response = create_test_response("Hello, Redis!");
+
+        let json = encode_entry(&response, Serialization::Json, &metrics).unwrap();
+        let packed = encode_entry(&response, Serialization::MessagePack, &metrics).unwrap();
+        assert_eq!(json[0], b'{');
+        assert_eq!(packed[0], MESSAGE_PACK_MARKER);
+        assert!(packed.len() < json.len());
+
+        for entry in [json, packed] {
+            let decoded = decode_entry(&entry, &metrics).unwrap();
+            assert_eq!(decoded.content, response.content);
+            assert_eq!(decoded.model, response.model);
+            assert_eq!(decoded.cached_at, response.cached_at);
+            assert_eq!(decoded.tokens.unwrap().total_tokens, 30);
+        }
+        assert_eq!(metrics.snapshot().l2_deserialization_errors, 0);
+    }
+
+    #[test]
+    fn test_parse_serialization() {
+        assert_eq!(Serialization::parse(""), Some(Serialization::Json));
+        assert_eq!(
+            Serialization::parse("MessagePack"),
+            Some(Serialization::MessagePack)
+        );
+        assert_eq!(
+            Serialization::parse("msgpack"),
+            Some(Serialization::MessagePack)
+        );
+        assert_eq!(Serialization::parse("cbor"), None);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_l2_message_pack_reads_existing_json_entries() {
+        let metrics = CacheMetrics::new();
+        let json_cache = L2Cache::new(metrics.clone())
+            .await
+            .expect("Redis not available");
+        let packed_cache = L2Cache::with_config(
+            L2Config {
+                serialization: Serialization::MessagePack,
+                ..Default::default()
+            },
+            metrics.clone(),
+        )
+        .await
+        .expect("Redis not available");
+
+        // Written before the switch, read after it
+        let key = "test_mixed_format_key".to_string();
+        json_cache
+            .set(key.clone(), create_test_response("from json"))
+            .await
+            .unwrap();
+        let cached = packed_cache.get(&key).await.unwrap().unwrap();
+        assert_eq!(cached.content, "from json");
+
+        // And the other way round, for instances rolled back to JSON
+        packed_cache
+            .set(key.clone(), create_test_response("from msgpack"))
+            .await
+            .unwrap();
+        let cached = json_cache.get(&key).await.unwrap().unwrap();
+        assert_eq!(cached.content, "from msgpack");
+        assert_eq!(metrics.snapshot().l2_deserialization_errors, 0);
+
+        json_cache.remove(&key).await.unwrap();
+    }
+
+    async fn client_id(cache: &L2Cache) -> i64 {
+        let mut conn = cache.conn.clone();
+        redis::cmd("CLIENT")
+            .arg("ID")
+            .query_async(&mut conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_l2_reuses_single_connection() {
+        let metrics = CacheMetrics::new();
+        let cache = L2Cache::new(metrics).await.expect("Redis not available");
+
+        let before = client_id(&cache).await;
+
+        for i in 0..100 {
+            let key = format!("test_reuse_key_{}", i);
+            cache
+                .set(key.clone(), create_test_response("test"))
+                .await
+                .unwrap();
+            assert!(cache.get(&key).await.unwrap().is_some());
+            cache.remove(&key).await.unwrap();
+        }
+
+        // Clones share the same connection too
+        let clone = cache.clone();
+        assert_eq!(client_id(&clone).await, before);
+        assert_eq!(client_id(&cache).await, before);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_l2_reconnects_after_connection_drop() {
+        let metrics = CacheMetrics::new();
+        let cache = L2Cache::new(metrics).await.expect("Redis not available");
+        let key = "test_reconnect_key".to_string();
+
+        // Kill our connection from a separate client
+        let old_id = client_id(&cache).await;
+        let admin = redis::Client::open(cache.config().redis_url.as_str()).unwrap();
+        let mut admin_conn = admin.get_multiplexed_async_connection().await.unwrap();
+        let _: () = redis::cmd("CLIENT")
+            .arg("KILL")
+            .arg("ID")
+            .arg(old_id)
+            .query_async(&mut admin_conn)
+            .await
+            .unwrap();
+
+        // The first operation may observe the drop; later ones succeed on a new connection
+        let mut recovered = false;
+        for _ in 0..20 {
+            match cache.set(key.clone(), create_test_response("back")).await {
+                Ok(()) => {
+                    recovered = true;
+                    break;
+                }
+                Err(L2Error::Unavailable) | Err(L2Error::Timeout) => {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => panic!("unexpected error after drop: {}", e),
+            }
+        }
+
+        assert!(recovered, "L2 cache did not reconnect");
+        assert_ne!(client_id(&cache).await, old_id);
+        assert!(cache.get(&key).await.unwrap().is_some());
+
+        // Cleanup
+        cache.remove(&key).await.unwrap();
+    }
+
+    async fn keys_command_calls(conn: &mut ConnectionManager) -> u64 {
+        let info: String = redis::cmd("INFO")
+            .arg("commandstats")
+            .query_async(conn)
+            .await
+            .unwrap();
+
+        info.lines()
+            .find_map(|line| line.strip_prefix("cmdstat_keys:calls="))
+            .and_then(|rest| rest.split(',').next())
+            .and_then(|calls| calls.parse().ok())
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_l2_clear_uses_scan() {
+        let metrics = CacheMetrics::new();
+        let config = L2Config {
+            key_prefix: "test_scan_clear:".to_string(),
+            ..Default::default()
+        };
+        let cache = L2Cache::with_config(config, metrics)
+            .await
+            .expect("Redis not available");
+
+        // More keys than one SCAN page
+        for i in 0..(SCAN_PAGE_SIZE * 3) {
+            cache
+                .set(format!("key_{}", i), create_test_response("test"))
+                .await
+                .unwrap();
+        }
+        assert_eq!(cache.approximate_size().await.unwrap(), SCAN_PAGE_SIZE * 3);
+
+        let mut conn = cache.conn.clone();
+        let keys_calls_before = keys_command_calls(&mut conn).await;
+
+        cache.clear().await.unwrap();
+
+        assert_eq!(cache.approximate_size().await.unwrap(), 0);
+        assert_eq!(keys_command_calls(&mut conn).await, keys_calls_before);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_write_limiter_caps_in_flight_writes() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let limiter = WriteLimiter::new(
+            4,
+            WriteOverflowPolicy::Wait(Duration::from_secs(5)),
+            CacheMetrics::new(),
+        );
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let completed = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..100 {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            let completed = completed.clone();
+            let spawned = limiter
+                .spawn(async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(2)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    completed.fetch_add(1, Ordering::SeqCst);
+                })
+                .await;
+            assert!(spawned);
+        }
+
+        while completed.load(Ordering::SeqCst) < 100 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 4);
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_limiter_drops_when_full() {
+        let metrics = CacheMetrics::new();
+        let limiter = WriteLimiter::new(2, WriteOverflowPolicy::Drop, metrics.clone());
+        let (release, released) = tokio::sync::watch::channel(false);
+
+        let mut spawned = 0;
+        for _ in 0..5 {
+            let mut released = released.clone();
+            if limiter
+                .spawn(async move {
+                    let _ = released.wait_for(|done| *done).await;
+                })
+                .await
+            {
+                spawned += 1;
+            }
+        }
+
+        assert_eq!(spawned, 2);
+        assert_eq!(metrics.snapshot().l2_writes_dropped, 3);
+
+        // Slots free up once the in-flight writes finish
+        release.send(true).unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(limiter.spawn(async {}).await);
+    }
 }
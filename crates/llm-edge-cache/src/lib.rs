@@ -2,7 +2,8 @@
 //!
 //! This module implements a high-performance multi-tier caching system with:
 //! - L1: In-memory cache (Moka) - <1ms latency, TinyLFU eviction
-//! - L2: Distributed cache (Redis) - 1-2ms latency, persistent across instances
+//! - L2: Distributed cache (Redis by default, or any [`DistributedCache`]) -
+//!   1-2ms latency, persistent across instances
 //!
 //! # Architecture
 //!
@@ -17,7 +18,7 @@
 //!                ↓
 //!           Provider Execution
 //!                ↓
-//!           Async Write → L1 + L2 (non-blocking)
+//!           Write → L1, then bounded background write → L2
 //! ```
 //!
 //! # Performance Targets
@@ -26,17 +27,38 @@
 //! - Overall Hit Rate: >50% (MVP), >70% (Beta)
 //! - L1 TTL: 5 minutes (default)
 //! - L2 TTL: 1 hour (default)
+//!
+//! # Stale-while-revalidate
+//!
+//! With a [`StaleWhileRevalidate`] policy, [`CacheManager::lookup_or_refresh`]
+//! serves entries older than the soft TTL immediately and refreshes them in
+//! the background; entries older than the hard TTL are treated as misses.
+//!
+//! # Promotion
+//!
+//! L2 hits are copied into L1 on the first hit by default. A
+//! [`PromotionPolicy`] with `min_hits` above 1 only promotes entries hit that
+//! often within its window, so one-off queries don't evict hot L1 entries.
 
+pub mod backend;
 pub mod key;
 pub mod l1;
 pub mod l2;
 pub mod metrics;
+pub mod promotion;
 
-use self::key::{generate_cache_key, CacheableRequest};
-use self::l1::{CachedResponse, L1Cache};
-use self::l2::{create_l2_cache_optional, L2Cache, L2Config};
-use self::metrics::{CacheMetrics, MetricsSnapshot};
-use std::sync::Arc;
+pub use self::backend::DistributedCache;
+pub use self::promotion::PromotionPolicy;
+
+use self::key::{generate_versioned_cache_key, CacheableRequest};
+use self::l1::{CachedResponse, L1Cache, L1Config};
+use self::l2::{create_l2_cache_optional, L2Config};
+use self::metrics::{CacheMetrics, CacheTier, MetricsSnapshot};
+use self::promotion::PromotionGate;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 /// Result of a cache lookup operation
@@ -63,14 +85,113 @@ impl CacheLookupResult {
     }
 }
 
+/// Ages, in seconds since `cached_at`, at which entries go stale or expire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleWhileRevalidate {
+    /// Older entries are still served, but refreshed in the background
+    pub soft_ttl_seconds: u64,
+    /// Older entries are not served at all
+    pub hard_ttl_seconds: u64,
+}
+
+/// Largest responses each tier stores, by content length in bytes
+///
+/// A handful of very large completions can take as much L1 memory as
+/// thousands of small hot entries and push them out. Responses over a tier's
+/// limit are still returned to the client, just not written to that tier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponseSizeLimits {
+    /// `None` caches responses of any size in L1
+    pub l1_max_bytes: Option<usize>,
+    /// `None` caches responses of any size in L2
+    pub l2_max_bytes: Option<usize>,
+}
+
+impl ResponseSizeLimits {
+    /// Whether `response` is within `tier`'s limit
+    fn fits(&self, tier: CacheTier, response: &CachedResponse) -> bool {
+        let limit = match tier {
+            CacheTier::L1 => self.l1_max_bytes,
+            _ => self.l2_max_bytes,
+        };
+        !limit.is_some_and(|max| response.content.len() > max)
+    }
+
+    /// Whether `response` may be written to `tier`; counts it if not
+    fn admits(&self, tier: CacheTier, response: &CachedResponse, metrics: &CacheMetrics) -> bool {
+        if !self.fits(tier, response) {
+            debug!(
+                tier = tier.as_str(),
+                bytes = response.content.len(),
+                "Response too large to cache"
+            );
+            metrics.record_skipped_too_large(tier);
+            return false;
+        }
+        true
+    }
+}
+
+/// How an entry's age compares to the [`StaleWhileRevalidate`] thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Freshness {
+    Fresh,
+    Stale,
+    Expired,
+}
+
+/// Current Unix time in seconds, compared against `cached_at`
+pub type Clock = Arc<dyn Fn() -> i64 + Send + Sync>;
+
 /// Multi-tier cache orchestrator
 ///
 /// This is the main interface for cache operations. It coordinates
 /// lookups and writes across L1 and L2 cache tiers.
 pub struct CacheManager {
     l1: L1Cache,
-    l2: Option<L2Cache>,
+    l2: Option<Arc<dyn DistributedCache>>,
     metrics: CacheMetrics,
+    stale_policy: Option<StaleWhileRevalidate>,
+    clock: Clock,
+    /// Mixed into every key; bumping it invalidates all existing entries
+    key_version: u32,
+    /// Keys with a background refresh in flight
+    refreshing: Arc<Mutex<HashSet<String>>>,
+    /// Decides which L2 hits are copied into L1; hit counts are shared by
+    /// clones of the manager
+    promotion: Arc<PromotionGate>,
+    size_limits: ResponseSizeLimits,
+    /// L2 maintenance task, shared by clones of the manager
+    maintenance: Option<Arc<MaintenanceTask>>,
+}
+
+/// Background task aborted once the last manager sharing it is dropped
+struct MaintenanceTask(JoinHandle<()>);
+
+impl Drop for MaintenanceTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn system_clock() -> Clock {
+    Arc::new(|| chrono::Utc::now().timestamp())
+}
+
+/// Marks a key as being refreshed until dropped, so a refresh that panics
+/// or is cancelled does not block every later refresh of the key
+struct RefreshGuard {
+    refreshing: Arc<Mutex<HashSet<String>>>,
+    cache_key: String,
+}
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        self.refreshing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.cache_key);
+    }
 }
 
 impl CacheManager {
@@ -83,6 +204,13 @@ impl CacheManager {
             l1,
             l2: None,
             metrics,
+            stale_policy: None,
+            clock: system_clock(),
+            key_version: 0,
+            refreshing: Arc::default(),
+            promotion: Arc::new(PromotionGate::new(PromotionPolicy::default())),
+            size_limits: ResponseSizeLimits::default(),
+            maintenance: None,
         }
     }
 
@@ -92,7 +220,98 @@ impl CacheManager {
         let l1 = L1Cache::new(metrics.clone());
         let l2 = create_l2_cache_optional(l2_config, metrics.clone()).await;
 
-        Self { l1, l2, metrics }
+        let maintenance = l2
+            .as_ref()
+            .and_then(|l2| l2.spawn_maintenance())
+            .map(|task| Arc::new(MaintenanceTask(task)));
+
+        Self {
+            l1,
+            l2: l2.map(|l2| Arc::new(l2) as Arc<dyn DistributedCache>),
+            metrics,
+            stale_policy: None,
+            clock: system_clock(),
+            key_version: 0,
+            refreshing: Arc::default(),
+            promotion: Arc::new(PromotionGate::new(PromotionPolicy::default())),
+            size_limits: ResponseSizeLimits::default(),
+            maintenance,
+        }
+    }
+
+    /// Create a new cache manager with L1 and a custom L2 backend
+    pub fn with_backend(backend: Arc<dyn DistributedCache>) -> Self {
+        let metrics = CacheMetrics::new();
+        let l1 = L1Cache::new(metrics.clone());
+
+        Self {
+            l1,
+            l2: Some(backend),
+            metrics,
+            stale_policy: None,
+            clock: system_clock(),
+            key_version: 0,
+            refreshing: Arc::default(),
+            promotion: Arc::new(PromotionGate::new(PromotionPolicy::default())),
+            size_limits: ResponseSizeLimits::default(),
+            maintenance: None,
+        }
+    }
+
+    /// Replace the L1 tier with one built from `config`
+    ///
+    /// Call before the cache is used: entries already in L1 are dropped.
+    pub fn with_l1_config(mut self, config: L1Config) -> Self {
+        self.l1 = L1Cache::with_config(config, self.metrics.clone());
+        self
+    }
+
+    /// Current L1 settings
+    pub fn l1_config(&self) -> &L1Config {
+        self.l1.config()
+    }
+
+    /// Serve soft-stale entries while refreshing them (see [`Self::lookup_or_refresh`])
+    pub fn with_stale_while_revalidate(mut self, policy: StaleWhileRevalidate) -> Self {
+        self.stale_policy = Some(policy);
+        self
+    }
+
+    /// Generate keys under `version`
+    ///
+    /// Entries stored under any other version become unreachable and age out
+    /// of each tier on their own, so bumping the version invalidates the
+    /// whole cache without a flush.
+    pub fn with_key_version(mut self, version: u32) -> Self {
+        self.key_version = version;
+        self
+    }
+
+    /// Version keys are currently generated under
+    pub fn key_version(&self) -> u32 {
+        self.key_version
+    }
+
+    fn cache_key(&self, request: &CacheableRequest) -> String {
+        generate_versioned_cache_key(request, self.key_version)
+    }
+
+    /// Only promote L2 hits into L1 as `policy` allows
+    pub fn with_promotion_policy(mut self, policy: PromotionPolicy) -> Self {
+        self.promotion = Arc::new(PromotionGate::new(policy));
+        self
+    }
+
+    /// Leave responses over these sizes out of the cache
+    pub fn with_response_size_limits(mut self, limits: ResponseSizeLimits) -> Self {
+        self.size_limits = limits;
+        self
+    }
+
+    /// Replace the clock entry ages are measured with
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
     }
 
     /// Lookup a request in the cache
@@ -100,36 +319,148 @@ impl CacheManager {
     /// # Flow
     /// 1. Check L1 (in-memory)
     /// 2. If miss, check L2 (Redis)
-    /// 3. If L2 hit, populate L1
+    /// 3. If L2 hit, populate L1 (once hit often enough, see [`PromotionPolicy`])
     /// 4. Return result
     ///
     /// # Performance
     /// - L1 hit: <1ms
     /// - L2 hit: 1-2ms
+    ///
+    /// With a [`StaleWhileRevalidate`] policy, entries past the hard TTL are
+    /// misses; soft-stale entries are returned like fresh ones.
     pub async fn lookup(&self, request: &CacheableRequest) -> CacheLookupResult {
-        let cache_key = generate_cache_key(request);
+        self.lookup_with_freshness(request).await.0
+    }
+
+    /// Lookup a request, refreshing a soft-stale hit in the background
+    ///
+    /// A hit older than the soft TTL is returned immediately and counted in
+    /// `llm_edge_cache_stale_served_total`; `refresh` is then spawned and its
+    /// response, if any, stored in every tier. Only one refresh per key runs
+    /// at a time. Without a [`StaleWhileRevalidate`] policy this is
+    /// [`Self::lookup`] and `refresh` is never called.
+    pub async fn lookup_or_refresh<F, Fut>(
+        &self,
+        request: &CacheableRequest,
+        refresh: F,
+    ) -> CacheLookupResult
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<CachedResponse>> + Send + 'static,
+    {
+        let (result, freshness) = self.lookup_with_freshness(request).await;
+        if freshness != Some(Freshness::Stale) {
+            return result;
+        }
+
+        let tier = match result {
+            CacheLookupResult::L2Hit(_) => CacheTier::L2,
+            _ => CacheTier::L1,
+        };
+        self.metrics.record_stale_served(tier, &request.model);
+
+        let cache_key = self.cache_key(request);
+        if !self.refreshing.lock().unwrap().insert(cache_key.clone()) {
+            debug!("Stale entry already being refreshed");
+            return result;
+        }
+
+        // Released however the refresh ends, panics included
+        let guard = RefreshGuard {
+            refreshing: self.refreshing.clone(),
+            cache_key: cache_key.clone(),
+        };
+
+        debug!("Serving stale entry, refreshing in background");
+        let refresh = refresh();
+        let l1 = self.l1.clone();
+        let l2 = self.l2.clone();
+        let model = request.model.clone();
+        let size_limits = self.size_limits;
+        let metrics = self.metrics.clone();
+        tokio::spawn(async move {
+            if let Some(response) = refresh.await {
+                if size_limits.admits(CacheTier::L1, &response, &metrics) {
+                    l1.set_for_model(cache_key.clone(), response.clone(), Some(&model))
+                        .await;
+                }
+                if let Some(l2) =
+                    l2.filter(|_| size_limits.admits(CacheTier::L2, &response, &metrics))
+                {
+                    if let Err(e) = l2.set(cache_key.clone(), response, Some(&model)).await {
+                        warn!("L2 cache write error during refresh: {}", e);
+                    }
+                }
+            }
+            drop(guard);
+        });
+
+        result
+    }
+
+    /// Age class of a cached entry under the stale policy, if one is set
+    fn freshness(&self, response: &CachedResponse) -> Option<Freshness> {
+        let policy = self.stale_policy?;
+        let age = (self.clock)().saturating_sub(response.cached_at).max(0) as u64;
+
+        Some(if age > policy.hard_ttl_seconds {
+            Freshness::Expired
+        } else if age > policy.soft_ttl_seconds {
+            Freshness::Stale
+        } else {
+            Freshness::Fresh
+        })
+    }
+
+    async fn lookup_with_freshness(
+        &self,
+        request: &CacheableRequest,
+    ) -> (CacheLookupResult, Option<Freshness>) {
+        let cache_key = self.cache_key(request);
+        let model = Some(request.model.as_str());
 
         // L1 lookup
-        if let Some(response) = self.l1.get(&cache_key).await {
+        if let Some(response) = self.l1.get_for_model(&cache_key, model).await {
+            let freshness = self.freshness(&response);
+            if freshness == Some(Freshness::Expired) {
+                // L2 holds the same write, so it is past the hard TTL too
+                debug!("Cache entry past hard TTL: L1");
+                return (CacheLookupResult::Miss, None);
+            }
             debug!("Cache HIT: L1");
-            return CacheLookupResult::L1Hit(response);
+            return (CacheLookupResult::L1Hit(response), freshness);
         }
 
         // L2 lookup (if available)
         if let Some(ref l2) = self.l2 {
-            match l2.get(&cache_key).await {
+            match l2.get(&cache_key, model).await {
                 Ok(Some(response)) => {
+                    let freshness = self.freshness(&response);
+                    if freshness == Some(Freshness::Expired) {
+                        debug!("Cache entry past hard TTL: L2");
+                        return (CacheLookupResult::Miss, None);
+                    }
                     debug!("Cache HIT: L2");
 
-                    // Populate L1 asynchronously (fire-and-forget)
-                    let l1_clone = self.l1.clone();
-                    let key_clone = cache_key.clone();
-                    let response_clone = response.clone();
-                    tokio::spawn(async move {
-                        l1_clone.set(key_clone, response_clone).await;
-                    });
+                    // Populate L1 asynchronously (fire-and-forget); a late
+                    // promotion never replaces a fresher direct store. Entries
+                    // over the L1 size limit stay in L2 only.
+                    if !self.size_limits.fits(CacheTier::L1, &response) {
+                        debug!("L2 hit too large for L1");
+                    } else if self.promotion.record_hit(&cache_key) {
+                        self.metrics.record_l1_promotion();
+                        let l1_clone = self.l1.clone();
+                        let key_clone = cache_key.clone();
+                        let response_clone = response.clone();
+                        tokio::spawn(async move {
+                            l1_clone.promote(key_clone, response_clone).await;
+                        });
+                    } else {
+                        debug!("L2 hit not promoted to L1 yet");
+                        self.metrics.record_l1_promotion_skipped();
+                    }
 
-                    return CacheLookupResult::L2Hit(Arc::new(response));
+                    return (CacheLookupResult::L2Hit(Arc::new(response)), freshness);
                 }
                 Ok(None) => {
                     debug!("Cache MISS: L2");
@@ -141,33 +472,50 @@ impl CacheManager {
         }
 
         debug!("Cache MISS: all tiers");
-        CacheLookupResult::Miss
+        (CacheLookupResult::Miss, None)
     }
 
     /// Store a response in the cache
     ///
-    /// Writes to both L1 and L2 asynchronously (non-blocking).
     /// This should be called after receiving a response from the LLM provider.
     ///
     /// # Performance
-    /// Non-blocking, returns immediately. Cache writes happen in background.
+    /// The L1 write completes before this returns; the L2 write runs in the
+    /// background. At most `L2Config::max_concurrent_writes` L2 writes are in
+    /// flight at once. When every slot is busy the L2 write is dropped, or
+    /// with `WriteOverflowPolicy::Wait` this waits up to that long for a slot
+    /// before dropping it. Dropped writes are counted in
+    /// `llm_edge_cache_l2_writes_dropped_total`.
     pub async fn store(&self, request: &CacheableRequest, response: CachedResponse) {
-        let cache_key = generate_cache_key(request);
+        let cache_key = self.cache_key(request);
+        let model = Some(request.model.as_str());
 
         // Write to L1 (fast, in-memory)
-        self.l1.set(cache_key.clone(), response.clone()).await;
+        if self
+            .size_limits
+            .admits(CacheTier::L1, &response, &self.metrics)
+        {
+            self.l1
+                .set_for_model(cache_key.clone(), response.clone(), model)
+                .await;
+        }
 
-        // Write to L2 asynchronously (fire-and-forget)
-        if let Some(ref l2) = self.l2 {
+        // Write to L2 in the background, bounded by the write slots
+        if let Some(l2) = self.l2_for(&response) {
             let l2_clone = l2.clone();
             let key_clone = cache_key.clone();
             let response_clone = response.clone();
+            let model_clone = request.model.clone();
 
-            tokio::spawn(async move {
-                if let Err(e) = l2_clone.set(key_clone, response_clone).await {
+            l2.spawn_write(Box::pin(async move {
+                if let Err(e) = l2_clone
+                    .set(key_clone, response_clone, Some(&model_clone))
+                    .await
+                {
                     warn!("L2 cache write error: {}", e);
                 }
-            });
+            }))
+            .await;
         }
     }
 
@@ -178,31 +526,53 @@ impl CacheManager {
         response: CachedResponse,
         l2_ttl_seconds: u64,
     ) {
-        let cache_key = generate_cache_key(request);
+        let cache_key = self.cache_key(request);
 
         // Write to L1
-        self.l1.set(cache_key.clone(), response.clone()).await;
+        if self
+            .size_limits
+            .admits(CacheTier::L1, &response, &self.metrics)
+        {
+            self.l1
+                .set_for_model(cache_key.clone(), response.clone(), Some(&request.model))
+                .await;
+        }
 
         // Write to L2 with custom TTL
-        if let Some(ref l2) = self.l2 {
+        if let Some(l2) = self.l2_for(&response) {
             let l2_clone = l2.clone();
             let key_clone = cache_key.clone();
             let response_clone = response.clone();
+            let model_clone = request.model.clone();
 
-            tokio::spawn(async move {
+            l2.spawn_write(Box::pin(async move {
                 if let Err(e) = l2_clone
-                    .set_with_ttl(key_clone, response_clone, l2_ttl_seconds)
+                    .set_with_ttl(
+                        key_clone,
+                        response_clone,
+                        l2_ttl_seconds,
+                        Some(&model_clone),
+                    )
                     .await
                 {
                     warn!("L2 cache write with TTL error: {}", e);
                 }
-            });
+            }))
+            .await;
         }
     }
 
+    /// L2, if configured and `response` is within its size limit
+    fn l2_for(&self, response: &CachedResponse) -> Option<&Arc<dyn DistributedCache>> {
+        self.l2.as_ref().filter(|_| {
+            self.size_limits
+                .admits(CacheTier::L2, response, &self.metrics)
+        })
+    }
+
     /// Invalidate a cache entry across all tiers
     pub async fn invalidate(&self, request: &CacheableRequest) {
-        let cache_key = generate_cache_key(request);
+        let cache_key = self.cache_key(request);
 
         // Remove from L1
         self.l1.remove(&cache_key).await;
@@ -272,14 +642,30 @@ impl CacheManager {
     pub fn metrics(&self) -> &CacheMetrics {
         &self.metrics
     }
+
+    /// Stop background L2 maintenance
+    ///
+    /// Also happens when the last clone of the manager is dropped.
+    pub fn shutdown(&self) {
+        if let Some(task) = &self.maintenance {
+            task.0.abort();
+        }
+    }
 }
 
 impl Clone for CacheManager {
     fn clone(&self) -> Self {
         Self {
             l1: L1Cache::with_config(self.l1.config().clone(), self.metrics.clone()),
-            l2: None, // L2 uses ConnectionManager which is Clone-able, but we'd need to expose it
+            l2: self.l2.clone(),
             metrics: self.metrics.clone(),
+            stale_policy: self.stale_policy,
+            clock: self.clock.clone(),
+            key_version: self.key_version,
+            refreshing: self.refreshing.clone(),
+            promotion: self.promotion.clone(),
+            size_limits: self.size_limits,
+            maintenance: self.maintenance.clone(),
         }
     }
 }
@@ -356,4 +742,477 @@ mod tests {
             panic!("Expected L1 hit");
         }
     }
+
+    #[test]
+    fn test_lookup_emits_model_labeled_counters() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        ::metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                let cache = CacheManager::new();
+                let request = create_test_request();
+
+                cache.lookup(&request).await;
+                cache.store(&request, create_test_response("hi")).await;
+                cache.lookup(&request).await;
+            });
+        });
+
+        let counter = |name: &str| {
+            snapshotter
+                .snapshot()
+                .into_vec()
+                .into_iter()
+                .find_map(|(key, _, _, value)| {
+                    let key = key.key();
+                    let labeled = key.name() == name
+                        && key.labels().any(|l| l.key() == "tier" && l.value() == "l1")
+                        && key
+                            .labels()
+                            .any(|l| l.key() == "model" && l.value() == "gpt-4");
+                    match value {
+                        DebugValue::Counter(v) if labeled => Some(v),
+                        _ => None,
+                    }
+                })
+        };
+
+        assert_eq!(counter("llm_edge_cache_misses_total"), Some(1));
+        assert_eq!(counter("llm_edge_cache_hits_total"), Some(1));
+        assert_eq!(counter("llm_edge_cache_writes_total"), Some(1));
+    }
+
+    /// Clock reading a shared counter of seconds
+    fn fake_clock(now: &Arc<std::sync::atomic::AtomicI64>) -> Clock {
+        let now = now.clone();
+        Arc::new(move || now.load(std::sync::atomic::Ordering::SeqCst))
+    }
+
+    fn response_at(content: &str, cached_at: i64) -> CachedResponse {
+        CachedResponse {
+            cached_at,
+            ..create_test_response(content)
+        }
+    }
+
+    fn stale_cache(now: &Arc<std::sync::atomic::AtomicI64>) -> CacheManager {
+        CacheManager::new()
+            .with_stale_while_revalidate(StaleWhileRevalidate {
+                soft_ttl_seconds: 60,
+                hard_ttl_seconds: 300,
+            })
+            .with_clock(fake_clock(now))
+    }
+
+    #[tokio::test]
+    async fn test_soft_stale_entry_served_then_refreshed() {
+        use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+        let now = Arc::new(AtomicI64::new(1_000));
+        let cache = stale_cache(&now);
+        let request = create_test_request();
+        cache.store(&request, response_at("old", 1_000)).await;
+
+        // Past the soft TTL: the old answer comes back without waiting
+        now.store(1_100, Ordering::SeqCst);
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(tokio::sync::Notify::new());
+        let refresh = || {
+            let refreshes = refreshes.clone();
+            let release = release.clone();
+            move || async move {
+                refreshes.fetch_add(1, Ordering::SeqCst);
+                release.notified().await;
+                Some(response_at("new", 1_100))
+            }
+        };
+
+        for _ in 0..2 {
+            let result = cache.lookup_or_refresh(&request, refresh()).await;
+            assert_eq!(result.response().unwrap().content, "old");
+        }
+        tokio::task::yield_now().await;
+
+        // One refresh per key, however many stale hits arrive meanwhile
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.metrics_snapshot().stale_served, 2);
+
+        release.notify_one();
+        for _ in 0..50 {
+            if cache.lookup(&request).await.response().unwrap().content == "new" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let result = cache.lookup_or_refresh(&request, refresh()).await;
+        assert_eq!(result.response().unwrap().content, "new");
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_refresh_does_not_block_the_key() {
+        use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+        let now = Arc::new(AtomicI64::new(1_000));
+        let cache = stale_cache(&now);
+        let request = create_test_request();
+        cache.store(&request, response_at("old", 1_000)).await;
+        now.store(1_100, Ordering::SeqCst);
+
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let refresh = || {
+            let refreshes = refreshes.clone();
+            move || async move {
+                if refreshes.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("provider client bug");
+                }
+                None
+            }
+        };
+
+        cache.lookup_or_refresh(&request, refresh()).await;
+        for _ in 0..50 {
+            if cache.refreshing.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(cache.refreshing.lock().unwrap().is_empty());
+
+        // The next stale hit refreshes again
+        cache.lookup_or_refresh(&request, refresh()).await;
+        tokio::task::yield_now().await;
+        assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_stops_with_the_last_manager_or_on_shutdown() {
+        fn with_maintenance() -> (CacheManager, tokio::task::AbortHandle) {
+            let task = tokio::spawn(std::future::pending::<()>());
+            let handle = task.abort_handle();
+            let mut cache = CacheManager::new();
+            cache.maintenance = Some(Arc::new(MaintenanceTask(task)));
+            (cache, handle)
+        }
+
+        let (cache, task) = with_maintenance();
+        let clone = cache.clone();
+        drop(cache);
+        tokio::task::yield_now().await;
+        assert!(!task.is_finished());
+        drop(clone);
+        tokio::task::yield_now().await;
+        assert!(task.is_finished());
+
+        let (cache, task) = with_maintenance();
+        cache.shutdown();
+        tokio::task::yield_now().await;
+        assert!(task.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_fresh_and_hard_expired_entries_are_not_refreshed() {
+        use std::sync::atomic::{AtomicI64, Ordering};
+
+        let now = Arc::new(AtomicI64::new(1_000));
+        let cache = stale_cache(&now);
+        let request = create_test_request();
+        cache.store(&request, response_at("cached", 1_000)).await;
+
+        let no_refresh = || || async { panic!("refresh must not run") };
+
+        now.store(1_060, Ordering::SeqCst);
+        let result = cache.lookup_or_refresh(&request, no_refresh()).await;
+        assert!(matches!(result, CacheLookupResult::L1Hit(_)));
+
+        // Past the hard TTL the entry is not served at all
+        now.store(1_301, Ordering::SeqCst);
+        let result = cache.lookup_or_refresh(&request, no_refresh()).await;
+        assert!(matches!(result, CacheLookupResult::Miss));
+        assert!(matches!(
+            cache.lookup(&request).await,
+            CacheLookupResult::Miss
+        ));
+        assert_eq!(cache.metrics_snapshot().stale_served, 0);
+    }
+
+    /// In-memory stand-in for a distributed cache
+    #[derive(Default)]
+    struct MemoryBackend {
+        entries: std::sync::Mutex<std::collections::HashMap<String, CachedResponse>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DistributedCache for MemoryBackend {
+        async fn get(
+            &self,
+            key: &str,
+            _model: Option<&str>,
+        ) -> Result<Option<CachedResponse>, l2::L2Error> {
+            Ok(self.entries.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set(
+            &self,
+            key: String,
+            value: CachedResponse,
+            _model: Option<&str>,
+        ) -> Result<(), l2::L2Error> {
+            self.entries.lock().unwrap().insert(key, value);
+            Ok(())
+        }
+
+        async fn set_with_ttl(
+            &self,
+            key: String,
+            value: CachedResponse,
+            _ttl_seconds: u64,
+            model: Option<&str>,
+        ) -> Result<(), l2::L2Error> {
+            self.set(key, value, model).await
+        }
+
+        async fn remove(&self, key: &str) -> Result<(), l2::L2Error> {
+            self.entries.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        async fn clear(&self) -> Result<(), l2::L2Error> {
+            self.entries.lock().unwrap().clear();
+            Ok(())
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+
+        async fn approximate_size(&self) -> Result<usize, l2::L2Error> {
+            Ok(self.entries.lock().unwrap().len())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_manager_with_custom_backend() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache = CacheManager::with_backend(backend.clone());
+        let request = create_test_request();
+
+        assert!(cache.has_l2());
+        assert!(cache.health_check().await.is_fully_healthy());
+
+        cache
+            .store(&request, create_test_response("From backend"))
+            .await;
+
+        // The L2 write runs in the background
+        for _ in 0..50 {
+            if cache.l2_approximate_size().await == Some(1) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(cache.l2_approximate_size().await, Some(1));
+
+        // With L1 emptied the lookup is served by the backend
+        cache.l1.clear().await;
+        match cache.lookup(&request).await {
+            CacheLookupResult::L2Hit(response) => assert_eq!(response.content, "From backend"),
+            other => panic!("Expected L2 hit, got {:?}", other),
+        }
+
+        cache.invalidate(&request).await;
+        assert_eq!(backend.approximate_size().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_key_version_bump_hides_old_entries() {
+        let backend = Arc::new(MemoryBackend::default());
+        let request = create_test_request();
+
+        let v1 = CacheManager::with_backend(backend.clone()).with_key_version(1);
+        v1.store(&request, create_test_response("v1 answer")).await;
+        for _ in 0..50 {
+            if backend.approximate_size().await.unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // A fresh instance sharing the backend still reads it under v1
+        let restarted = CacheManager::with_backend(backend.clone()).with_key_version(1);
+        assert!(restarted.lookup(&request).await.is_hit());
+
+        let v2 = CacheManager::with_backend(backend.clone()).with_key_version(2);
+        assert_eq!(v2.key_version(), 2);
+        assert!(matches!(v2.lookup(&request).await, CacheLookupResult::Miss));
+        // Nothing was flushed
+        assert_eq!(backend.approximate_size().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_l2_hits_promoted_only_once_frequent() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache =
+            CacheManager::with_backend(backend.clone()).with_promotion_policy(PromotionPolicy {
+                min_hits: 3,
+                window: std::time::Duration::from_secs(60),
+            });
+        let hot = create_test_request();
+        let cold = CacheableRequest::new("gpt-4", "Asked only once");
+        cache.store(&hot, create_test_response("hot")).await;
+        cache.store(&cold, create_test_response("cold")).await;
+        for _ in 0..50 {
+            if backend.approximate_size().await.unwrap() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        cache.l1.clear().await;
+
+        let settle = || tokio::time::sleep(std::time::Duration::from_millis(20));
+        assert!(matches!(
+            cache.lookup(&cold).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+        for _ in 0..3 {
+            assert!(matches!(
+                cache.lookup(&hot).await,
+                CacheLookupResult::L2Hit(_)
+            ));
+            settle().await;
+        }
+
+        // Only the entry hit three times made it into L1
+        assert!(matches!(
+            cache.lookup(&hot).await,
+            CacheLookupResult::L1Hit(_)
+        ));
+        assert!(matches!(
+            cache.lookup(&cold).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+        let metrics = cache.metrics_snapshot();
+        assert_eq!(metrics.l1_promotions, 1);
+        assert_eq!(metrics.l1_promotions_skipped, 4);
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_promotion_hit_counts() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache =
+            CacheManager::with_backend(backend.clone()).with_promotion_policy(PromotionPolicy {
+                min_hits: 2,
+                window: std::time::Duration::from_secs(60),
+            });
+        let request = create_test_request();
+        cache.store(&request, create_test_response("answer")).await;
+        for _ in 0..50 {
+            if backend.approximate_size().await.unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        cache.l1.clear().await;
+        let clone = cache.clone();
+
+        // One hit through each handle adds up to the two needed
+        assert!(matches!(
+            cache.lookup(&request).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+        assert!(matches!(
+            clone.lookup(&request).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let metrics = cache.metrics_snapshot();
+        assert_eq!(metrics.l1_promotions, 1);
+        assert_eq!(metrics.l1_promotions_skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn test_every_l2_hit_promoted_by_default() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache = CacheManager::with_backend(backend.clone());
+        let request = create_test_request();
+        cache.store(&request, create_test_response("answer")).await;
+        for _ in 0..50 {
+            if backend.approximate_size().await.unwrap() == 1 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        cache.l1.clear().await;
+
+        assert!(matches!(
+            cache.lookup(&request).await,
+            CacheLookupResult::L2Hit(_)
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(matches!(
+            cache.lookup(&request).await,
+            CacheLookupResult::L1Hit(_)
+        ));
+        assert_eq!(cache.metrics_snapshot().l1_promotions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_responses_skip_each_tier_independently() {
+        let backend = Arc::new(MemoryBackend::default());
+        let cache = CacheManager::with_backend(backend.clone()).with_response_size_limits(
+            ResponseSizeLimits {
+                l1_max_bytes: Some(100),
+                l2_max_bytes: Some(1000),
+            },
+        );
+        let small = CacheableRequest::new("gpt-4", "Small");
+        let medium = CacheableRequest::new("gpt-4", "Medium");
+        let large = CacheableRequest::new("gpt-4", "Large");
+        cache
+            .store(&small, create_test_response(&"s".repeat(100)))
+            .await;
+        cache
+            .store(&medium, create_test_response(&"m".repeat(500)))
+            .await;
+        cache
+            .store_with_ttl(&large, create_test_response(&"l".repeat(5000)), 60)
+            .await;
+        for _ in 0..50 {
+            if backend.approximate_size().await.unwrap() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // At the limit is still cached
+        assert!(matches!(
+            cache.lookup(&small).await,
+            CacheLookupResult::L1Hit(_)
+        ));
+        // Over the L1 limit: L2 only, and never promoted
+        for _ in 0..2 {
+            assert!(matches!(
+                cache.lookup(&medium).await,
+                CacheLookupResult::L2Hit(_)
+            ));
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        // Over both limits: served to the client, cached nowhere
+        assert!(matches!(
+            cache.lookup(&large).await,
+            CacheLookupResult::Miss
+        ));
+        assert_eq!(backend.approximate_size().await.unwrap(), 2);
+
+        let metrics = cache.metrics_snapshot();
+        assert_eq!(metrics.l1_skipped_too_large, 2);
+        assert_eq!(metrics.l2_skipped_too_large, 1);
+        assert_eq!(metrics.l1_promotions, 0);
+    }
 }
@@ -5,7 +5,7 @@
 
 use metrics::{counter, gauge, histogram};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 /// Cache tier identifier
@@ -26,6 +26,37 @@ impl CacheTier {
     }
 }
 
+/// Model families reported as-is in metric labels (longest prefix first)
+const KNOWN_MODEL_LABELS: &[&str] = &[
+    "gpt-4o-mini",
+    "gpt-4o",
+    "gpt-4-turbo",
+    "gpt-4",
+    "gpt-3.5-turbo",
+    "o1-mini",
+    "o1",
+    "claude-3-5-sonnet",
+    "claude-3-5-haiku",
+    "claude-3-opus",
+    "claude-3-sonnet",
+    "claude-3-haiku",
+];
+
+/// Label used for models outside [`KNOWN_MODEL_LABELS`]
+pub const OTHER_MODEL_LABEL: &str = "other";
+
+/// Map a request model to a bounded set of metric label values
+///
+/// Dated or suffixed variants collapse onto their family
+/// (`gpt-4-0613` -> `gpt-4`); anything unrecognized becomes `"other"`.
+pub fn normalize_model_label(model: &str) -> &'static str {
+    KNOWN_MODEL_LABELS
+        .iter()
+        .find(|known| model.starts_with(*known))
+        .copied()
+        .unwrap_or(OTHER_MODEL_LABEL)
+}
+
 /// Cache operation type
 #[derive(Debug, Clone, Copy)]
 pub enum CacheOperation {
@@ -35,6 +66,75 @@ pub enum CacheOperation {
     Delete,
 }
 
+/// Why an L1 entry was evicted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionCause {
+    /// TTL or TTI elapsed
+    Expired,
+    /// Removed to stay within `max_capacity`
+    Size,
+    /// Invalidated by `remove`/`clear`
+    Explicit,
+}
+
+impl EvictionCause {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionCause::Expired => "expired",
+            EvictionCause::Size => "size",
+            EvictionCause::Explicit => "explicit",
+        }
+    }
+}
+
+/// Most recent operation latencies kept per tier for percentiles
+pub const LATENCY_RESERVOIR_SIZE: usize = 1024;
+
+/// Ring buffer of the last [`LATENCY_RESERVOIR_SIZE`] latencies, in ms
+#[derive(Debug, Default)]
+struct LatencyReservoir {
+    samples: Vec<f64>,
+    next: usize,
+}
+
+impl LatencyReservoir {
+    fn record(&mut self, latency_ms: f64) {
+        if self.samples.len() < LATENCY_RESERVOIR_SIZE {
+            self.samples.push(latency_ms);
+        } else {
+            self.samples[self.next] = latency_ms;
+        }
+        self.next = (self.next + 1) % LATENCY_RESERVOIR_SIZE;
+    }
+
+    fn percentiles(&self) -> Option<LatencyPercentiles> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        // Nearest rank
+        let at = |p: f64| sorted[((p * sorted.len() as f64).ceil() as usize).max(1) - 1];
+
+        Some(LatencyPercentiles {
+            p50_ms: at(0.50),
+            p95_ms: at(0.95),
+            p99_ms: at(0.99),
+            samples: sorted.len(),
+        })
+    }
+}
+
+/// Latency percentiles over a tier's recent operations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyPercentiles {
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// Operations the percentiles are computed over
+    pub samples: usize,
+}
+
 /// Metrics collector for cache operations
 #[derive(Debug, Clone)]
 pub struct CacheMetrics {
@@ -42,14 +142,28 @@ pub struct CacheMetrics {
     l1_hits: Arc<AtomicU64>,
     l1_misses: Arc<AtomicU64>,
     l1_writes: Arc<AtomicU64>,
+    l1_promotions: Arc<AtomicU64>,
+    l1_promotions_skipped: Arc<AtomicU64>,
+    l1_skipped_too_large: Arc<AtomicU64>,
 
     // L2 metrics
     l2_hits: Arc<AtomicU64>,
     l2_misses: Arc<AtomicU64>,
     l2_writes: Arc<AtomicU64>,
+    l2_writes_dropped: Arc<AtomicU64>,
+    l2_serialization_errors: Arc<AtomicU64>,
+    l2_deserialization_errors: Arc<AtomicU64>,
+    l2_skipped_too_large: Arc<AtomicU64>,
+
+    // Stale-while-revalidate
+    stale_served: Arc<AtomicU64>,
 
     // Overall metrics
     total_requests: Arc<AtomicU64>,
+
+    // Recent read and write latencies
+    l1_latency: Arc<Mutex<LatencyReservoir>>,
+    l2_latency: Arc<Mutex<LatencyReservoir>>,
 }
 
 impl CacheMetrics {
@@ -59,53 +173,126 @@ impl CacheMetrics {
             l1_hits: Arc::new(AtomicU64::new(0)),
             l1_misses: Arc::new(AtomicU64::new(0)),
             l1_writes: Arc::new(AtomicU64::new(0)),
+            l1_promotions: Arc::new(AtomicU64::new(0)),
+            l1_promotions_skipped: Arc::new(AtomicU64::new(0)),
+            l1_skipped_too_large: Arc::new(AtomicU64::new(0)),
             l2_hits: Arc::new(AtomicU64::new(0)),
             l2_misses: Arc::new(AtomicU64::new(0)),
             l2_writes: Arc::new(AtomicU64::new(0)),
+            l2_writes_dropped: Arc::new(AtomicU64::new(0)),
+            l2_serialization_errors: Arc::new(AtomicU64::new(0)),
+            l2_deserialization_errors: Arc::new(AtomicU64::new(0)),
+            l2_skipped_too_large: Arc::new(AtomicU64::new(0)),
+            stale_served: Arc::new(AtomicU64::new(0)),
             total_requests: Arc::new(AtomicU64::new(0)),
+            l1_latency: Arc::new(Mutex::new(LatencyReservoir::default())),
+            l2_latency: Arc::new(Mutex::new(LatencyReservoir::default())),
         }
     }
 
     /// Record a cache operation
-    pub fn record_operation(&self, tier: CacheTier, operation: CacheOperation) {
-        match (tier, operation) {
-            (CacheTier::L1, CacheOperation::Hit) => {
-                self.l1_hits.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_hits_total", "tier" => "l1").increment(1);
-            }
-            (CacheTier::L1, CacheOperation::Miss) => {
-                self.l1_misses.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_misses_total", "tier" => "l1").increment(1);
-            }
-            (CacheTier::L1, CacheOperation::Write) => {
-                self.l1_writes.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_writes_total", "tier" => "l1").increment(1);
-            }
-            (CacheTier::L2, CacheOperation::Hit) => {
-                self.l2_hits.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_hits_total", "tier" => "l2").increment(1);
-            }
-            (CacheTier::L2, CacheOperation::Miss) => {
-                self.l2_misses.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_misses_total", "tier" => "l2").increment(1);
-            }
-            (CacheTier::L2, CacheOperation::Write) => {
-                self.l2_writes.fetch_add(1, Ordering::Relaxed);
-                counter!("llm_edge_cache_writes_total", "tier" => "l2").increment(1);
-            }
-            (CacheTier::L3, CacheOperation::Hit) => {
-                counter!("llm_edge_cache_hits_total", "tier" => "l3").increment(1);
-            }
-            (CacheTier::L3, CacheOperation::Miss) => {
-                counter!("llm_edge_cache_misses_total", "tier" => "l3").increment(1);
-            }
-            (CacheTier::L3, CacheOperation::Write) => {
-                counter!("llm_edge_cache_writes_total", "tier" => "l3").increment(1);
-            }
-            _ => {}
+    ///
+    /// When `model` is given, the Prometheus counter also carries a `model`
+    /// label (see [`normalize_model_label`]). The internal counters are
+    /// tier-only either way.
+    pub fn record_operation(
+        &self,
+        tier: CacheTier,
+        operation: CacheOperation,
+        model: Option<&str>,
+    ) {
+        let counter = match (tier, operation) {
+            (CacheTier::L1, CacheOperation::Hit) => Some(&self.l1_hits),
+            (CacheTier::L1, CacheOperation::Miss) => Some(&self.l1_misses),
+            (CacheTier::L1, CacheOperation::Write) => Some(&self.l1_writes),
+            (CacheTier::L2, CacheOperation::Hit) => Some(&self.l2_hits),
+            (CacheTier::L2, CacheOperation::Miss) => Some(&self.l2_misses),
+            (CacheTier::L2, CacheOperation::Write) => Some(&self.l2_writes),
+            _ => None,
+        };
+        if let Some(counter) = counter {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let name = match operation {
+            CacheOperation::Hit => "llm_edge_cache_hits_total",
+            CacheOperation::Miss => "llm_edge_cache_misses_total",
+            CacheOperation::Write => "llm_edge_cache_writes_total",
+            CacheOperation::Delete => return,
+        };
+
+        match model {
+            Some(model) => counter!(
+                name,
+                "tier" => tier.as_str(),
+                "model" => normalize_model_label(model)
+            )
+            .increment(1),
+            None => counter!(name, "tier" => tier.as_str()).increment(1),
         }
     }
 
+    /// Record an L1 eviction
+    pub fn record_l1_eviction(&self, cause: EvictionCause) {
+        counter!(
+            "llm_edge_cache_l1_evictions_total",
+            "cause" => cause.as_str()
+        )
+        .increment(1);
+    }
+
+    /// Record an L2 hit copied into L1
+    pub fn record_l1_promotion(&self) {
+        self.l1_promotions.fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_l1_promotions_total").increment(1);
+    }
+
+    /// Record an L2 hit left out of L1 because it is not hit often enough yet
+    pub fn record_l1_promotion_skipped(&self) {
+        self.l1_promotions_skipped.fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_l1_promotions_skipped_total").increment(1);
+    }
+
+    /// Record a response not written to `tier` because it is over the size limit
+    pub fn record_skipped_too_large(&self, tier: CacheTier) {
+        match tier {
+            CacheTier::L1 => &self.l1_skipped_too_large,
+            _ => &self.l2_skipped_too_large,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_skipped_too_large_total", "tier" => tier.as_str()).increment(1);
+    }
+
+    /// Record an L2 write skipped because all write slots were busy
+    pub fn record_l2_write_dropped(&self) {
+        self.l2_writes_dropped.fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_l2_writes_dropped_total").increment(1);
+    }
+
+    /// Record an L2 value that could not be encoded for writing
+    pub fn record_l2_serialization_error(&self) {
+        self.l2_serialization_errors.fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_l2_serialization_errors_total").increment(1);
+    }
+
+    /// Record a corrupt L2 entry that could not be decoded on read
+    pub fn record_l2_deserialization_error(&self) {
+        self.l2_deserialization_errors
+            .fetch_add(1, Ordering::Relaxed);
+        counter!("llm_edge_cache_l2_deserialization_errors_total").increment(1);
+    }
+
+    /// Record a stale entry served while it is refreshed in the background
+    pub fn record_stale_served(&self, tier: CacheTier, model: &str) {
+        self.stale_served.fetch_add(1, Ordering::Relaxed);
+        counter!(
+            "llm_edge_cache_stale_served_total",
+            "tier" => tier.as_str(),
+            "model" => normalize_model_label(model)
+        )
+        .increment(1);
+    }
+
     /// Record cache lookup latency
     pub fn record_latency(&self, tier: CacheTier, duration: Duration) {
         let latency_ms = duration.as_secs_f64() * 1000.0;
@@ -114,6 +301,24 @@ impl CacheMetrics {
             "tier" => tier.as_str()
         )
         .record(latency_ms);
+
+        if let Some(reservoir) = self.latency_reservoir(tier) {
+            reservoir.lock().unwrap().record(latency_ms);
+        }
+    }
+
+    /// p50/p95/p99 over the last [`LATENCY_RESERVOIR_SIZE`] L1 or L2
+    /// operations, or `None` before the first one
+    pub fn latency_percentiles(&self, tier: CacheTier) -> Option<LatencyPercentiles> {
+        self.latency_reservoir(tier)?.lock().unwrap().percentiles()
+    }
+
+    fn latency_reservoir(&self, tier: CacheTier) -> Option<&Mutex<LatencyReservoir>> {
+        match tier {
+            CacheTier::L1 => Some(&self.l1_latency),
+            CacheTier::L2 => Some(&self.l2_latency),
+            CacheTier::L3 => None,
+        }
     }
 
     /// Record a request (for overall metrics)
@@ -193,9 +398,17 @@ impl CacheMetrics {
             l1_hits: self.l1_hits.load(Ordering::Relaxed),
             l1_misses: self.l1_misses.load(Ordering::Relaxed),
             l1_writes: self.l1_writes.load(Ordering::Relaxed),
+            l1_promotions: self.l1_promotions.load(Ordering::Relaxed),
+            l1_promotions_skipped: self.l1_promotions_skipped.load(Ordering::Relaxed),
+            l1_skipped_too_large: self.l1_skipped_too_large.load(Ordering::Relaxed),
             l2_hits: self.l2_hits.load(Ordering::Relaxed),
             l2_misses: self.l2_misses.load(Ordering::Relaxed),
             l2_writes: self.l2_writes.load(Ordering::Relaxed),
+            l2_writes_dropped: self.l2_writes_dropped.load(Ordering::Relaxed),
+            l2_serialization_errors: self.l2_serialization_errors.load(Ordering::Relaxed),
+            l2_deserialization_errors: self.l2_deserialization_errors.load(Ordering::Relaxed),
+            l2_skipped_too_large: self.l2_skipped_too_large.load(Ordering::Relaxed),
+            stale_served: self.stale_served.load(Ordering::Relaxed),
             total_requests: self.total_requests.load(Ordering::Relaxed),
         }
     }
@@ -213,9 +426,17 @@ pub struct MetricsSnapshot {
     pub l1_hits: u64,
     pub l1_misses: u64,
     pub l1_writes: u64,
+    pub l1_promotions: u64,
+    pub l1_promotions_skipped: u64,
+    pub l1_skipped_too_large: u64,
     pub l2_hits: u64,
     pub l2_misses: u64,
     pub l2_writes: u64,
+    pub l2_writes_dropped: u64,
+    pub l2_serialization_errors: u64,
+    pub l2_deserialization_errors: u64,
+    pub l2_skipped_too_large: u64,
+    pub stale_served: u64,
     pub total_requests: u64,
 }
 
@@ -251,6 +472,9 @@ impl MetricsSnapshot {
 }
 
 /// Helper to measure operation latency
+///
+/// The latency is recorded when the timer is finished or dropped, so a
+/// timer held in `_timer` covers the rest of the scope.
 pub struct LatencyTimer {
     start: Instant,
     tier: CacheTier,
@@ -267,8 +491,13 @@ impl LatencyTimer {
     }
 
     pub fn finish(self) {
-        let duration = self.start.elapsed();
-        self.metrics.record_latency(self.tier, duration);
+        drop(self);
+    }
+}
+
+impl Drop for LatencyTimer {
+    fn drop(&mut self) {
+        self.metrics.record_latency(self.tier, self.start.elapsed());
     }
 }
 
@@ -280,9 +509,9 @@ mod tests {
     fn test_metrics_recording() {
         let metrics = CacheMetrics::new();
 
-        metrics.record_operation(CacheTier::L1, CacheOperation::Hit);
-        metrics.record_operation(CacheTier::L1, CacheOperation::Miss);
-        metrics.record_operation(CacheTier::L1, CacheOperation::Hit);
+        metrics.record_operation(CacheTier::L1, CacheOperation::Hit, None);
+        metrics.record_operation(CacheTier::L1, CacheOperation::Miss, None);
+        metrics.record_operation(CacheTier::L1, CacheOperation::Hit, None);
 
         assert_eq!(metrics.l1_hits.load(Ordering::Relaxed), 2);
         assert_eq!(metrics.l1_misses.load(Ordering::Relaxed), 1);
@@ -294,10 +523,10 @@ mod tests {
 
         // Record 7 hits and 3 misses = 70% hit rate
         for _ in 0..7 {
-            metrics.record_operation(CacheTier::L1, CacheOperation::Hit);
+            metrics.record_operation(CacheTier::L1, CacheOperation::Hit, None);
         }
         for _ in 0..3 {
-            metrics.record_operation(CacheTier::L1, CacheOperation::Miss);
+            metrics.record_operation(CacheTier::L1, CacheOperation::Miss, None);
         }
 
         let hit_rate = metrics.l1_hit_rate();
@@ -322,8 +551,8 @@ mod tests {
     fn test_metrics_snapshot() {
         let metrics = CacheMetrics::new();
 
-        metrics.record_operation(CacheTier::L1, CacheOperation::Hit);
-        metrics.record_operation(CacheTier::L2, CacheOperation::Miss);
+        metrics.record_operation(CacheTier::L1, CacheOperation::Hit, None);
+        metrics.record_operation(CacheTier::L2, CacheOperation::Miss, None);
 
         let snapshot = metrics.snapshot();
         assert_eq!(snapshot.l1_hits, 1);
@@ -336,15 +565,15 @@ mod tests {
 
         // 10 L1 requests: 6 hits, 4 misses
         for _ in 0..6 {
-            metrics.record_operation(CacheTier::L1, CacheOperation::Hit);
+            metrics.record_operation(CacheTier::L1, CacheOperation::Hit, None);
         }
         for _ in 0..4 {
-            metrics.record_operation(CacheTier::L1, CacheOperation::Miss);
+            metrics.record_operation(CacheTier::L1, CacheOperation::Miss, None);
         }
 
         // Of the 4 L1 misses, 2 hit L2, 2 miss L2
         for _ in 0..2 {
-            metrics.record_operation(CacheTier::L2, CacheOperation::Hit);
+            metrics.record_operation(CacheTier::L2, CacheOperation::Hit, None);
         }
 
         // Overall: 8 hits (6 L1 + 2 L2) out of 10 requests = 80%
@@ -355,4 +584,82 @@ mod tests {
             overall
         );
     }
+
+    #[test]
+    fn test_latency_percentiles_per_tier() {
+        let metrics = CacheMetrics::new();
+        assert_eq!(metrics.latency_percentiles(CacheTier::L1), None);
+
+        // 1..=100 ms in shuffled order, plus a slow L2
+        for i in 0..100u64 {
+            let ms = (i * 37) % 100 + 1;
+            metrics.record_latency(CacheTier::L1, Duration::from_millis(ms));
+            metrics.record_latency(CacheTier::L2, Duration::from_millis(ms * 10));
+        }
+
+        let l1 = metrics.latency_percentiles(CacheTier::L1).unwrap();
+        assert_eq!(l1.samples, 100);
+        assert!((l1.p50_ms - 50.0).abs() < 1.0, "{:?}", l1);
+        assert!((l1.p95_ms - 95.0).abs() < 1.0, "{:?}", l1);
+        assert!((l1.p99_ms - 99.0).abs() < 1.0, "{:?}", l1);
+        let l2 = metrics.latency_percentiles(CacheTier::L2).unwrap();
+        assert!((l2.p50_ms - 500.0).abs() < 10.0, "{:?}", l2);
+        assert!((l2.p99_ms - 990.0).abs() < 10.0, "{:?}", l2);
+    }
+
+    #[test]
+    fn test_latency_percentiles_roll_over_old_samples() {
+        let metrics = CacheMetrics::new();
+
+        for _ in 0..LATENCY_RESERVOIR_SIZE {
+            metrics.record_latency(CacheTier::L1, Duration::from_millis(500));
+        }
+        for _ in 0..LATENCY_RESERVOIR_SIZE {
+            metrics.record_latency(CacheTier::L1, Duration::from_micros(200));
+        }
+
+        let l1 = metrics.latency_percentiles(CacheTier::L1).unwrap();
+        assert_eq!(l1.samples, LATENCY_RESERVOIR_SIZE);
+        assert!(l1.p99_ms < 1.0, "{:?}", l1);
+    }
+
+    #[test]
+    fn test_dropped_timer_records_latency() {
+        let metrics = CacheMetrics::new();
+
+        {
+            let _timer = LatencyTimer::new(CacheTier::L2, metrics.clone());
+        }
+        LatencyTimer::new(CacheTier::L2, metrics.clone()).finish();
+
+        let l2 = metrics.latency_percentiles(CacheTier::L2).unwrap();
+        assert_eq!(l2.samples, 2);
+    }
+
+    #[test]
+    fn test_normalize_model_label() {
+        assert_eq!(normalize_model_label("gpt-4"), "gpt-4");
+        assert_eq!(normalize_model_label("gpt-4-0613"), "gpt-4");
+        assert_eq!(
+            normalize_model_label("gpt-4o-mini-2024-07-18"),
+            "gpt-4o-mini"
+        );
+        assert_eq!(
+            normalize_model_label("claude-3-5-sonnet-20241022"),
+            "claude-3-5-sonnet"
+        );
+        assert_eq!(normalize_model_label("my-finetune-v7"), OTHER_MODEL_LABEL);
+    }
+
+    #[test]
+    fn test_model_label_keeps_tier_counters() {
+        let metrics = CacheMetrics::new();
+
+        metrics.record_operation(CacheTier::L1, CacheOperation::Hit, Some("gpt-4"));
+        metrics.record_operation(CacheTier::L1, CacheOperation::Miss, Some("unknown"));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.l1_hits, 1);
+        assert_eq!(snapshot.l1_misses, 1);
+    }
 }
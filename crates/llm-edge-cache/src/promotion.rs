@@ -0,0 +1,122 @@
+//! Frequency-gated promotion of L2 hits into L1
+//!
+//! Promoting every L2 hit lets one-off queries push genuinely hot entries out
+//! of L1. With a [`PromotionPolicy`] of `min_hits` above 1, an L2 entry is
+//! only copied into L1 once it has been hit that many times within `window`.
+//!
+//! Hits are counted in a small count-min sketch that is cleared at the start
+//! of every window, so memory stays fixed however many keys are seen. The
+//! sketch can only overcount, which at worst promotes an entry early.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default window hits are counted over
+pub const DEFAULT_PROMOTION_WINDOW: Duration = Duration::from_secs(60);
+
+/// Independent hash rows in the sketch
+const SKETCH_DEPTH: usize = 4;
+
+/// Counters per row
+const SKETCH_WIDTH: usize = 2048;
+
+/// When an L2 hit is copied into L1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromotionPolicy {
+    /// L2 hits within `window` before an entry is promoted; 0 or 1 promotes
+    /// on the first hit
+    pub min_hits: u32,
+
+    /// Period hit counts are kept for
+    pub window: Duration,
+}
+
+impl Default for PromotionPolicy {
+    fn default() -> Self {
+        Self {
+            min_hits: 1,
+            window: DEFAULT_PROMOTION_WINDOW,
+        }
+    }
+}
+
+/// Counts L2 hits per key and decides which ones are promoted
+pub(crate) struct PromotionGate {
+    policy: PromotionPolicy,
+    sketch: Mutex<WindowedSketch>,
+}
+
+struct WindowedSketch {
+    counters: Vec<u32>,
+    window_started: Instant,
+}
+
+impl PromotionGate {
+    pub(crate) fn new(policy: PromotionPolicy) -> Self {
+        Self {
+            policy,
+            sketch: Mutex::new(WindowedSketch {
+                counters: Vec::new(),
+                window_started: Instant::now(),
+            }),
+        }
+    }
+
+    /// Count an L2 hit on `key`; true if the entry should now be promoted
+    pub(crate) fn record_hit(&self, key: &str) -> bool {
+        if self.policy.min_hits <= 1 {
+            return true;
+        }
+
+        let mut sketch = self.sketch.lock().unwrap();
+        if sketch.counters.is_empty() || sketch.window_started.elapsed() >= self.policy.window {
+            sketch.counters = vec![0; SKETCH_DEPTH * SKETCH_WIDTH];
+            sketch.window_started = Instant::now();
+        }
+
+        let mut estimate = u32::MAX;
+        for row in 0..SKETCH_DEPTH {
+            let counter = &mut sketch.counters[row * SKETCH_WIDTH + slot(key, row)];
+            *counter = counter.saturating_add(1);
+            estimate = estimate.min(*counter);
+        }
+        estimate >= self.policy.min_hits
+    }
+}
+
+/// Column of `key` in sketch row `row`
+fn slot(key: &str, row: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    row.hash(&mut hasher);
+    key.hash(&mut hasher);
+    (hasher.finish() % SKETCH_WIDTH as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_promotes_after_min_hits_within_window() {
+        let gate = PromotionGate::new(PromotionPolicy {
+            min_hits: 3,
+            window: Duration::from_secs(60),
+        });
+
+        assert!(!gate.record_hit("hot"));
+        assert!(!gate.record_hit("cold"));
+        assert!(!gate.record_hit("hot"));
+        assert!(gate.record_hit("hot"));
+        assert!(!gate.record_hit("cold"));
+
+        // Counts start over once the window has passed
+        let gate = PromotionGate::new(PromotionPolicy {
+            min_hits: 2,
+            window: Duration::ZERO,
+        });
+        assert!(!gate.record_hit("hot"));
+        assert!(!gate.record_hit("hot"));
+    }
+}
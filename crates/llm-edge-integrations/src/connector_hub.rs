@@ -133,10 +133,13 @@ impl ConnectorHubAdapter {
 
         debug!("Fetching provider routes from Connector-Hub");
 
-        self.client
-            .get_routes()
-            .await
-            .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch provider routes: {}", e)))
+        crate::telemetry::track("connector_hub", "get_provider_routes", async {
+            self.client
+                .get_routes()
+                .await
+                .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch provider routes: {}", e)))
+        })
+        .await
     }
 
     /// Consume provider route by provider name
@@ -146,10 +149,13 @@ impl ConnectorHubAdapter {
     pub async fn get_provider_route(&self, provider: &str) -> Result<Option<ProviderRoute>, crate::IntegrationError> {
         debug!("Fetching provider route for: {}", provider);
 
-        self.client
-            .get_route(provider)
-            .await
-            .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch provider route: {}", e)))
+        crate::telemetry::track("connector_hub", "get_provider_route", async {
+            self.client
+                .get_route(provider)
+                .await
+                .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch provider route: {}", e)))
+        })
+        .await
     }
 
     /// Consume backend adapter metadata from Connector-Hub
@@ -164,10 +170,13 @@ impl ConnectorHubAdapter {
 
         debug!("Fetching backend adapters from Connector-Hub");
 
-        self.client
-            .get_adapters()
-            .await
-            .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch backend adapters: {}", e)))
+        crate::telemetry::track("connector_hub", "get_backend_adapters", async {
+            self.client
+                .get_adapters()
+                .await
+                .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch backend adapters: {}", e)))
+        })
+        .await
     }
 
     /// Consume adapter metadata by adapter ID
@@ -177,10 +186,13 @@ impl ConnectorHubAdapter {
     pub async fn get_adapter_metadata(&self, adapter_id: &str) -> Result<Option<AdapterMetadata>, crate::IntegrationError> {
         debug!("Fetching adapter metadata for: {}", adapter_id);
 
-        self.client
-            .get_adapter_metadata(adapter_id)
-            .await
-            .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch adapter metadata: {}", e)))
+        crate::telemetry::track("connector_hub", "get_adapter_metadata", async {
+            self.client
+                .get_adapter_metadata(adapter_id)
+                .await
+                .map_err(|e| crate::IntegrationError::ConnectorHub(format!("Failed to fetch adapter metadata: {}", e)))
+        })
+        .await
     }
 
     /// Get all provider routing definitions as a map
@@ -216,8 +228,4 @@ impl ConnectorHubAdapter {
             "Connector-Hub feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
@@ -143,10 +143,13 @@ impl CostOpsAdapter {
             provider, model, input_tokens, output_tokens
         );
 
-        self.client
-            .calculate_cost(provider, model, input_tokens, output_tokens)
-            .await
-            .map_err(|e| crate::IntegrationError::CostOps(format!("Cost calculation failed: {}", e)))
+        crate::telemetry::track("cost_ops", "calculate_request_cost", async {
+            self.client
+                .calculate_cost(provider, model, input_tokens, output_tokens)
+                .await
+                .map_err(|e| crate::IntegrationError::CostOps(format!("Cost calculation failed: {}", e)))
+        })
+        .await
     }
 
     /// Consume token cost projection
@@ -169,10 +172,13 @@ impl CostOpsAdapter {
             provider, model, estimated_tokens
         );
 
-        self.client
-            .project_cost(provider, model, estimated_tokens)
-            .await
-            .map_err(|e| crate::IntegrationError::CostOps(format!("Token cost projection failed: {}", e)))
+        crate::telemetry::track("cost_ops", "project_token_cost", async {
+            self.client
+                .project_cost(provider, model, estimated_tokens)
+                .await
+                .map_err(|e| crate::IntegrationError::CostOps(format!("Token cost projection failed: {}", e)))
+        })
+        .await
     }
 
     /// Consume account limits from CostOps
@@ -187,10 +193,13 @@ impl CostOpsAdapter {
 
         debug!("Fetching account limits for: {}", account_id);
 
-        self.client
-            .get_limits(account_id)
-            .await
-            .map_err(|e| crate::IntegrationError::CostOps(format!("Failed to fetch account limits: {}", e)))
+        crate::telemetry::track("cost_ops", "get_account_limits", async {
+            self.client
+                .get_limits(account_id)
+                .await
+                .map_err(|e| crate::IntegrationError::CostOps(format!("Failed to fetch account limits: {}", e)))
+        })
+        .await
     }
 
     /// Consume usage report from CostOps
@@ -209,10 +218,13 @@ impl CostOpsAdapter {
             account_id, start_time, end_time
         );
 
-        self.client
-            .get_usage_report(account_id, start_time, end_time)
-            .await
-            .map_err(|e| crate::IntegrationError::CostOps(format!("Failed to fetch usage report: {}", e)))
+        crate::telemetry::track("cost_ops", "get_usage_report", async {
+            self.client
+                .get_usage_report(account_id, start_time, end_time)
+                .await
+                .map_err(|e| crate::IntegrationError::CostOps(format!("Failed to fetch usage report: {}", e)))
+        })
+        .await
     }
 
     /// Check if account is approaching limit
@@ -248,8 +260,4 @@ impl CostOpsAdapter {
             "CostOps feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
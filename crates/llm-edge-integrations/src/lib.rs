@@ -21,6 +21,7 @@
 //! 4. **Thin Adapters**: Minimal logic, just data translation and caching
 //! 5. **Observable**: All integration points emit telemetry
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tracing::{info, warn};
 
@@ -31,6 +32,9 @@ pub mod connector_hub;
 pub mod cost_ops;
 pub mod observatory;
 pub mod policy_engine;
+pub mod telemetry;
+
+pub use telemetry::{emit_event, IntegrationEvent, IntegrationOutcome};
 
 /// Unified integration manager that coordinates all upstream consumption adapters
 ///
@@ -101,7 +105,13 @@ impl IntegrationManager {
         #[cfg(feature = "shield")]
         {
             if config.shield_enabled {
-                match shield::ShieldAdapter::new(&config.shield_config).await {
+                match telemetry::track(
+                    "shield",
+                    "initialize",
+                    shield::ShieldAdapter::new(&config.shield_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("Shield integration initialized successfully");
                         self.shield = Some(Arc::new(adapter));
@@ -117,7 +127,13 @@ impl IntegrationManager {
         #[cfg(feature = "sentinel")]
         {
             if config.sentinel_enabled {
-                match sentinel::SentinelAdapter::new(&config.sentinel_config).await {
+                match telemetry::track(
+                    "sentinel",
+                    "initialize",
+                    sentinel::SentinelAdapter::new(&config.sentinel_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("Sentinel integration initialized successfully");
                         self.sentinel = Some(Arc::new(adapter));
@@ -133,7 +149,13 @@ impl IntegrationManager {
         #[cfg(feature = "connector-hub")]
         {
             if config.connector_hub_enabled {
-                match connector_hub::ConnectorHubAdapter::new(&config.connector_hub_config).await {
+                match telemetry::track(
+                    "connector_hub",
+                    "initialize",
+                    connector_hub::ConnectorHubAdapter::new(&config.connector_hub_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("Connector-Hub integration initialized successfully");
                         self.connector_hub = Some(Arc::new(adapter));
@@ -149,7 +171,13 @@ impl IntegrationManager {
         #[cfg(feature = "cost-ops")]
         {
             if config.cost_ops_enabled {
-                match cost_ops::CostOpsAdapter::new(&config.cost_ops_config).await {
+                match telemetry::track(
+                    "cost_ops",
+                    "initialize",
+                    cost_ops::CostOpsAdapter::new(&config.cost_ops_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("CostOps integration initialized successfully");
                         self.cost_ops = Some(Arc::new(adapter));
@@ -165,7 +193,13 @@ impl IntegrationManager {
         #[cfg(feature = "observatory")]
         {
             if config.observatory_enabled {
-                match observatory::ObservatoryAdapter::new(&config.observatory_config).await {
+                match telemetry::track(
+                    "observatory",
+                    "initialize",
+                    observatory::ObservatoryAdapter::new(&config.observatory_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("Observatory integration initialized successfully");
                         self.observatory = Some(Arc::new(adapter));
@@ -181,7 +215,13 @@ impl IntegrationManager {
         #[cfg(feature = "policy-engine")]
         {
             if config.policy_engine_enabled {
-                match policy_engine::PolicyEngineAdapter::new(&config.policy_engine_config).await {
+                match telemetry::track(
+                    "policy_engine",
+                    "initialize",
+                    policy_engine::PolicyEngineAdapter::new(&config.policy_engine_config),
+                )
+                .await
+                {
                     Ok(adapter) => {
                         info!("Policy-Engine integration initialized successfully");
                         self.policy_engine = Some(Arc::new(adapter));
@@ -331,6 +371,29 @@ pub struct IntegrationHealth {
     pub policy_engine_healthy: bool,
 }
 
+impl IntegrationHealth {
+    /// Health of each compiled-in integration, keyed by integration name
+    ///
+    /// Integrations whose feature is disabled are omitted.
+    pub fn statuses(&self) -> BTreeMap<&'static str, bool> {
+        #[allow(unused_mut)]
+        let mut statuses = BTreeMap::new();
+        #[cfg(feature = "shield")]
+        statuses.insert("shield", self.shield_healthy);
+        #[cfg(feature = "sentinel")]
+        statuses.insert("sentinel", self.sentinel_healthy);
+        #[cfg(feature = "connector-hub")]
+        statuses.insert("connector_hub", self.connector_hub_healthy);
+        #[cfg(feature = "cost-ops")]
+        statuses.insert("cost_ops", self.cost_ops_healthy);
+        #[cfg(feature = "observatory")]
+        statuses.insert("observatory", self.observatory_healthy);
+        #[cfg(feature = "policy-engine")]
+        statuses.insert("policy_engine", self.policy_engine_healthy);
+        statuses
+    }
+}
+
 /// Common error type for integration operations
 #[derive(Debug, thiserror::Error)]
 pub enum IntegrationError {
@@ -371,4 +434,14 @@ mod tests {
         assert!(!config.observatory_enabled);
         assert!(!config.policy_engine_enabled);
     }
+
+    #[tokio::test]
+    async fn test_health_statuses_list_only_compiled_integrations() {
+        let statuses = IntegrationManager::new().health_check().await.statuses();
+
+        assert_eq!(statuses.contains_key("shield"), cfg!(feature = "shield"));
+        assert_eq!(statuses.contains_key("policy_engine"), cfg!(feature = "policy-engine"));
+        // Compiled in but not initialized
+        assert!(statuses.values().all(|healthy| !healthy));
+    }
 }
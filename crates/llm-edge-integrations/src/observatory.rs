@@ -134,10 +134,13 @@ impl ObservatoryAdapter {
 
         debug!("Fetching telemetry streams from Observatory");
 
-        self.client
-            .get_streams()
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch telemetry streams: {}", e)))
+        crate::telemetry::track("observatory", "get_telemetry_streams", async {
+            self.client
+                .get_streams()
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch telemetry streams: {}", e)))
+        })
+        .await
     }
 
     /// Consume telemetry stream by name
@@ -147,10 +150,13 @@ impl ObservatoryAdapter {
     pub async fn get_telemetry_stream(&self, stream_name: &str) -> Result<Option<TelemetryStream>, crate::IntegrationError> {
         debug!("Fetching telemetry stream: {}", stream_name);
 
-        self.client
-            .get_stream(stream_name)
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch telemetry stream: {}", e)))
+        crate::telemetry::track("observatory", "get_telemetry_stream", async {
+            self.client
+                .get_stream(stream_name)
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch telemetry stream: {}", e)))
+        })
+        .await
     }
 
     /// Consume event pipeline configurations from Observatory
@@ -165,10 +171,13 @@ impl ObservatoryAdapter {
 
         debug!("Fetching event pipelines from Observatory");
 
-        self.client
-            .get_pipelines()
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch event pipelines: {}", e)))
+        crate::telemetry::track("observatory", "get_event_pipelines", async {
+            self.client
+                .get_pipelines()
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch event pipelines: {}", e)))
+        })
+        .await
     }
 
     /// Consume event pipeline by name
@@ -178,10 +187,13 @@ impl ObservatoryAdapter {
     pub async fn get_event_pipeline(&self, pipeline_name: &str) -> Result<Option<EventPipeline>, crate::IntegrationError> {
         debug!("Fetching event pipeline: {}", pipeline_name);
 
-        self.client
-            .get_pipeline(pipeline_name)
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch event pipeline: {}", e)))
+        crate::telemetry::track("observatory", "get_event_pipeline", async {
+            self.client
+                .get_pipeline(pipeline_name)
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch event pipeline: {}", e)))
+        })
+        .await
     }
 
     /// Consume metric definitions from Observatory
@@ -192,10 +204,13 @@ impl ObservatoryAdapter {
     pub async fn get_metric_definitions(&self) -> Result<Vec<MetricDefinition>, crate::IntegrationError> {
         debug!("Fetching metric definitions from Observatory");
 
-        self.client
-            .get_metrics()
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch metric definitions: {}", e)))
+        crate::telemetry::track("observatory", "get_metric_definitions", async {
+            self.client
+                .get_metrics()
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch metric definitions: {}", e)))
+        })
+        .await
     }
 
     /// Consume trace definitions from Observatory
@@ -206,10 +221,13 @@ impl ObservatoryAdapter {
     pub async fn get_trace_definitions(&self) -> Result<Vec<TraceDefinition>, crate::IntegrationError> {
         debug!("Fetching trace definitions from Observatory");
 
-        self.client
-            .get_traces()
-            .await
-            .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch trace definitions: {}", e)))
+        crate::telemetry::track("observatory", "get_trace_definitions", async {
+            self.client
+                .get_traces()
+                .await
+                .map_err(|e| crate::IntegrationError::Observatory(format!("Failed to fetch trace definitions: {}", e)))
+        })
+        .await
     }
 
     /// Get all telemetry streams as a map
@@ -245,8 +263,4 @@ impl ObservatoryAdapter {
             "Observatory feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
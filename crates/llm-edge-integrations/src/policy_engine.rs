@@ -142,10 +142,13 @@ impl PolicyEngineAdapter {
 
         debug!("Fetching enforcement rules from Policy-Engine");
 
-        self.client
-            .get_rules()
-            .await
-            .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch enforcement rules: {}", e)))
+        crate::telemetry::track("policy_engine", "get_enforcement_rules", async {
+            self.client
+                .get_rules()
+                .await
+                .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch enforcement rules: {}", e)))
+        })
+        .await
     }
 
     /// Consume enforcement rule by ID
@@ -155,10 +158,13 @@ impl PolicyEngineAdapter {
     pub async fn get_enforcement_rule(&self, rule_id: &str) -> Result<Option<EnforcementRule>, crate::IntegrationError> {
         debug!("Fetching enforcement rule: {}", rule_id);
 
-        self.client
-            .get_rule(rule_id)
-            .await
-            .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch enforcement rule: {}", e)))
+        crate::telemetry::track("policy_engine", "get_enforcement_rule", async {
+            self.client
+                .get_rule(rule_id)
+                .await
+                .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch enforcement rule: {}", e)))
+        })
+        .await
     }
 
     /// Consume policy validation result
@@ -182,10 +188,13 @@ impl PolicyEngineAdapter {
             user_id, resource, action
         );
 
-        self.client
-            .validate(user_id, resource, action, context)
-            .await
-            .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Policy validation failed: {}", e)))
+        crate::telemetry::track("policy_engine", "validate_request", async {
+            self.client
+                .validate(user_id, resource, action, context)
+                .await
+                .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Policy validation failed: {}", e)))
+        })
+        .await
     }
 
     /// Consume routing permissions from Policy-Engine
@@ -200,10 +209,13 @@ impl PolicyEngineAdapter {
 
         debug!("Fetching routing permissions for user: {}", user_id);
 
-        self.client
-            .get_permissions(user_id)
-            .await
-            .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch routing permissions: {}", e)))
+        crate::telemetry::track("policy_engine", "get_routing_permissions", async {
+            self.client
+                .get_permissions(user_id)
+                .await
+                .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Failed to fetch routing permissions: {}", e)))
+        })
+        .await
     }
 
     /// Consume policy decision for a specific action
@@ -222,10 +234,13 @@ impl PolicyEngineAdapter {
             user_id, resource, action
         );
 
-        self.client
-            .evaluate_policy(user_id, resource, action)
-            .await
-            .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Policy decision failed: {}", e)))
+        crate::telemetry::track("policy_engine", "get_policy_decision", async {
+            self.client
+                .evaluate_policy(user_id, resource, action)
+                .await
+                .map_err(|e| crate::IntegrationError::PolicyEngine(format!("Policy decision failed: {}", e)))
+        })
+        .await
     }
 
     /// Check if user is permitted to route to a specific provider
@@ -270,8 +285,4 @@ impl PolicyEngineAdapter {
             "Policy-Engine feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
@@ -132,10 +132,13 @@ impl SentinelAdapter {
 
         debug!("Fetching anomaly flags from Sentinel");
 
-        self.client
-            .get_active_anomalies()
-            .await
-            .map_err(|e| crate::IntegrationError::Sentinel(format!("Failed to fetch anomaly flags: {}", e)))
+        crate::telemetry::track("sentinel", "get_anomaly_flags", async {
+            self.client
+                .get_active_anomalies()
+                .await
+                .map_err(|e| crate::IntegrationError::Sentinel(format!("Failed to fetch anomaly flags: {}", e)))
+        })
+        .await
     }
 
     /// Consume risk score from Sentinel
@@ -150,10 +153,13 @@ impl SentinelAdapter {
 
         debug!("Calculating risk score for request: {}", request_id);
 
-        self.client
-            .calculate_risk(request_id, user_id)
-            .await
-            .map_err(|e| crate::IntegrationError::Sentinel(format!("Risk score calculation failed: {}", e)))
+        crate::telemetry::track("sentinel", "calculate_risk_score", async {
+            self.client
+                .calculate_risk(request_id, user_id)
+                .await
+                .map_err(|e| crate::IntegrationError::Sentinel(format!("Risk score calculation failed: {}", e)))
+        })
+        .await
     }
 
     /// Consume runtime alerts from Sentinel
@@ -164,10 +170,13 @@ impl SentinelAdapter {
     pub async fn get_runtime_alerts(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<RuntimeAlert>, crate::IntegrationError> {
         debug!("Fetching runtime alerts from Sentinel");
 
-        self.client
-            .get_alerts(since)
-            .await
-            .map_err(|e| crate::IntegrationError::Sentinel(format!("Failed to fetch runtime alerts: {}", e)))
+        crate::telemetry::track("sentinel", "get_runtime_alerts", async {
+            self.client
+                .get_alerts(since)
+                .await
+                .map_err(|e| crate::IntegrationError::Sentinel(format!("Failed to fetch runtime alerts: {}", e)))
+        })
+        .await
     }
 
     /// Check if a risk score exceeds the configured threshold
@@ -197,8 +206,4 @@ impl SentinelAdapter {
             "Sentinel feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
@@ -121,10 +121,13 @@ impl ShieldAdapter {
     pub async fn get_security_filters(&self) -> Result<Vec<SecurityFilter>, crate::IntegrationError> {
         debug!("Fetching security filters from Shield");
 
-        self.client
-            .get_active_filters()
-            .await
-            .map_err(|e| crate::IntegrationError::Shield(format!("Failed to fetch security filters: {}", e)))
+        crate::telemetry::track("shield", "get_security_filters", async {
+            self.client
+                .get_active_filters()
+                .await
+                .map_err(|e| crate::IntegrationError::Shield(format!("Failed to fetch security filters: {}", e)))
+        })
+        .await
     }
 
     /// Consume PII detection results from Shield
@@ -139,10 +142,13 @@ impl ShieldAdapter {
 
         debug!("Requesting PII detection from Shield");
 
-        self.client
-            .detect_pii(content)
-            .await
-            .map_err(|e| crate::IntegrationError::Shield(format!("PII detection failed: {}", e)))
+        crate::telemetry::track("shield", "detect_pii", async {
+            self.client
+                .detect_pii(content)
+                .await
+                .map_err(|e| crate::IntegrationError::Shield(format!("PII detection failed: {}", e)))
+        })
+        .await
     }
 
     /// Consume policy block events from Shield
@@ -153,10 +159,13 @@ impl ShieldAdapter {
     pub async fn get_policy_block_events(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<PolicyBlockEvent>, crate::IntegrationError> {
         debug!("Fetching policy block events from Shield");
 
-        self.client
-            .get_block_events(since)
-            .await
-            .map_err(|e| crate::IntegrationError::Shield(format!("Failed to fetch policy block events: {}", e)))
+        crate::telemetry::track("shield", "get_policy_block_events", async {
+            self.client
+                .get_block_events(since)
+                .await
+                .map_err(|e| crate::IntegrationError::Shield(format!("Failed to fetch policy block events: {}", e)))
+        })
+        .await
     }
 
     /// Check if the Shield adapter is healthy
@@ -182,8 +191,4 @@ impl ShieldAdapter {
             "Shield feature not enabled".to_string(),
         ))
     }
-
-    pub fn health_check(&self) -> bool {
-        false
-    }
 }
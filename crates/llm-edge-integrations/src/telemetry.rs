@@ -0,0 +1,182 @@
+//! Integration telemetry
+//!
+//! Every upstream adapter call is wrapped with [`track`], which times the call
+//! and emits a structured [`IntegrationEvent`] through `tracing`.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Tracing target used for all integration events
+pub const EVENT_TARGET: &str = "llm_edge_integrations::telemetry";
+
+/// Outcome of an adapter call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationOutcome {
+    Success,
+    Failure,
+}
+
+impl IntegrationOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntegrationOutcome::Success => "success",
+            IntegrationOutcome::Failure => "failure",
+        }
+    }
+}
+
+/// Structured telemetry event for a single adapter call
+#[derive(Debug, Clone)]
+pub struct IntegrationEvent {
+    /// Adapter name (e.g. "shield", "cost_ops")
+    pub adapter: &'static str,
+    /// Operation name (usually the adapter method)
+    pub operation: &'static str,
+    /// Wall-clock duration of the call
+    pub duration: Duration,
+    /// Whether the call succeeded
+    pub outcome: IntegrationOutcome,
+    /// Error message on failure
+    pub error: Option<String>,
+}
+
+/// Emit an integration event via tracing
+pub fn emit_event(event: &IntegrationEvent) {
+    let duration_ms = event.duration.as_secs_f64() * 1000.0;
+
+    match event.outcome {
+        IntegrationOutcome::Success => info!(
+            target: EVENT_TARGET,
+            adapter = event.adapter,
+            operation = event.operation,
+            duration_ms,
+            outcome = event.outcome.as_str(),
+            "integration event"
+        ),
+        IntegrationOutcome::Failure => warn!(
+            target: EVENT_TARGET,
+            adapter = event.adapter,
+            operation = event.operation,
+            duration_ms,
+            outcome = event.outcome.as_str(),
+            error = event.error.as_deref().unwrap_or(""),
+            "integration event"
+        ),
+    }
+}
+
+/// Run an adapter call, emitting an [`IntegrationEvent`] when it completes
+pub async fn track<T, F>(
+    adapter: &'static str,
+    operation: &'static str,
+    call: F,
+) -> Result<T, crate::IntegrationError>
+where
+    F: Future<Output = Result<T, crate::IntegrationError>>,
+{
+    let start = Instant::now();
+    let result = call.await;
+
+    let (outcome, error) = match &result {
+        Ok(_) => (IntegrationOutcome::Success, None),
+        Err(e) => (IntegrationOutcome::Failure, Some(e.to_string())),
+    };
+
+    emit_event(&IntegrationEvent {
+        adapter,
+        operation,
+        duration: start.elapsed(),
+        outcome,
+        error,
+    });
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    type Fields = Vec<(String, String)>;
+
+    /// Captures the fields of events emitted on the integration target
+    #[derive(Clone, Default)]
+    struct CaptureLayer {
+        events: Arc<Mutex<Vec<Fields>>>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut Fields);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for CaptureLayer {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            if event.metadata().target() != EVENT_TARGET {
+                return;
+            }
+            let mut fields = Vec::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+    }
+
+    fn field<'a>(fields: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        fields
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[tokio::test]
+    async fn test_track_emits_success_event() {
+        let layer = CaptureLayer::default();
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let result = track("shield", "get_security_filters", async {
+            Ok::<_, crate::IntegrationError>(3)
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        let events = layer.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(field(&events[0], "adapter"), Some("shield"));
+        assert_eq!(field(&events[0], "operation"), Some("get_security_filters"));
+        assert_eq!(field(&events[0], "outcome"), Some("success"));
+        assert!(field(&events[0], "duration_ms").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_track_emits_failure_event() {
+        let layer = CaptureLayer::default();
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let result: Result<(), _> = track("cost_ops", "get_account_limits", async {
+            Err(crate::IntegrationError::CostOps("unreachable".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+
+        let events = layer.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(field(&events[0], "adapter"), Some("cost_ops"));
+        assert_eq!(field(&events[0], "outcome"), Some("failure"));
+        assert!(field(&events[0], "error").unwrap().contains("unreachable"));
+    }
+}
@@ -1,6 +1,6 @@
 //! Prometheus metrics
 
-use metrics::{counter, gauge, histogram};
+use metrics::{counter, gauge, histogram, Label};
 
 /// Records a successful request
 pub fn record_request_success(provider: &str, model: &str, latency_ms: u64) {
@@ -23,15 +23,82 @@ pub fn record_cache_miss(tier: &str) {
     counter!("llm_edge_cache_misses_total", "tier" => tier.to_string()).increment(1);
 }
 
-/// Records token usage
-pub fn record_token_usage(provider: &str, model: &str, input_tokens: usize, output_tokens: usize) {
-    counter!("llm_edge_tokens_total", "provider" => provider.to_string(), "model" => model.to_string(), "type" => "input").increment(input_tokens as u64);
-    counter!("llm_edge_tokens_total", "provider" => provider.to_string(), "model" => model.to_string(), "type" => "output").increment(output_tokens as u64);
+/// Records a cache lookup skipped because of a high request temperature
+pub fn record_cache_skipped_high_temp() {
+    counter!("llm_edge_cache_skipped_high_temp_total").increment(1);
 }
 
-/// Records cost
-pub fn record_cost(provider: &str, model: &str, cost_usd: f64) {
-    counter!("llm_edge_cost_usd_total", "provider" => provider.to_string(), "model" => model.to_string()).increment(cost_usd as u64);
+/// `provider` and `model` labels followed by the request's tag labels
+fn tagged_labels(provider: &str, model: &str, tags: &[(String, String)]) -> Vec<Label> {
+    let mut labels = vec![
+        Label::new("provider", provider.to_string()),
+        Label::new("model", model.to_string()),
+    ];
+    labels.extend(
+        tags.iter()
+            .map(|(key, value)| Label::new(key.clone(), value.clone())),
+    );
+    labels
+}
+
+/// Records token usage, labeled with the request's allowlisted tags
+pub fn record_token_usage(
+    provider: &str,
+    model: &str,
+    input_tokens: usize,
+    output_tokens: usize,
+    tags: &[(String, String)],
+) {
+    let labels = tagged_labels(provider, model, tags);
+    for (kind, tokens) in [("input", input_tokens), ("output", output_tokens)] {
+        let mut labels = labels.clone();
+        labels.push(Label::new("type", kind));
+        counter!("llm_edge_tokens_total", labels).increment(tokens as u64);
+    }
+}
+
+/// `cost_usd` in whole micro-dollars, since counters only take integers
+///
+/// Rounded rather than truncated, so a request costing a fraction of a cent
+/// still counts.
+pub fn micro_usd(cost_usd: f64) -> u64 {
+    (cost_usd * 1_000_000.0).round() as u64
+}
+
+/// Records cost in micro-dollars, labeled with the request's allowlisted tags
+pub fn record_cost(provider: &str, model: &str, cost_usd: f64, tags: &[(String, String)]) {
+    counter!(
+        "llm_edge_cost_micro_usd_total",
+        tagged_labels(provider, model, tags)
+    )
+    .increment(micro_usd(cost_usd));
+}
+
+/// Records a shadow request and whether the shadow provider answered
+pub fn record_shadow_request(provider: &str, model: &str, outcome: &str) {
+    counter!(
+        "llm_edge_shadow_requests_total",
+        "provider" => provider.to_string(),
+        "model" => model.to_string(),
+        "outcome" => outcome.to_string()
+    )
+    .increment(1);
+}
+
+/// Records the cost of a shadow request in micro-dollars, kept apart from
+/// `llm_edge_cost_micro_usd_total`
+pub fn record_shadow_cost(provider: &str, model: &str, cost_usd: f64) {
+    counter!(
+        "llm_edge_shadow_cost_micro_usd_total",
+        "provider" => provider.to_string(),
+        "model" => model.to_string()
+    )
+    .increment(micro_usd(cost_usd));
+}
+
+/// Records a static fallback served instead of a failed request
+pub fn record_static_fallback(reason: &str) {
+    counter!("llm_edge_static_fallback_total", "reason" => reason.to_string()).increment(1);
 }
 
 /// Records active requests
@@ -39,6 +106,11 @@ pub fn record_active_requests(count: usize) {
     gauge!("llm_edge_active_requests").set(count as f64);
 }
 
+/// Records how many concurrent request permits are free
+pub fn record_concurrency_permits_available(count: usize) {
+    gauge!("llm_concurrency_permits_available").set(count as f64);
+}
+
 /// Records provider health
 pub fn record_provider_health(provider: &str, is_healthy: bool) {
     gauge!("llm_edge_provider_available", "provider" => provider.to_string()).set(if is_healthy {
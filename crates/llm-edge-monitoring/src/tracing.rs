@@ -1,6 +1,39 @@
 //! OpenTelemetry tracing utilities
+//!
+//! W3C trace context propagation: incoming `traceparent`/`tracestate` headers
+//! become the parent of the request span, and provider adapters inject the
+//! current span's context into their upstream requests.
 
-// TODO: Implement OpenTelemetry tracing setup
-// - OTLP exporter configuration
-// - Span creation utilities
-// - Trace context propagation
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::TracerProvider};
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Instrumentation scope name for spans created by the agent
+pub const TRACER_NAME: &str = "llm-edge-agent";
+
+/// Install the W3C trace context propagator globally
+///
+/// Without it the global propagator is a no-op and neither extraction nor
+/// injection does anything.
+pub fn init_propagation() {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+}
+
+/// Layer giving every `tracing` span an OpenTelemetry span context
+///
+/// Uses `provider` when given (e.g. one with an OTLP exporter); otherwise spans
+/// get trace/span ids for propagation but are not exported anywhere.
+pub fn context_layer<S>(
+    provider: Option<&TracerProvider>,
+) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let tracer = match provider {
+        Some(provider) => provider.tracer(TRACER_NAME),
+        None => TracerProvider::builder().build().tracer(TRACER_NAME),
+    };
+    tracing_opentelemetry::layer().with_tracer(tracer)
+}
@@ -1,5 +1,29 @@
-use crate::{ProviderResult, UnifiedRequest, UnifiedResponse};
+use crate::{
+    recording::REDACTED_HEADER_VALUE,
+    streaming::{ChunkStream, StreamChunk},
+    timeouts::read_body,
+    FinishReason, ProviderError, ProviderResult, RateLimitState, UnifiedRequest, UnifiedResponse,
+};
 use async_trait::async_trait;
+use opentelemetry_http::HeaderInjector;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{RequestBuilder, Response};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tracing::warn;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Headers that are never forwarded upstream, even if allowlisted
+pub const DENIED_PASSTHROUGH_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+    "host",
+    "cookie",
+    "content-length",
+];
 
 /// Health status of a provider
 #[derive(Debug, Clone)]
@@ -10,12 +34,45 @@ pub enum HealthStatus {
 }
 
 /// Pricing information for a model
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PricingInfo {
     pub input_cost_per_1k: f64,
     pub output_cost_per_1k: f64,
 }
 
+/// What a model accepts and how many tokens it handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ModelCapabilities {
+    /// Accepts image input
+    pub supports_vision: bool,
+    /// Supports tool/function calling
+    pub supports_tools: bool,
+    /// Context window, prompt and output together
+    pub max_context_tokens: u32,
+    /// Maximum output tokens
+    pub max_output_tokens: u32,
+}
+
+/// List price of `model`, whichever provider serves it
+///
+/// One table for every adapter, so cost-based routing compares candidates
+/// by the model each would actually run rather than only pricing a
+/// provider's own models. Pricing as of 2024 (update regularly).
+pub fn model_pricing(model: &str) -> Option<PricingInfo> {
+    let (input_cost_per_1k, output_cost_per_1k) = match model {
+        "gpt-4" => (0.03, 0.06),
+        "gpt-3.5-turbo" => (0.0005, 0.0015),
+        "claude-3-5-sonnet-20240229" => (0.003, 0.015),
+        "claude-3-opus-20240229" => (0.015, 0.075),
+        "claude-3-haiku-20240307" => (0.00025, 0.00125),
+        _ => return None,
+    };
+    Some(PricingInfo {
+        input_cost_per_1k,
+        output_cost_per_1k,
+    })
+}
+
 /// Trait that all LLM provider adapters must implement
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
@@ -25,9 +82,372 @@ pub trait LLMProvider: Send + Sync {
     /// Sends a request to the provider
     async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse>;
 
+    /// Sends a request and streams the completion as the provider produces it
+    ///
+    /// Fails before returning the stream if the provider rejects the request.
+    /// The default sends it with [`send`](Self::send) and yields the whole
+    /// response as one chunk, for providers without a streaming API.
+    async fn send_stream(&self, request: UnifiedRequest) -> ProviderResult<ChunkStream> {
+        let response = self.send(request).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(StreamChunk::from(response))
+        })))
+    }
+
     /// Gets pricing information for a model
     fn get_pricing(&self, model: &str) -> Option<PricingInfo>;
 
+    /// Maximum number of output tokens the model can generate, if known
+    fn max_output_tokens(&self, _model: &str) -> Option<u32> {
+        None
+    }
+
+    /// Models this provider serves, as clients name them
+    fn list_models(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Capabilities of `model`, if known
+    fn capabilities(&self, _model: &str) -> Option<ModelCapabilities> {
+        None
+    }
+
     /// Checks provider health
     async fn health(&self) -> HealthStatus;
+
+    /// Reject a request this provider cannot serve, before it is sent
+    ///
+    /// Fails with [`ProviderError::UnsupportedParameter`] naming the
+    /// offending parameter.
+    fn check_request(&self, _request: &UnifiedRequest) -> ProviderResult<()> {
+        Ok(())
+    }
+
+    /// Request quota from the provider's most recent rate-limit headers
+    fn rate_limit_state(&self) -> Option<RateLimitState> {
+        None
+    }
+
+    /// Replace the API key used for subsequent requests
+    ///
+    /// Requests already built keep the key they captured.
+    fn rotate_key(&self, _new_key: String) -> ProviderResult<()> {
+        Err(ProviderError::Configuration(format!(
+            "Provider {} does not support key rotation",
+            self.name()
+        )))
+    }
+}
+
+/// Select the inbound headers that may be forwarded to a provider
+///
+/// Only headers named in `allowlist` (case-insensitive) are kept, and anything
+/// in [`DENIED_PASSTHROUGH_HEADERS`] is dropped regardless of the allowlist.
+pub fn filter_passthrough_headers(inbound: &HeaderMap, allowlist: &[String]) -> HeaderMap {
+    let mut forwarded = HeaderMap::new();
+
+    for name in allowlist {
+        let Ok(name) = HeaderName::from_bytes(name.trim().to_ascii_lowercase().as_bytes()) else {
+            continue;
+        };
+
+        if DENIED_PASSTHROUGH_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+
+        for value in inbound.get_all(&name) {
+            forwarded.append(name.clone(), value.clone());
+        }
+    }
+
+    forwarded
+}
+
+/// Attach a request's passthrough headers to an outgoing provider request
+///
+/// Values replace any static header of the same name. Credential headers are
+/// dropped, so the provider's own auth always takes precedence.
+pub fn apply_extra_headers(builder: RequestBuilder, extra_headers: &HeaderMap) -> RequestBuilder {
+    let mut headers = extra_headers.clone();
+    for denied in DENIED_PASSTHROUGH_HEADERS {
+        headers.remove(*denied);
+    }
+    builder.headers(headers)
+}
+
+/// Parse the static headers an adapter sends with every request
+///
+/// Names are case-insensitive. Entries with an invalid name or value, or
+/// naming one of the [`DENIED_PASSTHROUGH_HEADERS`] the adapter sets itself,
+/// are skipped with a warning that never includes the value.
+pub fn parse_static_headers(headers: &HashMap<String, String>) -> HeaderMap {
+    let mut parsed = HeaderMap::new();
+    for (name, value) in headers {
+        match (
+            HeaderName::from_bytes(name.trim().to_ascii_lowercase().as_bytes()),
+            HeaderValue::from_str(value.trim()),
+        ) {
+            (Ok(name), Ok(mut value)) if !DENIED_PASSTHROUGH_HEADERS.contains(&name.as_str()) => {
+                value.set_sensitive(is_sensitive_header(name.as_str()));
+                parsed.insert(name, value);
+            }
+            _ => warn!(header = %name, "Ignoring invalid static provider header"),
+        }
+    }
+    parsed
+}
+
+/// Headers as a name-to-value map that is safe to log
+///
+/// Values of [sensitive](is_sensitive_header) headers are replaced by
+/// [`REDACTED_HEADER_VALUE`].
+pub fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive_header(name.as_str()) || value.is_sensitive() {
+                REDACTED_HEADER_VALUE.to_string()
+            } else {
+                value.to_str().unwrap_or_default().to_string()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// Whether a header carries credentials and must never be logged or written
+/// to disk
+pub fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    DENIED_PASSTHROUGH_HEADERS.contains(&name.as_str())
+        || ["key", "token", "secret", "auth"]
+            .iter()
+            .any(|marker| name.contains(marker))
+}
+
+/// Attach the current span's W3C trace context (`traceparent`/`tracestate`)
+///
+/// Uses the global propagator, so nothing is added until propagation has been
+/// initialised (see `llm_edge_monitoring::tracing::init_propagation`).
+pub fn inject_trace_context(builder: RequestBuilder) -> RequestBuilder {
+    let cx = tracing::Span::current().context();
+    let mut headers = HeaderMap::new();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut headers))
+    });
+    builder.headers(headers)
+}
+
+/// `response` if the provider answered with success, otherwise the typed
+/// error for its status
+///
+/// An error body is read with the `idle` timeout, so a stalled error
+/// response cannot hang the request.
+pub async fn error_for_status(
+    response: Response,
+    model: &str,
+    idle: Duration,
+) -> ProviderResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = read_body(response, idle).await?;
+    Err(ProviderError::from_status(
+        status.as_u16(),
+        model,
+        String::from_utf8_lossy(&body),
+    ))
+}
+
+/// Whether a provider answered with a server-sent event stream
+pub fn is_event_stream(response: &Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+/// Reject a response whose finish reason indicates content filtering
+///
+/// OpenAI reports `content_filter` and Anthropic a `refusal` stop reason;
+/// both normalize to [`FinishReason::ContentFilter`]. Checked on every
+/// provider response so a filtered completion
+/// surfaces as [`ProviderError::ContentFiltered`] instead of an empty success.
+pub fn check_content_filter(response: &UnifiedResponse) -> ProviderResult<()> {
+    let filtered = response
+        .choices
+        .iter()
+        .find(|choice| choice.finish_reason == Some(FinishReason::ContentFilter))
+        .map(|choice| {
+            let reason = choice
+                .native_finish_reason
+                .as_deref()
+                .unwrap_or(FinishReason::ContentFilter.as_str());
+            (choice.index, reason)
+        });
+
+    match filtered {
+        Some((index, reason)) => Err(ProviderError::ContentFiltered {
+            provider: response.metadata.provider.clone(),
+            details: format!("choice {} finished with '{}'", index, reason),
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Choice, ResponseMetadata};
+    use crate::{Message, Usage};
+    use reqwest::header::HeaderValue;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn inbound_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("openai-organization", HeaderValue::from_static("org-123"));
+        headers.insert("x-internal-debug", HeaderValue::from_static("1"));
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer client-key"),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_filter_passthrough_headers_allowlist() {
+        let allowlist = vec![
+            "OpenAI-Organization".to_string(),
+            "authorization".to_string(),
+        ];
+        let forwarded = filter_passthrough_headers(&inbound_headers(), &allowlist);
+
+        assert_eq!(forwarded.get("openai-organization").unwrap(), "org-123");
+        assert!(forwarded.get("x-internal-debug").is_none());
+        // Denied even though allowlisted
+        assert!(forwarded.get("authorization").is_none());
+    }
+
+    #[test]
+    fn test_filter_passthrough_headers_empty_allowlist() {
+        let forwarded = filter_passthrough_headers(&inbound_headers(), &[]);
+        assert!(forwarded.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_header_reaches_upstream() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let allowlist = vec!["openai-organization".to_string()];
+        let forwarded = filter_passthrough_headers(&inbound_headers(), &allowlist);
+
+        let client = reqwest::Client::new();
+        let builder = client.post(format!("{}/chat/completions", server.uri()));
+        apply_extra_headers(builder, &forwarded)
+            .header("authorization", "Bearer provider-key")
+            .send()
+            .await
+            .unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received.len(), 1);
+        let headers = &received[0].headers;
+        assert_eq!(headers.get("openai-organization").unwrap(), "org-123");
+        assert!(headers.get("x-internal-debug").is_none());
+        assert_eq!(headers.get("authorization").unwrap(), "Bearer provider-key");
+    }
+
+    #[test]
+    fn test_static_headers_parsed_and_redacted() {
+        let parsed = parse_static_headers(&HashMap::from([
+            ("OpenAI-Organization".to_string(), "org-123".to_string()),
+            ("x-portkey-api-key".to_string(), "pk-secret".to_string()),
+            ("bad header".to_string(), "x".to_string()),
+            ("Authorization".to_string(), "Bearer other".to_string()),
+        ]));
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.get("openai-organization").unwrap(), "org-123");
+        assert_eq!(
+            redact_headers(&parsed),
+            BTreeMap::from([
+                ("openai-organization".to_string(), "org-123".to_string()),
+                (
+                    "x-portkey-api-key".to_string(),
+                    REDACTED_HEADER_VALUE.to_string()
+                ),
+            ])
+        );
+    }
+
+    fn response(provider: &str, finish_reason: &str) -> UnifiedResponse {
+        UnifiedResponse {
+            id: "resp-1".to_string(),
+            model: "test-model".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content: String::new(),
+                },
+                finish_reason: Some(FinishReason::from_native(finish_reason)),
+                native_finish_reason: Some(finish_reason.to_string()),
+                logprobs: None,
+                tool_calls: Vec::new(),
+            }],
+            usage: Usage {
+                prompt_tokens: 5,
+                completion_tokens: 0,
+                total_tokens: 5,
+            },
+            metadata: ResponseMetadata {
+                provider: provider.to_string(),
+                cached: false,
+                latency_ms: 0,
+                cost_usd: None,
+                usage_estimated: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_openai_content_filter_is_typed_and_not_retryable() {
+        let err = check_content_filter(&response("openai", "content_filter")).unwrap_err();
+
+        match &err {
+            ProviderError::ContentFiltered { provider, details } => {
+                assert_eq!(provider, "openai");
+                assert!(details.contains("content_filter"));
+            }
+            other => panic!("expected ContentFiltered, got {:?}", other),
+        }
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_anthropic_refusal_is_typed_and_not_retryable() {
+        let err = check_content_filter(&response("anthropic", "refusal")).unwrap_err();
+
+        assert!(matches!(
+            &err,
+            ProviderError::ContentFiltered { provider, .. } if provider == "anthropic"
+        ));
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_normal_finish_reasons_pass() {
+        assert!(check_content_filter(&response("openai", "stop")).is_ok());
+        assert!(check_content_filter(&response("anthropic", "end_turn")).is_ok());
+        assert!(ProviderError::RateLimitExceeded.is_retryable());
+    }
 }
@@ -1,29 +1,575 @@
 //! Anthropic provider adapter
 
 use crate::{
-    adapter::{HealthStatus, LLMProvider, PricingInfo},
-    ProviderResult, UnifiedRequest, UnifiedResponse,
+    adapter::{
+        apply_extra_headers, error_for_status, inject_trace_context, is_event_stream,
+        model_pricing, parse_static_headers, HealthStatus, LLMProvider, ModelCapabilities,
+        PricingInfo,
+    },
+    streaming::{
+        complete_via_stream, sse_data, ChoiceDelta, ChunkStream, StreamChunk, ToolCallDelta,
+    },
+    timeouts::read_body,
+    types::{Choice, ResponseMetadata, ToolCall, Usage},
+    FinishReason, Message, ProviderError, ProviderResult, ProviderTimeouts, RateLimitState,
+    RateLimitTracker, ResponseFormat, UnifiedRequest, UnifiedResponse, UpstreamProxy,
+    USER_ID_METADATA_KEY,
 };
 use async_trait::async_trait;
-use secrecy::Secret;
+use futures::StreamExt;
+use reqwest::{header::HeaderMap, RequestBuilder, Response};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+use std::time::Instant;
+use tracing::info;
+
+/// Anthropic API version sent with every request
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` sent when neither the client nor the model table gives one
+///
+/// Anthropic requires the field on every request.
+pub const DEFAULT_MAX_TOKENS: usize = 4096;
 
 pub struct AnthropicAdapter {
-    #[allow(dead_code)]
     client: reqwest::Client,
-    #[allow(dead_code)]
-    api_key: Secret<String>,
-    #[allow(dead_code)]
+    api_key: RwLock<Secret<String>>,
     base_url: String,
+    timeouts: ProviderTimeouts,
+    proxy: Option<UpstreamProxy>,
+    static_headers: HeaderMap,
+    rate_limits: RateLimitTracker,
+    model_map: HashMap<String, String>,
 }
 
 impl AnthropicAdapter {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            api_key: Secret::new(api_key),
+            client: ProviderTimeouts::default().build_client(),
+            api_key: RwLock::new(Secret::new(api_key)),
             base_url: "https://api.anthropic.com/v1".to_string(),
+            timeouts: ProviderTimeouts::default(),
+            proxy: None,
+            static_headers: HeaderMap::new(),
+            rate_limits: RateLimitTracker::new(),
+            model_map: HashMap::new(),
+        }
+    }
+
+    /// Override the API base URL (e.g. for a gateway or a test server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Replace the connect, idle and total timeouts
+    pub fn with_timeouts(mut self, timeouts: ProviderTimeouts) -> Self {
+        self.client = timeouts.build_client_with_proxy(self.proxy.as_ref());
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Timeouts applied to provider requests
+    pub fn timeouts(&self) -> &ProviderTimeouts {
+        &self.timeouts
+    }
+
+    /// Send every request through this forward proxy
+    pub fn with_proxy(mut self, proxy: UpstreamProxy) -> Self {
+        self.client = self.timeouts.build_client_with_proxy(Some(&proxy));
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Forward proxy requests go through, if any
+    pub fn proxy(&self) -> Option<&UpstreamProxy> {
+        self.proxy.as_ref()
+    }
+
+    /// Send these headers with every request (e.g. a gateway header)
+    ///
+    /// Per-request passthrough headers of the same name replace them.
+    pub fn with_static_headers(mut self, headers: &HashMap<String, String>) -> Self {
+        self.static_headers = parse_static_headers(headers);
+        self
+    }
+
+    /// Headers sent with every request
+    pub fn static_headers(&self) -> &HeaderMap {
+        &self.static_headers
+    }
+
+    /// Record the rate-limit headers of a provider response
+    pub fn observe_rate_limits(&self, headers: &HeaderMap) {
+        self.rate_limits.observe("anthropic", headers);
+    }
+
+    /// Send these model names upstream in place of the client-facing ones
+    /// (e.g. `gpt-4` to an Azure deployment name)
+    ///
+    /// Cache keys, pricing and responses keep the client-facing name.
+    pub fn with_model_map(mut self, model_map: HashMap<String, String>) -> Self {
+        self.model_map = model_map;
+        self
+    }
+
+    /// Name sent upstream for the client-facing `model`
+    pub fn upstream_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_map.get(model).map_or(model, String::as_str)
+    }
+
+    /// Start a POST to `path` with the static headers, authenticated with
+    /// the current API key
+    ///
+    /// The key is read once here, so a rotation only affects requests built
+    /// afterwards.
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        let api_key = self.api_key.read().unwrap_or_else(PoisonError::into_inner);
+        inject_trace_context(self.client.post(format!("{}{}", self.base_url, path)))
+            .headers(self.static_headers.clone())
+            .header("x-api-key", api_key.expose_secret())
+            .header("anthropic-version", ANTHROPIC_VERSION)
+    }
+
+    /// Build the messages request body for `request`
+    ///
+    /// System messages move to the top-level `system` field. `max_tokens` is
+    /// required, so it falls back to the model's output limit. A
+    /// `response_format` becomes a [`StructuredOutputTool`] the model is
+    /// forced to call. `model` is mapped with the model map.
+    pub fn build_request_body(
+        &self,
+        request: &UnifiedRequest,
+    ) -> ProviderResult<MessagesRequestBody> {
+        let tool = match &request.response_format {
+            Some(format) => StructuredOutputTool::from_response_format(format)?,
+            None => None,
+        };
+        let (system, messages): (Vec<_>, Vec<_>) = request
+            .messages
+            .iter()
+            .cloned()
+            .partition(|message| message.role == "system");
+        let max_tokens = request
+            .max_tokens
+            .or_else(|| {
+                self.max_output_tokens(&request.model)
+                    .map(|max| max as usize)
+            })
+            .unwrap_or(DEFAULT_MAX_TOKENS);
+
+        Ok(MessagesRequestBody {
+            model: self.upstream_model(&request.model).to_string(),
+            system: (!system.is_empty()).then(|| {
+                system
+                    .into_iter()
+                    .map(|message| message.content)
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            }),
+            messages,
+            max_tokens,
+            temperature: request.temperature,
+            tool_choice: tool.as_ref().map(StructuredOutputTool::tool_choice),
+            tools: tool.into_iter().collect(),
+            stream: request.stream,
+            metadata: request
+                .metadata
+                .get(USER_ID_METADATA_KEY)
+                .map(|user_id| MessagesMetadata {
+                    user_id: user_id.clone(),
+                }),
+        })
+    }
+
+    /// Send `request` and return the response once it has started, or the
+    /// error for its status, with the name of the structured output tool
+    /// the model was forced to call
+    async fn start(&self, request: &UnifiedRequest) -> ProviderResult<(Response, Option<String>)> {
+        let body = self.build_request_body(request)?;
+        let response =
+            apply_extra_headers(self.post("/messages").json(&body), &request.extra_headers)
+                .send()
+                .await?;
+        self.observe_rate_limits(response.headers());
+        let response = error_for_status(response, &request.model, self.timeouts.read).await?;
+        Ok((
+            response,
+            body.tools.into_iter().next().map(|tool| tool.name),
+        ))
+    }
+
+    /// Read a whole (non-streamed) messages response
+    async fn read_response(
+        &self,
+        response: Response,
+        structured_tool: Option<&str>,
+    ) -> ProviderResult<UnifiedResponse> {
+        let mut unified = if is_event_stream(&response) {
+            complete_via_stream(
+                stream_chunks(response, self.timeouts.read, false),
+                "anthropic",
+            )
+            .await?
+        } else {
+            let body = read_body(response, self.timeouts.read).await?;
+            serde_json::from_slice::<MessagesResponseBody>(&body)?.into_unified()
+        };
+        if let Some(tool) = structured_tool {
+            unwrap_structured_output(&mut unified, tool);
+        }
+        Ok(unified)
+    }
+}
+
+/// Chunks of a streamed messages response
+///
+/// With `structured`, the forced structured output tool call is streamed as
+/// content, like [`unwrap_structured_output`] does for whole responses.
+fn stream_chunks(response: Response, idle: std::time::Duration, structured: bool) -> ChunkStream {
+    sse_data(response, idle)
+        .filter_map(move |data| async move {
+            data.and_then(|data| serde_json::from_str::<StreamEvent>(&data)?.into_chunk(structured))
+                .transpose()
+        })
+        .boxed()
+}
+
+/// Anthropic messages request body
+#[derive(Debug, Serialize)]
+pub struct MessagesRequestBody {
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Message>,
+    max_tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<StructuredOutputTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<MessagesMetadata>,
+}
+
+#[derive(Debug, Serialize)]
+struct MessagesMetadata {
+    user_id: String,
+}
+
+/// Anthropic messages response body
+#[derive(Debug, Deserialize)]
+struct MessagesResponseBody {
+    id: String,
+    #[serde(default)]
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: MessagesUsage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessagesUsage {
+    #[serde(default)]
+    input_tokens: usize,
+    #[serde(default)]
+    output_tokens: usize,
+}
+
+/// One event of a streamed messages response
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: StreamMessageStart,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: BlockDelta,
+    },
+    MessageDelta {
+        delta: StreamMessageDelta,
+        #[serde(default)]
+        usage: MessagesUsage,
+    },
+    Error {
+        error: StreamError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessageStart {
+    id: String,
+    #[serde(default)]
+    usage: MessagesUsage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BlockDelta {
+    TextDelta {
+        text: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessageDelta {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    #[serde(default)]
+    message: String,
+}
+
+impl MessagesResponseBody {
+    /// The response; the caller fills in the model and latency
+    fn into_unified(self) -> UnifiedResponse {
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in self.content {
+            match block {
+                ContentBlock::Text { text } => content.push_str(&text),
+                ContentBlock::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input.to_string(),
+                }),
+                ContentBlock::Other => {}
+            }
+        }
+
+        UnifiedResponse {
+            id: self.id,
+            model: String::new(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: "assistant".to_string(),
+                    content,
+                },
+                finish_reason: self.stop_reason.as_deref().map(FinishReason::from_native),
+                native_finish_reason: self.stop_reason,
+                logprobs: None,
+                tool_calls,
+            }],
+            usage: Usage {
+                prompt_tokens: self.usage.input_tokens,
+                completion_tokens: self.usage.output_tokens,
+                total_tokens: self.usage.input_tokens + self.usage.output_tokens,
+            },
+            metadata: ResponseMetadata {
+                provider: "anthropic".to_string(),
+                cached: false,
+                latency_ms: 0,
+                cost_usd: None,
+                usage_estimated: false,
+            },
+        }
+    }
+}
+
+impl StreamEvent {
+    /// The event as a stream chunk; `None` for events carrying nothing a
+    /// response keeps
+    ///
+    /// Content blocks become tool calls by their block index, or with
+    /// `structured` the tool input becomes content. Usage is only taken from
+    /// `message_start` (input) and `message_delta` (output), so the sum over
+    /// the stream is the total.
+    fn into_chunk(self, structured: bool) -> ProviderResult<Option<StreamChunk>> {
+        let delta = |delta: ChoiceDelta| StreamChunk {
+            choices: vec![delta],
+            ..Default::default()
+        };
+
+        Ok(match self {
+            StreamEvent::MessageStart { message } => Some(StreamChunk {
+                id: Some(message.id),
+                choices: vec![ChoiceDelta {
+                    role: Some("assistant".to_string()),
+                    ..Default::default()
+                }],
+                usage: Some(Usage {
+                    prompt_tokens: message.usage.input_tokens,
+                    completion_tokens: 0,
+                    total_tokens: message.usage.input_tokens,
+                }),
+                ..Default::default()
+            }),
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block: ContentBlock::ToolUse { id, name, .. },
+            } if !structured => Some(delta(ChoiceDelta {
+                tool_calls: vec![ToolCallDelta {
+                    index,
+                    id: Some(id),
+                    name: Some(name),
+                    arguments: None,
+                }],
+                ..Default::default()
+            })),
+            StreamEvent::ContentBlockDelta {
+                delta: BlockDelta::TextDelta { text },
+                ..
+            } => Some(delta(ChoiceDelta {
+                content: Some(text),
+                ..Default::default()
+            })),
+            StreamEvent::ContentBlockDelta {
+                delta: BlockDelta::InputJsonDelta { partial_json },
+                ..
+            } if structured => Some(delta(ChoiceDelta {
+                content: Some(partial_json),
+                ..Default::default()
+            })),
+            StreamEvent::ContentBlockDelta {
+                index,
+                delta: BlockDelta::InputJsonDelta { partial_json },
+            } => Some(delta(ChoiceDelta {
+                tool_calls: vec![ToolCallDelta {
+                    index,
+                    arguments: Some(partial_json),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })),
+            StreamEvent::MessageDelta {
+                delta: message,
+                usage,
+            } => Some(StreamChunk {
+                choices: vec![ChoiceDelta {
+                    // The forced tool call is the answer, not a call to run
+                    finish_reason: match message.stop_reason {
+                        Some(reason) if structured && reason == "tool_use" => {
+                            Some("end_turn".to_string())
+                        }
+                        reason => reason,
+                    },
+                    ..Default::default()
+                }],
+                usage: Some(Usage {
+                    prompt_tokens: 0,
+                    completion_tokens: usage.output_tokens,
+                    total_tokens: usage.output_tokens,
+                }),
+                ..Default::default()
+            }),
+            StreamEvent::Error { error } => {
+                return Err(ProviderError::ApiError {
+                    status: 500,
+                    message: error.message,
+                })
+            }
+            _ => None,
+        })
+    }
+}
+
+/// Turn the call of the structured output tool `tool` into the content
+///
+/// The model was forced to call it, so the call is the answer rather than a
+/// tool call for the client to run, and the choice finished normally.
+fn unwrap_structured_output(response: &mut UnifiedResponse, tool: &str) {
+    for choice in &mut response.choices {
+        if let Some(position) = choice.tool_calls.iter().position(|call| call.name == tool) {
+            choice.message.content = choice.tool_calls.remove(position).arguments;
+            choice.finish_reason = Some(FinishReason::Stop);
+        }
+    }
+}
+
+/// Tool name used for `json_object` structured output
+pub const JSON_OBJECT_TOOL: &str = "json_object";
+
+/// Tool carrying a structured output
+///
+/// Anthropic has no `response_format`. The requested schema becomes the input
+/// schema of a tool, `tool_choice` forces the model to call it, and the tool
+/// call's input is the JSON output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StructuredOutputTool {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub input_schema: serde_json::Value,
+}
+
+impl StructuredOutputTool {
+    /// Translate a response format; `None` for plain text
+    ///
+    /// Tool inputs are always objects, so a `json_schema` without an object
+    /// schema is rejected.
+    pub fn from_response_format(format: &ResponseFormat) -> ProviderResult<Option<Self>> {
+        match format {
+            ResponseFormat::Text => Ok(None),
+            ResponseFormat::JsonObject => Ok(Some(Self {
+                name: JSON_OBJECT_TOOL.to_string(),
+                description: Some("Respond with a JSON object".to_string()),
+                input_schema: serde_json::json!({ "type": "object" }),
+            })),
+            ResponseFormat::JsonSchema { json_schema } => {
+                let schema = json_schema
+                    .schema
+                    .as_ref()
+                    .filter(|schema| schema["type"] == "object")
+                    .ok_or_else(|| ProviderError::UnsupportedParameter {
+                        param: "response_format".to_string(),
+                        message: format!(
+                            "Anthropic models only support json_schema response formats whose \
+                             schema describes an object (\"type\": \"object\"); '{}' does not",
+                            json_schema.name
+                        ),
+                    })?;
+
+                Ok(Some(Self {
+                    name: json_schema.name.clone(),
+                    description: json_schema.description.clone(),
+                    input_schema: schema.clone(),
+                }))
+            }
         }
     }
+
+    /// `tool_choice` forcing the model to call this tool
+    pub fn tool_choice(&self) -> serde_json::Value {
+        serde_json::json!({ "type": "tool", "name": self.name })
+    }
 }
 
 #[async_trait]
@@ -32,32 +578,493 @@ impl LLMProvider for AnthropicAdapter {
         "anthropic"
     }
 
-    async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
-        // TODO: Implement Anthropic API call
-        todo!("Anthropic adapter implementation")
+    /// Send `request` to the messages endpoint
+    ///
+    /// The body is read with the idle timeout. A response that arrives as an
+    /// event stream is collected with [`complete_via_stream`]. A structured
+    /// output comes back as the content, and the response names
+    /// `request.model`, not the upstream name the model map sent.
+    async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+        let start = Instant::now();
+        let (response, structured_tool) = self.start(&request).await?;
+
+        let mut response = self
+            .read_response(response, structured_tool.as_deref())
+            .await?;
+        response.model = request.model;
+        response.metadata.latency_ms = start.elapsed().as_millis() as u64;
+        Ok(response)
+    }
+
+    /// Stream `request` from the messages endpoint
+    ///
+    /// Each chunk is awaited with the idle timeout, and a structured output
+    /// is streamed as content. A gateway that answers with a whole response
+    /// instead yields it as one chunk.
+    async fn send_stream(&self, mut request: UnifiedRequest) -> ProviderResult<ChunkStream> {
+        request.stream = true;
+        let (response, structured_tool) = self.start(&request).await?;
+        if is_event_stream(&response) {
+            return Ok(stream_chunks(
+                response,
+                self.timeouts.read,
+                structured_tool.is_some(),
+            ));
+        }
+
+        let mut response = self
+            .read_response(response, structured_tool.as_deref())
+            .await?;
+        response.model = request.model;
+        Ok(futures::stream::once(async move { Ok(StreamChunk::from(response)) }).boxed())
     }
 
     fn get_pricing(&self, model: &str) -> Option<PricingInfo> {
-        // Pricing as of 2024
+        model_pricing(self.upstream_model(model))
+    }
+
+    fn max_output_tokens(&self, model: &str) -> Option<u32> {
         match model {
-            "claude-3-5-sonnet-20240229" => Some(PricingInfo {
-                input_cost_per_1k: 0.003,
-                output_cost_per_1k: 0.015,
-            }),
-            "claude-3-opus-20240229" => Some(PricingInfo {
-                input_cost_per_1k: 0.015,
-                output_cost_per_1k: 0.075,
-            }),
-            "claude-3-haiku-20240307" => Some(PricingInfo {
-                input_cost_per_1k: 0.00025,
-                output_cost_per_1k: 0.00125,
-            }),
+            m if m.starts_with("claude-3-5") => Some(8192),
+            m if m.starts_with("claude-3") => Some(4096),
             _ => None,
         }
     }
 
+    fn list_models(&self) -> Vec<String> {
+        [
+            "claude-3-5-sonnet-20240229",
+            "claude-3-opus-20240229",
+            "claude-3-haiku-20240307",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    fn capabilities(&self, model: &str) -> Option<ModelCapabilities> {
+        // Every Claude 3 model takes images and tools in a 200k window
+        Some(ModelCapabilities {
+            supports_vision: true,
+            supports_tools: true,
+            max_context_tokens: 200_000,
+            max_output_tokens: self.max_output_tokens(model)?,
+        })
+    }
+
     async fn health(&self) -> HealthStatus {
         // TODO: Implement health check
         HealthStatus::Healthy
     }
+
+    fn rate_limit_state(&self) -> Option<RateLimitState> {
+        self.rate_limits.current()
+    }
+
+    fn check_request(&self, request: &UnifiedRequest) -> ProviderResult<()> {
+        if let Some(format) = &request.response_format {
+            StructuredOutputTool::from_response_format(format)?;
+        }
+        Ok(())
+    }
+
+    fn rotate_key(&self, new_key: String) -> ProviderResult<()> {
+        if new_key.trim().is_empty() {
+            return Err(ProviderError::Configuration(
+                "API key must not be empty".to_string(),
+            ));
+        }
+
+        *self.api_key.write().unwrap_or_else(PoisonError::into_inner) = Secret::new(new_key);
+        info!(provider = "anthropic", "API key rotated");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FinishReason;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_model_map_pins_upstream_version() {
+        let adapter =
+            AnthropicAdapter::new("sk-ant".to_string()).with_model_map(HashMap::from([(
+                "claude-3-5-sonnet".to_string(),
+                "claude-3-5-sonnet-20241022".to_string(),
+            )]));
+
+        assert_eq!(
+            adapter.upstream_model("claude-3-5-sonnet"),
+            "claude-3-5-sonnet-20241022"
+        );
+        assert_eq!(adapter.upstream_model("claude-3-opus"), "claude-3-opus");
+    }
+
+    #[tokio::test]
+    async fn test_rotated_key_used_on_next_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(header("x-api-key", "sk-ant-new"))
+            .and(header("anthropic-version", ANTHROPIC_VERSION))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let adapter = AnthropicAdapter::new("sk-ant-old".to_string()).with_base_url(server.uri());
+        let response = adapter.post("/messages").send().await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        adapter.rotate_key("sk-ant-new".to_string()).unwrap();
+        let response = adapter.post("/messages").send().await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_static_headers_sent_and_overridden_per_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let adapter = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .with_static_headers(&HashMap::from([
+                ("x-portkey-provider".to_string(), "anthropic".to_string()),
+                ("anthropic-beta".to_string(), "static-beta".to_string()),
+                ("x-api-key".to_string(), "sk-static".to_string()),
+            ]));
+        let mut extra_headers = HeaderMap::new();
+        extra_headers.insert(
+            "anthropic-beta",
+            reqwest::header::HeaderValue::from_static("request-beta"),
+        );
+
+        crate::adapter::apply_extra_headers(adapter.post("/messages"), &extra_headers)
+            .send()
+            .await
+            .unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let headers = &received[0].headers;
+        assert_eq!(headers.get("x-portkey-provider").unwrap(), "anthropic");
+        assert_eq!(headers.get("anthropic-beta").unwrap(), "request-beta");
+        // Provider credentials cannot be replaced by a static header
+        assert_eq!(
+            headers.get_all("x-api-key").iter().collect::<Vec<_>>(),
+            ["sk-ant"]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_becomes_forced_tool() {
+        let format: ResponseFormat = serde_json::from_value(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "weather",
+                "description": "Current weather",
+                "schema": {"type": "object", "properties": {"temp": {"type": "number"}}}
+            }
+        }))
+        .unwrap();
+
+        let tool = StructuredOutputTool::from_response_format(&format)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tool.name, "weather");
+        assert_eq!(tool.input_schema["properties"]["temp"]["type"], "number");
+        assert_eq!(
+            tool.tool_choice(),
+            serde_json::json!({"type": "tool", "name": "weather"})
+        );
+
+        let json_object = StructuredOutputTool::from_response_format(&ResponseFormat::JsonObject)
+            .unwrap()
+            .unwrap();
+        assert_eq!(json_object.name, JSON_OBJECT_TOOL);
+        assert!(
+            StructuredOutputTool::from_response_format(&ResponseFormat::Text)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_response_format_rejected() {
+        let format: ResponseFormat = serde_json::from_value(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {"name": "tags", "schema": {"type": "array", "items": {"type": "string"}}}
+        }))
+        .unwrap();
+        let request = UnifiedRequest {
+            model: "claude-3-opus-20240229".to_string(),
+            messages: Vec::new(),
+            temperature: None,
+            max_tokens: None,
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: Some(format),
+            stream: false,
+            metadata: Default::default(),
+            extra_headers: Default::default(),
+        };
+
+        let err = AnthropicAdapter::new("sk-ant".to_string())
+            .check_request(&request)
+            .unwrap_err();
+        match &err {
+            ProviderError::UnsupportedParameter { param, message } => {
+                assert_eq!(param, "response_format");
+                assert!(message.contains("'tags'"), "{}", message);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_stop_reasons_map_to_openai_vocabulary() {
+        for (native, canonical) in [
+            ("end_turn", FinishReason::Stop),
+            ("stop_sequence", FinishReason::Stop),
+            ("max_tokens", FinishReason::Length),
+            ("tool_use", FinishReason::ToolCalls),
+            ("refusal", FinishReason::ContentFilter),
+        ] {
+            assert_eq!(FinishReason::from_native(native), canonical, "{}", native);
+        }
+        assert_eq!(
+            serde_json::to_value(FinishReason::from_native("max_tokens")).unwrap(),
+            "length"
+        );
+    }
+
+    fn request(model: &str) -> UnifiedRequest {
+        UnifiedRequest {
+            model: model.to_string(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: "Be brief".to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: "Hello".to_string(),
+                },
+            ],
+            temperature: None,
+            max_tokens: None,
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream: false,
+            metadata: Default::default(),
+            extra_headers: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_uses_upstream_model_and_reports_client_model() {
+        use wiremock::matchers::body_partial_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "claude-3-5-sonnet-20241022",
+                "system": "Be brief",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "max_tokens": 8192
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("anthropic-ratelimit-requests-remaining", "3")
+                    .set_body_json(serde_json::json!({
+                        "id": "msg_1",
+                        "type": "message",
+                        "role": "assistant",
+                        "model": "claude-3-5-sonnet-20241022",
+                        "content": [{"type": "text", "text": "Hi!"}],
+                        "stop_reason": "end_turn",
+                        "usage": {"input_tokens": 10, "output_tokens": 3}
+                    })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let adapter = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .with_model_map(HashMap::from([(
+                "claude-3-5-sonnet".to_string(),
+                "claude-3-5-sonnet-20241022".to_string(),
+            )]));
+
+        let response = adapter.send(request("claude-3-5-sonnet")).await.unwrap();
+        assert_eq!(response.model, "claude-3-5-sonnet");
+        assert_eq!(response.id, "msg_1");
+        assert_eq!(response.choices[0].message.content, "Hi!");
+        assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            response.choices[0].native_finish_reason.as_deref(),
+            Some("end_turn")
+        );
+        assert_eq!(response.usage.total_tokens, 13);
+        assert_eq!(adapter.rate_limit_state().unwrap().remaining, 3);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_returned_as_content() {
+        use wiremock::matchers::body_partial_json;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "tools": [{"name": "weather"}],
+                "tool_choice": {"type": "tool", "name": "weather"}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_2",
+                "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "weather",
+                    "input": {"temp": 21}
+                }],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut request = request("claude-3-opus-20240229");
+        request.response_format = Some(
+            serde_json::from_value(serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "weather", "schema": {"type": "object"}}
+            }))
+            .unwrap(),
+        );
+
+        let response = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .send(request)
+            .await
+            .unwrap();
+        let choice = &response.choices[0];
+        assert_eq!(choice.message.content, r#"{"temp":21}"#);
+        assert!(choice.tool_calls.is_empty());
+        assert_eq!(choice.finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_response_is_collected() {
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_3","usage":{"input_tokens":8,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hel"}}"#,
+            r#"{"type":"ping"}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"lo"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"max_tokens"},"usage":{"output_tokens":2}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let body = events
+            .iter()
+            .map(|event| format!("event: x\ndata: {}\n\n", event))
+            .collect::<String>();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let response = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .send(request("claude-3-haiku-20240307"))
+            .await
+            .unwrap();
+        assert_eq!(response.id, "msg_3");
+        assert_eq!(response.model, "claude-3-haiku-20240307");
+        assert_eq!(response.choices[0].message.content, "Hello");
+        assert_eq!(
+            response.choices[0].finish_reason,
+            Some(FinishReason::Length)
+        );
+        assert_eq!(response.usage.prompt_tokens, 8);
+        assert_eq!(response.usage.completion_tokens, 2);
+    }
+
+    #[tokio::test]
+    async fn test_structured_output_streams_as_content() {
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_4","usage":{"input_tokens":8}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"weather","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"temp\":"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"21}"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":5}}"#,
+        ];
+        let body = events
+            .iter()
+            .map(|event| format!("data: {}\n\n", event))
+            .collect::<String>();
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let mut request = request("claude-3-opus-20240229");
+        request.response_format = Some(ResponseFormat::JsonObject);
+        let chunks: Vec<_> = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .send_stream(request)
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let choices: Vec<_> = chunks.iter().flat_map(|chunk| &chunk.choices).collect();
+        let content: String = choices
+            .iter()
+            .filter_map(|choice| choice.content.as_deref())
+            .collect();
+        assert_eq!(content, r#"{"temp":21}"#);
+        assert!(choices.iter().all(|choice| choice.tool_calls.is_empty()));
+        assert_eq!(
+            choices.last().unwrap().finish_reason.as_deref(),
+            Some("end_turn")
+        );
+
+        let sent: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(sent["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_error_status_becomes_typed_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid x-api-key"))
+            .mount(&server)
+            .await;
+
+        let err = AnthropicAdapter::new("sk-ant".to_string())
+            .with_base_url(server.uri())
+            .send(request("claude-3-haiku-20240307"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::InvalidApiKey), "{:?}", err);
+    }
 }
@@ -1,3 +1,4 @@
+use reqwest::StatusCode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,11 +18,240 @@ pub enum ProviderError {
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    /// The provider rejected our API key
+    #[error("Invalid API key")]
+    InvalidApiKey,
+
+    /// The provider does not serve this model
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    /// The provider rejected the request as malformed
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
     #[error("Invalid configuration: {0}")]
     Configuration(String),
 
     #[error("Internal error: {0}")]
     Internal(String),
+
+    /// The provider cannot serve a request parameter as sent
+    #[error("Unsupported {param}: {message}")]
+    UnsupportedParameter { param: String, message: String },
+
+    /// The provider refused or filtered the completion on content policy grounds
+    #[error("Content filtered by {provider}: {details}")]
+    ContentFiltered { provider: String, details: String },
+}
+
+impl ProviderError {
+    /// Error for an upstream response with a non-success `status`
+    ///
+    /// Statuses with a dedicated variant get it, so adapters report e.g. a
+    /// rejected key as [`ProviderError::InvalidApiKey`] rather than a bare
+    /// `ApiError`; `model` names the model for a 404.
+    pub fn from_status(status: u16, model: &str, message: impl Into<String>) -> Self {
+        match status {
+            401 | 403 => ProviderError::InvalidApiKey,
+            404 => ProviderError::ModelNotFound(model.to_string()),
+            400 | 422 => ProviderError::InvalidRequest(message.into()),
+            429 => ProviderError::RateLimitExceeded,
+            status => ProviderError::ApiError {
+                status,
+                message: message.into(),
+            },
+        }
+    }
+
+    /// Whether sending the same request again could succeed
+    ///
+    /// Content policy outcomes and unsupported parameters are deterministic
+    /// for a given request, so they are never retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ProviderError::Http(e) => e.is_timeout() || e.is_connect(),
+            ProviderError::ApiError { status, .. } => *status >= 500,
+            ProviderError::Timeout | ProviderError::RateLimitExceeded => true,
+            ProviderError::Serialization(_)
+            | ProviderError::InvalidApiKey
+            | ProviderError::ModelNotFound(_)
+            | ProviderError::InvalidRequest(_)
+            | ProviderError::Configuration(_)
+            | ProviderError::Internal(_)
+            | ProviderError::UnsupportedParameter { .. }
+            | ProviderError::ContentFiltered { .. } => false,
+        }
+    }
+
+    /// Whether the provider turned the request away for exceeding its rate
+    /// limit, rather than failing it
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            ProviderError::RateLimitExceeded | ProviderError::ApiError { status: 429, .. }
+        )
+    }
 }
 
 pub type ProviderResult<T> = Result<T, ProviderError>;
+
+/// HTTP status a client is answered with when a provider call fails with
+/// `error`
+///
+/// The one place provider failures get a status, so the same failure looks
+/// the same on every endpoint:
+///
+/// | Error | Status |
+/// |-------|--------|
+/// | rate limited (`RateLimitExceeded`, upstream 429) | 429 |
+/// | `InvalidApiKey`, upstream 401/403 | 502: our key, not the client's fault |
+/// | `ModelNotFound`, upstream 404 | 404 |
+/// | `Timeout`, timed-out connection, upstream 408/504 | 504 |
+/// | `InvalidRequest`, `UnsupportedParameter`, upstream 400/422 | 400 |
+/// | `ContentFiltered` | 422 |
+/// | `Configuration` | 500 |
+/// | anything else | 502 |
+pub fn error_to_status(error: &ProviderError) -> StatusCode {
+    match error {
+        ProviderError::RateLimitExceeded | ProviderError::ApiError { status: 429, .. } => {
+            StatusCode::TOO_MANY_REQUESTS
+        }
+        ProviderError::InvalidApiKey
+        | ProviderError::ApiError {
+            status: 401 | 403, ..
+        } => StatusCode::BAD_GATEWAY,
+        ProviderError::ModelNotFound(_) | ProviderError::ApiError { status: 404, .. } => {
+            StatusCode::NOT_FOUND
+        }
+        ProviderError::Timeout
+        | ProviderError::ApiError {
+            status: 408 | 504, ..
+        } => StatusCode::GATEWAY_TIMEOUT,
+        ProviderError::Http(e) if e.is_timeout() => StatusCode::GATEWAY_TIMEOUT,
+        ProviderError::InvalidRequest(_)
+        | ProviderError::UnsupportedParameter { .. }
+        | ProviderError::ApiError {
+            status: 400 | 422, ..
+        } => StatusCode::BAD_REQUEST,
+        ProviderError::ContentFiltered { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        ProviderError::Configuration(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        ProviderError::Http(_)
+        | ProviderError::Serialization(_)
+        | ProviderError::ApiError { .. }
+        | ProviderError::Internal(_) => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Machine-readable `code` sent with [`error_to_status`]'s status
+///
+/// Only failures a client can act on have one; the rest are `None`.
+pub fn error_code(error: &ProviderError) -> Option<&'static str> {
+    match error_to_status(error) {
+        StatusCode::TOO_MANY_REQUESTS => Some("rate_limit_exceeded"),
+        StatusCode::NOT_FOUND => Some("model_not_found"),
+        StatusCode::UNPROCESSABLE_ENTITY => Some("content_policy_violation"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(status: u16) -> ProviderError {
+        ProviderError::ApiError {
+            status,
+            message: "upstream said no".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_each_error_maps_to_documented_status() {
+        let cases = [
+            (ProviderError::RateLimitExceeded, 429),
+            (api_error(429), 429),
+            (ProviderError::InvalidApiKey, 502),
+            (api_error(401), 502),
+            (api_error(403), 502),
+            (ProviderError::ModelNotFound("gpt-9".to_string()), 404),
+            (api_error(404), 404),
+            (ProviderError::Timeout, 504),
+            (api_error(504), 504),
+            (ProviderError::InvalidRequest("bad".to_string()), 400),
+            (
+                ProviderError::UnsupportedParameter {
+                    param: "logprobs".to_string(),
+                    message: "not supported".to_string(),
+                },
+                400,
+            ),
+            (api_error(400), 400),
+            (
+                ProviderError::ContentFiltered {
+                    provider: "openai".to_string(),
+                    details: "content_filter".to_string(),
+                },
+                422,
+            ),
+            (ProviderError::Configuration("no key".to_string()), 500),
+            (ProviderError::Internal("boom".to_string()), 502),
+            (api_error(500), 502),
+            (api_error(503), 502),
+            (
+                ProviderError::Serialization(serde_json::from_str::<u8>("x").unwrap_err()),
+                502,
+            ),
+        ];
+
+        for (error, status) in cases {
+            assert_eq!(error_to_status(&error).as_u16(), status, "{error}");
+        }
+    }
+
+    #[test]
+    fn test_error_codes_follow_status() {
+        assert_eq!(
+            error_code(&ProviderError::RateLimitExceeded),
+            Some("rate_limit_exceeded")
+        );
+        assert_eq!(
+            error_code(&ProviderError::ModelNotFound("gpt-9".to_string())),
+            Some("model_not_found")
+        );
+        assert_eq!(
+            error_code(&ProviderError::ContentFiltered {
+                provider: "anthropic".to_string(),
+                details: "refusal".to_string(),
+            }),
+            Some("content_policy_violation")
+        );
+        assert_eq!(error_code(&ProviderError::InvalidApiKey), None);
+        assert_eq!(error_code(&ProviderError::Timeout), None);
+    }
+
+    #[test]
+    fn test_upstream_statuses_become_typed_errors() {
+        let error = |status| ProviderError::from_status(status, "gpt-9", "upstream said no");
+
+        assert!(matches!(error(401), ProviderError::InvalidApiKey));
+        assert!(matches!(error(403), ProviderError::InvalidApiKey));
+        assert!(matches!(error(404), ProviderError::ModelNotFound(model) if model == "gpt-9"));
+        assert!(matches!(error(400), ProviderError::InvalidRequest(_)));
+        assert!(matches!(error(422), ProviderError::InvalidRequest(_)));
+        assert!(matches!(error(429), ProviderError::RateLimitExceeded));
+        assert!(matches!(
+            error(503),
+            ProviderError::ApiError { status: 503, .. }
+        ));
+
+        // The typed variants keep the status the raw status would have had
+        for status in [400, 401, 403, 404, 408, 422, 429, 500, 502, 503, 504] {
+            assert_eq!(
+                error_to_status(&error(status)),
+                error_to_status(&api_error(status)),
+                "{status}"
+            );
+        }
+    }
+}
@@ -11,11 +11,24 @@ pub mod adapter;
 pub mod anthropic;
 pub mod error;
 pub mod openai;
+pub mod rate_limit;
+pub mod recording;
+pub mod streaming;
+pub mod timeouts;
 pub mod types;
+pub mod upstream_proxy;
 
 pub use adapter::LLMProvider;
-pub use error::{ProviderError, ProviderResult};
-pub use types::{Message, UnifiedRequest, UnifiedResponse, Usage};
+pub use error::{error_code, error_to_status, ProviderError, ProviderResult};
+pub use rate_limit::{RateLimitState, RateLimitTracker};
+pub use recording::{RecordMode, RecordingProvider};
+pub use streaming::{complete_via_stream, ChoiceDelta, ChunkStream, StreamChunk, ToolCallDelta};
+pub use timeouts::ProviderTimeouts;
+pub use types::{
+    FinishReason, JsonSchemaFormat, Message, ResponseFormat, ToolCall, UnifiedRequest,
+    UnifiedResponse, Usage, USER_ID_METADATA_KEY,
+};
+pub use upstream_proxy::UpstreamProxy;
 
 #[cfg(test)]
 mod tests {
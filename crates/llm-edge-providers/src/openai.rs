@@ -1,27 +1,405 @@
 //! OpenAI provider adapter
 
 use crate::{
-    adapter::{HealthStatus, LLMProvider, PricingInfo},
-    ProviderResult, UnifiedRequest, UnifiedResponse,
+    adapter::{
+        apply_extra_headers, error_for_status, inject_trace_context, is_event_stream,
+        model_pricing, parse_static_headers, HealthStatus, LLMProvider, ModelCapabilities,
+        PricingInfo,
+    },
+    streaming::{
+        complete_via_stream, sse_data, ChoiceDelta, ChunkStream, StreamChunk, ToolCallDelta,
+    },
+    timeouts::read_body,
+    types::{Choice, ResponseMetadata, ToolCall, Usage},
+    FinishReason, Message, ProviderError, ProviderResult, ProviderTimeouts, RateLimitState,
+    RateLimitTracker, ResponseFormat, UnifiedRequest, UnifiedResponse, UpstreamProxy,
+    USER_ID_METADATA_KEY,
 };
 use async_trait::async_trait;
-use secrecy::Secret;
+use futures::StreamExt;
+use reqwest::{header::HeaderMap, RequestBuilder, Response};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Model prefixes treated as reasoning models unless overridden
+pub const DEFAULT_REASONING_MODEL_PREFIXES: &[&str] = &["o1", "o3", "o4"];
 
 pub struct OpenAIAdapter {
-    #[allow(dead_code)]
     client: reqwest::Client,
-    #[allow(dead_code)]
-    api_key: Secret<String>,
-    #[allow(dead_code)]
+    api_key: RwLock<Secret<String>>,
     base_url: String,
+    timeouts: ProviderTimeouts,
+    proxy: Option<UpstreamProxy>,
+    static_headers: HeaderMap,
+    rate_limits: RateLimitTracker,
+    reasoning_model_prefixes: Vec<String>,
+    model_map: HashMap<String, String>,
 }
 
 impl OpenAIAdapter {
     pub fn new(api_key: String) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            api_key: Secret::new(api_key),
+            client: ProviderTimeouts::default().build_client(),
+            api_key: RwLock::new(Secret::new(api_key)),
             base_url: "https://api.openai.com/v1".to_string(),
+            timeouts: ProviderTimeouts::default(),
+            proxy: None,
+            static_headers: HeaderMap::new(),
+            rate_limits: RateLimitTracker::new(),
+            reasoning_model_prefixes: DEFAULT_REASONING_MODEL_PREFIXES
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            model_map: HashMap::new(),
+        }
+    }
+
+    /// Override the API base URL (e.g. for a gateway or a test server)
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Replace the connect, idle and total timeouts
+    pub fn with_timeouts(mut self, timeouts: ProviderTimeouts) -> Self {
+        self.client = timeouts.build_client_with_proxy(self.proxy.as_ref());
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Timeouts applied to provider requests
+    pub fn timeouts(&self) -> &ProviderTimeouts {
+        &self.timeouts
+    }
+
+    /// Send every request through this forward proxy
+    pub fn with_proxy(mut self, proxy: UpstreamProxy) -> Self {
+        self.client = self.timeouts.build_client_with_proxy(Some(&proxy));
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Forward proxy requests go through, if any
+    pub fn proxy(&self) -> Option<&UpstreamProxy> {
+        self.proxy.as_ref()
+    }
+
+    /// Send these headers with every request (e.g. a gateway header)
+    ///
+    /// Per-request passthrough headers of the same name replace them.
+    pub fn with_static_headers(mut self, headers: &HashMap<String, String>) -> Self {
+        self.static_headers = parse_static_headers(headers);
+        self
+    }
+
+    /// Headers sent with every request
+    pub fn static_headers(&self) -> &HeaderMap {
+        &self.static_headers
+    }
+
+    /// Record the rate-limit headers of a provider response
+    pub fn observe_rate_limits(&self, headers: &HeaderMap) {
+        self.rate_limits.observe("openai", headers);
+    }
+
+    /// Start a POST to `path` with the static headers, authenticated with
+    /// the current API key
+    ///
+    /// The key is read once here, so a rotation only affects requests built
+    /// afterwards.
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        let api_key = self.api_key.read().unwrap_or_else(PoisonError::into_inner);
+        inject_trace_context(self.client.post(format!("{}{}", self.base_url, path)))
+            .headers(self.static_headers.clone())
+            .bearer_auth(api_key.expose_secret())
+    }
+
+    /// Override which model name prefixes are treated as reasoning models
+    pub fn with_reasoning_model_prefixes(mut self, prefixes: Vec<String>) -> Self {
+        self.reasoning_model_prefixes = prefixes;
+        self
+    }
+
+    /// Whether `model` is a reasoning (o1/o3-style) model
+    pub fn is_reasoning_model(&self, model: &str) -> bool {
+        self.reasoning_model_prefixes
+            .iter()
+            .any(|prefix| model.starts_with(prefix.as_str()))
+    }
+
+    /// Send these model names upstream in place of the client-facing ones
+    /// (e.g. `gpt-4` to an Azure deployment name)
+    ///
+    /// Cache keys, pricing and responses keep the client-facing name.
+    pub fn with_model_map(mut self, model_map: HashMap<String, String>) -> Self {
+        self.model_map = model_map;
+        self
+    }
+
+    /// Name sent upstream for the client-facing `model`
+    pub fn upstream_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_map.get(model).map_or(model, String::as_str)
+    }
+
+    /// Build the chat completions request body for `request`
+    ///
+    /// Reasoning models reject `temperature` and take `max_completion_tokens`
+    /// instead of `max_tokens`, so those are translated here; a client
+    /// `temperature` is dropped with a warning rather than failing the request.
+    /// `reasoning_effort` is only forwarded to reasoning models. The
+    /// `user_id` metadata entry is sent as `user`, for OpenAI's abuse
+    /// monitoring. `model` is mapped with the model map; reasoning models are
+    /// still recognised by the client-facing name.
+    pub fn build_request_body(&self, request: &UnifiedRequest) -> ChatRequestBody {
+        let mut body = ChatRequestBody {
+            model: self.upstream_model(&request.model).to_string(),
+            messages: request.messages.clone(),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            max_completion_tokens: None,
+            reasoning_effort: None,
+            logprobs: request.logprobs,
+            top_logprobs: request.top_logprobs,
+            response_format: request.response_format.clone(),
+            stream: request.stream,
+            stream_options: request.stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+            user: request.metadata.get(USER_ID_METADATA_KEY).cloned(),
+        };
+
+        if self.is_reasoning_model(&request.model) {
+            if body.temperature.take().is_some() {
+                warn!(
+                    model = %request.model,
+                    "Dropping temperature: not supported by reasoning models"
+                );
+            }
+            body.max_completion_tokens = body.max_tokens.take();
+            body.reasoning_effort = request.reasoning_effort.clone();
+        }
+
+        body
+    }
+
+    /// Send `request` and return the response once it has started, or the
+    /// error for its status
+    async fn start(&self, request: &UnifiedRequest) -> ProviderResult<Response> {
+        let body = self.build_request_body(request);
+        let response = apply_extra_headers(
+            self.post("/chat/completions").json(&body),
+            &request.extra_headers,
+        )
+        .send()
+        .await?;
+        self.observe_rate_limits(response.headers());
+        error_for_status(response, &request.model, self.timeouts.read).await
+    }
+}
+
+/// Chunks of a streamed chat completions response
+fn stream_chunks(response: Response, idle: std::time::Duration) -> ChunkStream {
+    sse_data(response, idle)
+        .map(|data| Ok(serde_json::from_str::<ChatChunkBody>(&data?)?.into()))
+        .boxed()
+}
+
+/// OpenAI chat completions request body
+#[derive(Debug, Serialize)]
+pub struct ChatRequestBody {
+    model: String,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// Report usage in a final chunk
+    include_usage: bool,
+}
+
+/// OpenAI chat completions response body
+#[derive(Debug, Deserialize)]
+struct ChatResponseBody {
+    id: String,
+    #[serde(default)]
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    index: usize,
+    message: ChatMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    logprobs: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ChatToolCall>,
+}
+
+/// A tool call, or in a stream chunk the next piece of one
+#[derive(Debug, Deserialize)]
+struct ChatToolCall {
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: ChatFunction,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChatFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+/// One `chat.completion.chunk` event of a streamed response
+#[derive(Debug, Deserialize)]
+struct ChatChunkBody {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    choices: Vec<ChatChunkChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChunkChoice {
+    index: usize,
+    #[serde(default)]
+    delta: ChatMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+impl ChatResponseBody {
+    /// The response to `request`; the caller fills in the model and latency
+    ///
+    /// Some OpenAI-compatible gateways leave out `usage`; it is then
+    /// estimated from `request` and the completion, and flagged with
+    /// `usage_estimated`.
+    fn into_unified(self, request: &UnifiedRequest) -> UnifiedResponse {
+        let choices: Vec<Choice> = self
+            .choices
+            .into_iter()
+            .map(|choice| Choice {
+                index: choice.index,
+                message: Message {
+                    role: choice
+                        .message
+                        .role
+                        .unwrap_or_else(|| "assistant".to_string()),
+                    content: choice.message.content.unwrap_or_default(),
+                },
+                finish_reason: choice
+                    .finish_reason
+                    .as_deref()
+                    .map(FinishReason::from_native),
+                native_finish_reason: choice.finish_reason,
+                logprobs: choice.logprobs,
+                tool_calls: choice
+                    .message
+                    .tool_calls
+                    .into_iter()
+                    .map(|call| ToolCall {
+                        id: call.id.unwrap_or_default(),
+                        name: call.function.name.unwrap_or_default(),
+                        arguments: call.function.arguments.unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let usage_estimated = self.usage.is_none();
+        let usage = self.usage.unwrap_or_else(|| {
+            warn!(
+                model = %request.model,
+                "Response has no usage, estimating token counts"
+            );
+            Usage::estimate(request, &choices)
+        });
+
+        UnifiedResponse {
+            id: self.id,
+            model: String::new(),
+            choices,
+            usage,
+            metadata: ResponseMetadata {
+                provider: "openai".to_string(),
+                cached: false,
+                latency_ms: 0,
+                cost_usd: None,
+                usage_estimated,
+            },
+        }
+    }
+}
+
+impl From<ChatChunkBody> for StreamChunk {
+    fn from(chunk: ChatChunkBody) -> Self {
+        StreamChunk {
+            id: chunk.id,
+            // The response reports the client-facing name, not this one
+            model: None,
+            choices: chunk
+                .choices
+                .into_iter()
+                .map(|choice| ChoiceDelta {
+                    index: choice.index,
+                    role: choice.delta.role,
+                    content: choice.delta.content,
+                    tool_calls: choice
+                        .delta
+                        .tool_calls
+                        .into_iter()
+                        .map(|call| ToolCallDelta {
+                            index: call.index,
+                            id: call.id,
+                            name: call.function.name,
+                            arguments: call.function.arguments,
+                        })
+                        .collect(),
+                    finish_reason: choice.finish_reason,
+                })
+                .collect(),
+            usage: chunk.usage,
         }
     }
 }
@@ -32,31 +410,509 @@ impl LLMProvider for OpenAIAdapter {
         "openai"
     }
 
-    async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
-        // TODO: Implement OpenAI API call
-        // - Transform UnifiedRequest to OpenAI format
-        // - Make HTTP request
-        // - Transform response to UnifiedResponse
-        todo!("OpenAI adapter implementation")
+    /// Send `request` to the chat completions endpoint
+    ///
+    /// The body is read with the idle timeout. A response that arrives as an
+    /// event stream (a model that only streams) is collected with
+    /// [`complete_via_stream`]. The response names `request.model`, not the
+    /// upstream name the model map sent.
+    async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+        let start = Instant::now();
+        let response = self.start(&request).await?;
+
+        let mut response = if is_event_stream(&response) {
+            complete_via_stream(stream_chunks(response, self.timeouts.read), "openai").await?
+        } else {
+            let body = read_body(response, self.timeouts.read).await?;
+            serde_json::from_slice::<ChatResponseBody>(&body)?.into_unified(&request)
+        };
+        response.model = request.model;
+        response.metadata.latency_ms = start.elapsed().as_millis() as u64;
+        Ok(response)
+    }
+
+    /// Stream `request` from the chat completions endpoint
+    ///
+    /// Each chunk is awaited with the idle timeout. A gateway that answers
+    /// with a whole response instead yields it as one chunk.
+    async fn send_stream(&self, mut request: UnifiedRequest) -> ProviderResult<ChunkStream> {
+        request.stream = true;
+        let response = self.start(&request).await?;
+        if is_event_stream(&response) {
+            return Ok(stream_chunks(response, self.timeouts.read));
+        }
+
+        let body = read_body(response, self.timeouts.read).await?;
+        let mut response =
+            serde_json::from_slice::<ChatResponseBody>(&body)?.into_unified(&request);
+        response.model = request.model;
+        Ok(futures::stream::once(async move { Ok(StreamChunk::from(response)) }).boxed())
     }
 
     fn get_pricing(&self, model: &str) -> Option<PricingInfo> {
-        // Pricing as of 2024 (update regularly)
+        model_pricing(self.upstream_model(model))
+    }
+
+    fn max_output_tokens(&self, model: &str) -> Option<u32> {
         match model {
-            "gpt-4" => Some(PricingInfo {
-                input_cost_per_1k: 0.03,
-                output_cost_per_1k: 0.06,
-            }),
-            "gpt-3.5-turbo" => Some(PricingInfo {
-                input_cost_per_1k: 0.0005,
-                output_cost_per_1k: 0.0015,
-            }),
+            m if m.starts_with("gpt-4o") => Some(16384),
+            // 8192 is gpt-4's whole context window, prompt included. Other
+            // gpt-4 variants (gpt-4-32k, gpt-4.1, ...) have other limits, so
+            // only the ones known here get a default
+            "gpt-4" | "gpt-4-0613" | "gpt-4-0314" => Some(4096),
+            "gpt-4-turbo" | "gpt-4-turbo-2024-04-09" | "gpt-4-turbo-preview" => Some(4096),
+            m if m.starts_with("gpt-3.5-turbo") => Some(4096),
             _ => None,
         }
     }
 
+    fn list_models(&self) -> Vec<String> {
+        ["gpt-4o", "gpt-4-turbo", "gpt-4", "gpt-3.5-turbo"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    fn capabilities(&self, model: &str) -> Option<ModelCapabilities> {
+        let (max_context_tokens, supports_vision) = match model {
+            m if m.starts_with("gpt-4o") => (128_000, true),
+            m if m.starts_with("gpt-4-turbo") => (128_000, true),
+            m if m.starts_with("gpt-4") => (8192, false),
+            m if m.starts_with("gpt-3.5-turbo") => (16_385, false),
+            _ => return None,
+        };
+        Some(ModelCapabilities {
+            supports_vision,
+            supports_tools: true,
+            max_context_tokens,
+            max_output_tokens: self.max_output_tokens(model)?,
+        })
+    }
+
     async fn health(&self) -> HealthStatus {
         // TODO: Implement health check
         HealthStatus::Healthy
     }
+
+    fn rate_limit_state(&self) -> Option<RateLimitState> {
+        self.rate_limits.current()
+    }
+
+    fn rotate_key(&self, new_key: String) -> ProviderResult<()> {
+        if new_key.trim().is_empty() {
+            return Err(ProviderError::Configuration(
+                "API key must not be empty".to_string(),
+            ));
+        }
+
+        *self.api_key.write().unwrap_or_else(PoisonError::into_inner) = Secret::new(new_key);
+        info!(provider = "openai", "API key rotated");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str) -> UnifiedRequest {
+        UnifiedRequest {
+            model: model.to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+            }],
+            temperature: Some(0.7),
+            max_tokens: Some(256),
+            reasoning_effort: Some("high".to_string()),
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream: false,
+            metadata: Default::default(),
+            extra_headers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_reasoning_model_request_translation() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let body =
+            serde_json::to_value(adapter.build_request_body(&request("o1-preview"))).unwrap();
+
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(body["max_completion_tokens"], 256);
+        assert_eq!(body["reasoning_effort"], "high");
+    }
+
+    #[test]
+    fn test_standard_model_request_unchanged() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let body = serde_json::to_value(adapter.build_request_body(&request("gpt-4o"))).unwrap();
+
+        assert!((body["temperature"].as_f64().unwrap() - 0.7).abs() < 1e-6);
+        assert_eq!(body["max_tokens"], 256);
+        assert!(body.get("max_completion_tokens").is_none());
+        assert!(body.get("reasoning_effort").is_none());
+        assert!(body.get("logprobs").is_none());
+    }
+
+    #[test]
+    fn test_logprobs_forwarded() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let mut request = request("gpt-4o");
+        request.logprobs = Some(true);
+        request.top_logprobs = Some(5);
+
+        let body = serde_json::to_value(adapter.build_request_body(&request)).unwrap();
+        assert_eq!(body["logprobs"], true);
+        assert_eq!(body["top_logprobs"], 5);
+    }
+
+    #[test]
+    fn test_user_id_forwarded_as_user() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let mut request = request("gpt-4o");
+        let body = serde_json::to_value(adapter.build_request_body(&request)).unwrap();
+        assert!(body.get("user").is_none());
+
+        request
+            .metadata
+            .insert(USER_ID_METADATA_KEY.to_string(), "user-1234".to_string());
+        let body = serde_json::to_value(adapter.build_request_body(&request)).unwrap();
+        assert_eq!(body["user"], "user-1234");
+    }
+
+    #[test]
+    fn test_model_map_renames_upstream_model_only() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string()).with_model_map(HashMap::from([
+            ("gpt-4".to_string(), "prod-gpt4-deployment".to_string()),
+            ("o1".to_string(), "o1-2024-12-17".to_string()),
+        ]));
+
+        let body = serde_json::to_value(adapter.build_request_body(&request("gpt-4"))).unwrap();
+        assert_eq!(body["model"], "prod-gpt4-deployment");
+        assert_eq!(body["max_tokens"], 256);
+
+        // Unmapped names pass through unchanged
+        let body = serde_json::to_value(adapter.build_request_body(&request("gpt-4o"))).unwrap();
+        assert_eq!(body["model"], "gpt-4o");
+
+        // Pinned versions of reasoning models are still translated as such
+        let body = serde_json::to_value(adapter.build_request_body(&request("o1"))).unwrap();
+        assert_eq!(body["model"], "o1-2024-12-17");
+        assert_eq!(body["max_completion_tokens"], 256);
+    }
+
+    #[test]
+    fn test_response_format_forwarded() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+        let format = serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "weather",
+                "schema": {"type": "object", "properties": {"temp": {"type": "number"}}},
+                "strict": true
+            }
+        });
+        let mut request = request("gpt-4o");
+        request.response_format = Some(serde_json::from_value(format.clone()).unwrap());
+
+        let body = serde_json::to_value(adapter.build_request_body(&request)).unwrap();
+        assert_eq!(body["response_format"], format);
+
+        request.response_format = Some(ResponseFormat::JsonObject);
+        let body = serde_json::to_value(adapter.build_request_body(&request)).unwrap();
+        assert_eq!(
+            body["response_format"],
+            serde_json::json!({"type": "json_object"})
+        );
+    }
+
+    #[test]
+    fn test_max_output_tokens() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+
+        assert_eq!(adapter.max_output_tokens("gpt-4o-mini"), Some(16384));
+        assert_eq!(adapter.max_output_tokens("gpt-4-turbo"), Some(4096));
+        assert_eq!(adapter.max_output_tokens("gpt-4"), Some(4096));
+        assert_eq!(adapter.max_output_tokens("gpt-4-0613"), Some(4096));
+        assert_eq!(adapter.max_output_tokens("gpt-3.5-turbo"), Some(4096));
+        assert_eq!(adapter.max_output_tokens("o1"), None);
+        // Unknown gpt-4 variants get no default rather than gpt-4's
+        assert_eq!(adapter.max_output_tokens("gpt-4-32k"), None);
+        assert_eq!(adapter.max_output_tokens("gpt-4.1"), None);
+        assert!(adapter.capabilities("gpt-4.1").is_none());
+    }
+
+    #[test]
+    fn test_listed_models_have_capabilities() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string());
+
+        for model in adapter.list_models() {
+            assert!(adapter.capabilities(&model).is_some(), "{}", model);
+        }
+
+        let gpt4o = adapter.capabilities("gpt-4o").unwrap();
+        assert!(gpt4o.supports_vision);
+        assert_eq!(gpt4o.max_context_tokens, 128_000);
+        assert_eq!(gpt4o.max_output_tokens, 16384);
+
+        let gpt4 = adapter.capabilities("gpt-4").unwrap();
+        assert!(!gpt4.supports_vision);
+        assert_eq!(gpt4.max_context_tokens, 8192);
+        assert_eq!(adapter.capabilities("o1"), None);
+    }
+
+    #[test]
+    fn test_reasoning_model_prefixes_are_configurable() {
+        let adapter = OpenAIAdapter::new("sk-test".to_string())
+            .with_reasoning_model_prefixes(vec!["my-reasoner".to_string()]);
+
+        assert!(adapter.is_reasoning_model("my-reasoner-v2"));
+        assert!(!adapter.is_reasoning_model("o1"));
+    }
+
+    #[tokio::test]
+    async fn test_rotated_key_used_on_next_request() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("authorization", "Bearer sk-new"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let adapter = OpenAIAdapter::new("sk-old".to_string()).with_base_url(server.uri());
+
+        // Built before the rotation, so it keeps the old key
+        let in_flight = adapter.post("/chat/completions");
+        adapter.rotate_key("sk-new".to_string()).unwrap();
+
+        let response = adapter.post("/chat/completions").send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response = in_flight.send().await.unwrap();
+        assert_eq!(response.status(), 404);
+
+        assert!(adapter.rotate_key("  ".to_string()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_response_rate_limit_headers_surface_to_routing() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining-requests", "1")
+                    .insert_header("x-ratelimit-reset-requests", "6m0s")
+                    .set_body_json(completion_body()),
+            )
+            .mount(&server)
+            .await;
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string()).with_base_url(server.uri());
+        assert!(adapter.rate_limit_state().is_none());
+
+        adapter.send(request("gpt-4o")).await.unwrap();
+
+        let state = adapter.rate_limit_state().unwrap();
+        assert_eq!(state.remaining, 1);
+        assert!(state.is_exhausted(1, std::time::Instant::now()));
+    }
+
+    fn completion_body() -> serde_json::Value {
+        serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "model": "prod-gpt4-deployment",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 7, "total_tokens": 19}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_send_uses_upstream_model_and_reports_client_model() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "prod-gpt4-deployment"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string())
+            .with_base_url(server.uri())
+            .with_model_map(HashMap::from([(
+                "gpt-4".to_string(),
+                "prod-gpt4-deployment".to_string(),
+            )]));
+
+        let response = adapter.send(request("gpt-4")).await.unwrap();
+        assert_eq!(response.model, "gpt-4");
+        assert_eq!(response.id, "chatcmpl-1");
+        assert_eq!(response.usage.total_tokens, 19);
+        assert!(!response.metadata.usage_estimated);
+        assert_eq!(response.metadata.provider, "openai");
+
+        let choice = &response.choices[0];
+        assert_eq!(choice.message.content, "");
+        assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(choice.native_finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(
+            choice.tool_calls,
+            [ToolCall {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                arguments: "{\"city\":\"Paris\"}".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_missing_usage_is_estimated() {
+        let body = serde_json::json!({
+            "id": "chatcmpl-1",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello there!"},
+                "finish_reason": "stop"
+            }]
+        });
+
+        let response = serde_json::from_value::<ChatResponseBody>(body)
+            .unwrap()
+            .into_unified(&request("gpt-4"));
+
+        // 5 prompt chars and 12 completion chars at ~4 chars per token
+        assert_eq!(response.usage.prompt_tokens, 2);
+        assert_eq!(response.usage.completion_tokens, 3);
+        assert_eq!(response.usage.total_tokens, 5);
+        assert!(response.metadata.usage_estimated);
+    }
+
+    /// Server streaming `events` as a chat completions event stream
+    async fn streaming_server(events: &[&str]) -> wiremock::MockServer {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let body = events
+            .iter()
+            .map(|event| format!("data: {}\n\n", event))
+            .chain(std::iter::once("data: [DONE]\n\n".to_string()))
+            .collect::<String>();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    const STREAM_EVENTS: [&str; 3] = [
+        r#"{"id":"chatcmpl-2","model":"gpt-4o","choices":[{"index":0,"delta":{"role":"assistant","content":"Hel"}}]}"#,
+        r#"{"id":"chatcmpl-2","choices":[{"index":0,"delta":{"content":"lo"},"finish_reason":"stop"}]}"#,
+        r#"{"id":"chatcmpl-2","choices":[],"usage":{"prompt_tokens":5,"completion_tokens":2,"total_tokens":7}}"#,
+    ];
+
+    #[tokio::test]
+    async fn test_streamed_response_is_collected() {
+        let server = streaming_server(&STREAM_EVENTS).await;
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string()).with_base_url(server.uri());
+        let response = adapter.send(request("gpt-4o")).await.unwrap();
+
+        assert_eq!(response.id, "chatcmpl-2");
+        assert_eq!(response.model, "gpt-4o");
+        assert_eq!(response.choices[0].message.content, "Hello");
+        assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Stop));
+        assert_eq!(response.usage.total_tokens, 7);
+    }
+
+    #[tokio::test]
+    async fn test_send_stream_yields_chunks_as_sent() {
+        let server = streaming_server(&STREAM_EVENTS).await;
+
+        let adapter = OpenAIAdapter::new("sk-test".to_string()).with_base_url(server.uri());
+        let chunks: Vec<_> = adapter
+            .send_stream(request("gpt-4o"))
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let content: Vec<_> = chunks
+            .iter()
+            .flat_map(|chunk| &chunk.choices)
+            .filter_map(|choice| choice.content.as_deref())
+            .collect();
+        assert_eq!(content, ["Hel", "lo"]);
+        assert_eq!(chunks[1].choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(chunks[2].usage.as_ref().unwrap().completion_tokens, 2);
+
+        let sent: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(sent["stream"], true);
+        assert_eq!(sent["stream_options"]["include_usage"], true);
+    }
+
+    #[tokio::test]
+    async fn test_error_statuses_become_typed_errors() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        for (status, check) in [
+            (
+                401,
+                (|e| matches!(e, ProviderError::InvalidApiKey)) as fn(&ProviderError) -> bool,
+            ),
+            (
+                404,
+                |e| matches!(e, ProviderError::ModelNotFound(m) if m == "gpt-4o"),
+            ),
+            (429, |e| matches!(e, ProviderError::RateLimitExceeded)),
+            (
+                503,
+                |e| matches!(e, ProviderError::ApiError { status: 503, message } if message.contains("overloaded")),
+            ),
+        ] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .respond_with(
+                    ResponseTemplate::new(status)
+                        .set_body_string(r#"{"error":{"message":"overloaded"}}"#),
+                )
+                .mount(&server)
+                .await;
+
+            let adapter = OpenAIAdapter::new("sk-test".to_string()).with_base_url(server.uri());
+            let err = adapter.send(request("gpt-4o")).await.unwrap_err();
+            assert!(check(&err), "{}: {:?}", status, err);
+        }
+    }
 }
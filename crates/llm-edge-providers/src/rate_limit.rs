@@ -0,0 +1,267 @@
+//! Provider rate-limit headers
+//!
+//! Providers report their remaining request quota on every response
+//! (`x-ratelimit-remaining-requests` for OpenAI,
+//! `anthropic-ratelimit-requests-remaining` for Anthropic, or the generic
+//! `x-ratelimit-remaining`). Adapters feed those headers to a
+//! [`RateLimitTracker`] so routing can steer away from a provider before it
+//! starts answering `429`.
+
+use reqwest::header::HeaderMap;
+use std::sync::{PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+/// Headers carrying the remaining request quota, in order of preference
+pub const REMAINING_HEADERS: &[&str] = &[
+    "x-ratelimit-remaining-requests",
+    "anthropic-ratelimit-requests-remaining",
+    "x-ratelimit-remaining",
+];
+
+/// Headers carrying when the request quota resets, in order of preference
+pub const RESET_HEADERS: &[&str] = &[
+    "x-ratelimit-reset-requests",
+    "anthropic-ratelimit-requests-reset",
+    "x-ratelimit-reset",
+];
+
+/// Numeric resets above this are Unix timestamps rather than seconds to wait
+///
+/// 1e9 seconds is over 31 years as a delay and September 2001 as a timestamp.
+pub const EPOCH_RESET_THRESHOLD: f64 = 1e9;
+
+/// Longest a reported reset keeps a provider out of rotation
+///
+/// A day covers daily quotas; anything longer is a bogus header that would
+/// otherwise park the provider indefinitely.
+pub const MAX_RESET_DELAY: Duration = Duration::from_secs(24 * 3600);
+
+/// Request quota last reported by a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitState {
+    /// Requests left in the current window
+    pub remaining: u64,
+
+    /// When the window resets, if the provider said
+    pub reset_at: Option<Instant>,
+}
+
+impl RateLimitState {
+    /// Parse the quota from response headers
+    ///
+    /// Returns `None` if the response carries no remaining-quota header.
+    /// Resets may be a duration (`20ms`, `1s`, `6m0s`), a number of seconds,
+    /// a Unix timestamp in seconds, or an RFC 3339 timestamp, and are capped
+    /// at [`MAX_RESET_DELAY`].
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let remaining = first_header(headers, REMAINING_HEADERS)?
+            .trim()
+            .parse()
+            .ok()?;
+        let reset_at = first_header(headers, RESET_HEADERS)
+            .and_then(parse_reset)
+            .map(|delay| Instant::now() + delay);
+
+        Some(Self {
+            remaining,
+            reset_at,
+        })
+    }
+
+    /// Whether at most `min_remaining` requests are left and the window has
+    /// not reset yet
+    ///
+    /// Without a reset time there is no telling when the quota comes back,
+    /// so the state never counts as exhausted.
+    pub fn is_exhausted(&self, min_remaining: u64, now: Instant) -> bool {
+        self.remaining <= min_remaining && self.reset_at.is_some_and(|reset_at| now < reset_at)
+    }
+}
+
+/// Latest [`RateLimitState`] reported by one provider
+#[derive(Debug, Default)]
+pub struct RateLimitTracker {
+    state: RwLock<Option<RateLimitState>>,
+}
+
+impl RateLimitTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update from a provider response's headers
+    ///
+    /// Responses without rate-limit headers leave the last state in place.
+    pub fn observe(&self, provider: &str, headers: &HeaderMap) {
+        let Some(state) = RateLimitState::from_headers(headers) else {
+            return;
+        };
+
+        metrics::gauge!("llm_edge_provider_ratelimit_remaining", "provider" => provider.to_string())
+            .set(state.remaining as f64);
+        *self.state.write().unwrap_or_else(PoisonError::into_inner) = Some(state);
+    }
+
+    /// Last reported state, if any
+    pub fn current(&self) -> Option<RateLimitState> {
+        *self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn first_header<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok())
+}
+
+/// Time until the quota resets, at most [`MAX_RESET_DELAY`]
+fn parse_reset(value: &str) -> Option<Duration> {
+    parse_reset_at(value, chrono::Utc::now())
+}
+
+fn parse_reset_at(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let delay = if let Ok(seconds) = value.parse::<f64>() {
+        if seconds > EPOCH_RESET_THRESHOLD {
+            let now = now.timestamp_millis() as f64 / 1000.0;
+            Duration::try_from_secs_f64((seconds - now).max(0.0)).ok()
+        } else {
+            Duration::try_from_secs_f64(seconds).ok()
+        }
+    } else if let Ok(at) = chrono::DateTime::parse_from_rfc3339(value) {
+        Some(
+            (at.with_timezone(&chrono::Utc) - now)
+                .to_std()
+                .unwrap_or_default(),
+        )
+    } else {
+        parse_duration(value)
+    };
+    delay.map(|delay| delay.min(MAX_RESET_DELAY))
+}
+
+/// Parse Go-style durations such as `20ms`, `1s` or `1h6m0.5s`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|&i| i > 0)?;
+        let (number, tail) = rest.split_at(split);
+        let number: f64 = number.parse().ok()?;
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        total += number
+            * match unit {
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                "ms" => 0.001,
+                _ => return None,
+            };
+        rest = tail;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_reset_formats() {
+        assert_eq!(parse_reset("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_reset("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset("1h1m1.5s"),
+            Some(Duration::from_millis(3_661_500))
+        );
+        assert_eq!(parse_reset("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_reset("2000-01-01T00:00:00Z"), Some(Duration::ZERO));
+        assert_eq!(parse_reset("soon"), None);
+        assert_eq!(parse_reset("5d"), None);
+    }
+
+    #[test]
+    fn test_epoch_resets_are_absolute_and_capped() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let epoch = now.timestamp();
+
+        assert_eq!(
+            parse_reset_at(&(epoch + 30).to_string(), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_reset_at(&format!("{}.5", epoch + 2), now),
+            Some(Duration::from_millis(2500))
+        );
+        // Already past
+        assert_eq!(
+            parse_reset_at(&(epoch - 60).to_string(), now),
+            Some(Duration::ZERO)
+        );
+        // Days away, as a timestamp, a delay or a date, is capped
+        assert_eq!(
+            parse_reset_at(&(epoch + 2 * 86_400).to_string(), now),
+            Some(MAX_RESET_DELAY)
+        );
+        assert_eq!(parse_reset_at("172800", now), Some(MAX_RESET_DELAY));
+        assert_eq!(
+            parse_reset_at("2024-06-05T00:00:00Z", now),
+            Some(MAX_RESET_DELAY)
+        );
+        assert_eq!(parse_reset_at("30h", now), Some(MAX_RESET_DELAY));
+    }
+
+    #[test]
+    fn test_state_from_provider_headers() {
+        let openai = RateLimitState::from_headers(&headers(&[
+            ("x-ratelimit-remaining-requests", "0"),
+            ("x-ratelimit-reset-requests", "1m0s"),
+        ]))
+        .unwrap();
+        assert_eq!(openai.remaining, 0);
+        let now = Instant::now();
+        assert!(openai.is_exhausted(1, now));
+        assert!(!openai.is_exhausted(1, now + Duration::from_secs(61)));
+
+        let anthropic = RateLimitState::from_headers(&headers(&[(
+            "anthropic-ratelimit-requests-remaining",
+            "250",
+        )]))
+        .unwrap();
+        assert_eq!(anthropic.remaining, 250);
+        assert!(anthropic.reset_at.is_none());
+        assert!(!anthropic.is_exhausted(1, Instant::now()));
+
+        assert!(RateLimitState::from_headers(&HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_tracker_keeps_last_reported_state() {
+        let tracker = RateLimitTracker::new();
+        assert!(tracker.current().is_none());
+
+        tracker.observe(
+            "openai",
+            &headers(&[("x-ratelimit-remaining", "3"), ("x-ratelimit-reset", "10")]),
+        );
+        tracker.observe("openai", &HeaderMap::new());
+
+        assert_eq!(tracker.current().unwrap().remaining, 3);
+    }
+}
@@ -0,0 +1,516 @@
+//! Provider request/response recording for replay testing
+//!
+//! [`RecordingProvider`] wraps any [`LLMProvider`]. In record mode every
+//! successful interaction is appended to a JSONL cassette, PII-redacted and
+//! keyed by a hash of the request. In replay mode responses are served from
+//! the cassette and the wrapped provider is never called.
+
+use crate::{
+    adapter::{is_sensitive_header, HealthStatus, LLMProvider, ModelCapabilities, PricingInfo},
+    Message, ProviderError, ProviderResult, RateLimitState, ResponseFormat, UnifiedRequest,
+    UnifiedResponse,
+};
+use async_trait::async_trait;
+use llm_edge_security::PIIRedactor;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Placeholder written in place of sensitive header values
+pub const REDACTED_HEADER_VALUE: &str = "[REDACTED]";
+
+/// Recording mode for provider interactions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RecordMode {
+    #[default]
+    Off,
+    /// Append interactions to the JSONL file at this path
+    Record(PathBuf),
+    /// Serve responses from the JSONL file at this path
+    Replay(PathBuf),
+}
+
+impl RecordMode {
+    /// Parse `off`, `record:<path>` or `replay:<path>`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("off") {
+            return Some(RecordMode::Off);
+        }
+
+        match value.split_once(':') {
+            Some(("record", path)) if !path.is_empty() => Some(RecordMode::Record(path.into())),
+            Some(("replay", path)) if !path.is_empty() => Some(RecordMode::Replay(path.into())),
+            _ => None,
+        }
+    }
+}
+
+/// Redacted view of a recorded request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<usize>,
+    pub reasoning_effort: Option<String>,
+    #[serde(default)]
+    pub logprobs: Option<bool>,
+    #[serde(default)]
+    pub top_logprobs: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    pub stream: bool,
+    /// Forwarded headers, with credentials replaced by [`REDACTED_HEADER_VALUE`]
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// One line of a cassette file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Hash of the provider name and redacted request body (headers excluded)
+    pub key: String,
+    pub provider: String,
+    pub recorded_at: String,
+    pub request: RecordedRequest,
+    pub response: UnifiedResponse,
+}
+
+enum Cassette {
+    Record {
+        path: PathBuf,
+        file: Mutex<tokio::fs::File>,
+    },
+    Replay {
+        path: PathBuf,
+        responses: HashMap<String, UnifiedResponse>,
+    },
+}
+
+/// Provider wrapper that records or replays interactions
+pub struct RecordingProvider {
+    inner: Arc<dyn LLMProvider>,
+    cassette: Cassette,
+    redactor: PIIRedactor,
+}
+
+impl RecordingProvider {
+    /// Wrap `inner` according to `mode`
+    ///
+    /// Returns `inner` unchanged for [`RecordMode::Off`]. Replay loads the
+    /// whole cassette up front, so a missing or malformed file fails here
+    /// rather than on the first request.
+    pub async fn wrap(
+        inner: Arc<dyn LLMProvider>,
+        mode: &RecordMode,
+    ) -> ProviderResult<Arc<dyn LLMProvider>> {
+        let cassette = match mode {
+            RecordMode::Off => return Ok(inner),
+            RecordMode::Record(path) => {
+                let file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .map_err(|e| cassette_error(path, e))?;
+                info!(
+                    provider = inner.name(),
+                    path = %path.display(),
+                    "Recording provider interactions"
+                );
+                Cassette::Record {
+                    path: path.clone(),
+                    file: Mutex::new(file),
+                }
+            }
+            RecordMode::Replay(path) => {
+                let contents = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| cassette_error(path, e))?;
+                let mut responses = HashMap::new();
+                for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+                    let interaction: Interaction = serde_json::from_str(line)?;
+                    if interaction.provider == inner.name() {
+                        responses.insert(interaction.key, interaction.response);
+                    }
+                }
+                info!(
+                    provider = inner.name(),
+                    path = %path.display(),
+                    interactions = responses.len(),
+                    "Replaying recorded provider interactions"
+                );
+                Cassette::Replay {
+                    path: path.clone(),
+                    responses,
+                }
+            }
+        };
+
+        Ok(Arc::new(Self {
+            inner,
+            cassette,
+            redactor: PIIRedactor::new(),
+        }))
+    }
+
+    /// Redacted copy of `request` as it is written to the cassette
+    fn redact_request(&self, request: &UnifiedRequest) -> RecordedRequest {
+        let headers = request
+            .extra_headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_header(name.as_str()) {
+                    REDACTED_HEADER_VALUE.to_string()
+                } else {
+                    self.redactor.redact(value.to_str().unwrap_or_default())
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+
+        RecordedRequest {
+            model: request.model.clone(),
+            messages: self.redact_messages(&request.messages),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            reasoning_effort: request.reasoning_effort.clone(),
+            logprobs: request.logprobs,
+            top_logprobs: request.top_logprobs,
+            response_format: request.response_format.clone(),
+            stream: request.stream,
+            headers,
+        }
+    }
+
+    fn redact_messages(&self, messages: &[Message]) -> Vec<Message> {
+        messages
+            .iter()
+            .map(|m| Message {
+                role: m.role.clone(),
+                content: self.redactor.redact(&m.content),
+            })
+            .collect()
+    }
+
+    /// Cassette key for a redacted request
+    ///
+    /// Headers are left out so that replay matches regardless of which
+    /// passthrough headers the client happened to send. `response_format` is
+    /// only part of the key when set, so existing cassettes still match.
+    fn key_for(&self, request: &RecordedRequest) -> String {
+        let mut body = serde_json::json!({
+            "provider": self.inner.name(),
+            "model": request.model,
+            "messages": request.messages,
+            "temperature": request.temperature,
+            "max_tokens": request.max_tokens,
+            "reasoning_effort": request.reasoning_effort,
+            "logprobs": request.logprobs,
+            "top_logprobs": request.top_logprobs,
+            "stream": request.stream,
+        });
+        if let Some(format) = &request.response_format {
+            body["response_format"] = serde_json::json!(format);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(body.to_string().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    async fn append(&self, file: &Mutex<tokio::fs::File>, path: &Path, interaction: &Interaction) {
+        let mut line = match serde_json::to_string(interaction) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to serialize recorded interaction");
+                return;
+            }
+        };
+        line.push('\n');
+
+        let mut file = file.lock().await;
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            warn!(path = %path.display(), error = %e, "Failed to write recorded interaction");
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RecordingProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+        let recorded = self.redact_request(&request);
+        let key = self.key_for(&recorded);
+
+        match &self.cassette {
+            Cassette::Replay { path, responses } => {
+                debug!(provider = self.inner.name(), key = %key, "Replaying provider response");
+                responses.get(&key).cloned().ok_or_else(|| {
+                    ProviderError::Internal(format!(
+                        "No recorded response for request {} in {}",
+                        key,
+                        path.display()
+                    ))
+                })
+            }
+            Cassette::Record { path, file } => {
+                let response = self.inner.send(request).await?;
+
+                let mut redacted = response.clone();
+                for choice in &mut redacted.choices {
+                    choice.message.content = self.redactor.redact(&choice.message.content);
+                }
+
+                let interaction = Interaction {
+                    key,
+                    provider: self.inner.name().to_string(),
+                    recorded_at: chrono::Utc::now().to_rfc3339(),
+                    request: recorded,
+                    response: redacted,
+                };
+                self.append(file, path, &interaction).await;
+
+                Ok(response)
+            }
+        }
+    }
+
+    fn get_pricing(&self, model: &str) -> Option<PricingInfo> {
+        self.inner.get_pricing(model)
+    }
+
+    fn max_output_tokens(&self, model: &str) -> Option<u32> {
+        self.inner.max_output_tokens(model)
+    }
+
+    fn list_models(&self) -> Vec<String> {
+        self.inner.list_models()
+    }
+
+    fn capabilities(&self, model: &str) -> Option<ModelCapabilities> {
+        self.inner.capabilities(model)
+    }
+
+    fn rotate_key(&self, new_key: String) -> ProviderResult<()> {
+        self.inner.rotate_key(new_key)
+    }
+
+    fn rate_limit_state(&self) -> Option<RateLimitState> {
+        self.inner.rate_limit_state()
+    }
+
+    fn check_request(&self, request: &UnifiedRequest) -> ProviderResult<()> {
+        self.inner.check_request(request)
+    }
+
+    async fn health(&self) -> HealthStatus {
+        match self.cassette {
+            // Replay never touches the network
+            Cassette::Replay { .. } => HealthStatus::Healthy,
+            Cassette::Record { .. } => self.inner.health().await,
+        }
+    }
+}
+
+fn cassette_error(path: &Path, e: std::io::Error) -> ProviderError {
+    ProviderError::Configuration(format!("Cassette {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Choice, FinishReason, ResponseMetadata};
+    use crate::Usage;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider standing in for the real upstream while recording
+    struct MockUpstream {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for MockUpstream {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "Recorded answer".to_string(),
+                    },
+                    finish_reason: Some(FinishReason::Stop),
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 4,
+                    completion_tokens: 2,
+                    total_tokens: 6,
+                },
+                metadata: ResponseMetadata {
+                    provider: "openai".to_string(),
+                    cached: false,
+                    latency_ms: 12,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    /// Provider that must not be reached during replay
+    struct Offline;
+
+    #[async_trait]
+    impl LLMProvider for Offline {
+        fn name(&self) -> &str {
+            "openai"
+        }
+
+        async fn send(&self, _request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            panic!("replay must not hit the network");
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Unhealthy
+        }
+    }
+
+    fn request(content: &str) -> UnifiedRequest {
+        let mut extra_headers = HeaderMap::new();
+        extra_headers.insert("x-api-key", HeaderValue::from_static("sk-live-secret"));
+        extra_headers.insert("openai-organization", HeaderValue::from_static("org-123"));
+
+        UnifiedRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            temperature: Some(0.0),
+            max_tokens: Some(16),
+            reasoning_effort: None,
+            logprobs: None,
+            top_logprobs: None,
+            response_format: None,
+            stream: false,
+            metadata: HashMap::new(),
+            extra_headers,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_without_upstream() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.jsonl");
+
+        let upstream = Arc::new(MockUpstream {
+            calls: AtomicUsize::new(0),
+        });
+        let recorder = RecordingProvider::wrap(upstream.clone(), &RecordMode::Record(path.clone()))
+            .await
+            .unwrap();
+        let recorded = recorder
+            .send(request("Email me at jane@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(upstream.calls.load(Ordering::SeqCst), 1);
+
+        let replayer = RecordingProvider::wrap(Arc::new(Offline), &RecordMode::Replay(path))
+            .await
+            .unwrap();
+        let replayed = replayer
+            .send(request("Email me at jane@example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(replayed.id, recorded.id);
+        assert_eq!(
+            replayed.choices[0].message.content,
+            recorded.choices[0].message.content
+        );
+        assert!(matches!(replayer.health().await, HealthStatus::Healthy));
+
+        // Unrecorded requests fail instead of reaching the network
+        let miss = replayer.send(request("Something else")).await;
+        assert!(matches!(miss, Err(ProviderError::Internal(_))));
+    }
+
+    #[tokio::test]
+    async fn test_recording_redacts_keys_and_pii() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.jsonl");
+
+        let upstream = Arc::new(MockUpstream {
+            calls: AtomicUsize::new(0),
+        });
+        let recorder = RecordingProvider::wrap(upstream, &RecordMode::Record(path.clone()))
+            .await
+            .unwrap();
+        recorder
+            .send(request("My SSN is 123-45-6789"))
+            .await
+            .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("sk-live-secret"));
+        assert!(!contents.contains("123-45-6789"));
+
+        let interaction: Interaction = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(
+            interaction.request.headers["x-api-key"],
+            REDACTED_HEADER_VALUE
+        );
+        assert_eq!(
+            interaction.request.headers["openai-organization"],
+            "org-123"
+        );
+    }
+
+    #[test]
+    fn test_record_mode_parse() {
+        assert_eq!(RecordMode::parse("off"), Some(RecordMode::Off));
+        assert_eq!(
+            RecordMode::parse("record:/tmp/c.jsonl"),
+            Some(RecordMode::Record("/tmp/c.jsonl".into()))
+        );
+        assert_eq!(
+            RecordMode::parse("replay:/tmp/c.jsonl"),
+            Some(RecordMode::Replay("/tmp/c.jsonl".into()))
+        );
+        assert_eq!(RecordMode::parse("replay:"), None);
+        assert_eq!(RecordMode::parse("tape"), None);
+    }
+}
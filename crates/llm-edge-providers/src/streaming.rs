@@ -0,0 +1,364 @@
+//! Serving non-streaming requests from streamed responses
+//!
+//! Some models are only offered as a stream. Adapters translate the
+//! provider's stream events into [`StreamChunk`]s and hand them to
+//! [`complete_via_stream`], which collects them into the single
+//! [`UnifiedResponse`] a non-streaming client expects:
+//!
+//! - content deltas are concatenated per choice
+//! - tool call deltas are merged per call: the id and name arrive once, the
+//!   arguments arrive in pieces that are concatenated
+//! - the last finish reason seen for a choice wins
+//! - usage reported across chunks is summed, so adapters report increments
+//!   rather than running totals
+//!
+//! Streamed requests get the same chunks as a [`ChunkStream`] from
+//! [`LLMProvider::send_stream`](crate::LLMProvider::send_stream) instead.
+
+use crate::timeouts::next_chunk;
+use crate::types::{Choice, ResponseMetadata, ToolCall, Usage};
+use crate::{FinishReason, Message, ProviderError, ProviderResult, UnifiedResponse};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use reqwest::Response;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Chunks of a streamed completion, in the order the provider sent them
+pub type ChunkStream = BoxStream<'static, ProviderResult<StreamChunk>>;
+
+/// One event of a streamed completion
+#[derive(Debug, Clone, Default)]
+pub struct StreamChunk {
+    /// Response id; usually only on the first chunk
+    pub id: Option<String>,
+    pub model: Option<String>,
+    pub choices: Vec<ChoiceDelta>,
+    /// Tokens this chunk accounts for, if it reports any
+    pub usage: Option<Usage>,
+}
+
+/// Increment to one choice
+#[derive(Debug, Clone, Default)]
+pub struct ChoiceDelta {
+    pub index: usize,
+    pub role: Option<String>,
+    pub content: Option<String>,
+    pub tool_calls: Vec<ToolCallDelta>,
+    /// The provider's own finish/stop reason, once the choice is done
+    pub finish_reason: Option<String>,
+}
+
+/// Increment to one tool call, identified by its position in the choice
+#[derive(Debug, Clone, Default)]
+pub struct ToolCallDelta {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    /// Next piece of the JSON arguments
+    pub arguments: Option<String>,
+}
+
+#[derive(Default)]
+struct ChoiceState {
+    role: Option<String>,
+    content: String,
+    tool_calls: BTreeMap<usize, ToolCall>,
+    finish_reason: Option<String>,
+}
+
+/// Read a streamed completion to the end and return it as one response
+///
+/// Fails with the first error the stream yields, or if it ends without
+/// producing any choice.
+pub async fn complete_via_stream<S>(chunks: S, provider: &str) -> ProviderResult<UnifiedResponse>
+where
+    S: Stream<Item = ProviderResult<StreamChunk>>,
+{
+    let start = Instant::now();
+    let mut chunks = std::pin::pin!(chunks);
+    let mut id = None;
+    let mut model = None;
+    let mut choices: BTreeMap<usize, ChoiceState> = BTreeMap::new();
+    let mut usage = Usage {
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        total_tokens: 0,
+    };
+
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        id = id.or(chunk.id);
+        model = model.or(chunk.model);
+        if let Some(chunk_usage) = chunk.usage {
+            usage.prompt_tokens += chunk_usage.prompt_tokens;
+            usage.completion_tokens += chunk_usage.completion_tokens;
+        }
+
+        for delta in chunk.choices {
+            let choice = choices.entry(delta.index).or_default();
+            if delta.role.is_some() {
+                choice.role = delta.role;
+            }
+            if let Some(content) = delta.content {
+                choice.content.push_str(&content);
+            }
+            for call in delta.tool_calls {
+                let merged = choice
+                    .tool_calls
+                    .entry(call.index)
+                    .or_insert_with(|| ToolCall {
+                        id: String::new(),
+                        name: String::new(),
+                        arguments: String::new(),
+                    });
+                if let Some(id) = call.id {
+                    merged.id = id;
+                }
+                if let Some(name) = call.name {
+                    merged.name = name;
+                }
+                if let Some(arguments) = call.arguments {
+                    merged.arguments.push_str(&arguments);
+                }
+            }
+            if delta.finish_reason.is_some() {
+                choice.finish_reason = delta.finish_reason;
+            }
+        }
+    }
+
+    if choices.is_empty() {
+        return Err(ProviderError::Internal(format!(
+            "Stream from {} ended without a completion",
+            provider
+        )));
+    }
+    usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+
+    Ok(UnifiedResponse {
+        id: id.unwrap_or_default(),
+        model: model.unwrap_or_default(),
+        choices: choices
+            .into_iter()
+            .map(|(index, choice)| Choice {
+                index,
+                message: Message {
+                    role: choice.role.unwrap_or_else(|| "assistant".to_string()),
+                    content: choice.content,
+                },
+                finish_reason: choice
+                    .finish_reason
+                    .as_deref()
+                    .map(FinishReason::from_native),
+                native_finish_reason: choice.finish_reason,
+                logprobs: None,
+                tool_calls: choice.tool_calls.into_values().collect(),
+            })
+            .collect(),
+        usage,
+        metadata: ResponseMetadata {
+            provider: provider.to_string(),
+            cached: false,
+            latency_ms: start.elapsed().as_millis() as u64,
+            cost_usd: None,
+            usage_estimated: false,
+        },
+    })
+}
+
+impl From<UnifiedResponse> for StreamChunk {
+    /// The whole response as a single chunk, for providers that cannot
+    /// stream
+    fn from(response: UnifiedResponse) -> Self {
+        StreamChunk {
+            id: Some(response.id),
+            model: Some(response.model),
+            choices: response
+                .choices
+                .into_iter()
+                .map(|choice| ChoiceDelta {
+                    index: choice.index,
+                    role: Some(choice.message.role),
+                    content: Some(choice.message.content),
+                    tool_calls: choice
+                        .tool_calls
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, call)| ToolCallDelta {
+                            index,
+                            id: Some(call.id),
+                            name: Some(call.name),
+                            arguments: Some(call.arguments),
+                        })
+                        .collect(),
+                    finish_reason: choice
+                        .native_finish_reason
+                        .or_else(|| choice.finish_reason.map(|r| r.as_str().to_string())),
+                })
+                .collect(),
+            usage: Some(response.usage),
+        }
+    }
+}
+
+/// `data` payloads of a server-sent event response, as they arrive
+///
+/// Each read of the body waits at most `idle`. Comments, other fields and
+/// OpenAI's closing `[DONE]` are skipped.
+pub fn sse_data(response: Response, idle: Duration) -> BoxStream<'static, ProviderResult<String>> {
+    futures::stream::unfold(
+        (Some(response), Vec::new()),
+        move |(mut response, mut buffer)| async move {
+            loop {
+                let line = match buffer.iter().position(|b| *b == b'\n') {
+                    Some(end) => buffer.drain(..=end).collect::<Vec<_>>(),
+                    None => match response.as_mut() {
+                        Some(body) => match next_chunk(body, idle).await {
+                            Ok(Some(chunk)) => {
+                                buffer.extend_from_slice(&chunk);
+                                continue;
+                            }
+                            Ok(None) => {
+                                response = None;
+                                std::mem::take(&mut buffer)
+                            }
+                            Err(e) => return Some((Err(e), (None, Vec::new()))),
+                        },
+                        // Body finished and its last line handled
+                        None => return None,
+                    },
+                };
+
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if !data.is_empty() && data != "[DONE]" {
+                    return Some((Ok(data.to_string()), (response, buffer)));
+                }
+            }
+        },
+    )
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(text: &str) -> StreamChunk {
+        StreamChunk {
+            choices: vec![ChoiceDelta {
+                content: Some(text.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn tool_call(index: usize, id_and_name: Option<(&str, &str)>, arguments: &str) -> StreamChunk {
+        StreamChunk {
+            choices: vec![ChoiceDelta {
+                tool_calls: vec![ToolCallDelta {
+                    index,
+                    id: id_and_name.map(|(id, _)| id.to_string()),
+                    name: id_and_name.map(|(_, name)| name.to_string()),
+                    arguments: Some(arguments.to_string()),
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn usage(prompt_tokens: usize, completion_tokens: usize) -> Option<Usage> {
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_chunks_aggregate_into_full_response() {
+        let chunks = vec![
+            StreamChunk {
+                id: Some("msg_1".to_string()),
+                model: Some("claude-3-5-sonnet".to_string()),
+                choices: vec![ChoiceDelta {
+                    role: Some("assistant".to_string()),
+                    ..Default::default()
+                }],
+                usage: usage(12, 0),
+            },
+            content("The weather "),
+            content("in Paris:"),
+            tool_call(0, Some(("call_a", "get_weather")), "{\"city\":"),
+            tool_call(1, Some(("call_b", "get_time")), "{\"tz\":\"CET\"}"),
+            tool_call(0, None, "\"Paris\"}"),
+            StreamChunk {
+                choices: vec![ChoiceDelta {
+                    finish_reason: Some("tool_use".to_string()),
+                    ..Default::default()
+                }],
+                usage: usage(0, 9),
+                ..Default::default()
+            },
+        ];
+
+        let response = complete_via_stream(
+            futures::stream::iter(chunks.into_iter().map(Ok)),
+            "anthropic",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.id, "msg_1");
+        assert_eq!(response.model, "claude-3-5-sonnet");
+        assert_eq!(response.metadata.provider, "anthropic");
+        assert_eq!(response.choices.len(), 1);
+        let choice = &response.choices[0];
+        assert_eq!(choice.message.role, "assistant");
+        assert_eq!(choice.message.content, "The weather in Paris:");
+        assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(choice.native_finish_reason.as_deref(), Some("tool_use"));
+        assert_eq!(
+            choice.tool_calls,
+            [
+                ToolCall {
+                    id: "call_a".to_string(),
+                    name: "get_weather".to_string(),
+                    arguments: "{\"city\":\"Paris\"}".to_string(),
+                },
+                ToolCall {
+                    id: "call_b".to_string(),
+                    name: "get_time".to_string(),
+                    arguments: "{\"tz\":\"CET\"}".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            (
+                response.usage.prompt_tokens,
+                response.usage.completion_tokens,
+                response.usage.total_tokens
+            ),
+            (12, 9, 21)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_errors_and_empty_streams_fail() {
+        let chunks = vec![Ok(content("partial")), Err(ProviderError::Timeout)];
+        let err = complete_via_stream(futures::stream::iter(chunks), "openai")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::Timeout));
+
+        let err = complete_via_stream(futures::stream::empty(), "openai")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::Internal(_)));
+    }
+}
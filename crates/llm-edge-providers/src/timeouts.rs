@@ -0,0 +1,206 @@
+//! Provider HTTP timeouts
+//!
+//! One timeout for the whole request lets a hung connect use up the entire
+//! budget, and does not suit streaming, where a long response is fine as long
+//! as bytes keep arriving. [`ProviderTimeouts`] splits it into a connect
+//! timeout, an idle timeout between body chunks, and an optional total
+//! timeout. reqwest enforces the connect and total timeouts; the idle timeout
+//! is enforced by reading the body through [`next_chunk`] or [`read_body`].
+
+use crate::{ProviderError, ProviderResult, UpstreamProxy};
+use reqwest::Response;
+use std::time::Duration;
+use tracing::warn;
+
+/// Default time allowed to establish a connection
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default longest gap between two chunks of a response body
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connect, idle and total timeouts for one provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderTimeouts {
+    /// Time allowed to establish the connection (including TLS)
+    pub connect: Duration,
+
+    /// Longest wait for the next body chunk once the response has started
+    pub read: Duration,
+
+    /// Bound on the whole request including the body
+    ///
+    /// `None` by default: streams may legitimately run long, and the
+    /// caller's request deadline bounds non-streaming calls.
+    pub total: Option<Duration>,
+}
+
+impl Default for ProviderTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: DEFAULT_CONNECT_TIMEOUT,
+            read: DEFAULT_READ_TIMEOUT,
+            total: None,
+        }
+    }
+}
+
+impl ProviderTimeouts {
+    /// HTTP client enforcing the connect and total timeouts
+    pub fn build_client(&self) -> reqwest::Client {
+        self.build_client_with_proxy(None)
+    }
+
+    /// HTTP client enforcing the timeouts, sending requests through `proxy`
+    /// if one is given
+    pub fn build_client_with_proxy(&self, proxy: Option<&UpstreamProxy>) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder().connect_timeout(self.connect);
+        if let Some(total) = self.total {
+            builder = builder.timeout(total);
+        }
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy.reqwest_proxy());
+        }
+
+        builder.build().unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to build HTTP client with timeouts, using defaults");
+            reqwest::Client::new()
+        })
+    }
+}
+
+/// Next chunk of a response body, or `None` once it is complete
+///
+/// Fails with [`ProviderError::Timeout`] if nothing arrives within `idle`.
+/// Streaming adapters call this per chunk, so a slow stream survives as long
+/// as it keeps making progress.
+pub async fn next_chunk(
+    response: &mut Response,
+    idle: Duration,
+) -> ProviderResult<Option<bytes::Bytes>> {
+    match tokio::time::timeout(idle, response.chunk()).await {
+        Ok(chunk) => Ok(chunk?),
+        Err(_) => Err(ProviderError::Timeout),
+    }
+}
+
+/// Read a whole response body, applying the idle timeout between chunks
+pub async fn read_body(mut response: Response, idle: Duration) -> ProviderResult<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = next_chunk(&mut response, idle).await? {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Server answering one request with a chunked body, sending each chunk
+    /// after its delay
+    async fn drip_server(chunks: Vec<(Duration, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n")
+                .await
+                .unwrap();
+            for (delay, chunk) in chunks {
+                tokio::time::sleep(delay).await;
+                let frame = format!("{:x}\r\n{}\r\n", chunk.len(), chunk);
+                if socket.write_all(frame.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+
+        format!("http://{}", addr)
+    }
+
+    fn timeouts(read: Duration) -> ProviderTimeouts {
+        ProviderTimeouts {
+            connect: Duration::from_millis(200),
+            read,
+            total: Some(Duration::from_secs(10)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_fires_quickly() {
+        // A listener that never accepts, with its backlog already full, so
+        // further connection attempts hang instead of being refused
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(1).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut queued = Vec::new();
+        while let Ok(Ok(stream)) = tokio::time::timeout(
+            Duration::from_millis(100),
+            tokio::net::TcpStream::connect(addr),
+        )
+        .await
+        {
+            queued.push(stream);
+        }
+
+        let timeouts = ProviderTimeouts {
+            connect: Duration::from_millis(100),
+            ..ProviderTimeouts::default()
+        };
+        let start = Instant::now();
+        let err = timeouts
+            .build_client()
+            .get(format!("http://{}/", addr))
+            .send()
+            .await
+            .unwrap_err();
+
+        assert!(err.is_connect() && err.is_timeout(), "{:?}", err);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_slow_but_progressing_body_is_not_killed() {
+        let tick = Duration::from_millis(150);
+        let url = drip_server(vec![(tick, "a"), (tick, "b"), (tick, "c"), (tick, "d")]).await;
+        let timeouts = timeouts(Duration::from_millis(400));
+
+        let start = Instant::now();
+        let response = timeouts.build_client().get(url).send().await.unwrap();
+        let body = read_body(response, timeouts.read).await.unwrap();
+
+        assert_eq!(body, b"abcd");
+        // Longer in total than the idle timeout allows for any single gap
+        assert!(start.elapsed() > timeouts.read);
+    }
+
+    #[tokio::test]
+    async fn test_stalled_body_times_out() {
+        let url = drip_server(vec![(Duration::ZERO, "a"), (Duration::from_secs(5), "b")]).await;
+        let timeouts = timeouts(Duration::from_millis(200));
+
+        let mut response = timeouts.build_client().get(url).send().await.unwrap();
+        assert_eq!(
+            next_chunk(&mut response, timeouts.read)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(&b"a"[..])
+        );
+
+        let start = Instant::now();
+        let err = next_chunk(&mut response, timeouts.read).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Timeout));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -1,6 +1,11 @@
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// `UnifiedRequest::metadata` entry holding the end user's id, forwarded to
+/// providers that take one for abuse monitoring
+pub const USER_ID_METADATA_KEY: &str = "user_id";
+
 /// Unified request format across all providers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnifiedRequest {
@@ -10,10 +15,49 @@ pub struct UnifiedRequest {
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<usize>,
+    /// Reasoning effort for reasoning models ("low" | "medium" | "high")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    /// Return log probabilities of the output tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// Most likely alternatives to return per token (requires `logprobs`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u8>,
+    /// Structured output format (OpenAI's `response_format`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
     #[serde(default)]
     pub stream: bool,
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+    /// Client headers forwarded to the upstream request (already allowlisted)
+    #[serde(skip)]
+    pub extra_headers: HeaderMap,
+}
+
+/// Output format requested by the client, in OpenAI's `response_format` shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Plain text (the default)
+    Text,
+    /// Any valid JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+/// Schema of a `json_schema` response format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 /// A message in the conversation
@@ -38,7 +82,72 @@ pub struct UnifiedResponse {
 pub struct Choice {
     pub index: usize,
     pub message: Message,
-    pub finish_reason: Option<String>,
+    pub finish_reason: Option<FinishReason>,
+    /// The provider's own stop reason (e.g. `end_turn`), for debugging
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native_finish_reason: Option<String>,
+    /// Provider `logprobs` object, passed through unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<serde_json::Value>,
+    /// Tools the model called, in call order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// A tool call made by the model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// Arguments as the JSON text the model produced
+    pub arguments: String,
+}
+
+/// Why a choice stopped, serialized with OpenAI's vocabulary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// Natural end of the completion or a stop sequence
+    Stop,
+    /// Hit `max_tokens` or the context window
+    Length,
+    /// Filtered or refused by the provider
+    ContentFilter,
+    /// The model called a tool
+    ToolCalls,
+}
+
+impl FinishReason {
+    /// Map a provider's native finish/stop reason
+    ///
+    /// Understands OpenAI finish reasons (`stop`, `length`, `content_filter`,
+    /// `tool_calls`) and Anthropic stop reasons (`end_turn`, `max_tokens`,
+    /// `stop_sequence`, `tool_use`, `refusal`). Unknown values map to `Stop`;
+    /// adapters keep the original in [`Choice::native_finish_reason`].
+    pub fn from_native(reason: &str) -> Self {
+        match reason {
+            "length" | "max_tokens" | "model_context_window_exceeded" => Self::Length,
+            "content_filter" | "refusal" => Self::ContentFilter,
+            "tool_calls" | "function_call" | "tool_use" => Self::ToolCalls,
+            _ => Self::Stop,
+        }
+    }
+
+    /// OpenAI-compatible string form
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Length => "length",
+            Self::ContentFilter => "content_filter",
+            Self::ToolCalls => "tool_calls",
+        }
+    }
+}
+
+impl std::fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Token usage statistics
@@ -49,6 +158,31 @@ pub struct Usage {
     pub total_tokens: usize,
 }
 
+/// Characters per token assumed when estimating usage
+const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
+
+impl Usage {
+    /// Rough usage of `request` and its `choices`, for responses that
+    /// report none
+    ///
+    /// Counts about four characters per token; good enough for cost
+    /// tracking and limits, not for reconciling a provider's bill.
+    pub fn estimate(request: &UnifiedRequest, choices: &[Choice]) -> Self {
+        let tokens = |messages: &mut dyn Iterator<Item = &Message>| -> usize {
+            let chars: usize = messages.map(|m| m.content.chars().count()).sum();
+            chars.div_ceil(ESTIMATED_CHARS_PER_TOKEN)
+        };
+        let prompt_tokens = tokens(&mut request.messages.iter());
+        let completion_tokens = tokens(&mut choices.iter().map(|c| &c.message));
+
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+}
+
 /// Response metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResponseMetadata {
@@ -56,4 +190,7 @@ pub struct ResponseMetadata {
     pub cached: bool,
     pub latency_ms: u64,
     pub cost_usd: Option<f64>,
+    /// The provider reported no usage, so it was estimated
+    #[serde(default)]
+    pub usage_estimated: bool,
 }
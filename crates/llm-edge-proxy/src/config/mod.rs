@@ -1,7 +1,10 @@
 //! Configuration management for LLM Edge Agent
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use std::time::Duration;
+use thiserror::Error;
 
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +30,9 @@ pub struct RateLimitConfig {
     pub enabled: bool,
     pub requests_per_minute: u32,
     pub burst_size: u32,
+    /// Redis keeping per-key counts shared by every instance; counts stay
+    /// in this process when unset
+    pub redis_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +40,52 @@ pub struct AuthConfig {
     pub enabled: bool,
     pub api_keys: Vec<String>,
     pub require_auth_for_health: bool,
+    /// Scopes granted to each API key (plain or SHA-256 hashed)
+    ///
+    /// Keys listed here are valid even if absent from `api_keys`. Keys with no
+    /// entry keep access to every scope.
+    #[serde(default)]
+    pub key_scopes: HashMap<String, Vec<String>>,
+    /// Model patterns each API key (plain or SHA-256 hashed) may request
+    ///
+    /// A trailing `*` matches any suffix, e.g. `claude-3-haiku*`. Keys with
+    /// no entry, or an empty list, may use every model. Keys must still be
+    /// configured in `api_keys` or `key_scopes`.
+    #[serde(default)]
+    pub key_models: HashMap<String, Vec<String>>,
+    /// What to do when an external auth backend (e.g. JWKS) is unreachable
+    ///
+    /// API-key auth has no backend and is unaffected.
+    #[serde(default)]
+    pub on_backend_error: FailMode,
+    /// Also accept the key from an `?api_key=` query parameter
+    ///
+    /// Off by default: query strings end up in access logs and proxies.
+    #[serde(default)]
+    pub allow_query_api_key: bool,
+}
+
+/// Auth behaviour while the auth backend is unavailable
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailMode {
+    /// Reject requests with `503`
+    #[default]
+    FailClosed,
+    /// Let requests through without token checks
+    FailOpen,
+}
+
+impl std::str::FromStr for FailMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fail_closed" | "closed" => Ok(FailMode::FailClosed),
+            "fail_open" | "open" => Ok(FailMode::FailOpen),
+            other => anyhow::bail!("Invalid auth fail mode '{}'", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +94,43 @@ pub struct ObservabilityConfig {
     pub enable_metrics: bool,
     pub log_level: String,
     pub otlp_endpoint: Option<String>,
+    /// Reuse a valid client-supplied `X-Request-Id` instead of generating one
+    #[serde(default = "default_trust_request_id")]
+    pub trust_request_id: bool,
+    /// Fraction of successful requests with an access log line (0.0 to 1.0);
+    /// errors are always logged
+    #[serde(default = "default_access_log_sample_rate")]
+    pub access_log_sample_rate: f64,
+}
+
+fn default_trust_request_id() -> bool {
+    true
+}
+
+fn default_access_log_sample_rate() -> f64 {
+    1.0
+}
+
+/// A setting, or combination of settings, that cannot work
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("ENABLE_TLS is set but {0} is missing")]
+    MissingTlsPath(&'static str),
+
+    #[error("{var} '{path}' does not exist")]
+    TlsFileNotFound { var: &'static str, path: String },
+
+    #[error("{0} must be greater than 0 when rate limiting is enabled")]
+    NonPositiveRateLimit(&'static str),
+
+    #[error("AUTH_ENABLED is set but no API keys are configured (API_KEYS or API_KEY_SCOPES)")]
+    NoApiKeys,
+
+    #[error("OTLP_ENDPOINT '{0}' is not a valid http(s) URL")]
+    InvalidOtlpEndpoint(String),
+
+    #[error("ACCESS_LOG_SAMPLE_RATE {0} is not between 0 and 1")]
+    InvalidSampleRate(String),
 }
 
 impl Config {
@@ -72,6 +161,9 @@ impl Config {
             burst_size: std::env::var("RATE_LIMIT_BURST")
                 .unwrap_or_else(|_| "100".to_string())
                 .parse()?,
+            redis_url: std::env::var("RATE_LIMIT_REDIS_URL")
+                .ok()
+                .filter(|url| !url.trim().is_empty()),
         };
 
         let auth = AuthConfig {
@@ -87,6 +179,14 @@ impl Config {
             require_auth_for_health: std::env::var("AUTH_HEALTH_CHECK")
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()?,
+            key_scopes: parse_key_lists(&std::env::var("API_KEY_SCOPES").unwrap_or_default()),
+            key_models: parse_key_lists(&std::env::var("API_KEY_MODELS").unwrap_or_default()),
+            on_backend_error: std::env::var("AUTH_ON_BACKEND_ERROR")
+                .unwrap_or_else(|_| "fail_closed".to_string())
+                .parse()?,
+            allow_query_api_key: std::env::var("AUTH_ALLOW_QUERY_KEY")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()?,
         };
 
         let observability = ObservabilityConfig {
@@ -98,6 +198,12 @@ impl Config {
                 .parse()?,
             log_level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
             otlp_endpoint: std::env::var("OTLP_ENDPOINT").ok(),
+            trust_request_id: std::env::var("TRUST_REQUEST_ID")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()?,
+            access_log_sample_rate: std::env::var("ACCESS_LOG_SAMPLE_RATE")
+                .unwrap_or_else(|_| "1.0".to_string())
+                .parse()?,
         };
 
         Ok(Config {
@@ -108,11 +214,93 @@ impl Config {
         })
     }
 
+    /// Check for settings that parse but cannot work together
+    ///
+    /// Collects every problem instead of stopping at the first, so startup
+    /// can report them all at once.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        if self.server.enable_tls {
+            for (var, path) in [
+                ("TLS_CERT_PATH", &self.server.tls_cert_path),
+                ("TLS_KEY_PATH", &self.server.tls_key_path),
+            ] {
+                match path {
+                    None => errors.push(ConfigError::MissingTlsPath(var)),
+                    Some(path) if !Path::new(path).is_file() => {
+                        errors.push(ConfigError::TlsFileNotFound {
+                            var,
+                            path: path.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if self.rate_limit.enabled {
+            if self.rate_limit.requests_per_minute == 0 {
+                errors.push(ConfigError::NonPositiveRateLimit("RATE_LIMIT_RPM"));
+            }
+            if self.rate_limit.burst_size == 0 {
+                errors.push(ConfigError::NonPositiveRateLimit("RATE_LIMIT_BURST"));
+            }
+        }
+
+        if self.auth.enabled && self.auth.api_keys.is_empty() && self.auth.key_scopes.is_empty() {
+            errors.push(ConfigError::NoApiKeys);
+        }
+
+        if let Some(endpoint) = &self.observability.otlp_endpoint {
+            if !is_http_url(endpoint) {
+                errors.push(ConfigError::InvalidOtlpEndpoint(endpoint.clone()));
+            }
+        }
+
+        let sample_rate = self.observability.access_log_sample_rate;
+        if !(0.0..=1.0).contains(&sample_rate) {
+            errors.push(ConfigError::InvalidSampleRate(sample_rate.to_string()));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     pub fn timeout_duration(&self) -> Duration {
         Duration::from_secs(self.server.timeout_seconds)
     }
 }
 
+/// Whether `raw` is an absolute `http://` or `https://` URL with a host
+fn is_http_url(raw: &str) -> bool {
+    raw.parse::<axum::http::Uri>().is_ok_and(|uri| {
+        matches!(uri.scheme_str(), Some("http" | "https"))
+            && uri.host().is_some_and(|host| !host.is_empty())
+    })
+}
+
+/// Parse per-key lists such as `API_KEY_SCOPES` (`key:scope+scope,key:scope`)
+/// and `API_KEY_MODELS` (`key:model+model`)
+fn parse_key_lists(raw: &str) -> HashMap<String, Vec<String>> {
+    raw.split(',')
+        .filter_map(|entry| entry.trim().split_once(':'))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, values)| {
+            let values = values
+                .split('+')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            (key.to_string(), values)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +311,171 @@ mod tests {
         let config = Config::from_env().unwrap();
         assert_eq!(config.server.address, "0.0.0.0:8080");
     }
+
+    fn valid_config() -> Config {
+        Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: true,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: true,
+                api_keys: vec!["test-key".to_string()],
+                require_auth_for_health: false,
+                key_scopes: HashMap::new(),
+                key_models: Default::default(),
+                on_backend_error: FailMode::FailClosed,
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: Some("http://localhost:4317".to_string()),
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_config() {
+        assert_eq!(valid_config().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_tls_paths() {
+        let mut config = valid_config();
+        config.server.enable_tls = true;
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                ConfigError::MissingTlsPath("TLS_CERT_PATH"),
+                ConfigError::MissingTlsPath("TLS_KEY_PATH"),
+            ])
+        );
+
+        let existing = std::env::current_exe().unwrap();
+        config.server.tls_cert_path = Some(existing.to_string_lossy().into_owned());
+        config.server.tls_key_path = Some("/nonexistent/key.pem".to_string());
+        assert_eq!(
+            config.validate(),
+            Err(vec![ConfigError::TlsFileNotFound {
+                var: "TLS_KEY_PATH",
+                path: "/nonexistent/key.pem".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_validate_rate_limit_values() {
+        let mut config = valid_config();
+        config.rate_limit.requests_per_minute = 0;
+        config.rate_limit.burst_size = 0;
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                ConfigError::NonPositiveRateLimit("RATE_LIMIT_RPM"),
+                ConfigError::NonPositiveRateLimit("RATE_LIMIT_BURST"),
+            ])
+        );
+
+        // Zero values are irrelevant while rate limiting is off
+        config.rate_limit.enabled = false;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_auth_requires_keys() {
+        let mut config = valid_config();
+        config.auth.api_keys.clear();
+        assert_eq!(config.validate(), Err(vec![ConfigError::NoApiKeys]));
+
+        // Scoped keys count as configured keys
+        config
+            .auth
+            .key_scopes
+            .insert("ops-key".to_string(), vec!["admin".to_string()]);
+        assert_eq!(config.validate(), Ok(()));
+
+        config.auth.key_scopes.clear();
+        config.auth.enabled = false;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_otlp_endpoint() {
+        let mut config = valid_config();
+        for endpoint in ["localhost:4317", "grpc://collector:4317", "not a url"] {
+            config.observability.otlp_endpoint = Some(endpoint.to_string());
+            assert_eq!(
+                config.validate(),
+                Err(vec![ConfigError::InvalidOtlpEndpoint(endpoint.to_string())]),
+                "{endpoint}"
+            );
+        }
+
+        config.observability.otlp_endpoint = Some("https://otel.example.com".to_string());
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_access_log_sample_rate() {
+        let mut config = valid_config();
+        for rate in [-0.1, 1.5, f64::NAN] {
+            config.observability.access_log_sample_rate = rate;
+            assert_eq!(
+                config.validate(),
+                Err(vec![ConfigError::InvalidSampleRate(rate.to_string())]),
+                "{rate}"
+            );
+        }
+
+        config.observability.access_log_sample_rate = 0.0;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let mut config = valid_config();
+        config.server.enable_tls = true;
+        config.rate_limit.requests_per_minute = 0;
+        config.auth.api_keys.clear();
+        config.observability.otlp_endpoint = Some("collector".to_string());
+
+        assert_eq!(config.validate().unwrap_err().len(), 5);
+    }
+
+    #[test]
+    fn test_parse_key_lists() {
+        let scopes = parse_key_lists("ops-key:admin+inference, app-key:inference,broken");
+
+        assert_eq!(scopes.len(), 2);
+        assert_eq!(scopes["ops-key"], vec!["admin", "inference"]);
+        assert_eq!(scopes["app-key"], vec!["inference"]);
+        assert!(parse_key_lists("").is_empty());
+
+        let models = parse_key_lists("cheap-key:gpt-3.5-turbo+claude-3-haiku*");
+        assert_eq!(
+            models["cheap-key"],
+            vec!["gpt-3.5-turbo", "claude-3-haiku*"]
+        );
+    }
+
+    #[test]
+    fn test_parse_fail_mode() {
+        assert_eq!("fail_open".parse::<FailMode>().unwrap(), FailMode::FailOpen);
+        assert_eq!("Closed".parse::<FailMode>().unwrap(), FailMode::FailClosed);
+        assert!("sometimes".parse::<FailMode>().is_err());
+    }
 }
@@ -3,6 +3,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use llm_edge_providers::{error_to_status, ProviderError};
 use serde::Serialize;
 use thiserror::Error;
 
@@ -17,6 +18,9 @@ pub enum ProxyError {
     #[error("Authentication failed: {0}")]
     Authentication(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Rate limit exceeded: {0}")]
     RateLimit(String),
 
@@ -37,6 +41,14 @@ pub enum ProxyError {
 
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
+
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    /// A provider call failed; answered with the status [`error_to_status`]
+    /// gives it
+    #[error("Provider error: {0}")]
+    Provider(#[from] ProviderError),
 }
 
 /// Error response structure
@@ -59,6 +71,7 @@ impl ProxyError {
             ProxyError::Http(_) => "HTTP_ERROR",
             ProxyError::Config(_) => "CONFIG_ERROR",
             ProxyError::Authentication(_) => "AUTH_ERROR",
+            ProxyError::Forbidden(_) => "FORBIDDEN",
             ProxyError::RateLimit(_) => "RATE_LIMIT_EXCEEDED",
             ProxyError::Validation(_) => "VALIDATION_ERROR",
             ProxyError::Timeout => "TIMEOUT",
@@ -66,6 +79,15 @@ impl ProxyError {
             ProxyError::InvalidRequest(_) => "INVALID_REQUEST",
             ProxyError::Internal(_) => "INTERNAL_ERROR",
             ProxyError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
+            ProxyError::MethodNotAllowed(_) => "METHOD_NOT_ALLOWED",
+            ProxyError::Provider(e) => match error_to_status(e) {
+                StatusCode::TOO_MANY_REQUESTS => "RATE_LIMIT_EXCEEDED",
+                StatusCode::NOT_FOUND => "MODEL_NOT_FOUND",
+                StatusCode::UNPROCESSABLE_ENTITY => "CONTENT_POLICY_VIOLATION",
+                StatusCode::GATEWAY_TIMEOUT => "TIMEOUT",
+                StatusCode::BAD_REQUEST => "INVALID_REQUEST",
+                _ => "PROVIDER_ERROR",
+            },
         }
     }
 
@@ -74,6 +96,7 @@ impl ProxyError {
             ProxyError::Http(_) => StatusCode::BAD_GATEWAY,
             ProxyError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ProxyError::Authentication(_) => StatusCode::UNAUTHORIZED,
+            ProxyError::Forbidden(_) => StatusCode::FORBIDDEN,
             ProxyError::RateLimit(_) => StatusCode::TOO_MANY_REQUESTS,
             ProxyError::Validation(_) => StatusCode::BAD_REQUEST,
             ProxyError::Timeout => StatusCode::GATEWAY_TIMEOUT,
@@ -81,6 +104,8 @@ impl ProxyError {
             ProxyError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
             ProxyError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ProxyError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ProxyError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            ProxyError::Provider(e) => error_to_status(e),
         }
     }
 }
@@ -114,3 +139,33 @@ impl From<serde_json::Error> for ProxyError {
 }
 
 pub type ProxyResult<T> = Result<T, ProxyError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_errors_use_the_shared_status_mapping() {
+        let cases = [
+            (ProviderError::RateLimitExceeded, "RATE_LIMIT_EXCEEDED"),
+            (ProviderError::InvalidApiKey, "PROVIDER_ERROR"),
+            (
+                ProviderError::ModelNotFound("gpt-9".to_string()),
+                "MODEL_NOT_FOUND",
+            ),
+            (ProviderError::Timeout, "TIMEOUT"),
+            (
+                ProviderError::InvalidRequest("bad".to_string()),
+                "INVALID_REQUEST",
+            ),
+        ];
+
+        for (error, code) in cases {
+            let status = error_to_status(&error);
+            let error = ProxyError::from(error);
+            assert_eq!(error.status_code(), status);
+            assert_eq!(error.error_code(), code);
+            assert_eq!(error.into_response().status(), status);
+        }
+    }
+}
@@ -13,7 +13,7 @@ pub mod error;
 pub mod middleware;
 pub mod server;
 
-pub use config::Config;
+pub use config::{Config, ConfigError, FailMode};
 pub use error::{ProxyError, ProxyResult};
 pub use server::{build_app, create_router, serve};
 
@@ -1,15 +1,35 @@
 //! Middleware implementations for the proxy
 //!
 //! Includes:
-//! - Rate limiting with tower-governor
+//! - Per-API-key fixed-window rate limiting with `X-RateLimit-*` headers
 //! - API key authentication
 //! - Request validation
 //! - Timeout handling
+//! - In-flight request gauges per route
+//! - Request ID propagation (`X-Request-Id`)
+//! - Sampled access logging
 
+pub mod access_log;
+pub mod active_requests;
 pub mod auth;
 pub mod rate_limit;
+pub mod request_id;
 pub mod timeout;
 
-pub use auth::auth_middleware;
-pub use rate_limit::create_rate_limiter;
+pub use access_log::{access_log_middleware, should_log, AccessLogSampler, DEBUG_TRACE_HEADER};
+pub use active_requests::{track_active_requests, ACTIVE_REQUESTS_GAUGE};
+pub use auth::{
+    api_key_identity, auth_middleware, auth_middleware_with_verifier, find_api_key,
+    presented_api_key, require_scope, resolve_backend_error, AllowedModels, AuthenticatedKey,
+    BackendAuthError, GrantedScopes, TokenVerifier, SCOPE_ADMIN, SCOPE_INFERENCE,
+};
+pub use rate_limit::{
+    create_rate_limiter, rate_limit_middleware, RateLimitBackend, RateLimitBackendError,
+    RateLimitStatus, RateLimiter, RedisRateLimitBackend, RATE_LIMIT_LIMIT_HEADER,
+    RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+};
+pub use request_id::{
+    is_valid_request_id, request_id_from_headers, request_id_middleware, RequestId,
+    RequestIdPolicy, REQUEST_ID_HEADER,
+};
 pub use timeout::TimeoutLayer;
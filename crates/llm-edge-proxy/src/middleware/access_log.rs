@@ -0,0 +1,228 @@
+//! Sampled access logging
+//!
+//! Logging every request is expensive and noisy at high volume. The access
+//! log decides when a request arrives (head-based sampling) whether it is
+//! sampled: a configured fraction of requests are, and so is every request
+//! sending `X-Debug-Trace`. Sampled requests get a `Request completed` line;
+//! `4xx` and `5xx` responses are logged whether sampled or not. The decision
+//! is recorded as `log_sampled` on the request span, so other logs of the
+//! request can follow it.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use tracing::info;
+
+use crate::Config;
+
+/// Header forcing a request to be sampled
+pub const DEBUG_TRACE_HEADER: &str = "x-debug-trace";
+
+/// Head-based sampling decision for the access log
+#[derive(Debug, Clone)]
+pub struct AccessLogSampler {
+    sample_rate: f64,
+    rng: Arc<Mutex<StdRng>>,
+}
+
+impl AccessLogSampler {
+    /// Sample `sample_rate` (0.0 to 1.0) of requests
+    pub fn new(sample_rate: f64) -> Self {
+        Self::with_rng(sample_rate, StdRng::from_entropy())
+    }
+
+    /// Like [`Self::new`], with a reproducible sequence of decisions
+    pub fn with_seed(sample_rate: f64, seed: u64) -> Self {
+        Self::with_rng(sample_rate, StdRng::seed_from_u64(seed))
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.observability.access_log_sample_rate)
+    }
+
+    fn with_rng(sample_rate: f64, rng: StdRng) -> Self {
+        Self {
+            sample_rate: sample_rate.clamp(0.0, 1.0),
+            rng: Arc::new(Mutex::new(rng)),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Whether a request with these headers is sampled
+    pub fn sample(&self, headers: &HeaderMap) -> bool {
+        if headers.contains_key(DEBUG_TRACE_HEADER) || self.sample_rate >= 1.0 {
+            return true;
+        }
+        self.sample_rate > 0.0
+            && self
+                .rng
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .gen::<f64>()
+                < self.sample_rate
+    }
+}
+
+/// Whether a finished request gets an access log line
+pub fn should_log(sampled: bool, status: StatusCode) -> bool {
+    sampled || status.is_client_error() || status.is_server_error()
+}
+
+/// Access log middleware
+///
+/// Must run inside [`request_id_middleware`](super::request_id_middleware),
+/// whose span receives the `log_sampled` field.
+pub async fn access_log_middleware(
+    State(sampler): State<AccessLogSampler>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let sampled = sampler.sample(request.headers());
+    tracing::Span::current().record("log_sampled", sampled);
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    let status = response.status();
+    if should_log(sampled, status) {
+        info!(
+            method = %method,
+            path = %path,
+            status = status.as_u16(),
+            latency_ms = start.elapsed().as_millis() as u64,
+            sampled,
+            "Request completed"
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::{request_id_middleware, RequestIdPolicy};
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Statuses of logged requests, and `log_sampled` values recorded on spans
+    #[derive(Clone, Default)]
+    struct Capture {
+        logged: Arc<Mutex<Vec<u64>>>,
+        sampled: Arc<Mutex<Vec<bool>>>,
+    }
+
+    struct Fields<'a>(&'a Capture);
+
+    impl tracing::field::Visit for Fields<'_> {
+        fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+            if field.name() == "status" {
+                self.0.logged.lock().unwrap().push(value);
+            }
+        }
+
+        fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+            if field.name() == "log_sampled" {
+                self.0.sampled.lock().unwrap().push(value);
+            }
+        }
+
+        fn record_debug(&mut self, _field: &tracing::field::Field, _value: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Capture {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            event.record(&mut Fields(self));
+        }
+
+        fn on_record(
+            &self,
+            _span: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            values.record(&mut Fields(self));
+        }
+    }
+
+    fn app(sampler: AccessLogSampler) -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route(
+                "/boom",
+                get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "boom") }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                sampler,
+                access_log_middleware,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                RequestIdPolicy {
+                    trust_inbound: true,
+                },
+                request_id_middleware,
+            ))
+    }
+
+    fn get_request(uri: &str, debug_trace: bool) -> Request {
+        let mut request = Request::builder().uri(uri);
+        if debug_trace {
+            request = request.header(DEBUG_TRACE_HEADER, "1");
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_errors_logged_regardless_of_sample_rate() {
+        let capture = Capture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let app = app(AccessLogSampler::with_seed(0.0, 7));
+
+        for (uri, debug_trace) in [
+            ("/ok", false),
+            ("/missing", false),
+            ("/boom", false),
+            ("/ok", true),
+        ] {
+            app.clone()
+                .oneshot(get_request(uri, debug_trace))
+                .await
+                .unwrap();
+        }
+
+        // The unsampled success is the only request left out
+        assert_eq!(*capture.logged.lock().unwrap(), vec![404, 500, 200]);
+        assert_eq!(
+            *capture.sampled.lock().unwrap(),
+            vec![false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_success_sampling_fraction_respected() {
+        let sampler = AccessLogSampler::with_seed(0.1, 42);
+        let headers = HeaderMap::new();
+
+        let sampled = (0..10_000).filter(|_| sampler.sample(&headers)).count();
+        assert!((900..=1100).contains(&sampled), "{sampled}");
+
+        assert!((0..100).all(|_| AccessLogSampler::new(1.0).sample(&headers)));
+        assert!(!(0..100).any(|_| AccessLogSampler::new(0.0).sample(&headers)));
+    }
+}
@@ -0,0 +1,126 @@
+//! In-flight request tracking per route
+//!
+//! Maintains the `llm_active_requests{route}` gauge, labeled with the matched
+//! route pattern (e.g. `/admin/providers/{name}/rotate-key`) so label values
+//! stay bounded.
+
+use axum::{extract::MatchedPath, extract::Request, middleware::Next, response::Response};
+use metrics::gauge;
+
+/// Gauge of requests currently being handled, by route
+pub const ACTIVE_REQUESTS_GAUGE: &str = "llm_active_requests";
+
+/// Route label for requests that matched no route
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Keeps a request counted in [`ACTIVE_REQUESTS_GAUGE`] until dropped
+///
+/// Dropping (rather than decrementing after `next.run`) also covers handlers
+/// that panic or are cancelled.
+struct ActiveRequestGuard {
+    route: String,
+}
+
+impl ActiveRequestGuard {
+    fn new(route: String) -> Self {
+        gauge!(ACTIVE_REQUESTS_GAUGE, "route" => route.clone()).increment(1.0);
+        Self { route }
+    }
+}
+
+impl Drop for ActiveRequestGuard {
+    fn drop(&mut self) {
+        gauge!(ACTIVE_REQUESTS_GAUGE, "route" => self.route.clone()).decrement(1.0);
+    }
+}
+
+/// Active request tracking middleware
+///
+/// Must be added with `Router::layer` so the matched route is known.
+pub async fn track_active_requests(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let _guard = ActiveRequestGuard::new(route);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+    use tower::ServiceExt;
+
+    fn prometheus() -> &'static PrometheusHandle {
+        static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+        HANDLE.get_or_init(|| PrometheusBuilder::new().install_recorder().unwrap())
+    }
+
+    /// Current gauge value for `route` in the scraped output
+    fn active(route: &str) -> f64 {
+        let series = format!("{}{{route=\"{}\"}} ", ACTIVE_REQUESTS_GAUGE, route);
+        prometheus()
+            .render()
+            .lines()
+            .find_map(|line| line.strip_prefix(series.as_str()))
+            .map(|value| value.trim().parse().unwrap())
+            .unwrap_or(0.0)
+    }
+
+    #[tokio::test]
+    async fn test_gauge_tracks_in_flight_requests() {
+        prometheus();
+
+        let release = std::sync::Arc::new(Semaphore::new(0));
+        let app = Router::new()
+            .route(
+                "/slow/{id}",
+                get({
+                    let release = release.clone();
+                    move || async move {
+                        release.acquire().await.unwrap().forget();
+                        "done"
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn(track_active_requests));
+
+        let requests: Vec<_> = (0..2)
+            .map(|i| {
+                let app = app.clone();
+                tokio::spawn(async move {
+                    app.oneshot(
+                        Request::builder()
+                            .uri(format!("/slow/{}", i))
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap()
+                })
+            })
+            .collect();
+
+        // Both requests are parked in the handler
+        for _ in 0..100 {
+            if active("/slow/{id}") == 2.0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(active("/slow/{id}"), 2.0);
+
+        release.add_permits(2);
+        for request in requests {
+            request.await.unwrap();
+        }
+        assert_eq!(active("/slow/{id}"), 0.0);
+    }
+}
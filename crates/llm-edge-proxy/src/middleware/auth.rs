@@ -1,25 +1,117 @@
 //! Authentication middleware using API keys
 
+use async_trait::async_trait;
 use axum::{
-    extract::{Request, State},
+    extract::{Query, Request, State},
     http::HeaderMap,
     middleware::Next,
     response::Response,
+    Router,
 };
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tracing::{debug, warn};
 
+use crate::config::{AuthConfig, FailMode};
 use crate::error::ProxyError;
 use crate::Config;
 
 const API_KEY_HEADER: &str = "x-api-key";
+/// Azure OpenAI-style key header
+const AZURE_API_KEY_HEADER: &str = "api-key";
+/// Query parameter read when `allow_query_api_key` is set
+const API_KEY_QUERY_PARAM: &str = "api_key";
 const BEARER_PREFIX: &str = "Bearer ";
 
+/// Scope required for inference endpoints (`/v1/*`)
+pub const SCOPE_INFERENCE: &str = "inference";
+
+/// Scope required for admin endpoints (`/admin/*`)
+pub const SCOPE_ADMIN: &str = "admin";
+
+/// Scopes held by the authenticated caller
+///
+/// Inserted into request extensions by [`auth_middleware`] and checked by
+/// routes wrapped with [`require_scope`].
+#[derive(Debug, Clone)]
+pub enum GrantedScopes {
+    /// Auth disabled, or no keys configured
+    All,
+    Only(HashSet<String>),
+}
+
+impl GrantedScopes {
+    /// Scopes of a valid key without an `API_KEY_SCOPES` entry
+    pub fn inference_only() -> Self {
+        GrantedScopes::Only(HashSet::from([SCOPE_INFERENCE.to_string()]))
+    }
+
+    pub fn allows(&self, scope: &str) -> bool {
+        match self {
+            GrantedScopes::All => true,
+            GrantedScopes::Only(scopes) => scopes.contains(scope),
+        }
+    }
+}
+
+/// Models the authenticated caller may request
+///
+/// Inserted into request extensions by [`auth_middleware`] from
+/// `key_models`. Handlers check it against the model they end up routing,
+/// after any alias has been resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllowedModels {
+    /// Auth disabled, or a key without a model allowlist
+    All,
+    /// Model patterns; a trailing `*` matches any suffix
+    Only(Vec<String>),
+}
+
+impl AllowedModels {
+    fn for_patterns(patterns: Option<&Vec<String>>) -> Self {
+        match patterns {
+            Some(patterns) if !patterns.is_empty() => AllowedModels::Only(patterns.clone()),
+            _ => AllowedModels::All,
+        }
+    }
+
+    pub fn allows(&self, model: &str) -> bool {
+        match self {
+            AllowedModels::All => true,
+            AllowedModels::Only(patterns) => {
+                patterns
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => model.starts_with(prefix),
+                        None => pattern == model,
+                    })
+            }
+        }
+    }
+}
+
+/// Identity of the API key that authenticated the request
+///
+/// Inserted into request extensions by [`auth_middleware`] (see
+/// [`api_key_identity`]); absent when auth is disabled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthenticatedKey(pub String);
+
+/// External check for credentials that are not configured API keys (e.g.
+/// JWTs validated against a JWKS endpoint, or token introspection)
+#[async_trait]
+pub trait TokenVerifier: Send + Sync {
+    /// Scopes granted to `token`
+    async fn verify(&self, token: &str) -> Result<GrantedScopes, BackendAuthError>;
+}
+
 /// Authentication middleware
 ///
-/// Validates API keys from either:
-/// - x-api-key header
+/// Validates the API key from the first of:
 /// - Authorization: Bearer <key> header
+/// - x-api-key or api-key header
+/// - api_key query parameter (only with `allow_query_api_key`)
 ///
 /// Public endpoints (health, metrics) are always allowed.
 pub async fn auth_middleware(
@@ -27,15 +119,42 @@ pub async fn auth_middleware(
     headers: HeaderMap,
     request: Request,
     next: Next,
+) -> Result<Response, ProxyError> {
+    authenticate(&config, None, headers, request, next).await
+}
+
+/// [`auth_middleware`] that passes credentials matching no configured API
+/// key to `verifier`
+///
+/// A verifier that cannot be reached is handled by
+/// [`resolve_backend_error`] according to `on_backend_error`.
+pub async fn auth_middleware_with_verifier(
+    State((config, verifier)): State<(Config, Arc<dyn TokenVerifier>)>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, ProxyError> {
+    authenticate(&config, Some(verifier.as_ref()), headers, request, next).await
+}
+
+async fn authenticate(
+    config: &Config,
+    verifier: Option<&dyn TokenVerifier>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
 ) -> Result<Response, ProxyError> {
     // Skip auth if disabled
     if !config.auth.enabled {
         debug!("Authentication disabled, allowing request");
+        let mut request = request;
+        request.extensions_mut().insert(GrantedScopes::All);
+        request.extensions_mut().insert(AllowedModels::All);
         return Ok(next.run(request).await);
     }
 
     // Get the request path
-    let path = request.uri().path();
+    let path = request.uri().path().to_string();
 
     // Allow health and metrics endpoints without auth
     if !config.auth.require_auth_for_health && (path.starts_with("/health") || path == "/metrics") {
@@ -43,33 +162,152 @@ pub async fn auth_middleware(
         return Ok(next.run(request).await);
     }
 
-    // Extract API key from headers
-    let api_key = extract_api_key(&headers)?;
+    // Extract API key from headers (or the query string, if allowed)
+    let query_key = config
+        .auth
+        .allow_query_api_key
+        .then(|| query_api_key(request.uri()))
+        .flatten();
+    let api_key = extract_api_key(&headers).or_else(|e| query_key.ok_or(e))?;
 
     // Validate API key
-    if !validate_api_key(&api_key, &config.auth.api_keys) {
-        warn!(
-            path = %path,
-            "Invalid API key attempted"
-        );
-        return Err(ProxyError::Authentication("Invalid API key".to_string()));
-    }
+    let configured_keys = config
+        .auth
+        .api_keys
+        .iter()
+        .chain(config.auth.key_scopes.keys());
+    let no_keys_configured = config.auth.api_keys.is_empty() && config.auth.key_scopes.is_empty();
+    let scopes = match (find_api_key(&api_key, configured_keys), verifier) {
+        (Some(key), _) => match config.auth.key_scopes.get(key) {
+            Some(scopes) => GrantedScopes::Only(scopes.iter().cloned().collect()),
+            None => GrantedScopes::inference_only(),
+        },
+        (None, Some(verifier)) => match verifier.verify(&api_key).await {
+            Ok(scopes) => scopes,
+            Err(e) => resolve_backend_error(&config.auth, &path, e)?,
+        },
+        // If no keys configured, allow all (dev mode)
+        (None, None) if no_keys_configured => GrantedScopes::All,
+        (None, None) => {
+            warn!(
+                path = %path,
+                "Invalid API key attempted"
+            );
+            return Err(ProxyError::Authentication("Invalid API key".to_string()));
+        }
+    };
+
+    let models = AllowedModels::for_patterns(
+        find_api_key(&api_key, config.auth.key_models.keys())
+            .and_then(|key| config.auth.key_models.get(key)),
+    );
 
     debug!(path = %path, "Authentication successful");
+    let mut request = request;
+    request.extensions_mut().insert(scopes);
+    request.extensions_mut().insert(models);
+    request
+        .extensions_mut()
+        .insert(AuthenticatedKey(api_key_identity(&api_key)));
     Ok(next.run(request).await)
 }
 
-/// Extract API key from request headers
-fn extract_api_key(headers: &HeaderMap) -> Result<String, crate::error::ProxyError> {
-    // Try x-api-key header first
-    if let Some(key) = headers.get(API_KEY_HEADER) {
-        let key_str = key
-            .to_str()
-            .map_err(|_| ProxyError::Authentication("Invalid API key format".to_string()))?;
-        return Ok(key_str.to_string());
+/// Require `scope` on every route currently in `router`
+///
+/// Must sit inside [`auth_middleware`]: requests that reach it without
+/// [`GrantedScopes`] are rejected as unauthenticated, and a valid key that
+/// lacks the scope gets `403`.
+pub fn require_scope<S>(router: Router<S>, scope: &'static str) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.route_layer(axum::middleware::from_fn(
+        move |request: Request, next: Next| async move {
+            match request.extensions().get::<GrantedScopes>() {
+                Some(scopes) if scopes.allows(scope) => Ok(next.run(request).await),
+                Some(_) => {
+                    warn!(
+                        path = %request.uri().path(),
+                        scope,
+                        "API key lacks required scope"
+                    );
+                    Err(ProxyError::Forbidden(format!(
+                        "API key lacks the '{}' scope",
+                        scope
+                    )))
+                }
+                None => Err(ProxyError::Authentication(
+                    "Authentication required".to_string(),
+                )),
+            }
+        },
+    ))
+}
+
+/// Failure from an external auth backend (JWKS, token introspection)
+#[derive(Debug, Clone)]
+pub enum BackendAuthError {
+    /// The backend answered and the token is not valid
+    Rejected(String),
+    /// The backend could not be reached (e.g. a JWKS fetch failed)
+    Unavailable(String),
+}
+
+/// Resolve a backend auth failure according to `on_backend_error`
+///
+/// Rejected tokens are always `401`. An unavailable backend yields `503`
+/// under [`FailMode::FailClosed`]; under [`FailMode::FailOpen`] the request
+/// is let through with the `inference` scope only (never `admin`) and
+/// counted in `auth_fail_open_total`.
+pub fn resolve_backend_error(
+    auth: &AuthConfig,
+    path: &str,
+    error: BackendAuthError,
+) -> Result<GrantedScopes, ProxyError> {
+    match error {
+        BackendAuthError::Rejected(reason) => {
+            warn!(path = %path, reason = %reason, "Token rejected by auth backend");
+            Err(ProxyError::Authentication(reason))
+        }
+        BackendAuthError::Unavailable(reason) => match auth.on_backend_error {
+            FailMode::FailClosed => {
+                warn!(path = %path, reason = %reason, "Auth backend unavailable, failing closed");
+                Err(ProxyError::ServiceUnavailable(
+                    "Authentication backend unavailable".to_string(),
+                ))
+            }
+            FailMode::FailOpen => {
+                warn!(
+                    path = %path,
+                    reason = %reason,
+                    "AUTH FAIL-OPEN: auth backend unavailable, allowing request without token checks"
+                );
+                metrics::counter!("auth_fail_open_total").increment(1);
+                Ok(GrantedScopes::inference_only())
+            }
+        },
     }
+}
+
+/// API key presented by the client, if any
+///
+/// Reads the same headers as [`auth_middleware`] without validating the key.
+/// Keys sent as a query parameter are not seen here.
+pub fn presented_api_key(headers: &HeaderMap) -> Option<String> {
+    extract_api_key(headers).ok()
+}
+
+/// Stable, non-reversible identifier for an API key
+///
+/// A prefix of the key's SHA-256, safe to pass upstream (e.g. as OpenAI's
+/// `user`) to tell callers apart without revealing the key.
+pub fn api_key_identity(key: &str) -> String {
+    format!("key-{}", &hash_api_key(key)[..16])
+}
 
-    // Try Authorization: Bearer header
+/// Extract API key from request headers
+fn extract_api_key(headers: &HeaderMap) -> Result<String, crate::error::ProxyError> {
+    // Try Authorization: Bearer header first
     if let Some(auth) = headers.get("authorization") {
         let auth_str = auth
             .to_str()
@@ -80,28 +318,59 @@ fn extract_api_key(headers: &HeaderMap) -> Result<String, crate::error::ProxyErr
         }
     }
 
+    // Then the x-api-key and Azure-style api-key headers
+    for header in [API_KEY_HEADER, AZURE_API_KEY_HEADER] {
+        if let Some(key) = headers.get(header) {
+            let key_str = key
+                .to_str()
+                .map_err(|_| ProxyError::Authentication("Invalid API key format".to_string()))?;
+            return Ok(key_str.to_string());
+        }
+    }
+
     Err(ProxyError::Authentication(
-        "Missing API key. Provide either 'x-api-key' header or 'Authorization: Bearer <key>' header".to_string(),
+        "Missing API key. Provide 'Authorization: Bearer <key>', 'x-api-key' or 'api-key' header"
+            .to_string(),
     ))
 }
 
+/// API key from the `api_key` query parameter
+fn query_api_key(uri: &axum::http::Uri) -> Option<String> {
+    let Query(mut params) = Query::<HashMap<String, String>>::try_from_uri(uri).ok()?;
+    params
+        .remove(API_KEY_QUERY_PARAM)
+        .filter(|key| !key.is_empty())
+}
+
 /// Validate API key against configured keys
 ///
 /// Supports both plain-text and SHA-256 hashed keys
+#[cfg(test)]
 fn validate_api_key(provided_key: &str, valid_keys: &[String]) -> bool {
     if valid_keys.is_empty() {
         // If no keys configured, allow all (dev mode)
         return true;
     }
 
+    find_api_key(provided_key, valid_keys.iter()).is_some()
+}
+
+/// Find the configured key matching `provided_key`
+///
+/// Returns the configured entry (plain-text or SHA-256 hash) so callers can
+/// look up settings keyed by it, such as its scopes.
+pub fn find_api_key<'a>(
+    provided_key: &str,
+    valid_keys: impl Iterator<Item = &'a String> + Clone,
+) -> Option<&'a String> {
     // Check direct match first (for plain-text keys)
-    if valid_keys.iter().any(|k| k == provided_key) {
-        return true;
+    if let Some(key) = valid_keys.clone().find(|k| *k == provided_key) {
+        return Some(key);
     }
 
     // Check SHA-256 hash match (for hashed keys)
     let provided_hash = hash_api_key(provided_key);
-    valid_keys.iter().any(|k| k == &provided_hash)
+    valid_keys.into_iter().find(|k| **k == provided_hash)
 }
 
 /// Hash API key using SHA-256
@@ -146,4 +415,334 @@ mod tests {
         assert!(validate_api_key(key, &valid_keys));
         assert!(!validate_api_key("wrong-key", &valid_keys));
     }
+
+    #[test]
+    fn test_allowed_models_patterns() {
+        let models = AllowedModels::Only(vec![
+            "gpt-3.5-turbo".to_string(),
+            "claude-3-haiku*".to_string(),
+        ]);
+
+        assert!(models.allows("gpt-3.5-turbo"));
+        assert!(models.allows("claude-3-haiku-20240307"));
+        assert!(!models.allows("gpt-3.5-turbo-16k"));
+        assert!(!models.allows("gpt-4"));
+        assert!(AllowedModels::All.allows("gpt-4"));
+        assert_eq!(
+            AllowedModels::for_patterns(Some(&Vec::new())),
+            AllowedModels::All
+        );
+    }
+
+    mod scopes {
+        use super::super::*;
+        use crate::config::{AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig};
+        use axum::{body::Body, http::StatusCode, routing::get};
+        use std::collections::HashMap;
+        use tower::ServiceExt;
+
+        pub(super) fn scoped_config() -> Config {
+            let mut key_scopes = HashMap::new();
+            key_scopes.insert("app-key".to_string(), vec![SCOPE_INFERENCE.to_string()]);
+            key_scopes.insert(
+                hash_api_key("ops-key"),
+                vec![SCOPE_ADMIN.to_string(), SCOPE_INFERENCE.to_string()],
+            );
+
+            Config {
+                server: ServerConfig {
+                    address: "127.0.0.1:8080".to_string(),
+                    timeout_seconds: 30,
+                    max_request_size: 10485760,
+                    enable_tls: false,
+                    tls_cert_path: None,
+                    tls_key_path: None,
+                },
+                rate_limit: RateLimitConfig {
+                    enabled: false,
+                    requests_per_minute: 100,
+                    burst_size: 10,
+                    redis_url: None,
+                },
+                auth: AuthConfig {
+                    enabled: true,
+                    api_keys: vec!["legacy-key".to_string()],
+                    require_auth_for_health: false,
+                    key_scopes,
+                    key_models: Default::default(),
+                    on_backend_error: Default::default(),
+                    allow_query_api_key: false,
+                },
+                observability: ObservabilityConfig {
+                    enable_tracing: false,
+                    enable_metrics: false,
+                    log_level: "info".to_string(),
+                    otlp_endpoint: None,
+                    trust_request_id: true,
+                    access_log_sample_rate: 1.0,
+                },
+            }
+        }
+
+        fn scoped_app() -> Router {
+            let inference = require_scope(
+                Router::new().route("/v1/chat/completions", get(|| async { "ok" })),
+                SCOPE_INFERENCE,
+            );
+            let admin = require_scope(
+                Router::new().route("/admin/stats", get(|| async { "ok" })),
+                SCOPE_ADMIN,
+            );
+
+            Router::new()
+                .merge(inference)
+                .merge(admin)
+                .layer(axum::middleware::from_fn_with_state(
+                    scoped_config(),
+                    auth_middleware,
+                ))
+        }
+
+        async fn status_for(path: &str, key: &str) -> StatusCode {
+            let request = Request::builder()
+                .uri(path)
+                .header(API_KEY_HEADER, key)
+                .body(Body::empty())
+                .unwrap();
+            scoped_app().oneshot(request).await.unwrap().status()
+        }
+
+        #[tokio::test]
+        async fn test_inference_key_forbidden_on_admin_route() {
+            assert_eq!(
+                status_for("/v1/chat/completions", "app-key").await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                status_for("/admin/stats", "app-key").await,
+                StatusCode::FORBIDDEN
+            );
+        }
+
+        #[tokio::test]
+        async fn test_admin_key_allowed_on_admin_route() {
+            assert_eq!(status_for("/admin/stats", "ops-key").await, StatusCode::OK);
+            assert_eq!(
+                status_for("/v1/chat/completions", "ops-key").await,
+                StatusCode::OK
+            );
+        }
+
+        #[tokio::test]
+        async fn test_unscoped_and_invalid_keys() {
+            // Keys without a scope mapping may call inference but not admin
+            assert_eq!(
+                status_for("/v1/chat/completions", "legacy-key").await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                status_for("/admin/stats", "legacy-key").await,
+                StatusCode::FORBIDDEN
+            );
+            assert_eq!(
+                status_for("/admin/stats", "wrong-key").await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        fn key_source_app(allow_query_api_key: bool) -> Router {
+            let mut config = scoped_config();
+            config.auth.allow_query_api_key = allow_query_api_key;
+
+            Router::new()
+                .route("/v1/chat/completions", get(|| async { "ok" }))
+                .layer(axum::middleware::from_fn_with_state(
+                    config,
+                    auth_middleware,
+                ))
+        }
+
+        async fn key_source_status(app: Router, uri: &str, headers: &[(&str, &str)]) -> StatusCode {
+            let mut request = Request::builder().uri(uri);
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            let request = request.body(Body::empty()).unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+
+        #[tokio::test]
+        async fn test_key_accepted_from_each_header() {
+            for header in [
+                ("authorization", "Bearer legacy-key"),
+                (API_KEY_HEADER, "legacy-key"),
+                (AZURE_API_KEY_HEADER, "legacy-key"),
+            ] {
+                assert_eq!(
+                    key_source_status(key_source_app(false), "/v1/chat/completions", &[header])
+                        .await,
+                    StatusCode::OK,
+                    "{}",
+                    header.0
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn test_bearer_key_checked_before_api_key_header() {
+            assert_eq!(
+                key_source_status(
+                    key_source_app(false),
+                    "/v1/chat/completions",
+                    &[
+                        ("authorization", "Bearer wrong-key"),
+                        (AZURE_API_KEY_HEADER, "legacy-key"),
+                    ],
+                )
+                .await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        #[tokio::test]
+        async fn test_query_param_key_requires_flag() {
+            let uri = "/v1/chat/completions?api_key=legacy-key";
+
+            assert_eq!(
+                key_source_status(key_source_app(false), uri, &[]).await,
+                StatusCode::UNAUTHORIZED
+            );
+            assert_eq!(
+                key_source_status(key_source_app(true), uri, &[]).await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                key_source_status(
+                    key_source_app(true),
+                    "/v1/chat/completions?api_key=wrong-key",
+                    &[],
+                )
+                .await,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+    }
+
+    mod backend_outage {
+        use super::super::*;
+        use axum::{body::Body, http::StatusCode, routing::get};
+        use tower::ServiceExt;
+
+        /// Verifier whose JWKS endpoint is unreachable
+        struct JwksDown;
+
+        #[async_trait]
+        impl TokenVerifier for JwksDown {
+            async fn verify(&self, _token: &str) -> Result<GrantedScopes, BackendAuthError> {
+                Err(BackendAuthError::Unavailable(
+                    "JWKS fetch failed: connection refused".to_string(),
+                ))
+            }
+        }
+
+        /// Verifier that answers and rejects every token
+        struct RejectAll;
+
+        #[async_trait]
+        impl TokenVerifier for RejectAll {
+            async fn verify(&self, _token: &str) -> Result<GrantedScopes, BackendAuthError> {
+                Err(BackendAuthError::Rejected("Token expired".to_string()))
+            }
+        }
+
+        fn app_with(verifier: Arc<dyn TokenVerifier>, mode: FailMode) -> Router {
+            let mut config = super::scopes::scoped_config();
+            config.auth.on_backend_error = mode;
+
+            let inference = require_scope(
+                Router::new().route("/v1/chat/completions", get(|| async { "ok" })),
+                SCOPE_INFERENCE,
+            );
+            let admin = require_scope(
+                Router::new().route("/admin/stats", get(|| async { "ok" })),
+                SCOPE_ADMIN,
+            );
+
+            Router::new()
+                .merge(inference)
+                .merge(admin)
+                .layer(axum::middleware::from_fn_with_state(
+                    (config, verifier),
+                    auth_middleware_with_verifier,
+                ))
+        }
+
+        async fn status_for(
+            verifier: Arc<dyn TokenVerifier>,
+            mode: FailMode,
+            path: &str,
+        ) -> StatusCode {
+            let request = Request::builder()
+                .uri(path)
+                .header("authorization", "Bearer some.jwt.token")
+                .body(Body::empty())
+                .unwrap();
+            app_with(verifier, mode)
+                .oneshot(request)
+                .await
+                .unwrap()
+                .status()
+        }
+
+        #[tokio::test]
+        async fn test_jwks_outage_fail_closed_is_503() {
+            for path in ["/v1/chat/completions", "/admin/stats"] {
+                assert_eq!(
+                    status_for(Arc::new(JwksDown), FailMode::FailClosed, path).await,
+                    StatusCode::SERVICE_UNAVAILABLE
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn test_jwks_outage_fail_open_allows_inference_only() {
+            assert_eq!(
+                status_for(
+                    Arc::new(JwksDown),
+                    FailMode::FailOpen,
+                    "/v1/chat/completions"
+                )
+                .await,
+                StatusCode::OK
+            );
+            assert_eq!(
+                status_for(Arc::new(JwksDown), FailMode::FailOpen, "/admin/stats").await,
+                StatusCode::FORBIDDEN
+            );
+        }
+
+        #[tokio::test]
+        async fn test_configured_keys_skip_the_verifier() {
+            let request = Request::builder()
+                .uri("/admin/stats")
+                .header(API_KEY_HEADER, "ops-key")
+                .body(Body::empty())
+                .unwrap();
+            let response = app_with(Arc::new(JwksDown), FailMode::FailClosed)
+                .oneshot(request)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        #[tokio::test]
+        async fn test_rejected_token_is_401_in_either_mode() {
+            for mode in [FailMode::FailClosed, FailMode::FailOpen] {
+                assert_eq!(
+                    status_for(Arc::new(RejectAll), mode, "/v1/chat/completions").await,
+                    StatusCode::UNAUTHORIZED
+                );
+            }
+        }
+    }
 }
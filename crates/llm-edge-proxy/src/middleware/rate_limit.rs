@@ -1,38 +1,394 @@
-//! Rate limiting middleware using tower-governor
+//! Rate limiting middleware
 //!
-//! NOTE: This is currently a placeholder. The tower_governor API in version 0.4
-//! requires specific generic type parameters that need to be resolved.
-//! TODO: Implement proper rate limiting once the API is clarified.
+//! A fixed-window limiter with one bucket per API key: each window admits
+//! `requests_per_minute` requests from a key. Requests without an
+//! [`AuthenticatedKey`] (auth disabled) share one bucket. Responses carry
+//! `X-RateLimit-Limit`,
+//! `X-RateLimit-Remaining` and `X-RateLimit-Reset` so clients can throttle
+//! themselves; rejected requests get `429` with `Retry-After`.
+//!
+//! Counts live in this process unless a shared [`RateLimitBackend`] (e.g.
+//! [`RedisRateLimitBackend`] from `RATE_LIMIT_REDIS_URL`, for limits across
+//! instances) is attached. While that backend is
+//! unreachable the limiter falls back to its local window, which is
+//! per-instance and so less strict, and switches back once the backend
+//! answers again.
 
-use tracing::info;
+use async_trait::async_trait;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
+use tracing::{info, warn};
 
+use crate::config::RateLimitConfig;
+use crate::error::ProxyError;
+use crate::middleware::auth::AuthenticatedKey;
 use crate::Config;
 
-/// Create rate limiter layer from configuration
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// Length of a rate limit window for `requests_per_minute`
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Bucket shared by requests without an [`AuthenticatedKey`]
+const ANONYMOUS_BUCKET: &str = "anonymous";
+
+/// Connect and response timeout for the Redis backend, so an outage falls
+/// back to local limits instead of stalling requests
+const REDIS_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Bucket state after admitting (or rejecting) a request
 ///
-/// NOTE: Currently returns a no-op layer. Rate limiting should be implemented
-/// using tower_governor once the API compatibility issues are resolved.
-pub fn create_rate_limiter(
-    _config: &Config,
-) -> tower::util::BoxCloneService<
-    axum::http::Request<axum::body::Body>,
-    axum::response::Response,
-    std::convert::Infallible,
-> {
-    info!("Rate limiting configuration loaded (currently disabled - TODO)");
-
-    // Return a passthrough service
-    use tower::ServiceExt;
-    tower::service_fn(|_req: axum::http::Request<axum::body::Body>| async move {
-        Ok::<_, std::convert::Infallible>(axum::response::Response::new(axum::body::Body::empty()))
-    })
-    .map_request(|req| req)
-    .boxed_clone()
+/// Inserted into request extensions by [`rate_limit_middleware`] so handlers
+/// can see the caller's remaining quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Requests allowed per window
+    pub limit: u32,
+    /// Requests left in the current window
+    pub remaining: u32,
+    /// Time until the window resets
+    pub reset_after: Duration,
+}
+
+impl RateLimitStatus {
+    /// Add the `X-RateLimit-*` headers
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(
+            RATE_LIMIT_REMAINING_HEADER,
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            RATE_LIMIT_RESET_HEADER,
+            HeaderValue::from(self.reset_seconds()),
+        );
+    }
+
+    /// Seconds until the window resets, rounded up
+    pub fn reset_seconds(&self) -> u64 {
+        self.reset_after.as_millis().div_ceil(1000) as u64
+    }
+}
+
+/// A shared rate limit backend could not be reached
+#[derive(Debug, Clone)]
+pub struct RateLimitBackendError(pub String);
+
+impl std::fmt::Display for RateLimitBackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Store for request counts shared between instances
+#[async_trait]
+pub trait RateLimitBackend: Send + Sync {
+    /// Count a request against `key`'s current window of `limit` requests per `window`
+    ///
+    /// Returns the bucket state and whether the request is admitted.
+    async fn check(
+        &self,
+        key: &str,
+        limit: u32,
+        window: Duration,
+    ) -> Result<(RateLimitStatus, bool), RateLimitBackendError>;
+}
+
+/// [`RateLimitBackend`] keeping counts in Redis, shared by every instance
+///
+/// Each key's window is a counter, `ratelimit:{key}:{window number}`, that
+/// expires with the window. The connection is opened on first use and
+/// reopened after a failure, so the limiter leaves its local fallback once
+/// Redis is reachable again.
+pub struct RedisRateLimitBackend {
+    client: redis::Client,
+    connection: tokio::sync::Mutex<Option<redis::aio::MultiplexedConnection>>,
+}
+
+impl RedisRateLimitBackend {
+    /// Backend for the Redis at `url`; does not connect yet
+    pub fn open(url: &str) -> Result<Self, RateLimitBackendError> {
+        let client = redis::Client::open(url).map_err(|e| RateLimitBackendError(e.to_string()))?;
+        Ok(Self {
+            client,
+            connection: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    async fn connection(&self) -> redis::RedisResult<redis::aio::MultiplexedConnection> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            return Ok(connection.clone());
+        }
+
+        let config = redis::AsyncConnectionConfig::new()
+            .set_connection_timeout(REDIS_TIMEOUT)
+            .set_response_timeout(REDIS_TIMEOUT);
+        let fresh = self
+            .client
+            .get_multiplexed_async_connection_with_config(&config)
+            .await?;
+        *connection = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    async fn increment(&self, key: &str, window: Duration) -> redis::RedisResult<u32> {
+        let mut connection = self.connection().await?;
+        let window_ms = window.as_millis().max(1) as u64;
+        let (count,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(key, 1)
+            .pexpire(key, window_ms as i64)
+            .ignore()
+            .query_async(&mut connection)
+            .await?;
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl RateLimitBackend for RedisRateLimitBackend {
+    async fn check(
+        &self,
+        key: &str,
+        limit: u32,
+        window: Duration,
+    ) -> Result<(RateLimitStatus, bool), RateLimitBackendError> {
+        // Windows are aligned to the epoch so every instance agrees on them
+        let window_ms = window.as_millis().max(1);
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let redis_key = format!("ratelimit:{}:{}", key, now_ms / window_ms);
+
+        let count = match self.increment(&redis_key, window).await {
+            Ok(count) => count,
+            Err(e) => {
+                // Reconnect on the next request
+                self.connection.lock().await.take();
+                return Err(RateLimitBackendError(e.to_string()));
+            }
+        };
+
+        let status = RateLimitStatus {
+            limit,
+            remaining: limit.saturating_sub(count),
+            reset_after: Duration::from_millis((window_ms - now_ms % window_ms) as u64),
+        };
+        Ok((status, count <= limit))
+    }
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Fixed-window request limiter, one bucket per key
+///
+/// Clones share the same buckets.
+#[derive(Clone)]
+pub struct RateLimiter {
+    enabled: bool,
+    limit: u32,
+    window: Duration,
+    buckets: Arc<Mutex<HashMap<String, Window>>>,
+    backend: Option<Arc<dyn RateLimitBackend>>,
+    /// Set while `backend` is unreachable and the local window is in use
+    degraded: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("enabled", &self.enabled)
+            .field("limit", &self.limit)
+            .field("window", &self.window)
+            .field("shared_backend", &self.backend.is_some())
+            .field("degraded", &self.is_degraded())
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// Limiter admitting `limit` requests per `window` from each key
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            enabled: true,
+            limit,
+            window,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            backend: None,
+            degraded: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Keep counts in `backend`, falling back to the local window while it is down
+    pub fn with_backend(mut self, backend: Arc<dyn RateLimitBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Whether the shared backend is down and the local window is in use
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire)
+    }
+
+    /// Limiter for `config`; a disabled config admits everything without headers
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            ..Self::new(config.requests_per_minute, WINDOW)
+        }
+    }
+
+    /// Count a request from `key` in the shared backend, or locally if there is none
+    ///
+    /// A backend failure is counted in `rate_limit_fallback_local_total` and
+    /// the request is checked against the local window instead.
+    pub async fn admit(&self, key: &str) -> (RateLimitStatus, bool) {
+        let Some(backend) = &self.backend else {
+            return self.check(key);
+        };
+
+        match backend.check(key, self.limit, self.window).await {
+            Ok(result) => {
+                if self.degraded.swap(false, Ordering::AcqRel) {
+                    info!("Rate limit backend recovered, leaving local fallback");
+                }
+                result
+            }
+            Err(e) => {
+                if !self.degraded.swap(true, Ordering::AcqRel) {
+                    warn!(
+                        error = %e,
+                        "Rate limit backend unavailable, falling back to per-instance limits"
+                    );
+                }
+                metrics::counter!("rate_limit_fallback_local_total").increment(1);
+                self.check(key)
+            }
+        }
+    }
+
+    /// Count a request from `key` against its local window
+    ///
+    /// Returns the bucket state and whether the request is admitted.
+    pub fn check(&self, key: &str) -> (RateLimitStatus, bool) {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+
+        if !buckets.contains_key(key) {
+            // Forget keys whose window has run out before tracking a new one
+            buckets.retain(|_, window| now.duration_since(window.started) < self.window);
+        }
+        let window = buckets.entry(key.to_string()).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+
+        if now.duration_since(window.started) >= self.window {
+            window.started = now;
+            window.count = 0;
+        }
+
+        let admitted = window.count < self.limit;
+        if admitted {
+            window.count += 1;
+        }
+
+        let status = RateLimitStatus {
+            limit: self.limit,
+            remaining: self.limit - window.count,
+            reset_after: self.window - now.duration_since(window.started),
+        };
+        (status, admitted)
+    }
+}
+
+/// Create rate limiter from configuration
+///
+/// With `redis_url` set, counts are kept in Redis and fall back to this
+/// instance while it is unreachable.
+pub fn create_rate_limiter(config: &Config) -> RateLimiter {
+    info!(
+        enabled = config.rate_limit.enabled,
+        requests_per_minute = config.rate_limit.requests_per_minute,
+        shared = config.rate_limit.redis_url.is_some(),
+        "Rate limiting configuration loaded"
+    );
+    let limiter = RateLimiter::from_config(&config.rate_limit);
+
+    let Some(url) = &config.rate_limit.redis_url else {
+        return limiter;
+    };
+    match RedisRateLimitBackend::open(url) {
+        Ok(backend) => limiter.with_backend(Arc::new(backend)),
+        Err(e) => {
+            warn!(error = %e, "Invalid RATE_LIMIT_REDIS_URL, using per-instance rate limits");
+            limiter
+        }
+    }
+}
+
+/// Rate limiting middleware
+///
+/// Counts the request against the bucket of its [`AuthenticatedKey`], so it
+/// must sit inside the auth middleware. Adds the `X-RateLimit-*` headers to
+/// every response, error responses from inner layers included.
+pub async fn rate_limit_middleware(
+    State(limiter): State<RateLimiter>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !limiter.enabled {
+        return next.run(request).await;
+    }
+
+    let key = request
+        .extensions()
+        .get::<AuthenticatedKey>()
+        .map_or(ANONYMOUS_BUCKET, |key| key.0.as_str())
+        .to_string();
+    let (status, admitted) = limiter.admit(&key).await;
+
+    let mut response = if admitted {
+        request.extensions_mut().insert(status);
+        next.run(request).await
+    } else {
+        warn!(path = %request.uri().path(), "Rate limit exceeded");
+        let mut response =
+            ProxyError::RateLimit(format!("Limit of {} requests reached", status.limit))
+                .into_response();
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(status.reset_seconds()),
+        );
+        response
+    };
+
+    status.apply(response.headers_mut());
+    response
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
 
     #[test]
     fn test_rate_limit_config() {
@@ -49,17 +405,24 @@ mod tests {
                 enabled: true,
                 requests_per_minute: 100,
                 burst_size: 10,
+                redis_url: None,
             },
             auth: crate::config::AuthConfig {
                 enabled: false,
                 api_keys: vec![],
                 require_auth_for_health: false,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
             },
             observability: crate::config::ObservabilityConfig {
                 enable_tracing: false,
                 enable_metrics: false,
                 log_level: "info".to_string(),
                 otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
             },
         };
 
@@ -82,21 +445,264 @@ mod tests {
                 enabled: false,
                 requests_per_minute: 100,
                 burst_size: 10,
+                redis_url: None,
             },
             auth: crate::config::AuthConfig {
                 enabled: false,
                 api_keys: vec![],
                 require_auth_for_health: false,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
             },
             observability: crate::config::ObservabilityConfig {
                 enable_tracing: false,
                 enable_metrics: false,
                 log_level: "info".to_string(),
                 otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
             },
         };
 
         let _layer = create_rate_limiter(&config);
         // Should create very permissive limiter
     }
+
+    fn app(limiter: RateLimiter) -> Router {
+        Router::new().route("/", get(|| async { "ok" })).layer(
+            axum::middleware::from_fn_with_state(limiter, rate_limit_middleware),
+        )
+    }
+
+    async fn send(app: &Router) -> Response {
+        app.clone()
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn header(response: &Response, name: &str) -> u64 {
+        response.headers()[name].to_str().unwrap().parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_remaining_decrements_then_rejects() {
+        let app = app(RateLimiter::new(2, Duration::from_secs(60)));
+
+        let first = send(&app).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(header(&first, RATE_LIMIT_LIMIT_HEADER), 2);
+        assert_eq!(header(&first, RATE_LIMIT_REMAINING_HEADER), 1);
+        assert_eq!(header(&first, RATE_LIMIT_RESET_HEADER), 60);
+
+        let second = send(&app).await;
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(header(&second, RATE_LIMIT_REMAINING_HEADER), 0);
+
+        let third = send(&app).await;
+        assert_eq!(third.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&third, RATE_LIMIT_REMAINING_HEADER), 0);
+        assert!(header(&third, "retry-after") <= 60);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_remaining_resets_after_window() {
+        let app = app(RateLimiter::new(2, Duration::from_millis(100)));
+
+        send(&app).await;
+        let exhausted = send(&app).await;
+        assert_eq!(header(&exhausted, RATE_LIMIT_REMAINING_HEADER), 0);
+
+        tokio::time::advance(Duration::from_millis(150)).await;
+
+        let fresh = send(&app).await;
+        assert_eq!(fresh.status(), StatusCode::OK);
+        assert_eq!(header(&fresh, RATE_LIMIT_REMAINING_HEADER), 1);
+    }
+
+    async fn send_as(app: &Router, key: &str) -> Response {
+        let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(AuthenticatedKey(key.to_string()));
+        app.clone().oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_each_key_has_its_own_bucket() {
+        let app = app(RateLimiter::new(1, Duration::from_secs(60)));
+
+        assert_eq!(send_as(&app, "key-a").await.status(), StatusCode::OK);
+        let limited = send_as(&app, "key-a").await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let other = send_as(&app, "key-b").await;
+        assert_eq!(other.status(), StatusCode::OK);
+        assert_eq!(header(&other, RATE_LIMIT_REMAINING_HEADER), 0);
+        assert_eq!(send(&app).await.status(), StatusCode::OK);
+    }
+
+    /// Shared backend that can be switched off, admitting everything while up
+    #[derive(Default)]
+    struct FlakyBackend {
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl RateLimitBackend for FlakyBackend {
+        async fn check(
+            &self,
+            _key: &str,
+            limit: u32,
+            window: Duration,
+        ) -> Result<(RateLimitStatus, bool), RateLimitBackendError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(RateLimitBackendError("connection refused".to_string()));
+            }
+            let status = RateLimitStatus {
+                limit,
+                remaining: limit,
+                reset_after: window,
+            };
+            Ok((status, true))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backend_outage_falls_back_to_local_limit() {
+        let backend = Arc::new(FlakyBackend::default());
+        backend.down.store(true, Ordering::SeqCst);
+        let limiter = RateLimiter::new(2, Duration::from_secs(60)).with_backend(backend.clone());
+        let app = app(limiter.clone());
+
+        // Still limited, by this instance's own window
+        assert_eq!(send(&app).await.status(), StatusCode::OK);
+        assert_eq!(send(&app).await.status(), StatusCode::OK);
+        assert!(limiter.is_degraded());
+        let limited = send(&app).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&limited, RATE_LIMIT_REMAINING_HEADER), 0);
+
+        // Back on the shared backend once it answers again
+        backend.down.store(false, Ordering::SeqCst);
+        let recovered = send(&app).await;
+        assert_eq!(recovered.status(), StatusCode::OK);
+        assert_eq!(header(&recovered, RATE_LIMIT_REMAINING_HEADER), 2);
+        assert!(!limiter.is_degraded());
+    }
+
+    #[test]
+    fn test_backend_fallback_is_counted() {
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        let backend = Arc::new(FlakyBackend::default());
+        backend.down.store(true, Ordering::SeqCst);
+        let limiter = RateLimiter::new(5, Duration::from_secs(60)).with_backend(backend);
+
+        // The local recorder is per-thread, so drive the limiter on this one
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            runtime.block_on(async {
+                limiter.admit("key-a").await;
+                limiter.admit("key-a").await;
+            })
+        });
+
+        assert!(handle
+            .render()
+            .contains("rate_limit_fallback_local_total 2"));
+    }
+
+    /// Two requests per minute, counted in the Redis at `redis_url`
+    fn config_with_redis(redis_url: &str) -> Config {
+        Config {
+            server: crate::config::ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: crate::config::RateLimitConfig {
+                enabled: true,
+                requests_per_minute: 2,
+                burst_size: 10,
+                redis_url: Some(redis_url.to_string()),
+            },
+            auth: crate::config::AuthConfig {
+                enabled: false,
+                api_keys: vec![],
+                require_auth_for_health: false,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: crate::config::ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_redis_falls_back_to_local_limit() {
+        // Nothing listens on port 1
+        let limiter = create_rate_limiter(&config_with_redis("redis://127.0.0.1:1"));
+        let app = app(limiter.clone());
+
+        assert_eq!(send(&app).await.status(), StatusCode::OK);
+        assert!(limiter.is_degraded());
+        assert_eq!(send(&app).await.status(), StatusCode::OK);
+        assert_eq!(send(&app).await.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_redis_counts_shared_between_instances() {
+        let config = config_with_redis("redis://127.0.0.1:6379");
+        let key = format!("key-{}", uuid::Uuid::new_v4());
+        let first = app(create_rate_limiter(&config));
+        let second = app(create_rate_limiter(&config));
+
+        assert_eq!(send_as(&first, &key).await.status(), StatusCode::OK);
+        assert_eq!(send_as(&second, &key).await.status(), StatusCode::OK);
+        let limited = send_as(&first, &key).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&limited, RATE_LIMIT_REMAINING_HEADER), 0);
+    }
+
+    #[tokio::test]
+    async fn test_status_exposed_to_handlers() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(
+                    |axum::Extension(status): axum::Extension<RateLimitStatus>| async move {
+                        status.remaining.to_string()
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                RateLimiter::new(5, Duration::from_secs(60)),
+                rate_limit_middleware,
+            ));
+
+        let response = send(&app).await;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"4");
+    }
 }
@@ -0,0 +1,187 @@
+//! Request ID propagation
+//!
+//! Every request gets one id, used for the request span, the logs and the
+//! `X-Request-Id` response header. A client- or gateway-supplied
+//! `X-Request-Id` is reused when it passes [`is_valid_request_id`] and
+//! inbound ids are trusted; otherwise a UUID is generated. The resolved id
+//! replaces the inbound header, so handlers read it from `X-Request-Id`.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{debug, Instrument};
+use uuid::Uuid;
+
+use crate::Config;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest inbound request id that is reused
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id of the current request, inserted into request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Whether client-supplied request ids are honoured
+#[derive(Debug, Clone, Copy)]
+pub struct RequestIdPolicy {
+    /// Reuse a valid inbound `X-Request-Id` instead of generating one
+    pub trust_inbound: bool,
+}
+
+impl RequestIdPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            trust_inbound: config.observability.trust_request_id,
+        }
+    }
+}
+
+/// Whether `id` is short and only uses `[A-Za-z0-9._:-]`
+///
+/// Keeps ids safe to log and to echo back in a header.
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// The valid `X-Request-Id` in `headers`, or a new UUID
+pub fn request_id_from_headers(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Request ID middleware
+///
+/// Resolves the id, rewrites the request's `X-Request-Id` to it, runs the
+/// rest of the stack inside a `request` span carrying it and echoes it on
+/// the response. The span's `log_sampled` field is filled in by
+/// [`access_log_middleware`](super::access_log_middleware).
+pub async fn request_id_middleware(
+    State(policy): State<RequestIdPolicy>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !policy.trust_inbound {
+        request.headers_mut().remove(REQUEST_ID_HEADER);
+    } else if let Some(rejected) = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|v| !v.to_str().is_ok_and(is_valid_request_id))
+    {
+        debug!(
+            length = rejected.len(),
+            "Ignoring invalid inbound X-Request-Id"
+        );
+    }
+
+    let id = request_id_from_headers(request.headers());
+    // Only valid ids get here, so this always converts
+    let value = HeaderValue::from_str(&id).expect("request id is a valid header value");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, value.clone());
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        log_sampled = tracing::field::Empty
+    );
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    /// Echoes the id seen by the handler as `header|extension`
+    fn app(trust_inbound: bool) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(
+                    |headers: HeaderMap, Extension(id): Extension<RequestId>| async move {
+                        format!("{}|{}", request_id_from_headers(&headers), id.0)
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                RequestIdPolicy { trust_inbound },
+                request_id_middleware,
+            ))
+    }
+
+    /// Send a request with an optional inbound id; returns (response id, handler view)
+    async fn send(app: Router, inbound: Option<&str>) -> (String, String) {
+        let mut request = Request::builder().uri("/");
+        if let Some(id) = inbound {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (header, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_inbound_request_id_is_reused() {
+        let (header, seen) = send(app(true), Some("gw-7f3a.42:1")).await;
+
+        assert_eq!(header, "gw-7f3a.42:1");
+        assert_eq!(seen, "gw-7f3a.42:1|gw-7f3a.42:1");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_inbound_request_id_is_replaced() {
+        let too_long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        for inbound in ["bad id; drop table", "", too_long.as_str()] {
+            let (header, seen) = send(app(true), Some(inbound)).await;
+
+            assert_ne!(header, inbound);
+            assert!(Uuid::parse_str(&header).is_ok());
+            assert_eq!(seen, format!("{}|{}", header, header));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_inbound_request_id_is_replaced() {
+        let (header, seen) = send(app(false), Some("client-chosen-id")).await;
+
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(seen, format!("{}|{}", header, header));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_absent() {
+        let (first, _) = send(app(true), None).await;
+        let (second, _) = send(app(true), None).await;
+
+        assert!(Uuid::parse_str(&first).is_ok());
+        assert_ne!(first, second);
+    }
+}
@@ -6,18 +6,40 @@ pub mod routes;
 pub mod tls;
 pub mod tracing;
 
-use crate::config::Config;
+use crate::config::{Config, ServerConfig};
 use crate::error::ProxyError;
 use crate::middleware;
 use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, Method},
     routing::{get, post},
     Router,
 };
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
 use std::net::SocketAddr;
-use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{Any, CorsLayer},
+    decompression::RequestDecompressionLayer,
+    limit::RequestBodyLimitLayer,
+    trace::TraceLayer,
+};
 
 /// Build the Axum application with all middleware and routes
 pub async fn build_app(config: Config) -> Result<Router, ProxyError> {
+    // Protected proxy endpoints
+    let inference = middleware::require_scope(
+        Router::new()
+            .route("/v1/chat/completions", post(routes::chat_completions))
+            .route("/v1/completions", post(routes::completions)),
+        middleware::SCOPE_INFERENCE,
+    );
+
     // Build the router
     let app = Router::new()
         // Health check endpoints (no auth required by default)
@@ -26,27 +48,71 @@ pub async fn build_app(config: Config) -> Result<Router, ProxyError> {
         .route("/health/live", get(routes::liveness_check))
         // Metrics endpoint (no auth required)
         .route("/metrics", get(routes::metrics))
-        // Protected proxy endpoints
-        .route("/v1/chat/completions", post(routes::chat_completions))
-        .route("/v1/completions", post(routes::completions))
-        // NOTE: Rate limiting temporarily disabled due to tower_governor API compatibility
-        // TODO: Re-enable rate limiting once tower_governor integration is fixed
-        // .layer(middleware::create_rate_limiter(&config))
+        .merge(inference)
+        // JSON 405 for known paths hit with an unsupported method
+        .method_not_allowed_fallback(routes::method_not_allowed)
+        // Cap request bodies at max_request_size *after* decompression so a
+        // small gzip/deflate payload can't expand past the limit
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(config.server.max_request_size))
+        .layer(RequestDecompressionLayer::new())
+        // Rate limiting (inside auth so rejected credentials don't consume quota)
+        .layer(axum::middleware::from_fn_with_state(
+            middleware::create_rate_limiter(&config),
+            middleware::rate_limit_middleware,
+        ))
         // Apply authentication middleware
         .layer(axum::middleware::from_fn_with_state(
             config.clone(),
             middleware::auth_middleware,
         ))
+        // In-flight requests per matched route
+        .layer(axum::middleware::from_fn(middleware::track_active_requests))
         // Apply tower-http middleware
         .layer(TraceLayer::new_for_http())
+        // Sampled access log (inside the request span it records into)
+        .layer(axum::middleware::from_fn_with_state(
+            middleware::AccessLogSampler::from_config(&config),
+            middleware::access_log_middleware,
+        ))
+        // One request id for the trace span, logs and response header
+        .layer(axum::middleware::from_fn_with_state(
+            middleware::RequestIdPolicy::from_config(&config),
+            middleware::request_id_middleware,
+        ))
         .layer(CompressionLayer::new())
-        .layer(CorsLayer::permissive())
+        // Outermost so OPTIONS preflights are answered before auth
+        .layer(cors_layer())
         // Add shared state
         .with_state(config);
 
     Ok(app)
 }
 
+/// CORS policy for browser clients
+///
+/// Answers OPTIONS preflights for every route (including `/v1/*`) and
+/// advertises the methods and headers the proxy actually accepts.
+pub fn cors_layer() -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static("api-key"),
+            HeaderName::from_static("x-provider"),
+            HeaderName::from_static("x-cache-ttl"),
+            HeaderName::from_static("x-request-id"),
+            HeaderName::from_static("traceparent"),
+            HeaderName::from_static("x-deadline"),
+            HeaderName::from_static("x-debug-trace"),
+            HeaderName::from_static("x-routing-strategy"),
+        ])
+        .max_age(Duration::from_secs(3600))
+}
+
 /// Creates the main application router (legacy compatibility)
 pub fn create_router() -> Router {
     Router::new()
@@ -54,12 +120,303 @@ pub fn create_router() -> Router {
         .route("/health/ready", get(routes::readiness_check))
 }
 
-/// Starts the HTTP server
-pub async fn serve(addr: SocketAddr, router: Router) -> anyhow::Result<()> {
-    eprintln!("Starting server on {}", addr);
+/// Starts the server, terminating TLS when `server.enable_tls` is set
+///
+/// TLS settings are validated before binding, so a missing certificate or
+/// key fails startup instead of the first connection.
+pub async fn serve(addr: SocketAddr, router: Router, server: &ServerConfig) -> anyhow::Result<()> {
+    let acceptor = tls::acceptor_from_config(server)?;
+    let listener = TcpListener::bind(addr).await?;
+
+    match acceptor {
+        Some(acceptor) => {
+            ::tracing::info!(%addr, "Starting HTTPS server");
+            serve_tls(listener, router, acceptor).await
+        }
+        None => {
+            ::tracing::info!(%addr, "Starting HTTP server");
+            axum::serve(listener, router).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Serve `router` over TLS on an already-bound listener
+///
+/// Negotiates HTTP/2 or HTTP/1.1 per connection. Failed handshakes are
+/// logged and do not affect other connections. A failed accept is retried
+/// after a short pause.
+pub async fn serve_tls(
+    listener: TcpListener,
+    router: Router,
+    acceptor: TlsAcceptor,
+) -> anyhow::Result<()> {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // Usually out of file descriptors; back off instead of spinning
+                ::tracing::warn!(error = %e, "Failed to accept connection");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(router.clone());
+
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    ::tracing::debug!(%peer, error = %e, "TLS handshake failed");
+                    return;
+                }
+            };
+
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                ::tracing::debug!(%peer, error = %e, "Connection closed with error");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthConfig, ObservabilityConfig, RateLimitConfig, ServerConfig};
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use flate2::{
+        write::{GzEncoder, ZlibEncoder},
+        Compression,
+    };
+    use std::io::Write;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tower::ServiceExt;
+
+    fn chat_body() -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hello"}]
+        }))
+        .unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn compressed_chat_request(encoding: &str, body: Vec<u8>) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_ENCODING, encoding)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn test_config() -> Config {
+        Config {
+            server: ServerConfig {
+                address: "127.0.0.1:8080".to_string(),
+                timeout_seconds: 30,
+                max_request_size: 10485760,
+                enable_tls: false,
+                tls_cert_path: None,
+                tls_key_path: None,
+            },
+            rate_limit: RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 100,
+                burst_size: 10,
+                redis_url: None,
+            },
+            auth: AuthConfig {
+                enabled: false,
+                api_keys: vec![],
+                require_auth_for_health: false,
+                key_scopes: Default::default(),
+                key_models: Default::default(),
+                on_backend_error: Default::default(),
+                allow_query_api_key: false,
+            },
+            observability: ObservabilityConfig {
+                enable_tracing: false,
+                enable_metrics: false,
+                log_level: "info".to_string(),
+                otlp_endpoint: None,
+                trust_request_id: true,
+                access_log_sample_rate: 1.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preflight_options_on_chat_completions() {
+        let app = build_app(test_config()).await.unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/v1/chat/completions")
+                    .header(header::ORIGIN, "https://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(
+                        header::ACCESS_CONTROL_REQUEST_HEADERS,
+                        "authorization,content-type",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(response.status().is_success());
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(methods.contains("POST"));
+        assert!(methods.contains("OPTIONS"));
+        let allowed_headers = headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap();
+        assert!(allowed_headers.contains("authorization"));
+        assert!(allowed_headers.contains("content-type"));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_method_returns_json_405() {
+        let app = build_app(test_config()).await.unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/v1/chat/completions")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "METHOD_NOT_ALLOWED");
+    }
+
+    #[tokio::test]
+    async fn test_gzip_request_body_is_decompressed() {
+        let app = build_app(test_config()).await.unwrap();
+
+        let response = app
+            .oneshot(compressed_chat_request("gzip", gzip(&chat_body())))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["model"], "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_deflate_request_body_is_decompressed() {
+        let app = build_app(test_config()).await.unwrap();
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&chat_body()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let response = app
+            .oneshot(compressed_chat_request("deflate", body))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_decompression_bomb_is_rejected() {
+        let mut config = test_config();
+        config.server.max_request_size = 64 * 1024;
+        let app = build_app(config).await.unwrap();
+
+        // ~8MB of whitespace compresses to a few KB but expands far past the limit
+        let mut payload = chat_body();
+        payload.extend(std::iter::repeat(b' ').take(8 * 1024 * 1024));
+        let compressed = gzip(&payload);
+        assert!(compressed.len() < 64 * 1024);
+
+        let response = app
+            .oneshot(compressed_chat_request("gzip", compressed))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_serve_tls_handshake_with_self_signed_cert() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir().join(format!("llm-edge-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+        let mut config = test_config();
+        config.server.enable_tls = true;
+        config.server.tls_cert_path = Some(cert_path.to_string_lossy().into_owned());
+        config.server.tls_key_path = Some(key_path.to_string_lossy().into_owned());
+
+        let acceptor = tls::acceptor_from_config(&config.server).unwrap().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = build_app(config).await.unwrap();
+        tokio::spawn(serve_tls(listener, app, acceptor));
+
+        // Client trusting only the self-signed certificate
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert.cert.der().clone()).unwrap();
+        let client_config = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::aws_lc_rs::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client_config));
+
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let server_name = rustls::pki_types::ServerName::try_from("localhost").unwrap();
+        let mut stream = connector.connect(server_name, tcp).await.unwrap();
+
+        stream
+            .write_all(b"GET /health/live HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, router).await?;
+        let response = String::from_utf8_lossy(&response);
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
 
-    Ok(())
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -2,7 +2,7 @@
 
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{Method, StatusCode, Uri},
     response::{IntoResponse, Response},
     Json,
 };
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tracing::{info, instrument};
 
-use crate::error::ProxyResult;
+use crate::error::{ProxyError, ProxyResult};
 use crate::Config;
 
 /// Health check response
@@ -202,6 +202,11 @@ pub async fn completions(
     })))
 }
 
+/// Fallback for routes that exist but don't support the request method
+pub async fn method_not_allowed(method: Method, uri: Uri) -> ProxyError {
+    ProxyError::MethodNotAllowed(format!("{} is not supported on {}", method, uri.path()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
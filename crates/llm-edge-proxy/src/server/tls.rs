@@ -5,6 +5,7 @@ use rustls::ServerConfig;
 use rustls_pemfile::{certs, pkcs8_private_keys};
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
 use std::sync::Arc;
 use tokio_rustls::TlsAcceptor;
 use tracing::info;
@@ -43,11 +44,17 @@ pub fn load_tls_config(cert_path: &str, key_path: &str) -> Result<Arc<ServerConf
 
     let key = keys.remove(0);
 
-    // Build TLS config
-    let config = ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(cert_chain, key.into())
-        .context("Failed to build TLS configuration")?;
+    // Build TLS config. The provider is explicit because more than one
+    // rustls crypto backend is enabled in the dependency graph.
+    let mut config = ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .context("Failed to select TLS protocol versions")?
+    .with_no_client_auth()
+    .with_single_cert(cert_chain, key.into())
+    .context("Failed to build TLS configuration")?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
 
     info!("TLS configuration loaded successfully");
     Ok(Arc::new(config))
@@ -59,10 +66,69 @@ pub fn create_tls_acceptor(cert_path: &str, key_path: &str) -> Result<TlsAccepto
     Ok(TlsAcceptor::from(config))
 }
 
+/// Create a TLS acceptor from server configuration
+///
+/// Returns `None` when TLS is disabled. When enabled, the certificate and key
+/// paths must be set and point at existing files.
+pub fn acceptor_from_config(server: &crate::config::ServerConfig) -> Result<Option<TlsAcceptor>> {
+    if !server.enable_tls {
+        return Ok(None);
+    }
+
+    let cert_path = server
+        .tls_cert_path
+        .as_deref()
+        .context("TLS is enabled but TLS_CERT_PATH is not set")?;
+    let key_path = server
+        .tls_key_path
+        .as_deref()
+        .context("TLS is enabled but TLS_KEY_PATH is not set")?;
+
+    if !Path::new(cert_path).is_file() {
+        anyhow::bail!("TLS certificate file does not exist: {}", cert_path);
+    }
+    if !Path::new(key_path).is_file() {
+        anyhow::bail!("TLS private key file does not exist: {}", key_path);
+    }
+
+    create_tls_acceptor(cert_path, key_path).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn server_config(cert: Option<&str>, key: Option<&str>) -> crate::config::ServerConfig {
+        crate::config::ServerConfig {
+            address: "127.0.0.1:0".to_string(),
+            timeout_seconds: 30,
+            max_request_size: 10485760,
+            enable_tls: true,
+            tls_cert_path: cert.map(str::to_string),
+            tls_key_path: key.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_acceptor_from_config_disabled() {
+        let mut config = server_config(None, None);
+        config.enable_tls = false;
+        assert!(acceptor_from_config(&config).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_acceptor_from_config_reports_missing_paths() {
+        let error = |config| match acceptor_from_config(&config) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        };
+
+        assert!(error(server_config(None, Some("key.pem"))).contains("TLS_CERT_PATH"));
+        assert!(
+            error(server_config(Some("missing.crt"), Some("missing.key"))).contains("missing.crt")
+        );
+    }
+
     #[test]
     fn test_tls_config_missing_file() {
         let result = load_tls_config("nonexistent.crt", "nonexistent.key");
@@ -1,3 +1,4 @@
+use llm_edge_providers::ProviderError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +9,12 @@ pub enum RoutingError {
     #[error("All providers failed")]
     AllProvidersFailed,
 
+    #[error("No provider serves model: {0}")]
+    UnsupportedModel(String),
+
+    #[error("Provider error: {0}")]
+    Provider(#[from] ProviderError),
+
     #[error("Circuit breaker open for provider: {0}")]
     CircuitBreakerOpen(String),
 
@@ -6,12 +6,15 @@
 //! - Hybrid routing (multi-factor scoring)
 //! - Circuit breakers
 //! - Fallback chains
+//! - Model-family aware failover
 
 pub mod circuit_breaker;
 pub mod error;
+pub mod model_family;
 pub mod strategy;
 
 pub use error::{RoutingError, RoutingResult};
+pub use model_family::{ModelFamilyRouter, TranslatedModel};
 pub use strategy::{RoutingDecision, RoutingStrategy};
 
 #[cfg(test)]
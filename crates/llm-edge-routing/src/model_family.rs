@@ -0,0 +1,413 @@
+//! Model-family aware failover
+//!
+//! Failing over to "the next provider" only works if that provider can serve
+//! the same model. A degraded OpenAI can hand `gpt-4` to an Azure OpenAI
+//! deployment of `gpt-4`, but not to Anthropic. [`ModelFamilyRouter`] knows
+//! which providers serve which model families, and under which model id, and
+//! only spills a request over to providers serving its family.
+//!
+//! [`TranslatedModel`] lets a caller doing its own failover send a request
+//! to one of those providers under the model id the provider knows it by.
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use llm_edge_providers::{
+    adapter::{HealthStatus, ModelCapabilities, PricingInfo},
+    ChunkStream, LLMProvider, ProviderError, ProviderResult, RateLimitState, UnifiedRequest,
+    UnifiedResponse,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::{RoutingError, RoutingResult};
+
+/// A provider and the model families it serves
+struct FamilyProvider {
+    provider: Arc<dyn LLMProvider>,
+    /// Family name -> model id used with this provider
+    models: HashMap<String, String>,
+}
+
+/// Dispatches requests to the providers serving their model family
+///
+/// Providers are tried in registration order, so register the primary for a
+/// family first.
+#[derive(Default)]
+pub struct ModelFamilyRouter {
+    providers: Vec<FamilyProvider>,
+}
+
+impl ModelFamilyRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a provider with the families it serves, as
+    /// `(family, model id)` pairs
+    ///
+    /// The model id is what the provider is sent, e.g. an Azure deployment
+    /// name for the `gpt-4` family.
+    pub fn with_provider<F, M>(
+        mut self,
+        provider: Arc<dyn LLMProvider>,
+        models: impl IntoIterator<Item = (F, M)>,
+    ) -> Self
+    where
+        F: Into<String>,
+        M: Into<String>,
+    {
+        self.providers.push(FamilyProvider {
+            provider,
+            models: models
+                .into_iter()
+                .map(|(family, model)| (family.into(), model.into()))
+                .collect(),
+        });
+        self
+    }
+
+    /// Whether no provider is registered
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Registered provider named `name`
+    pub fn provider(&self, name: &str) -> Option<Arc<dyn LLMProvider>> {
+        self.providers
+            .iter()
+            .find(|p| p.provider.name() == name)
+            .map(|p| p.provider.clone())
+    }
+
+    /// Family of `model`, given either the family name or a provider's
+    /// model id for it
+    pub fn family_of(&self, model: &str) -> Option<&str> {
+        self.providers
+            .iter()
+            .flat_map(|p| p.models.iter())
+            .find(|(family, id)| family.as_str() == model || id.as_str() == model)
+            .map(|(family, _)| family.as_str())
+    }
+
+    /// Providers able to serve `model` in the order they are tried, with the
+    /// model id each is sent
+    pub fn candidates(&self, model: &str) -> Vec<(&str, &str)> {
+        let Some(family) = self.family_of(model) else {
+            return Vec::new();
+        };
+
+        self.providers
+            .iter()
+            .filter_map(|p| {
+                let id = p.models.get(family)?;
+                Some((p.provider.name(), id.as_str()))
+            })
+            .collect()
+    }
+
+    /// Send `request` to the first provider of its family that answers
+    ///
+    /// Retryable failures spill over to the next provider of the family;
+    /// other errors are returned as they are, since another provider would
+    /// reject the request too. A model no registered provider serves fails
+    /// with [`RoutingError::UnsupportedModel`] without sending anything, and
+    /// once every candidate has failed the last error is returned.
+    pub async fn dispatch(&self, request: UnifiedRequest) -> RoutingResult<UnifiedResponse> {
+        let family = self
+            .family_of(&request.model)
+            .ok_or_else(|| RoutingError::UnsupportedModel(request.model.clone()))?;
+
+        let mut last_error: Option<ProviderError> = None;
+        for candidate in &self.providers {
+            let Some(model) = candidate.models.get(family) else {
+                continue;
+            };
+            let provider = candidate.provider.name();
+            if let Some(previous) = &last_error {
+                warn!(
+                    provider,
+                    model = %model,
+                    family,
+                    error = %previous,
+                    "Spilling request over to another provider of the model family"
+                );
+            }
+
+            let mut attempt = request.clone();
+            attempt.model = model.clone();
+            match candidate.provider.send(attempt).await {
+                Ok(response) => return Ok(response),
+                Err(e) if e.is_retryable() => {
+                    debug!(provider, family, error = %e, "Provider failed; trying the next one");
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(last_error.map_or(RoutingError::NoProvidersAvailable, RoutingError::from))
+    }
+}
+
+/// A provider sent a fixed model id in place of the model a request names
+///
+/// Like an adapter's model map: pricing, output limits and capabilities are
+/// looked up by the translated id, while responses keep the name the client
+/// asked for.
+pub struct TranslatedModel {
+    inner: Arc<dyn LLMProvider>,
+    model: String,
+}
+
+impl TranslatedModel {
+    /// `inner` sending every request as `model`
+    pub fn wrap(inner: Arc<dyn LLMProvider>, model: impl Into<String>) -> Arc<dyn LLMProvider> {
+        Arc::new(Self {
+            inner,
+            model: model.into(),
+        })
+    }
+
+    /// `request` with the translated model, and the model it named
+    fn translate(&self, mut request: UnifiedRequest) -> (UnifiedRequest, String) {
+        let requested = std::mem::replace(&mut request.model, self.model.clone());
+        (request, requested)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for TranslatedModel {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+        let (request, requested) = self.translate(request);
+        let mut response = self.inner.send(request).await?;
+        response.model = requested;
+        Ok(response)
+    }
+
+    async fn send_stream(&self, request: UnifiedRequest) -> ProviderResult<ChunkStream> {
+        let (request, requested) = self.translate(request);
+        let stream = self.inner.send_stream(request).await?;
+        Ok(Box::pin(stream.map(move |chunk| {
+            chunk.map(|mut chunk| {
+                if chunk.model.is_some() {
+                    chunk.model = Some(requested.clone());
+                }
+                chunk
+            })
+        })))
+    }
+
+    fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+        self.inner.get_pricing(&self.model)
+    }
+
+    fn max_output_tokens(&self, _model: &str) -> Option<u32> {
+        self.inner.max_output_tokens(&self.model)
+    }
+
+    fn list_models(&self) -> Vec<String> {
+        self.inner.list_models()
+    }
+
+    async fn health(&self) -> HealthStatus {
+        self.inner.health().await
+    }
+
+    fn check_request(&self, request: &UnifiedRequest) -> ProviderResult<()> {
+        self.inner.check_request(&self.translate(request.clone()).0)
+    }
+
+    fn rate_limit_state(&self) -> Option<RateLimitState> {
+        self.inner.rate_limit_state()
+    }
+
+    fn rotate_key(&self, new_key: String) -> ProviderResult<()> {
+        self.inner.rotate_key(new_key)
+    }
+
+    fn capabilities(&self, _model: &str) -> Option<ModelCapabilities> {
+        self.inner.capabilities(&self.model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use llm_edge_providers::{
+        types::{Choice, ResponseMetadata},
+        Message, Usage,
+    };
+    use parking_lot::Mutex;
+
+    /// Provider recording the model of every request, failing with `error`
+    /// if set
+    struct Probe {
+        name: &'static str,
+        error: Option<fn() -> ProviderError>,
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl Probe {
+        fn new(name: &'static str, error: Option<fn() -> ProviderError>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                error,
+                sent: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LLMProvider for Probe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn send(&self, request: UnifiedRequest) -> ProviderResult<UnifiedResponse> {
+            self.sent.lock().push(request.model.clone());
+            if let Some(error) = self.error {
+                return Err(error());
+            }
+            Ok(UnifiedResponse {
+                id: "resp-1".to_string(),
+                model: request.model,
+                choices: vec![Choice {
+                    index: 0,
+                    message: Message {
+                        role: "assistant".to_string(),
+                        content: "ok".to_string(),
+                    },
+                    finish_reason: None,
+                    native_finish_reason: None,
+                    logprobs: None,
+                    tool_calls: Vec::new(),
+                }],
+                usage: Usage {
+                    prompt_tokens: 1,
+                    completion_tokens: 1,
+                    total_tokens: 2,
+                },
+                metadata: ResponseMetadata {
+                    provider: self.name.to_string(),
+                    cached: false,
+                    latency_ms: 0,
+                    cost_usd: None,
+                    usage_estimated: false,
+                },
+            })
+        }
+
+        fn get_pricing(&self, _model: &str) -> Option<PricingInfo> {
+            None
+        }
+
+        async fn health(&self) -> HealthStatus {
+            HealthStatus::Healthy
+        }
+    }
+
+    fn request(model: &str) -> UnifiedRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Hello"}],
+        }))
+        .unwrap()
+    }
+
+    fn outage() -> ProviderError {
+        ProviderError::ApiError {
+            status: 503,
+            message: "overloaded".to_string(),
+        }
+    }
+
+    fn router(
+        openai: &Arc<Probe>,
+        azure: &Arc<Probe>,
+        anthropic: &Arc<Probe>,
+    ) -> ModelFamilyRouter {
+        ModelFamilyRouter::new()
+            .with_provider(openai.clone(), [("gpt-4", "gpt-4")])
+            .with_provider(azure.clone(), [("gpt-4", "gpt4-prod-deployment")])
+            .with_provider(
+                anthropic.clone(),
+                [("claude-3-opus", "claude-3-opus-20240229")],
+            )
+    }
+
+    #[tokio::test]
+    async fn test_gpt4_spills_to_azure_with_translated_model() {
+        let openai = Probe::new("openai", Some(outage));
+        let azure = Probe::new("azure", None);
+        let anthropic = Probe::new("anthropic", None);
+        let router = router(&openai, &azure, &anthropic);
+
+        assert_eq!(
+            router.candidates("gpt-4"),
+            vec![("openai", "gpt-4"), ("azure", "gpt4-prod-deployment")]
+        );
+
+        let response = router.dispatch(request("gpt-4")).await.unwrap();
+
+        assert_eq!(response.metadata.provider, "azure");
+        assert_eq!(*openai.sent.lock(), vec!["gpt-4"]);
+        assert_eq!(*azure.sent.lock(), vec!["gpt4-prod-deployment"]);
+        assert!(anthropic.sent.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gpt4_never_spills_to_anthropic() {
+        let openai = Probe::new("openai", Some(outage));
+        let azure = Probe::new("azure", Some(outage));
+        let anthropic = Probe::new("anthropic", None);
+        let router = router(&openai, &azure, &anthropic);
+
+        let err = router.dispatch(request("gpt-4")).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            RoutingError::Provider(ProviderError::ApiError { status: 503, .. })
+        ));
+        assert_eq!(azure.sent.lock().len(), 1);
+        assert!(anthropic.sent.lock().is_empty());
+
+        // A family served by nothing fails without sending anywhere
+        let err = router.dispatch(request("gemini-pro")).await.unwrap_err();
+        assert!(matches!(err, RoutingError::UnsupportedModel(model) if model == "gemini-pro"));
+        assert_eq!(openai.sent.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_does_not_spill() {
+        let openai = Probe::new(
+            "openai",
+            Some(|| ProviderError::ApiError {
+                status: 400,
+                message: "bad request".to_string(),
+            }),
+        );
+        let azure = Probe::new("azure", None);
+        let anthropic = Probe::new("anthropic", None);
+        let router = router(&openai, &azure, &anthropic);
+
+        let err = router.dispatch(request("gpt-4")).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            RoutingError::Provider(ProviderError::ApiError { status: 400, .. })
+        ));
+        assert!(azure.sent.lock().is_empty());
+
+        // Provider model ids resolve to their family
+        assert_eq!(router.family_of("gpt4-prod-deployment"), Some("gpt-4"));
+        assert_eq!(
+            router.family_of("claude-3-opus-20240229"),
+            Some("claude-3-opus")
+        );
+    }
+}
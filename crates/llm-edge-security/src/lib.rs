@@ -14,7 +14,7 @@ pub mod validation;
 
 pub use auth::{ApiKeyAuth, JwtAuth};
 pub use error::{SecurityError, SecurityResult};
-pub use pii::PIIRedactor;
+pub use pii::{sanitize_log_data, PIIRedactor};
 
 #[cfg(test)]
 mod tests {
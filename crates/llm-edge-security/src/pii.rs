@@ -1,6 +1,57 @@
 //! PII detection and redaction
 
 use regex::Regex;
+use std::sync::OnceLock;
+
+/// Redactor shared by [`sanitize_log_data`]
+static LOG_REDACTOR: OnceLock<(PIIRedactor, Vec<(Regex, &'static str)>)> = OnceLock::new();
+
+/// Credential patterns that must never reach the logs
+fn secret_patterns() -> Vec<(Regex, &'static str)> {
+    vec![
+        // `api_key: "..."`, `x-api-key=...` and similar assignments
+        (
+            Regex::new(
+                r#"(?i)((?:x-)?api[_-]?(?:key|secret)["']?\s*[:=]\s*["']?)[A-Za-z0-9._-]{8,}"#,
+            )
+            .unwrap(),
+            "${1}[API_KEY_REDACTED]",
+        ),
+        // Authorization headers
+        (
+            Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]+").unwrap(),
+            "Bearer [TOKEN_REDACTED]",
+        ),
+        // OpenAI / Anthropic style keys appearing anywhere else
+        (
+            Regex::new(r"\bsk-[A-Za-z0-9_-]{16,}").unwrap(),
+            "[API_KEY_REDACTED]",
+        ),
+    ]
+}
+
+/// Redact PII and credentials from `data` and cap it at `max_length` bytes
+///
+/// Intended for anything written to the logs verbatim, such as serialized
+/// request and response bodies.
+pub fn sanitize_log_data(data: &str, max_length: usize) -> String {
+    let (redactor, secrets) = LOG_REDACTOR.get_or_init(|| (PIIRedactor::new(), secret_patterns()));
+
+    let mut redacted = redactor.redact(data);
+    for (pattern, replacement) in secrets {
+        redacted = pattern.replace_all(&redacted, *replacement).to_string();
+    }
+
+    if redacted.len() <= max_length {
+        return redacted;
+    }
+
+    let mut end = max_length;
+    while !redacted.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... [truncated]", &redacted[..end])
+}
 
 /// PII redactor that removes sensitive information
 pub struct PIIRedactor {
@@ -83,4 +134,23 @@ mod tests {
         assert!(redactor.contains_pii("Email: test@example.com"));
         assert!(!redactor.contains_pii("No PII here"));
     }
+
+    #[test]
+    fn test_sanitize_log_data_redacts_credentials() {
+        let data = r#"{"api_key":"sk-live-0123456789abcdef","auth":"Bearer abc.def-123","email":"a@b.io"}"#;
+        let sanitized = sanitize_log_data(data, 1024);
+
+        assert!(!sanitized.contains("sk-live-0123456789abcdef"));
+        assert!(!sanitized.contains("abc.def-123"));
+        assert!(!sanitized.contains("a@b.io"));
+        assert!(sanitized.contains("[API_KEY_REDACTED]"));
+        assert!(sanitized.contains("Bearer [TOKEN_REDACTED]"));
+    }
+
+    #[test]
+    fn test_sanitize_log_data_truncates_on_char_boundary() {
+        let sanitized = sanitize_log_data(&"é".repeat(100), 11);
+
+        assert_eq!(sanitized, format!("{}... [truncated]", "é".repeat(5)));
+    }
 }
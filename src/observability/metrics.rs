@@ -39,6 +39,10 @@ impl MetricsRegistry {
             .set_buckets_for_metric(
                 Matcher::Full("llm_provider_request_duration_seconds".to_string()),
                 &[0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0],
+            )?
+            .set_buckets_for_metric(
+                Matcher::Full("llm_routing_candidates".to_string()),
+                &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 8.0, 13.0],
             )?;
         
         let handle = builder.install_recorder()?;
@@ -121,6 +125,12 @@ impl MetricsRegistry {
             "Total errors per provider"
         );
         
+        describe_counter!(
+            "llm_provider_auth_failures_total",
+            Unit::Count,
+            "Requests rejected because the provider refused our API key"
+        );
+        
         describe_histogram!(
             "llm_provider_request_duration_seconds",
             Unit::Seconds,
@@ -133,6 +143,25 @@ impl MetricsRegistry {
             "Circuit breaker state (0=closed, 1=half-open, 2=open)"
         );
         
+        // Routing metrics
+        describe_counter!(
+            "llm_routing_selections_total",
+            Unit::Count,
+            "Providers selected per routing strategy"
+        );
+        
+        describe_counter!(
+            "llm_routing_no_provider_total",
+            Unit::Count,
+            "Selections where the routing strategy found no provider"
+        );
+        
+        describe_histogram!(
+            "llm_routing_candidates",
+            Unit::Count,
+            "Healthy, enabled providers considered per selection"
+        );
+        
         // Token and cost metrics
         describe_counter!(
             "llm_tokens_total",
@@ -251,6 +280,11 @@ impl ProviderMetrics {
         ).increment(1);
     }
     
+    /// Record a provider rejecting our API key
+    pub fn record_auth_failure(provider: &str) {
+        counter!("llm_provider_auth_failures_total", "provider" => provider.to_string()).increment(1);
+    }
+    
     /// Record provider request duration
     pub fn record_duration(provider: &str, duration: Duration) {
         histogram!("llm_provider_request_duration_seconds", "provider" => provider.to_string()).record(duration.as_secs_f64());
@@ -273,6 +307,32 @@ impl ProviderMetrics {
     }
 }
 
+/// Routing decision metrics tracker
+///
+/// Separate from request metrics: counts which provider each strategy
+/// picks, not how the request went.
+pub struct RoutingMetrics;
+
+impl RoutingMetrics {
+    /// Record a provider selected by `strategy`
+    pub fn record_selection(strategy: &str, provider: &str) {
+        counter!("llm_routing_selections_total",
+            "strategy" => strategy.to_string(),
+            "provider" => provider.to_string()
+        ).increment(1);
+    }
+    
+    /// Record a selection where `strategy` found no provider
+    pub fn record_no_provider(strategy: &str) {
+        counter!("llm_routing_no_provider_total", "strategy" => strategy.to_string()).increment(1);
+    }
+    
+    /// Record how many providers a selection could choose from
+    pub fn record_candidates(strategy: &str, candidates: usize) {
+        histogram!("llm_routing_candidates", "strategy" => strategy.to_string()).record(candidates as f64);
+    }
+}
+
 /// Circuit breaker state for metrics
 #[derive(Debug, Clone, Copy)]
 pub enum CircuitBreakerState {
@@ -16,6 +16,6 @@ pub use logging::{
 };
 pub use metrics::{
     CacheMetrics, MetricsRegistry, ProviderMetrics, RequestMetrics,
-    SystemMetrics, TokenMetrics,
+    RoutingMetrics, SystemMetrics, TokenMetrics,
 };
 pub use tracing::{init_tracing, shutdown_tracing, TracingConfig};
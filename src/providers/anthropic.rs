@@ -5,20 +5,40 @@ use super::{
     LLMProvider, LLMRequest, LLMResponse, Message, MessageContent, Choice, Usage,
     FinishReason, ProviderError, ProviderResult, HealthStatus, ProviderCapabilities, Role, ContentPart,
 };
+use crate::routing::strategies::{RetryBudget, RetryConfig};
 use async_trait::async_trait;
-use reqwest::{Client, header};
+use base64::Engine as _;
+use reqwest::{redirect, Client, Url, header};
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
 
 const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
+/// Default request body limit, matching the Messages API's 32 MB cap
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 32 * 1024 * 1024;
+
+/// Default limit for a fetched image, matching the Messages API's 5 MB cap
+pub const DEFAULT_MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Time allowed for fetching one image URL
+const IMAGE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Image media types the Messages API accepts
+const ALLOWED_IMAGE_TYPES: &[&str] = &["image/jpeg", "image/png", "image/gif", "image/webp"];
+
 /// Anthropic provider implementation
 pub struct AnthropicProvider {
     client: Client,
     api_key: String,
     timeout_ms: u64,
-    max_retries: u32,
+    retry: RetryConfig,
+    max_request_bytes: usize,
+    max_image_bytes: usize,
+    /// Fetch images over plain HTTP and from non-public addresses; only set
+    /// by tests that serve images from a local mock server
+    allow_private_image_hosts: bool,
 }
 
 impl AnthropicProvider {
@@ -38,18 +58,47 @@ impl AnthropicProvider {
             client,
             api_key,
             timeout_ms,
-            max_retries,
+            retry: RetryConfig::uniform(max_retries),
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+            allow_private_image_hosts: false,
         })
     }
 
+    /// Use separate retry budgets and backoff for timeouts and other errors
+    pub fn with_retry_config(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Reject requests whose serialized body exceeds `max_request_bytes`
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Reject image URLs whose content exceeds `max_image_bytes`
+    pub fn with_max_image_bytes(mut self, max_image_bytes: usize) -> Self {
+        self.max_image_bytes = max_image_bytes;
+        self
+    }
+
     /// Transform our unified request to Anthropic format
-    fn transform_request(&self, request: &LLMRequest) -> AnthropicRequest {
+    ///
+    /// Image URLs are fetched and inlined, since Anthropic only accepts
+    /// base64 image data.
+    async fn transform_request(&self, request: &LLMRequest) -> ProviderResult<AnthropicRequest> {
         // Separate system messages from other messages
-        let (system_message, messages) = self.extract_system_message(&request.messages);
+        let (system_message, other_messages) = self.extract_system_message(&request.messages);
 
-        AnthropicRequest {
+        let mut messages = Vec::with_capacity(other_messages.len());
+        for message in other_messages {
+            messages.push(self.transform_message(message).await?);
+        }
+
+        Ok(AnthropicRequest {
             model: request.model.clone(),
-            messages: messages.into_iter().map(|m| self.transform_message(m)).collect(),
+            messages,
             system: system_message,
             max_tokens: request.max_tokens.unwrap_or(4096),
             temperature: request.temperature,
@@ -58,7 +107,7 @@ impl AnthropicProvider {
             stop_sequences: request.stop_sequences.clone(),
             stream: Some(request.stream),
             metadata: None,
-        }
+        })
     }
 
     /// Extract system message and return it separately (Anthropic format)
@@ -86,27 +135,29 @@ impl AnthropicProvider {
     }
 
     /// Transform a message to Anthropic format
-    fn transform_message(&self, message: &Message) -> AnthropicMessage {
+    async fn transform_message(&self, message: &Message) -> ProviderResult<AnthropicMessage> {
         let content = match &message.content {
             MessageContent::Text(text) => {
                 AnthropicContent::Text(text.clone())
             }
             MessageContent::Parts(parts) => {
-                AnthropicContent::Blocks(
-                    parts.iter().map(|p| self.transform_content_part(p)).collect()
-                )
+                let mut blocks = Vec::with_capacity(parts.len());
+                for part in parts {
+                    blocks.push(self.transform_content_part(part).await?);
+                }
+                AnthropicContent::Blocks(blocks)
             }
         };
 
-        AnthropicMessage {
+        Ok(AnthropicMessage {
             role: self.transform_role(&message.role),
             content,
-        }
+        })
     }
 
     /// Transform content part to Anthropic format
-    fn transform_content_part(&self, part: &ContentPart) -> AnthropicContentBlock {
-        match part {
+    async fn transform_content_part(&self, part: &ContentPart) -> ProviderResult<AnthropicContentBlock> {
+        let block = match part {
             ContentPart::Text { text } => {
                 AnthropicContentBlock::Text {
                     r#type: "text".to_string(),
@@ -114,28 +165,125 @@ impl AnthropicProvider {
                 }
             }
             ContentPart::Image { source } => {
-                // Transform image source
-                match source {
-                    super::ImageSource::Url { url } => {
-                        // Anthropic doesn't support URLs directly, would need to download
-                        AnthropicContentBlock::Text {
-                            r#type: "text".to_string(),
-                            text: format!("[Image: {}]", url),
-                        }
-                    }
+                // Anthropic only takes base64 data, so URLs are fetched here
+                let (media_type, data) = match source {
+                    super::ImageSource::Url { url } => self.fetch_image(url).await?,
                     super::ImageSource::Base64 { media_type, data } => {
-                        AnthropicContentBlock::Image {
-                            r#type: "image".to_string(),
-                            source: AnthropicImageSource {
-                                r#type: "base64".to_string(),
-                                media_type: media_type.clone(),
-                                data: data.clone(),
-                            },
-                        }
+                        (media_type.clone(), data.clone())
                     }
+                };
+
+                AnthropicContentBlock::Image {
+                    r#type: "image".to_string(),
+                    source: AnthropicImageSource {
+                        r#type: "base64".to_string(),
+                        media_type,
+                        data,
+                    },
                 }
             }
+        };
+
+        Ok(block)
+    }
+
+    /// Download an image URL, returning its media type and base64 data
+    ///
+    /// The URL is caller-controlled, so only `https` is fetched, the host
+    /// must resolve to public addresses only, and redirects are not
+    /// followed. The connection is pinned to the address that was checked
+    /// so a second DNS lookup cannot point it elsewhere. Failures to reach
+    /// the image share one generic message, leaving nothing to learn about
+    /// the network behind the proxy.
+    ///
+    /// Bounded by [`IMAGE_FETCH_TIMEOUT`] and `max_image_bytes`; the body is
+    /// read in chunks so an oversized image is dropped without buffering it
+    /// all. Only the media types in [`ALLOWED_IMAGE_TYPES`] are accepted,
+    /// taken from the bytes themselves when the server's `Content-Type` is
+    /// missing or generic.
+    async fn fetch_image(&self, url: &str) -> ProviderResult<(String, String)> {
+        let invalid = |message: String| ProviderError::InvalidRequest { message };
+        let unreachable = |reason: &dyn std::fmt::Display| {
+            tracing::debug!(url = %url, reason = %reason, "Image URL rejected or unreachable");
+            invalid(format!("Image {} could not be fetched", url))
+        };
+
+        let parsed = Url::parse(url).map_err(|e| unreachable(&e))?;
+        let host = parsed.host_str().ok_or_else(|| unreachable(&"no host"))?.to_string();
+        let port = parsed.port_or_known_default().ok_or_else(|| unreachable(&"no port"))?;
+        if parsed.scheme() != "https" && !self.allow_private_image_hosts {
+            return Err(unreachable(&"scheme is not https"));
+        }
+
+        let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+            .await
+            .map_err(|e| unreachable(&e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(unreachable(&"host did not resolve"));
+        }
+        if !self.allow_private_image_hosts {
+            if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+                return Err(unreachable(&format!("{} is not a public address", addr.ip())));
+            }
+        }
+
+        let client = Client::builder()
+            .timeout(IMAGE_FETCH_TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .resolve_to_addrs(lookup_host, &addrs)
+            .use_rustls_tls()
+            .build()
+            .map_err(|e| ProviderError::InternalError(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut response = client
+            .get(parsed)
+            .send()
+            .await
+            .map_err(|e| unreachable(&e))?;
+
+        if !response.status().is_success() {
+            return Err(unreachable(&format!("server returned {}", response.status())));
+        }
+
+        let too_large = || invalid(format!(
+            "Image {} exceeds the {} byte limit",
+            url, self.max_image_bytes
+        ));
+        if response.content_length().is_some_and(|len| len > self.max_image_bytes as u64) {
+            return Err(too_large());
+        }
+
+        let declared_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .map(|v| v.trim().to_ascii_lowercase());
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| unreachable(&e))?
+        {
+            if bytes.len() + chunk.len() > self.max_image_bytes {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
         }
+
+        let media_type = declared_type
+            .filter(|t| ALLOWED_IMAGE_TYPES.contains(&t.as_str()))
+            .or_else(|| sniff_image_type(&bytes).map(str::to_string))
+            .ok_or_else(|| invalid(format!(
+                "Image {} is not one of the supported types ({})",
+                url,
+                ALLOWED_IMAGE_TYPES.join(", ")
+            )))?;
+
+        Ok((media_type, base64::engine::general_purpose::STANDARD.encode(&bytes)))
     }
 
     /// Transform role to Anthropic format
@@ -189,24 +337,20 @@ impl AnthropicProvider {
 
     /// Send a request with retry logic
     async fn send_request(&self, request: &LLMRequest) -> ProviderResult<AnthropicResponse> {
-        let anthropic_request = self.transform_request(request);
+        let anthropic_request = self.transform_request(request).await?;
+        let body = super::serialize_body("anthropic", &anthropic_request, self.max_request_bytes)?;
         let url = format!("{}/messages", ANTHROPIC_API_BASE);
 
-        let mut last_error = None;
+        let mut budget = RetryBudget::default();
 
-        for attempt in 0..=self.max_retries {
-            if attempt > 0 {
-                // Exponential backoff: 100ms, 200ms, 400ms, etc.
-                let backoff_ms = 100 * (1 << (attempt - 1));
-                tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
-            }
-
-            match self.client
+        loop {
+            let error = match self.client
                 .post(&url)
+                .headers(request.extra_headers.clone())
                 .header("x-api-key", &self.api_key)
                 .header("anthropic-version", ANTHROPIC_VERSION)
                 .header(header::CONTENT_TYPE, "application/json")
-                .json(&anthropic_request)
+                .body(body.clone())
                 .send()
                 .await
             {
@@ -217,10 +361,9 @@ impl AnthropicProvider {
                         match response.json::<AnthropicResponse>().await {
                             Ok(anthropic_response) => return Ok(anthropic_response),
                             Err(e) => {
-                                last_error = Some(ProviderError::SerializationError(
+                                ProviderError::SerializationError(
                                     serde_json::Error::custom(format!("Failed to parse response: {}", e))
-                                ));
-                                continue;
+                                )
                             }
                         }
                     } else if status.as_u16() == 401 {
@@ -229,29 +372,32 @@ impl AnthropicProvider {
                         });
                     } else if status.as_u16() == 429 {
                         // Rate limit - retry
-                        last_error = Some(ProviderError::RateLimitExceeded {
+                        ProviderError::RateLimitExceeded {
                             message: "Anthropic rate limit exceeded".to_string(),
-                        });
-                        continue;
+                        }
                     } else {
                         let error_body = response.text().await.unwrap_or_default();
                         return Err(ProviderError::ProviderError {
+                            status: status.as_u16(),
                             message: format!("Anthropic API error ({}): {}", status, error_body),
                         });
                     }
                 }
                 Err(e) if e.is_timeout() => {
-                    last_error = Some(ProviderError::Timeout { timeout_ms: self.timeout_ms });
-                    continue;
+                    ProviderError::Timeout { timeout_ms: self.timeout_ms }
                 }
                 Err(e) => {
-                    last_error = Some(ProviderError::HttpError(e));
-                    continue;
+                    ProviderError::HttpError(e)
                 }
+            };
+
+            // Timeouts and other failures draw on separate retry budgets
+            let timed_out = matches!(error, ProviderError::Timeout { .. });
+            match self.retry.next_retry(&mut budget, timed_out) {
+                Some(backoff) => self.retry.sleep(backoff).await,
+                None => return Err(error),
             }
         }
-
-        Err(last_error.unwrap_or_else(|| ProviderError::InternalError("Unknown error".to_string())))
     }
 }
 
@@ -271,6 +417,18 @@ impl LLMProvider for AnthropicProvider {
         }
     }
 
+    fn capabilities_for(&self, model: &str) -> ProviderCapabilities {
+        let base = self.capabilities();
+        if model.starts_with("claude-3-5") || model.starts_with("claude-3.5") {
+            ProviderCapabilities {
+                max_output_tokens: 8192,
+                ..base
+            }
+        } else {
+            base
+        }
+    }
+
     async fn complete(&self, request: LLMRequest) -> ProviderResult<LLMResponse> {
         let start = Instant::now();
 
@@ -339,7 +497,52 @@ impl LLMProvider for AnthropicProvider {
     }
 }
 
+/// Whether `ip` is routable on the public internet
+///
+/// Rejects loopback, private, link-local, carrier-grade NAT, unspecified,
+/// broadcast, documentation and multicast ranges, including IPv4 addresses
+/// embedded in IPv6.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Media type of an image, from its leading magic bytes
+fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
 // Anthropic API request format
+
 #[derive(Debug, Serialize)]
 struct AnthropicRequest {
     model: String,
@@ -459,4 +662,189 @@ mod tests {
         assert_eq!(system.unwrap(), "You are a helpful assistant");
         assert_eq!(other.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_oversized_request_rejected_before_sending() {
+        let provider = AnthropicProvider::new("test-key".to_string(), 30000, 0)
+            .unwrap()
+            .with_max_request_bytes(1024);
+        let request = LLMRequest::new("claude-3-haiku-20240307", vec![]).with_user_message("x".repeat(2048));
+
+        match provider.send_request(&request).await {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("exceeding the 1024 byte limit"));
+            }
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("oversized request was sent"),
+        }
+    }
+
+    /// Provider allowed to fetch images from the local mock server
+    fn local_image_provider(mut provider: AnthropicProvider) -> AnthropicProvider {
+        provider.allow_private_image_hosts = true;
+        provider
+    }
+
+    fn image_url(url: String) -> ContentPart {
+        ContentPart::Image {
+            source: crate::providers::ImageSource::Url { url },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_url_fetched_and_encoded() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let png = [&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A][..], &[0u8; 64]].concat();
+        let jpeg = [&[0xFF, 0xD8, 0xFF, 0xE0][..], &[0u8; 64]].concat();
+        Mock::given(method("GET"))
+            .and(path("/cat.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(png.clone(), "image/png"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/download"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(jpeg, "application/octet-stream"))
+            .mount(&server)
+            .await;
+        let provider = local_image_provider(AnthropicProvider::new("test-key".to_string(), 30000, 0).unwrap());
+
+        let block = provider
+            .transform_content_part(&image_url(format!("{}/cat.png", server.uri())))
+            .await
+            .unwrap();
+        match block {
+            AnthropicContentBlock::Image { source, .. } => {
+                assert_eq!(source.r#type, "base64");
+                assert_eq!(source.media_type, "image/png");
+                assert_eq!(source.data, base64::engine::general_purpose::STANDARD.encode(&png));
+            }
+            other => panic!("expected an image block, got {:?}", other),
+        }
+
+        // A generic content type falls back to the image's magic bytes
+        let block = provider
+            .transform_content_part(&image_url(format!("{}/download", server.uri())))
+            .await
+            .unwrap();
+        assert!(matches!(
+            block,
+            AnthropicContentBlock::Image { source, .. } if source.media_type == "image/jpeg"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_or_unsupported_image_rejected() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let large_png = [&[0x89, b'P', b'N', b'G'][..], &[0u8; 2048]].concat();
+        Mock::given(method("GET"))
+            .and(path("/large.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(large_png, "image/png"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html"))
+            .mount(&server)
+            .await;
+        let provider = local_image_provider(
+            AnthropicProvider::new("test-key".to_string(), 30000, 0)
+                .unwrap()
+                .with_max_image_bytes(1024),
+        );
+
+        match provider
+            .transform_content_part(&image_url(format!("{}/large.png", server.uri())))
+            .await
+        {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("exceeds the 1024 byte limit"), "{}", message);
+            }
+            other => panic!("oversized image was accepted: {:?}", other.map(|_| ())),
+        }
+
+        match provider
+            .transform_content_part(&image_url(format!("{}/page", server.uri())))
+            .await
+        {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("not one of the supported types"), "{}", message);
+            }
+            other => panic!("HTML page was accepted as an image: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_urls_to_internal_hosts_rejected() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Answers with an image, which must never be fetched
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0xFF, 0xD8, 0xFF, 0xE0], "image/jpeg"))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new("test-key".to_string(), 30000, 0).unwrap();
+
+        let port = server.address().port();
+        for url in [
+            server.uri() + "/cat.jpg",
+            format!("https://127.0.0.1:{}/cat.jpg", port),
+            format!("https://localhost:{}/cat.jpg", port),
+            "https://169.254.169.254/latest/meta-data/".to_string(),
+            "https://10.0.0.1/cat.jpg".to_string(),
+            "https://[::1]/cat.jpg".to_string(),
+            "https://[::ffff:192.168.1.1]/cat.jpg".to_string(),
+            "file:///etc/passwd".to_string(),
+        ] {
+            match provider.transform_content_part(&image_url(url.clone())).await {
+                Err(ProviderError::InvalidRequest { message }) => {
+                    assert_eq!(message, format!("Image {} could not be fetched", url));
+                }
+                other => panic!("{} was fetched: {:?}", url, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_image_redirects_not_followed() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cat.png"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", "http://169.254.169.254/"))
+            .mount(&server)
+            .await;
+        let provider = local_image_provider(AnthropicProvider::new("test-key".to_string(), 30000, 0).unwrap());
+
+        let url = format!("{}/cat.png", server.uri());
+        match provider.transform_content_part(&image_url(url.clone())).await {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert_eq!(message, format!("Image {} could not be fetched", url));
+            }
+            other => panic!("redirect was followed: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_public_ip_ranges() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.0.1", "169.254.169.254",
+            "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
 }
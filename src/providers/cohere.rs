@@ -0,0 +1,519 @@
+// Cohere provider implementation
+// Supports Command R and Command R+ via the v2 chat API
+
+use super::{
+    LLMProvider, LLMRequest, LLMResponse, Message, MessageContent, Choice, Usage,
+    FinishReason, ProviderError, ProviderResult, HealthStatus, ProviderCapabilities, Role,
+};
+use crate::routing::strategies::{RetryBudget, RetryConfig};
+use async_trait::async_trait;
+use reqwest::{Client, header};
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+const COHERE_API_BASE: &str = "https://api.cohere.com/v2";
+
+/// Default request body limit
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 10 * 1024 * 1024;
+
+/// Cohere provider implementation
+pub struct CohereProvider {
+    client: Client,
+    api_key: String,
+    timeout_ms: u64,
+    retry: RetryConfig,
+    max_request_bytes: usize,
+}
+
+impl CohereProvider {
+    /// Create a new Cohere provider
+    pub fn new(api_key: String, timeout_ms: u64, max_retries: u32) -> ProviderResult<Self> {
+        // Create HTTP client with connection pooling
+        let client = Client::builder()
+            .timeout(Duration::from_millis(timeout_ms))
+            .pool_max_idle_per_host(20)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60))
+            .use_rustls_tls()
+            .build()
+            .map_err(|e| ProviderError::InternalError(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            api_key,
+            timeout_ms,
+            retry: RetryConfig::uniform(max_retries),
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+        })
+    }
+
+    /// Use separate retry budgets and backoff for timeouts and other errors
+    pub fn with_retry_config(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Reject requests whose serialized body exceeds `max_request_bytes`
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+
+    /// Transform our unified request to Cohere format
+    ///
+    /// Cohere's `tools` and `tool_results` need tool definitions and call ids
+    /// the unified request does not carry, so tool use is rejected up front
+    /// rather than silently dropped.
+    fn transform_request(&self, request: &LLMRequest) -> ProviderResult<CohereRequest> {
+        let tool_params = ["tools", "tool_choice", "functions", "function_call"];
+        if let Some(param) = request
+            .extra_params
+            .iter()
+            .flat_map(|params| params.keys())
+            .find(|key| tool_params.contains(&key.as_str()))
+        {
+            return Err(ProviderError::InvalidRequest {
+                message: format!("Cohere adapter does not support tool definitions (`{}`)", param),
+            });
+        }
+
+        let messages = request
+            .messages
+            .iter()
+            .map(|m| self.transform_message(m))
+            .collect::<ProviderResult<Vec<_>>>()?;
+
+        Ok(CohereRequest {
+            model: request.model.clone(),
+            messages,
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            p: request.top_p,
+            k: request.top_k,
+            stop_sequences: request.stop_sequences.clone(),
+            stream: request.stream,
+        })
+    }
+
+    /// Transform a message to Cohere format
+    fn transform_message(&self, message: &Message) -> ProviderResult<CohereMessage> {
+        let role = match message.role {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool | Role::Function => {
+                return Err(ProviderError::InvalidRequest {
+                    message: "Cohere adapter does not support tool messages".to_string(),
+                });
+            }
+        };
+
+        let content = match &message.content {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => {
+                // Cohere chat is text-only; keep the text parts
+                parts
+                    .iter()
+                    .filter_map(|p| match p {
+                        super::ContentPart::Text { text } => Some(text.as_str()),
+                        super::ContentPart::Image { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        };
+
+        Ok(CohereMessage {
+            role: role.to_string(),
+            content,
+        })
+    }
+
+    /// Transform Cohere response to our unified format
+    fn transform_response(&self, response: CohereResponse, model: &str) -> LLMResponse {
+        let content = response
+            .message
+            .content
+            .iter()
+            .filter(|c| c.r#type == "text")
+            .map(|c| c.text.as_str())
+            .collect::<Vec<_>>()
+            .join("");
+
+        // `tokens` is what the model processed; `billed_units` can differ
+        let usage = response.usage.and_then(|u| u.tokens).unwrap_or_default();
+
+        LLMResponse {
+            id: response.id,
+            model: model.to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: Role::Assistant,
+                    content: MessageContent::Text(content),
+                    name: None,
+                },
+                finish_reason: response
+                    .finish_reason
+                    .as_deref()
+                    .and_then(|r| self.parse_finish_reason(r)),
+            }],
+            usage: Usage {
+                prompt_tokens: usage.input_tokens,
+                completion_tokens: usage.output_tokens,
+                total_tokens: usage.input_tokens + usage.output_tokens,
+            },
+            created: chrono::Utc::now().timestamp(),
+            metadata: None,
+        }
+    }
+
+    /// Parse finish reason
+    fn parse_finish_reason(&self, reason: &str) -> Option<FinishReason> {
+        match reason {
+            "COMPLETE" | "STOP_SEQUENCE" => Some(FinishReason::Stop),
+            "MAX_TOKENS" => Some(FinishReason::Length),
+            "TOOL_CALL" => Some(FinishReason::ToolCalls),
+            _ => None,
+        }
+    }
+
+    /// Send a request with retry logic
+    async fn send_request(&self, request: &LLMRequest) -> ProviderResult<CohereResponse> {
+        let cohere_request = self.transform_request(request)?;
+        let body = super::serialize_body("cohere", &cohere_request, self.max_request_bytes)?;
+        let url = format!("{}/chat", COHERE_API_BASE);
+
+        let mut budget = RetryBudget::default();
+
+        loop {
+            let error = match self.client
+                .post(&url)
+                .headers(request.extra_headers.clone())
+                .header(header::AUTHORIZATION, format!("Bearer {}", self.api_key))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    let status = response.status();
+
+                    if status.is_success() {
+                        match response.json::<CohereResponse>().await {
+                            Ok(cohere_response) => return Ok(cohere_response),
+                            Err(e) => {
+                                ProviderError::SerializationError(
+                                    serde_json::Error::custom(format!("Failed to parse response: {}", e))
+                                )
+                            }
+                        }
+                    } else if status.as_u16() == 401 {
+                        return Err(ProviderError::InvalidApiKey {
+                            provider: "cohere".to_string(),
+                        });
+                    } else if status.as_u16() == 429 {
+                        // Rate limit - retry
+                        ProviderError::RateLimitExceeded {
+                            message: "Cohere rate limit exceeded".to_string(),
+                        }
+                    } else {
+                        let error_body = response.text().await.unwrap_or_default();
+                        return Err(ProviderError::ProviderError {
+                            status: status.as_u16(),
+                            message: format!("Cohere API error ({}): {}", status, error_body),
+                        });
+                    }
+                }
+                Err(e) if e.is_timeout() => {
+                    ProviderError::Timeout { timeout_ms: self.timeout_ms }
+                }
+                Err(e) => {
+                    ProviderError::HttpError(e)
+                }
+            };
+
+            // Timeouts and other failures draw on separate retry budgets
+            let timed_out = matches!(error, ProviderError::Timeout { .. });
+            match self.retry.next_retry(&mut budget, timed_out) {
+                Some(backoff) => self.retry.sleep(backoff).await,
+                None => return Err(error),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for CohereProvider {
+    fn name(&self) -> &str {
+        "cohere"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_streaming: true,
+            supports_function_calling: false,
+            supports_vision: false,
+            max_context_tokens: 128000, // Command R / R+
+            max_output_tokens: 4000,
+        }
+    }
+
+    async fn complete(&self, request: LLMRequest) -> ProviderResult<LLMResponse> {
+        let start = Instant::now();
+
+        // Validate model
+        if !self.validate_model(&request.model) {
+            return Err(ProviderError::ModelNotFound {
+                model: request.model.clone(),
+            });
+        }
+
+        let cohere_response = self.send_request(&request).await?;
+        let response = self.transform_response(cohere_response, &request.model);
+
+        let elapsed = start.elapsed();
+        tracing::info!(
+            provider = "cohere",
+            model = %request.model,
+            tokens = response.usage.total_tokens,
+            latency_ms = elapsed.as_millis() as u64,
+            "Completed Cohere request"
+        );
+
+        Ok(response)
+    }
+
+    async fn health_check(&self) -> ProviderResult<HealthStatus> {
+        let start = Instant::now();
+
+        // Simple health check: make a minimal request
+        let test_request = LLMRequest::new(
+            "command-r",
+            vec![Message::user("Hi")]
+        ).with_max_tokens(10);
+
+        match self.send_request(&test_request).await {
+            Ok(_) => {
+                let elapsed = start.elapsed();
+                Ok(HealthStatus {
+                    healthy: true,
+                    last_check: chrono::Utc::now().timestamp(),
+                    response_time_ms: Some(elapsed.as_millis() as u64),
+                    error: None,
+                })
+            }
+            Err(e) => {
+                Ok(HealthStatus {
+                    healthy: false,
+                    last_check: chrono::Utc::now().timestamp(),
+                    response_time_ms: None,
+                    error: Some(e.to_string()),
+                })
+            }
+        }
+    }
+
+    fn list_models(&self) -> Vec<String> {
+        vec![
+            "command-r".to_string(),
+            "command-r-plus".to_string(),
+        ]
+    }
+}
+
+// Cohere v2 chat request format
+#[derive(Debug, Serialize)]
+struct CohereRequest {
+    model: String,
+    messages: Vec<CohereMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CohereMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereResponse {
+    id: String,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    message: CohereResponseMessage,
+    #[serde(default)]
+    usage: Option<CohereUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereResponseMessage {
+    #[serde(default)]
+    content: Vec<CohereContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereContent {
+    r#type: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereUsage {
+    #[serde(default)]
+    tokens: Option<CohereTokens>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CohereTokens {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Response recorded from `POST /v2/chat` with `command-r-plus`
+    const RECORDED_RESPONSE: &str = r#"{
+        "id": "c14c80c3-18eb-4519-9460-6c92edd8cfb4",
+        "finish_reason": "COMPLETE",
+        "message": {
+            "role": "assistant",
+            "content": [
+                {"type": "text", "text": "LLMs are neural networks trained to predict text."}
+            ]
+        },
+        "usage": {
+            "billed_units": {"input_tokens": 13, "output_tokens": 11},
+            "tokens": {"input_tokens": 209, "output_tokens": 11}
+        }
+    }"#;
+
+    fn provider() -> CohereProvider {
+        CohereProvider::new("test-key".to_string(), 30000, 3).unwrap()
+    }
+
+    #[test]
+    fn test_list_models() {
+        let provider = provider();
+        assert!(provider.validate_model("command-r"));
+        assert!(provider.validate_model("command-r-plus"));
+        assert!(!provider.validate_model("gpt-4"));
+    }
+
+    #[test]
+    fn test_request_transform() {
+        let request = LLMRequest::new(
+            "command-r-plus",
+            vec![
+                Message::system("Be brief"),
+                Message::user("What is an LLM?"),
+            ],
+        )
+        .with_max_tokens(64);
+
+        let body = serde_json::to_value(provider().transform_request(&request).unwrap()).unwrap();
+
+        assert_eq!(body["model"], "command-r-plus");
+        assert_eq!(body["max_tokens"], 64);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], "Be brief");
+        assert_eq!(body["messages"][1]["role"], "user");
+        assert_eq!(body["messages"][1]["content"], "What is an LLM?");
+        assert!(body.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_tool_use_rejected() {
+        let provider = provider();
+
+        let tool_message = LLMRequest::new(
+            "command-r",
+            vec![
+                Message::user("What's the weather?"),
+                Message {
+                    role: Role::Tool,
+                    content: MessageContent::Text("{\"temp\": 21}".to_string()),
+                    name: None,
+                },
+            ],
+        );
+        match provider.transform_request(&tool_message) {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("tool messages"));
+            }
+            other => panic!("tool message was not rejected: {:?}", other.map(|r| r.messages.len())),
+        }
+
+        let mut tool_definitions = LLMRequest::new("command-r", vec![Message::user("Hi")]);
+        tool_definitions.extra_params = Some(
+            [("tools".to_string(), serde_json::json!([{"type": "function"}]))]
+                .into_iter()
+                .collect(),
+        );
+        match provider.transform_request(&tool_definitions) {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("`tools`"));
+            }
+            other => panic!("tool definitions were not rejected: {:?}", other.map(|r| r.messages.len())),
+        }
+    }
+
+    #[test]
+    fn test_response_parsing_from_recorded_fixture() {
+        let recorded: CohereResponse = serde_json::from_str(RECORDED_RESPONSE).unwrap();
+        let response = provider().transform_response(recorded, "command-r-plus");
+
+        assert_eq!(response.id, "c14c80c3-18eb-4519-9460-6c92edd8cfb4");
+        assert_eq!(response.model, "command-r-plus");
+        assert_eq!(
+            response.choices[0].message.content,
+            MessageContent::Text("LLMs are neural networks trained to predict text.".to_string())
+        );
+        assert_eq!(response.choices[0].finish_reason, Some(FinishReason::Stop));
+        assert_eq!(response.usage.prompt_tokens, 209);
+        assert_eq!(response.usage.completion_tokens, 11);
+        assert_eq!(response.usage.total_tokens, 220);
+    }
+
+    #[test]
+    fn test_finish_reasons() {
+        let provider = provider();
+        assert_eq!(provider.parse_finish_reason("COMPLETE"), Some(FinishReason::Stop));
+        assert_eq!(provider.parse_finish_reason("STOP_SEQUENCE"), Some(FinishReason::Stop));
+        assert_eq!(provider.parse_finish_reason("MAX_TOKENS"), Some(FinishReason::Length));
+        assert_eq!(provider.parse_finish_reason("TOOL_CALL"), Some(FinishReason::ToolCalls));
+        assert_eq!(provider.parse_finish_reason("ERROR"), None);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_rejected_before_sending() {
+        let provider = CohereProvider::new("test-key".to_string(), 30000, 0)
+            .unwrap()
+            .with_max_request_bytes(1024);
+        let request = LLMRequest::new("command-r", vec![]).with_user_message("x".repeat(2048));
+
+        match provider.send_request(&request).await {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("exceeding the 1024 byte limit"));
+            }
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("oversized request was sent"),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+// Echo provider implementation
+// Deterministic in-process provider for examples, offline runs and tests
+
+use super::{
+    LLMProvider, LLMRequest, LLMResponse, Message, MessageContent, ContentPart, Choice, Usage,
+    FinishReason, ProviderError, ProviderResult, HealthStatus, ProviderCapabilities, Role,
+    ModelPricing,
+};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Model name served by the echo provider
+pub const ECHO_MODEL: &str = "echo";
+
+/// When the echo provider returns an injected error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailureInjection {
+    /// Never fail
+    #[default]
+    Never,
+    /// Fail every Nth call (calls N, 2N, 3N, ...)
+    EveryNth(u64),
+    /// Succeed for the first N calls, then fail every call
+    AfterCalls(u64),
+}
+
+impl FailureInjection {
+    /// Whether the given 1-based call number should fail
+    fn fails(&self, call: u64) -> bool {
+        match *self {
+            FailureInjection::Never => false,
+            FailureInjection::EveryNth(n) => n > 0 && call % n == 0,
+            FailureInjection::AfterCalls(n) => call > n,
+        }
+    }
+}
+
+/// Provider that answers without any network access
+///
+/// By default the response content is the last user message. Responses are
+/// identical for identical requests apart from the call-numbered id.
+pub struct EchoProvider {
+    canned_content: Option<String>,
+    latency: Duration,
+    failure: FailureInjection,
+    calls: AtomicU64,
+}
+
+impl EchoProvider {
+    /// Create an echo provider with no latency and no failures
+    pub fn new() -> Self {
+        Self {
+            canned_content: None,
+            latency: Duration::ZERO,
+            failure: FailureInjection::Never,
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    /// Return `content` instead of echoing the last user message
+    pub fn with_canned_response(mut self, content: impl Into<String>) -> Self {
+        self.canned_content = Some(content.into());
+        self
+    }
+
+    /// Delay every response by `latency`
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Inject errors according to `failure`
+    pub fn with_failure_injection(mut self, failure: FailureInjection) -> Self {
+        self.failure = failure;
+        self
+    }
+
+    /// Number of `complete` calls so far, including failed ones
+    pub fn call_count(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Text of the last user message (empty if there is none)
+    fn last_user_text(request: &LLMRequest) -> String {
+        request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map(|m| match &m.content {
+                MessageContent::Text(text) => text.clone(),
+                MessageContent::Parts(parts) => parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::Image { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whitespace-separated word count, used as a stand-in token count
+    fn count_tokens(text: &str) -> u32 {
+        text.split_whitespace().count() as u32
+    }
+}
+
+impl Default for EchoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LLMProvider for EchoProvider {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_streaming: false,
+            supports_function_calling: false,
+            supports_vision: false,
+            max_context_tokens: 128000,
+            max_output_tokens: 4096,
+        }
+    }
+
+    async fn complete(&self, request: LLMRequest) -> ProviderResult<LLMResponse> {
+        let call = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+
+        if self.failure.fails(call) {
+            return Err(ProviderError::ProviderError {
+                status: 500,
+                message: format!("Injected echo failure on call {}", call),
+            });
+        }
+
+        let content = self
+            .canned_content
+            .clone()
+            .unwrap_or_else(|| Self::last_user_text(&request));
+
+        let prompt_tokens = request
+            .messages
+            .iter()
+            .map(|m| match &m.content {
+                MessageContent::Text(text) => Self::count_tokens(text),
+                MessageContent::Parts(_) => 0,
+            })
+            .sum();
+        let completion_tokens = Self::count_tokens(&content);
+
+        Ok(LLMResponse {
+            id: format!("echo-{}", call),
+            model: request.model,
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(content),
+                finish_reason: Some(FinishReason::Stop),
+            }],
+            usage: Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            },
+            created: chrono::Utc::now().timestamp(),
+            metadata: None,
+        })
+    }
+
+    async fn health_check(&self) -> ProviderResult<HealthStatus> {
+        Ok(HealthStatus {
+            healthy: true,
+            last_check: chrono::Utc::now().timestamp(),
+            response_time_ms: Some(self.latency.as_millis() as u64),
+            error: None,
+        })
+    }
+
+    fn list_models(&self) -> Vec<String> {
+        vec![ECHO_MODEL.to_string()]
+    }
+
+    fn get_pricing(&self, _model: &str) -> Option<&'static ModelPricing> {
+        // Echo responses are free
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content: &str) -> LLMRequest {
+        LLMRequest::new(ECHO_MODEL, vec![Message::system("Be brief")])
+            .with_user_message("first question")
+            .with_user_message(content)
+    }
+
+    fn content(response: &LLMResponse) -> &str {
+        match &response.choices[0].message.content {
+            MessageContent::Text(text) => text,
+            MessageContent::Parts(_) => panic!("expected text content"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_echoes_last_user_message_deterministically() {
+        let provider = EchoProvider::new();
+
+        let first = provider.complete(request("hello edge agent")).await.unwrap();
+        let second = provider.complete(request("hello edge agent")).await.unwrap();
+
+        assert_eq!(content(&first), "hello edge agent");
+        assert_eq!(content(&first), content(&second));
+        assert_eq!(first.id, "echo-1");
+        assert_eq!(second.id, "echo-2");
+        assert_eq!(first.usage.completion_tokens, 3);
+        assert_eq!(first.usage.prompt_tokens, 7);
+        assert_eq!(first.choices[0].finish_reason, Some(FinishReason::Stop));
+    }
+
+    #[tokio::test]
+    async fn test_canned_response() {
+        let provider = EchoProvider::new().with_canned_response("fixed answer");
+
+        let response = provider.complete(request("anything")).await.unwrap();
+        assert_eq!(content(&response), "fixed answer");
+    }
+
+    #[tokio::test]
+    async fn test_fails_every_nth_call() {
+        let provider = EchoProvider::new().with_failure_injection(FailureInjection::EveryNth(3));
+
+        let mut outcomes = Vec::new();
+        for _ in 0..6 {
+            outcomes.push(provider.complete(request("ping")).await.is_ok());
+        }
+
+        assert_eq!(outcomes, vec![true, true, false, true, true, false]);
+        assert_eq!(provider.call_count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_fails_after_threshold() {
+        let provider = EchoProvider::new().with_failure_injection(FailureInjection::AfterCalls(2));
+
+        assert!(provider.complete(request("ping")).await.is_ok());
+        assert!(provider.complete(request("ping")).await.is_ok());
+        for _ in 0..3 {
+            let err = provider.complete(request("ping")).await.unwrap_err();
+            assert!(matches!(err, ProviderError::ProviderError { .. }));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latency_is_applied() {
+        let provider = EchoProvider::new().with_latency(Duration::from_millis(50));
+
+        let start = std::time::Instant::now();
+        provider.complete(request("ping")).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
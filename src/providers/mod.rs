@@ -5,12 +5,16 @@ pub mod types;
 pub mod pricing;
 pub mod openai;
 pub mod anthropic;
+pub mod cohere;
+pub mod mistral;
+pub mod echo;
 
 #[cfg(test)]
 mod tests;
 
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 pub use types::*;
@@ -34,8 +38,9 @@ pub enum ProviderError {
     #[error("Model not found: {model}")]
     ModelNotFound { model: String },
 
+    /// The provider answered with a non-success `status`
     #[error("Provider error: {message}")]
-    ProviderError { message: String },
+    ProviderError { status: u16, message: String },
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
@@ -47,9 +52,52 @@ pub enum ProviderError {
     InternalError(String),
 }
 
+impl crate::routing::strategies::ClassifyFailure for ProviderError {
+    fn status_class(&self) -> Option<crate::routing::strategies::StatusClass> {
+        use crate::routing::strategies::StatusClass;
+
+        match self {
+            ProviderError::HttpError(e) if e.is_timeout() => Some(StatusClass::Timeout),
+            ProviderError::HttpError(e) => e
+                .status()
+                .and_then(|status| StatusClass::from_status(status.as_u16())),
+            ProviderError::InvalidApiKey { .. } => Some(StatusClass::Auth),
+            ProviderError::InvalidRequest { .. }
+            | ProviderError::ModelNotFound { .. } => Some(StatusClass::Client4xx),
+            ProviderError::RateLimitExceeded { .. } => Some(StatusClass::RateLimit429),
+            ProviderError::ProviderError { status, .. } => StatusClass::from_status(*status),
+            ProviderError::Timeout { .. } => Some(StatusClass::Timeout),
+            ProviderError::SerializationError(_) | ProviderError::InternalError(_) => None,
+        }
+    }
+}
+
 /// Result type for provider operations
 pub type ProviderResult<T> = Result<T, ProviderError>;
 
+/// Serialize a request body, rejecting it if it exceeds `max_bytes`
+///
+/// Runs before anything is sent, so an oversized request fails fast instead
+/// of being retried against a provider that will only reject it.
+pub(crate) fn serialize_body<T: serde::Serialize>(
+    provider: &str,
+    body: &T,
+    max_bytes: usize,
+) -> ProviderResult<Vec<u8>> {
+    let bytes = serde_json::to_vec(body)?;
+    if bytes.len() > max_bytes {
+        return Err(ProviderError::InvalidRequest {
+            message: format!(
+                "Request body for {} is {} bytes, exceeding the {} byte limit",
+                provider,
+                bytes.len(),
+                max_bytes
+            ),
+        });
+    }
+    Ok(bytes)
+}
+
 /// Main trait for LLM provider implementations
 #[async_trait]
 pub trait LLMProvider: Send + Sync {
@@ -59,6 +107,15 @@ pub trait LLMProvider: Send + Sync {
     /// Get provider capabilities
     fn capabilities(&self) -> ProviderCapabilities;
 
+    /// Get the capabilities of a specific model
+    ///
+    /// Defaults to the provider-wide [`capabilities`](Self::capabilities);
+    /// providers whose models differ (context window, vision, tools)
+    /// override this.
+    fn capabilities_for(&self, _model: &str) -> ProviderCapabilities {
+        self.capabilities()
+    }
+
     /// Send a completion request
     async fn complete(&self, request: LLMRequest) -> ProviderResult<LLMResponse>;
 
@@ -84,11 +141,35 @@ pub trait LLMProvider: Send + Sync {
             pricing.calculate_cost(usage.prompt_tokens, usage.completion_tokens)
         })
     }
+
+    /// Describe a model for the `/v1/models` list
+    fn model_info(&self, model: &str) -> ModelInfo {
+        let caps = self.capabilities_for(model);
+        ModelInfo {
+            id: model.to_string(),
+            object: "model",
+            owned_by: self.name().to_string(),
+            supports_vision: caps.supports_vision,
+            supports_tools: caps.supports_function_calling,
+            max_context_tokens: caps.max_context_tokens,
+            max_output_tokens: caps.max_output_tokens,
+            pricing: self.get_pricing(model).cloned(),
+        }
+    }
 }
 
+/// How long `health_check_all` results are reused by default
+pub const DEFAULT_HEALTH_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Health of every provider, and when it was probed
+type CachedHealth = (Instant, std::collections::HashMap<String, HealthStatus>);
+
 /// Provider registry for managing multiple providers
 pub struct ProviderRegistry {
     providers: std::collections::HashMap<String, Arc<dyn LLMProvider>>,
+    health_cache_ttl: Duration,
+    /// Held while probing, so concurrent callers share a single probe
+    health_cache: tokio::sync::Mutex<Option<CachedHealth>>,
 }
 
 impl ProviderRegistry {
@@ -96,12 +177,22 @@ impl ProviderRegistry {
     pub fn new() -> Self {
         Self {
             providers: std::collections::HashMap::new(),
+            health_cache_ttl: DEFAULT_HEALTH_CACHE_TTL,
+            health_cache: tokio::sync::Mutex::new(None),
         }
     }
 
+    /// Reuse `health_check_all` results for `ttl` (zero probes on every call)
+    pub fn with_health_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.health_cache_ttl = ttl;
+        self
+    }
+
     /// Register a provider
     pub fn register(&mut self, provider: Arc<dyn LLMProvider>) {
         self.providers.insert(provider.name().to_string(), provider);
+        // The cached result does not cover the new provider
+        *self.health_cache.get_mut() = None;
     }
 
     /// Get a provider by name
@@ -121,14 +212,91 @@ impl ProviderRegistry {
             self.get("openai")
         } else if model.starts_with("claude-") {
             self.get("anthropic")
+        } else if model.starts_with("command-") {
+            self.get("cohere")
+        } else if model.starts_with("mistral-") || model.starts_with("codestral-") {
+            self.get("mistral")
+        } else if model == echo::ECHO_MODEL {
+            self.get("echo")
         } else {
             // Fallback: check all providers
             self.providers.values().find(|p| p.validate_model(model)).cloned()
         }
     }
 
+    /// Models of every registered provider, sorted by id
+    pub fn list_models(&self) -> ModelList {
+        let mut data: Vec<ModelInfo> = self
+            .providers
+            .values()
+            .flat_map(|provider| {
+                provider
+                    .list_models()
+                    .into_iter()
+                    .map(|model| provider.model_info(&model))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        data.sort_by(|a, b| a.id.cmp(&b.id));
+
+        ModelList {
+            object: "list",
+            data,
+        }
+    }
+
     /// Check health of all providers
+    ///
+    /// Liveness and readiness probes can call this every few seconds, so the
+    /// result is reused for the health cache TTL instead of probing every
+    /// upstream each time.
     pub async fn health_check_all(&self) -> std::collections::HashMap<String, HealthStatus> {
+        let mut cache = self.health_cache.lock().await;
+        if let Some((checked_at, results)) = cache.as_ref() {
+            if checked_at.elapsed() < self.health_cache_ttl {
+                return results.clone();
+            }
+        }
+
+        let results = self.probe_all().await;
+        *cache = Some((Instant::now(), results.clone()));
+        results
+    }
+
+    /// Probe every provider now and cache the result
+    pub async fn refresh_health(&self) -> std::collections::HashMap<String, HealthStatus> {
+        let mut cache = self.health_cache.lock().await;
+        let results = self.probe_all().await;
+        *cache = Some((Instant::now(), results.clone()));
+        results
+    }
+
+    /// Keep the cached health current in the background
+    ///
+    /// Re-probes twice per TTL so callers never wait on a probe. The task
+    /// stops once the registry is dropped, and does nothing when caching is
+    /// disabled.
+    pub fn spawn_health_refresher(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let registry = Arc::downgrade(self);
+        let period = self.health_cache_ttl / 2;
+
+        tokio::spawn(async move {
+            if period.is_zero() {
+                return;
+            }
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(registry) = registry.upgrade() else {
+                    return;
+                };
+                registry.refresh_health().await;
+            }
+        })
+    }
+
+    async fn probe_all(&self) -> std::collections::HashMap<String, HealthStatus> {
         let mut results = std::collections::HashMap::new();
 
         for (name, provider) in &self.providers {
@@ -161,8 +329,13 @@ impl Default for ProviderRegistry {
 pub struct ProviderRegistryBuilder {
     openai_api_key: Option<String>,
     anthropic_api_key: Option<String>,
+    cohere_api_key: Option<String>,
+    mistral_api_key: Option<String>,
+    echo_provider: Option<echo::EchoProvider>,
     timeout_ms: u64,
-    max_retries: u32,
+    retry: crate::routing::strategies::RetryConfig,
+    max_request_bytes: std::collections::HashMap<String, usize>,
+    health_cache_ttl: Duration,
 }
 
 impl ProviderRegistryBuilder {
@@ -171,8 +344,13 @@ impl ProviderRegistryBuilder {
         Self {
             openai_api_key: None,
             anthropic_api_key: None,
+            cohere_api_key: None,
+            mistral_api_key: None,
+            echo_provider: None,
             timeout_ms: 30000, // 30 seconds default
-            max_retries: 3,
+            retry: crate::routing::strategies::RetryConfig::default(),
+            max_request_bytes: std::collections::HashMap::new(),
+            health_cache_ttl: DEFAULT_HEALTH_CACHE_TTL,
         }
     }
 
@@ -188,31 +366,95 @@ impl ProviderRegistryBuilder {
         self
     }
 
+    /// Set Cohere API key
+    pub fn with_cohere_key(mut self, api_key: impl Into<String>) -> Self {
+        self.cohere_api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set Mistral (La Plateforme) API key
+    pub fn with_mistral_key(mut self, api_key: impl Into<String>) -> Self {
+        self.mistral_api_key = Some(api_key.into());
+        self
+    }
+
+    /// Register an in-process echo provider (no API key or network needed)
+    pub fn with_echo_provider(mut self, provider: echo::EchoProvider) -> Self {
+        self.echo_provider = Some(provider);
+        self
+    }
+
     /// Set request timeout
     pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
         self.timeout_ms = timeout_ms;
         self
     }
 
-    /// Set max retries
+    /// Set max retries, for both errors and timeouts
     pub fn with_max_retries(mut self, max_retries: u32) -> Self {
-        self.max_retries = max_retries;
+        self.retry.max_retries_on_error = max_retries;
+        self.retry.max_retries_on_timeout = max_retries;
+        self
+    }
+
+    /// Set the full retry configuration (separate error and timeout budgets)
+    pub fn with_retry_config(mut self, retry: crate::routing::strategies::RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Override the request body limit for one provider (`openai`, `anthropic`, `cohere`, `mistral`)
+    pub fn with_max_request_bytes(mut self, provider: impl Into<String>, max_bytes: usize) -> Self {
+        self.max_request_bytes.insert(provider.into(), max_bytes);
+        self
+    }
+
+    /// Reuse health check results for `ttl` (default 10s; zero disables caching)
+    pub fn with_health_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.health_cache_ttl = ttl;
         self
     }
 
     /// Build the registry
     pub fn build(self) -> ProviderResult<ProviderRegistry> {
-        let mut registry = ProviderRegistry::new();
+        let mut registry = ProviderRegistry::new().with_health_cache_ttl(self.health_cache_ttl);
+        let max_request_bytes = |provider: &str, default: usize| {
+            self.max_request_bytes.get(provider).copied().unwrap_or(default)
+        };
 
         // Register OpenAI if API key provided
         if let Some(api_key) = self.openai_api_key {
-            let provider = openai::OpenAIProvider::new(api_key, self.timeout_ms, self.max_retries)?;
+            let provider = openai::OpenAIProvider::new(api_key, self.timeout_ms, self.retry.max_retries_on_error)?
+                .with_retry_config(self.retry.clone())
+                .with_max_request_bytes(max_request_bytes("openai", openai::DEFAULT_MAX_REQUEST_BYTES));
             registry.register(Arc::new(provider));
         }
 
         // Register Anthropic if API key provided
         if let Some(api_key) = self.anthropic_api_key {
-            let provider = anthropic::AnthropicProvider::new(api_key, self.timeout_ms, self.max_retries)?;
+            let provider = anthropic::AnthropicProvider::new(api_key, self.timeout_ms, self.retry.max_retries_on_error)?
+                .with_retry_config(self.retry.clone())
+                .with_max_request_bytes(max_request_bytes("anthropic", anthropic::DEFAULT_MAX_REQUEST_BYTES));
+            registry.register(Arc::new(provider));
+        }
+
+        // Register Cohere if API key provided
+        if let Some(api_key) = self.cohere_api_key {
+            let provider = cohere::CohereProvider::new(api_key, self.timeout_ms, self.retry.max_retries_on_error)?
+                .with_retry_config(self.retry.clone())
+                .with_max_request_bytes(max_request_bytes("cohere", cohere::DEFAULT_MAX_REQUEST_BYTES));
+            registry.register(Arc::new(provider));
+        }
+
+        // Register Mistral if API key provided
+        if let Some(api_key) = self.mistral_api_key {
+            let provider = mistral::MistralProvider::new(api_key, self.timeout_ms, self.retry.max_retries_on_error)?
+                .with_retry_config(self.retry.clone())
+                .with_max_request_bytes(max_request_bytes("mistral", mistral::DEFAULT_MAX_REQUEST_BYTES));
+            registry.register(Arc::new(provider));
+        }
+
+        if let Some(provider) = self.echo_provider {
             registry.register(Arc::new(provider));
         }
 
@@ -5,6 +5,7 @@ use super::{
     LLMProvider, LLMRequest, LLMResponse, Message, MessageContent, Choice, Usage,
     FinishReason, ProviderError, ProviderResult, HealthStatus, ProviderCapabilities, Role,
 };
+use crate::routing::strategies::{RetryBudget, RetryConfig};
 use async_trait::async_trait;
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
@@ -13,12 +14,17 @@ use std::time::{Duration, Instant};
 const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
 const OPENAI_HEALTH_MODEL: &str = "gpt-3.5-turbo";
 
+/// Default request body limit
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 32 * 1024 * 1024;
+
 /// OpenAI provider implementation
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
     timeout_ms: u64,
-    max_retries: u32,
+    retry: RetryConfig,
+    max_request_bytes: usize,
+    base_url: String,
 }
 
 impl OpenAIProvider {
@@ -38,125 +44,46 @@ impl OpenAIProvider {
             client,
             api_key,
             timeout_ms,
-            max_retries,
+            retry: RetryConfig::uniform(max_retries),
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            base_url: OPENAI_API_BASE.to_string(),
         })
     }
 
-    /// Transform our unified request to OpenAI format
-    fn transform_request(&self, request: &LLMRequest) -> OpenAIRequest {
-        OpenAIRequest {
-            model: request.model.clone(),
-            messages: request.messages.iter().map(|m| self.transform_message(m)).collect(),
-            max_tokens: request.max_tokens,
-            temperature: request.temperature,
-            top_p: request.top_p,
-            stop: request.stop_sequences.clone(),
-            stream: Some(request.stream),
-            n: Some(1),
-            presence_penalty: None,
-            frequency_penalty: None,
-            logit_bias: None,
-            user: request.metadata.as_ref().and_then(|m| m.user_id.clone()),
-        }
-    }
-
-    /// Transform a message to OpenAI format
-    fn transform_message(&self, message: &Message) -> OpenAIMessage {
-        let content = match &message.content {
-            MessageContent::Text(text) => text.clone(),
-            MessageContent::Parts(parts) => {
-                // For OpenAI, we'll serialize complex content as JSON
-                // In a real implementation, this should handle vision content properly
-                serde_json::to_string(parts).unwrap_or_default()
-            }
-        };
-
-        OpenAIMessage {
-            role: self.transform_role(&message.role),
-            content,
-            name: message.name.clone(),
-        }
-    }
-
-    /// Transform role to OpenAI format
-    fn transform_role(&self, role: &Role) -> String {
-        match role {
-            Role::System => "system".to_string(),
-            Role::User => "user".to_string(),
-            Role::Assistant => "assistant".to_string(),
-            Role::Function => "function".to_string(),
-            Role::Tool => "tool".to_string(),
-        }
+    /// Send requests to `base_url` (e.g. an OpenAI-compatible gateway)
+    /// instead of the public API
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
     }
 
-    /// Transform OpenAI response to our unified format
-    fn transform_response(&self, response: OpenAIResponse) -> LLMResponse {
-        LLMResponse {
-            id: response.id,
-            model: response.model,
-            choices: response.choices.into_iter().map(|c| {
-                Choice {
-                    index: c.index,
-                    message: Message {
-                        role: self.parse_role(&c.message.role),
-                        content: MessageContent::Text(c.message.content.unwrap_or_default()),
-                        name: c.message.name,
-                    },
-                    finish_reason: c.finish_reason.and_then(|r| self.parse_finish_reason(&r)),
-                }
-            }).collect(),
-            usage: Usage {
-                prompt_tokens: response.usage.prompt_tokens,
-                completion_tokens: response.usage.completion_tokens,
-                total_tokens: response.usage.total_tokens,
-            },
-            created: response.created,
-            metadata: None,
-        }
+    /// Use separate retry budgets and backoff for timeouts and other errors
+    pub fn with_retry_config(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
     }
 
-    /// Parse role from string
-    fn parse_role(&self, role: &str) -> Role {
-        match role {
-            "system" => Role::System,
-            "user" => Role::User,
-            "assistant" => Role::Assistant,
-            "function" => Role::Function,
-            "tool" => Role::Tool,
-            _ => Role::Assistant, // Default fallback
-        }
-    }
-
-    /// Parse finish reason
-    fn parse_finish_reason(&self, reason: &str) -> Option<FinishReason> {
-        match reason {
-            "stop" => Some(FinishReason::Stop),
-            "length" => Some(FinishReason::Length),
-            "content_filter" => Some(FinishReason::ContentFilter),
-            "tool_calls" | "function_call" => Some(FinishReason::ToolCalls),
-            _ => None,
-        }
+    /// Reject requests whose serialized body exceeds `max_request_bytes`
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
     }
 
     /// Send a request with retry logic
     async fn send_request(&self, request: &LLMRequest) -> ProviderResult<OpenAIResponse> {
-        let openai_request = self.transform_request(request);
-        let url = format!("{}/chat/completions", OPENAI_API_BASE);
-
-        let mut last_error = None;
+        let openai_request = transform_request(request);
+        let body = super::serialize_body("openai", &openai_request, self.max_request_bytes)?;
+        let url = format!("{}/chat/completions", self.base_url);
 
-        for attempt in 0..=self.max_retries {
-            if attempt > 0 {
-                // Exponential backoff: 100ms, 200ms, 400ms, etc.
-                let backoff_ms = 100 * (1 << (attempt - 1));
-                tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
-            }
+        let mut budget = RetryBudget::default();
 
-            match self.client
+        loop {
+            let error = match self.client
                 .post(&url)
+                .headers(request.extra_headers.clone())
                 .header(header::AUTHORIZATION, format!("Bearer {}", self.api_key))
                 .header(header::CONTENT_TYPE, "application/json")
-                .json(&openai_request)
+                .body(body.clone())
                 .send()
                 .await
             {
@@ -167,10 +94,9 @@ impl OpenAIProvider {
                         match response.json::<OpenAIResponse>().await {
                             Ok(openai_response) => return Ok(openai_response),
                             Err(e) => {
-                                last_error = Some(ProviderError::SerializationError(
+                                ProviderError::SerializationError(
                                     serde_json::Error::custom(format!("Failed to parse response: {}", e))
-                                ));
-                                continue;
+                                )
                             }
                         }
                     } else if status.as_u16() == 401 {
@@ -179,29 +105,32 @@ impl OpenAIProvider {
                         });
                     } else if status.as_u16() == 429 {
                         // Rate limit - retry
-                        last_error = Some(ProviderError::RateLimitExceeded {
+                        ProviderError::RateLimitExceeded {
                             message: "OpenAI rate limit exceeded".to_string(),
-                        });
-                        continue;
+                        }
                     } else {
                         let error_body = response.text().await.unwrap_or_default();
                         return Err(ProviderError::ProviderError {
+                            status: status.as_u16(),
                             message: format!("OpenAI API error ({}): {}", status, error_body),
                         });
                     }
                 }
                 Err(e) if e.is_timeout() => {
-                    last_error = Some(ProviderError::Timeout { timeout_ms: self.timeout_ms });
-                    continue;
+                    ProviderError::Timeout { timeout_ms: self.timeout_ms }
                 }
                 Err(e) => {
-                    last_error = Some(ProviderError::HttpError(e));
-                    continue;
+                    ProviderError::HttpError(e)
                 }
+            };
+
+            // Timeouts and other failures draw on separate retry budgets
+            let timed_out = matches!(error, ProviderError::Timeout { .. });
+            match self.retry.next_retry(&mut budget, timed_out) {
+                Some(backoff) => self.retry.sleep(backoff).await,
+                None => return Err(error),
             }
         }
-
-        Err(last_error.unwrap_or_else(|| ProviderError::InternalError("Unknown error".to_string())))
     }
 }
 
@@ -221,6 +150,41 @@ impl LLMProvider for OpenAIProvider {
         }
     }
 
+    fn capabilities_for(&self, model: &str) -> ProviderCapabilities {
+        let base = self.capabilities();
+        match model {
+            m if m.starts_with("o1") => ProviderCapabilities {
+                supports_streaming: false,
+                supports_function_calling: false,
+                supports_vision: false,
+                max_context_tokens: 128000,
+                max_output_tokens: if m.starts_with("o1-mini") { 65536 } else { 32768 },
+            },
+            m if m.starts_with("gpt-4o") => ProviderCapabilities {
+                max_output_tokens: 16384,
+                ..base
+            },
+            // Vision arrived with the GA turbo model, not the preview
+            m if m.starts_with("gpt-4-turbo-preview") => ProviderCapabilities {
+                supports_vision: false,
+                ..base
+            },
+            m if m.starts_with("gpt-4-turbo") => base,
+            m if m.starts_with("gpt-4") => ProviderCapabilities {
+                supports_vision: false,
+                max_context_tokens: 8192,
+                max_output_tokens: 8192,
+                ..base
+            },
+            m if m.starts_with("gpt-3.5-turbo") => ProviderCapabilities {
+                supports_vision: false,
+                max_context_tokens: 16385,
+                ..base
+            },
+            _ => base,
+        }
+    }
+
     async fn complete(&self, request: LLMRequest) -> ProviderResult<LLMResponse> {
         let start = Instant::now();
 
@@ -232,7 +196,7 @@ impl LLMProvider for OpenAIProvider {
         }
 
         let openai_response = self.send_request(&request).await?;
-        let response = self.transform_response(openai_response);
+        let response = transform_response(&request, openai_response, parse_finish_reason);
 
         let elapsed = start.elapsed();
         tracing::info!(
@@ -250,7 +214,7 @@ impl LLMProvider for OpenAIProvider {
         let start = Instant::now();
 
         // Simple health check: try to list models
-        let url = format!("{}/models", OPENAI_API_BASE);
+        let url = format!("{}/models", self.base_url);
 
         match self.client
             .get(&url)
@@ -299,6 +263,131 @@ impl LLMProvider for OpenAIProvider {
     }
 }
 
+/// Transform our unified request to OpenAI format
+fn transform_request(request: &LLMRequest) -> OpenAIRequest {
+    OpenAIRequest {
+        model: request.model.clone(),
+        messages: request.messages.iter().map(transform_message).collect(),
+        max_tokens: request.max_tokens,
+        temperature: request.temperature,
+        top_p: request.top_p,
+        stop: request.stop_sequences.clone(),
+        stream: Some(request.stream),
+        n: Some(1),
+        presence_penalty: None,
+        frequency_penalty: None,
+        logit_bias: None,
+        user: request.metadata.as_ref().and_then(|m| m.user_id.clone()),
+    }
+}
+
+/// Transform a message to OpenAI format
+pub(super) fn transform_message(message: &Message) -> OpenAIMessage {
+    let content = match &message.content {
+        MessageContent::Text(text) => text.clone(),
+        MessageContent::Parts(parts) => {
+            // For OpenAI, we'll serialize complex content as JSON
+            // In a real implementation, this should handle vision content properly
+            serde_json::to_string(parts).unwrap_or_default()
+        }
+    };
+
+    OpenAIMessage {
+        role: transform_role(&message.role),
+        content,
+        name: message.name.clone(),
+    }
+}
+
+/// Transform role to OpenAI format
+fn transform_role(role: &Role) -> String {
+    match role {
+        Role::System => "system".to_string(),
+        Role::User => "user".to_string(),
+        Role::Assistant => "assistant".to_string(),
+        Role::Function => "function".to_string(),
+        Role::Tool => "tool".to_string(),
+    }
+}
+
+/// Transform an OpenAI-shaped response to our unified format
+///
+/// `parse_finish_reason` maps the provider's finish reasons, so
+/// OpenAI-compatible APIs with extra reasons can reuse this. Some gateways
+/// leave out `usage`; it is then estimated from `request` and the
+/// completion, and the response metadata gets `usage_estimated: true`.
+pub(super) fn transform_response(
+    request: &LLMRequest,
+    response: OpenAIResponse,
+    parse_finish_reason: impl Fn(&str) -> Option<FinishReason>,
+) -> LLMResponse {
+    let choices: Vec<Choice> = response.choices.into_iter().map(|c| {
+        Choice {
+            index: c.index,
+            message: Message {
+                role: parse_role(&c.message.role),
+                content: MessageContent::Text(c.message.content.unwrap_or_default()),
+                name: c.message.name,
+            },
+            finish_reason: c.finish_reason.and_then(|r| parse_finish_reason(&r)),
+        }
+    }).collect();
+
+    let (usage, metadata) = match response.usage {
+        Some(usage) => (
+            Usage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                total_tokens: usage.total_tokens,
+            },
+            None,
+        ),
+        None => {
+            tracing::warn!(
+                model = %response.model,
+                "Response has no usage, estimating token counts"
+            );
+            let metadata = std::collections::HashMap::from([(
+                "usage_estimated".to_string(),
+                serde_json::Value::Bool(true),
+            )]);
+            (Usage::estimate(request, &choices), Some(metadata))
+        }
+    };
+
+    LLMResponse {
+        id: response.id,
+        model: response.model,
+        choices,
+        usage,
+        created: response.created,
+        metadata,
+    }
+}
+
+/// Parse role from string
+fn parse_role(role: &str) -> Role {
+    match role {
+        "system" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        "function" => Role::Function,
+        "tool" => Role::Tool,
+        _ => Role::Assistant, // Default fallback
+    }
+}
+
+/// Parse finish reason
+pub(super) fn parse_finish_reason(reason: &str) -> Option<FinishReason> {
+    match reason {
+        "stop" => Some(FinishReason::Stop),
+        "length" => Some(FinishReason::Length),
+        "content_filter" => Some(FinishReason::ContentFilter),
+        "tool_calls" | "function_call" => Some(FinishReason::ToolCalls),
+        _ => None,
+    }
+}
+
 // OpenAI API request format
 #[derive(Debug, Serialize)]
 struct OpenAIRequest {
@@ -327,7 +416,7 @@ struct OpenAIRequest {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct OpenAIMessage {
+pub(super) struct OpenAIMessage {
     role: String,
     content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -335,12 +424,14 @@ struct OpenAIMessage {
 }
 
 #[derive(Debug, Deserialize)]
-struct OpenAIResponse {
+pub(super) struct OpenAIResponse {
     id: String,
     model: String,
     created: i64,
     choices: Vec<OpenAIChoice>,
-    usage: OpenAIUsage,
+    /// Left out by some OpenAI-compatible gateways
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -386,4 +477,104 @@ mod tests {
         assert!(!models.is_empty());
         assert!(models.contains(&"gpt-4".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_oversized_request_rejected_before_sending() {
+        let provider = OpenAIProvider::new("test-key".to_string(), 30000, 0)
+            .unwrap()
+            .with_max_request_bytes(1024);
+        let request = LLMRequest::new("gpt-4", vec![]).with_user_message("x".repeat(2048));
+
+        match provider.send_request(&request).await {
+            Err(ProviderError::InvalidRequest { message }) => {
+                assert!(message.contains("exceeding the 1024 byte limit"));
+            }
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("oversized request was sent"),
+        }
+    }
+
+    fn parse_response(usage: Option<serde_json::Value>) -> OpenAIResponse {
+        let mut body = serde_json::json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-4",
+            "created": 1700000000,
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello there!"},
+                "finish_reason": "stop"
+            }]
+        });
+        if let Some(usage) = usage {
+            body["usage"] = usage;
+        }
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_missing_usage_is_estimated() {
+        let request = LLMRequest::new("gpt-4", vec![]).with_user_message("Say hello to me");
+        let response = transform_response(&request, parse_response(None), |_| None);
+
+        // 15 prompt chars and 12 completion chars at ~4 chars per token
+        assert_eq!(response.usage.prompt_tokens, 4);
+        assert_eq!(response.usage.completion_tokens, 3);
+        assert_eq!(response.usage.total_tokens, 7);
+        assert_eq!(
+            response.metadata.unwrap()["usage_estimated"],
+            serde_json::Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_reported_usage_is_kept() {
+        let request = LLMRequest::new("gpt-4", vec![]).with_user_message("Say hello to me");
+        let usage = serde_json::json!({
+            "prompt_tokens": 10,
+            "completion_tokens": 20,
+            "total_tokens": 30
+        });
+        let response = transform_response(&request, parse_response(Some(usage)), |_| None);
+
+        assert_eq!(response.usage.prompt_tokens, 10);
+        assert_eq!(response.usage.completion_tokens, 20);
+        assert_eq!(response.usage.total_tokens, 30);
+        assert!(response.metadata.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_not_failed_over() {
+        use crate::routing::strategies::{ClassifyFailure, StatusClass};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(r#"{"error":{"message":"bad"}}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let provider = OpenAIProvider::new("test-key".to_string(), 30000, 3)
+            .unwrap()
+            .with_base_url(server.uri());
+        let request = LLMRequest::new("gpt-4", vec![]).with_user_message("hi");
+
+        let err = provider.complete(request.clone()).await.unwrap_err();
+        assert!(matches!(err, ProviderError::ProviderError { status: 400, .. }), "{}", err);
+        assert_eq!(err.status_class(), Some(StatusClass::Client4xx));
+        assert!(!RetryConfig::default().should_failover(err.status_class()));
+
+        // A 5xx is still failed over
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let err = provider.complete(request).await.unwrap_err();
+        assert_eq!(err.status_class(), Some(StatusClass::Server5xx));
+        assert!(RetryConfig::default().should_failover(err.status_class()));
+    }
 }
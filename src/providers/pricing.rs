@@ -190,6 +190,53 @@ pub static PRICING_DB: Lazy<HashMap<&'static str, ModelPricing>> = Lazy::new(||
         updated_at: "2025-01-01",
     });
 
+    // Cohere Command models
+    db.insert("command-r", ModelPricing {
+        model: "command-r".to_string(),
+        provider: "cohere".to_string(),
+        input_cost_per_1k: 0.00015,
+        output_cost_per_1k: 0.0006,
+        available: true,
+        updated_at: "2025-01-01",
+    });
+
+    db.insert("command-r-plus", ModelPricing {
+        model: "command-r-plus".to_string(),
+        provider: "cohere".to_string(),
+        input_cost_per_1k: 0.0025,
+        output_cost_per_1k: 0.01,
+        available: true,
+        updated_at: "2025-01-01",
+    });
+
+    // Mistral models
+    db.insert("mistral-large-latest", ModelPricing {
+        model: "mistral-large-latest".to_string(),
+        provider: "mistral".to_string(),
+        input_cost_per_1k: 0.002,
+        output_cost_per_1k: 0.006,
+        available: true,
+        updated_at: "2025-01-01",
+    });
+
+    db.insert("mistral-small-latest", ModelPricing {
+        model: "mistral-small-latest".to_string(),
+        provider: "mistral".to_string(),
+        input_cost_per_1k: 0.0002,
+        output_cost_per_1k: 0.0006,
+        available: true,
+        updated_at: "2025-01-01",
+    });
+
+    db.insert("codestral-latest", ModelPricing {
+        model: "codestral-latest".to_string(),
+        provider: "mistral".to_string(),
+        input_cost_per_1k: 0.0003,
+        output_cost_per_1k: 0.0009,
+        available: true,
+        updated_at: "2025-01-01",
+    });
+
     db
 });
 
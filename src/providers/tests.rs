@@ -211,6 +211,52 @@ mod provider_tests {
         assert_eq!(caps.max_context_tokens, 200000);
     }
 
+    #[test]
+    fn test_capabilities_resolved_per_model() {
+        let provider = openai::OpenAIProvider::new(
+            "test-key".to_string(),
+            30000,
+            3
+        ).unwrap();
+
+        let turbo = provider.model_info("gpt-4-turbo");
+        assert!(turbo.supports_vision);
+        assert!(turbo.supports_tools);
+        assert_eq!(turbo.max_context_tokens, 128000);
+
+        let gpt4 = provider.model_info("gpt-4");
+        assert!(!gpt4.supports_vision);
+        assert_eq!(gpt4.max_context_tokens, 8192);
+        assert!(gpt4.pricing.is_some());
+
+        let o1 = provider.model_info("o1-preview");
+        assert!(!o1.supports_tools);
+        assert_eq!(o1.max_output_tokens, 32768);
+    }
+
+    #[test]
+    fn test_registry_model_list() {
+        let registry = ProviderRegistryBuilder::new()
+            .with_anthropic_key("test-key")
+            .with_cohere_key("test-key")
+            .build()
+            .unwrap();
+
+        let list = serde_json::to_value(registry.list_models()).unwrap();
+        assert_eq!(list["object"], "list");
+
+        let models = list["data"].as_array().unwrap();
+        let opus = models.iter().find(|m| m["id"] == "claude-3-opus").unwrap();
+        assert_eq!(opus["object"], "model");
+        assert_eq!(opus["owned_by"], "anthropic");
+        assert_eq!(opus["supports_vision"], true);
+        assert_eq!(opus["max_context_tokens"], 200000);
+        assert!(opus["pricing"]["input_cost_per_1k"].as_f64().unwrap() > 0.0);
+
+        let command = models.iter().find(|m| m["owned_by"] == "cohere").unwrap();
+        assert_eq!(command["supports_vision"], false);
+    }
+
     #[test]
     fn test_registry_builder() {
         let builder = ProviderRegistryBuilder::new()
@@ -222,6 +268,43 @@ mod provider_tests {
         assert!(true);
     }
 
+    #[test]
+    fn test_registry_routes_command_models_to_cohere() {
+        let registry = ProviderRegistryBuilder::new()
+            .with_cohere_key("test-key")
+            .build()
+            .unwrap();
+
+        let provider = registry.get_for_model("command-r-plus").unwrap();
+        assert_eq!(provider.name(), "cohere");
+    }
+
+    #[test]
+    fn test_registry_routes_mistral_models() {
+        let registry = ProviderRegistryBuilder::new()
+            .with_mistral_key("test-key")
+            .build()
+            .unwrap();
+
+        assert_eq!(registry.get_for_model("mistral-large-latest").unwrap().name(), "mistral");
+        assert_eq!(registry.get_for_model("codestral-latest").unwrap().name(), "mistral");
+    }
+
+    #[tokio::test]
+    async fn test_registry_with_echo_provider() {
+        let registry = ProviderRegistryBuilder::new()
+            .with_echo_provider(echo::EchoProvider::new())
+            .build()
+            .unwrap();
+
+        let provider = registry.get_for_model(echo::ECHO_MODEL).unwrap();
+        assert_eq!(provider.name(), "echo");
+
+        let request = LLMRequest::new(echo::ECHO_MODEL, vec![]).with_user_message("offline");
+        let response = provider.complete(request).await.unwrap();
+        assert_eq!(response.choices[0].message.content, MessageContent::Text("offline".to_string()));
+    }
+
     #[test]
     fn test_error_types() {
         let error = ProviderError::InvalidApiKey {
@@ -274,6 +357,124 @@ mod provider_tests {
         // This tests the logic in get_for_model
         assert!(registry.get_for_model("gpt-4").is_none()); // No providers registered
         assert!(registry.get_for_model("claude-3-opus").is_none()); // No providers registered
+        assert!(registry.get_for_model("command-r").is_none()); // No providers registered
+        assert!(registry.get_for_model("codestral-latest").is_none()); // No providers registered
+    }
+
+    /// Provider counting its health probes, each taking `probe_time`
+    struct ProbeCounter {
+        probes: std::sync::atomic::AtomicUsize,
+        probe_time: std::time::Duration,
+    }
+
+    impl ProbeCounter {
+        fn new(probe_time: std::time::Duration) -> std::sync::Arc<Self> {
+            std::sync::Arc::new(Self {
+                probes: Default::default(),
+                probe_time,
+            })
+        }
+
+        fn probes(&self) -> usize {
+            self.probes.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for ProbeCounter {
+        fn name(&self) -> &str {
+            "probe-counter"
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities {
+                supports_streaming: false,
+                supports_function_calling: false,
+                supports_vision: false,
+                max_context_tokens: 8192,
+                max_output_tokens: 1024,
+            }
+        }
+
+        async fn complete(&self, _request: LLMRequest) -> ProviderResult<LLMResponse> {
+            Err(ProviderError::InternalError("not used".to_string()))
+        }
+
+        async fn health_check(&self) -> ProviderResult<HealthStatus> {
+            self.probes.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::time::sleep(self.probe_time).await;
+            Ok(HealthStatus {
+                healthy: true,
+                last_check: chrono::Utc::now().timestamp(),
+                response_time_ms: None,
+                error: None,
+            })
+        }
+
+        fn list_models(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_checks_within_ttl_probe_once() {
+        let provider = ProbeCounter::new(std::time::Duration::ZERO);
+        let mut registry =
+            ProviderRegistry::new().with_health_cache_ttl(std::time::Duration::from_millis(200));
+        registry.register(provider.clone());
+
+        for _ in 0..5 {
+            let health = registry.health_check_all().await;
+            assert!(health["probe-counter"].healthy);
+        }
+        assert_eq!(provider.probes(), 1);
+
+        // Concurrent callers wait for the one probe in flight
+        let slow = ProbeCounter::new(std::time::Duration::from_millis(50));
+        let mut registry =
+            ProviderRegistry::new().with_health_cache_ttl(std::time::Duration::from_secs(10));
+        registry.register(slow.clone());
+        let registry = std::sync::Arc::new(registry);
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let registry = registry.clone();
+                tokio::spawn(async move { registry.health_check_all().await })
+            })
+            .collect();
+        for caller in callers {
+            assert_eq!(caller.await.unwrap().len(), 1);
+        }
+        assert_eq!(slow.probes(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_health_cache_expires_and_refresher_keeps_it_current() {
+        let provider = ProbeCounter::new(std::time::Duration::ZERO);
+        let mut registry =
+            ProviderRegistry::new().with_health_cache_ttl(std::time::Duration::from_millis(40));
+        registry.register(provider.clone());
+
+        registry.health_check_all().await;
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        registry.health_check_all().await;
+        assert_eq!(provider.probes(), 2);
+
+        // With the refresher running, callers are served from the cache
+        let registry = std::sync::Arc::new(registry);
+        let refresher = registry.spawn_health_refresher();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let refreshed = provider.probes();
+        assert!(refreshed > 2, "refresher probed {} times", refreshed - 2);
+
+        registry.health_check_all().await;
+        assert!(provider.probes() <= refreshed + 1);
+
+        // Stops once the registry is gone
+        drop(registry);
+        tokio::time::timeout(std::time::Duration::from_secs(1), refresher)
+            .await
+            .unwrap()
+            .unwrap();
     }
 }
 
@@ -1,6 +1,7 @@
 // Unified types for LLM provider abstraction
 // This module defines provider-agnostic types for requests and responses
 
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -44,6 +45,10 @@ pub struct LLMRequest {
     /// Request metadata for tracing
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<RequestMetadata>,
+
+    /// Client headers forwarded to the provider (already allowlisted by the handler)
+    #[serde(skip)]
+    pub extra_headers: HeaderMap,
 }
 
 /// Message in a conversation
@@ -214,6 +219,45 @@ pub struct ProviderCapabilities {
     pub max_output_tokens: u32,
 }
 
+/// Entry in the `/v1/models` list: an OpenAI model object plus capabilities
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    /// Model identifier
+    pub id: String,
+
+    /// Always `"model"`
+    pub object: &'static str,
+
+    /// Provider serving the model
+    pub owned_by: String,
+
+    /// Accepts image input
+    pub supports_vision: bool,
+
+    /// Supports tool/function calling
+    pub supports_tools: bool,
+
+    /// Context window size
+    pub max_context_tokens: u32,
+
+    /// Maximum output tokens
+    pub max_output_tokens: u32,
+
+    /// Pricing, if the model is in the pricing database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<crate::providers::ModelPricing>,
+}
+
+/// OpenAI-compatible `/v1/models` response body
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelList {
+    /// Always `"list"`
+    pub object: &'static str,
+
+    /// Models across all registered providers
+    pub data: Vec<ModelInfo>,
+}
+
 impl LLMRequest {
     /// Create a simple text request
     pub fn new(model: impl Into<String>, messages: Vec<Message>) -> Self {
@@ -228,6 +272,7 @@ impl LLMRequest {
             stream: false,
             extra_params: None,
             metadata: None,
+            extra_headers: HeaderMap::new(),
         }
     }
 
@@ -258,6 +303,53 @@ impl LLMRequest {
         self.stream = stream;
         self
     }
+
+    /// Set headers to forward to the provider
+    pub fn with_extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+}
+
+/// Characters per token assumed when estimating usage
+const ESTIMATED_CHARS_PER_TOKEN: usize = 4;
+
+impl Usage {
+    /// Rough usage of `request` and its `choices`, for responses that
+    /// report none
+    ///
+    /// Counts about four characters per token; good enough for cost
+    /// tracking and limits, not for reconciling a provider's bill.
+    pub fn estimate(request: &LLMRequest, choices: &[Choice]) -> Self {
+        let tokens = |messages: &mut dyn Iterator<Item = &Message>| -> u32 {
+            let chars: usize = messages.map(|m| m.content.text_len()).sum();
+            chars.div_ceil(ESTIMATED_CHARS_PER_TOKEN) as u32
+        };
+        let prompt_tokens = tokens(&mut request.messages.iter());
+        let completion_tokens = tokens(&mut choices.iter().map(|c| &c.message));
+
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+}
+
+impl MessageContent {
+    /// Characters of text, ignoring images
+    fn text_len(&self) -> usize {
+        match self {
+            MessageContent::Text(text) => text.chars().count(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ContentPart::Text { text } => text.chars().count(),
+                    ContentPart::Image { .. } => 0,
+                })
+                .sum(),
+        }
+    }
 }
 
 impl Message {
@@ -12,11 +12,16 @@
 //! - Failure threshold: 5 consecutive failures
 //! - Timeout: 30 seconds before attempting recovery
 //! - Success threshold (half-open): 2 consecutive successes
+//! - Half-open probes: 1 request in flight at a time
+//! - Slow calls: optionally, the circuit also opens for `timeout` once too
+//!   many of the most recent calls took longer than a duration threshold
 
-use failsafe::{CircuitBreaker, Config, Error as FailsafeError};
-use std::sync::Arc;
-use std::time::Duration;
+use failsafe::{CircuitBreaker, Config, Error as FailsafeError, State};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
 
 /// Circuit breaker error types
@@ -44,6 +49,27 @@ pub struct LLMCircuitBreakerConfig {
     /// Number of successes required in half-open state
     pub success_threshold: u32,
     
+    /// Maximum concurrent probe requests in half-open state
+    ///
+    /// Requests beyond this are rejected as if the circuit were open.
+    pub half_open_max_calls: u32,
+    
+    /// Calls taking longer than this count as slow (`None` disables
+    /// slow-call tracking)
+    ///
+    /// A provider answering successfully but far outside the latency SLO is
+    /// as unusable as one that fails.
+    pub slow_call_threshold: Option<Duration>,
+    
+    /// Proportion of slow calls (0.0 to 1.0) in the window at which the
+    /// circuit opens
+    pub slow_call_rate_threshold: f64,
+    
+    /// Number of most recent calls the slow-call rate is measured over
+    ///
+    /// The rate is only evaluated once the window is full.
+    pub slow_call_window: usize,
+    
     /// Provider name for logging
     pub provider_name: String,
 }
@@ -54,15 +80,32 @@ impl Default for LLMCircuitBreakerConfig {
             failure_threshold: 5,
             timeout: Duration::from_secs(30),
             success_threshold: 2,
+            half_open_max_calls: 1,
+            slow_call_threshold: None,
+            slow_call_rate_threshold: 0.5,
+            slow_call_window: 20,
             provider_name: "unknown".to_string(),
         }
     }
 }
 
+/// Recent calls for slow-call detection
+#[derive(Debug, Default)]
+struct SlowCalls {
+    /// Whether each of the most recent calls was slow, oldest first
+    window: VecDeque<bool>,
+    /// The circuit is held open for slow calls until then
+    open_until: Option<Instant>,
+}
+
 /// Wrapper around failsafe CircuitBreaker with LLM-specific logic
 pub struct LLMCircuitBreaker {
     breaker: Arc<CircuitBreaker>,
     config: LLMCircuitBreakerConfig,
+    /// Permits for in-flight half-open probes
+    half_open_probes: Arc<Semaphore>,
+    /// Durations of recent calls, when slow-call tracking is enabled
+    slow_calls: Arc<Mutex<SlowCalls>>,
 }
 
 impl LLMCircuitBreaker {
@@ -76,11 +119,15 @@ impl LLMCircuitBreaker {
             provider = %config.provider_name,
             failure_threshold = config.failure_threshold,
             timeout_secs = config.timeout.as_secs(),
+            half_open_max_calls = config.half_open_max_calls,
+            slow_call_threshold_ms = config.slow_call_threshold.map(|t| t.as_millis() as u64),
             "Initialized circuit breaker"
         );
         
         Self {
             breaker: Arc::new(CircuitBreaker::new(cb_config)),
+            half_open_probes: Arc::new(Semaphore::new(config.half_open_max_calls.max(1) as usize)),
+            slow_calls: Arc::new(Mutex::new(SlowCalls::default())),
             config,
         }
     }
@@ -92,7 +139,7 @@ impl LLMCircuitBreaker {
         E: std::error::Error + Send + Sync + 'static,
     {
         // Check circuit state
-        if self.breaker.is_open() {
+        if self.is_open() {
             warn!(
                 provider = %self.config.provider_name,
                 "Circuit breaker is OPEN, failing fast"
@@ -102,6 +149,25 @@ impl LLMCircuitBreaker {
             ));
         }
         
+        // Only a bounded number of probes may test recovery at once; the
+        // permit is held until the probe completes
+        let _probe = if matches!(self.breaker.state(), State::HalfOpen) {
+            match self.half_open_probes.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    debug!(
+                        provider = %self.config.provider_name,
+                        "Half-open probe limit reached, failing fast"
+                    );
+                    return Err(CircuitBreakerError::Open(
+                        self.config.provider_name.clone()
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+        
         debug!(
             provider = %self.config.provider_name,
             state = ?self.breaker.state(),
@@ -109,7 +175,13 @@ impl LLMCircuitBreaker {
         );
         
         // Execute the request
-        match self.breaker.call(f).await {
+        let start = Instant::now();
+        let result = self.breaker.call(f).await;
+        if !matches!(result, Err(FailsafeError::Rejected)) {
+            self.record_duration(start.elapsed());
+        }
+        
+        match result {
             Ok(result) => {
                 debug!(
                     provider = %self.config.provider_name,
@@ -137,14 +209,61 @@ impl LLMCircuitBreaker {
         }
     }
     
+    /// Record how long a completed call took, opening the circuit if the
+    /// slow-call rate threshold is reached
+    fn record_duration(&self, elapsed: Duration) {
+        let Some(threshold) = self.config.slow_call_threshold else {
+            return;
+        };
+        let size = self.config.slow_call_window.max(1);
+        
+        let mut slow_calls = self.slow_calls.lock().unwrap();
+        slow_calls.window.push_back(elapsed > threshold);
+        if slow_calls.window.len() > size {
+            slow_calls.window.pop_front();
+        }
+        if slow_calls.window.len() < size {
+            return;
+        }
+        
+        let slow = slow_calls.window.iter().filter(|slow| **slow).count();
+        let rate = slow as f64 / size as f64;
+        if rate >= self.config.slow_call_rate_threshold {
+            warn!(
+                provider = %self.config.provider_name,
+                slow_call_rate = rate,
+                threshold_ms = threshold.as_millis() as u64,
+                "Too many slow calls, opening circuit"
+            );
+            slow_calls.window.clear();
+            slow_calls.open_until = Some(Instant::now() + self.config.timeout);
+        }
+    }
+    
+    /// Whether the circuit is held open because too many calls were slow
+    fn is_open_for_slow_calls(&self) -> bool {
+        let mut slow_calls = self.slow_calls.lock().unwrap();
+        match slow_calls.open_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                slow_calls.open_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+    
     /// Get current circuit breaker state
     pub fn state(&self) -> String {
+        if self.is_open_for_slow_calls() {
+            return "Open".to_string();
+        }
         format!("{:?}", self.breaker.state())
     }
     
     /// Check if circuit is open
     pub fn is_open(&self) -> bool {
-        self.breaker.is_open()
+        self.breaker.is_open() || self.is_open_for_slow_calls()
     }
     
     /// Get failure count (for metrics)
@@ -186,7 +305,9 @@ mod tests {
             failure_threshold: 3,
             timeout: Duration::from_secs(1),
             success_threshold: 1,
+            half_open_max_calls: 1,
             provider_name: "test-provider".to_string(),
+            ..LLMCircuitBreakerConfig::default()
         };
         
         let cb = LLMCircuitBreaker::new(config);
@@ -223,7 +344,9 @@ mod tests {
             failure_threshold: 5,
             timeout: Duration::from_secs(30),
             success_threshold: 2,
+            half_open_max_calls: 1,
             provider_name: "test-provider".to_string(),
+            ..LLMCircuitBreakerConfig::default()
         };
         
         let cb = LLMCircuitBreaker::new(config);
@@ -237,4 +360,123 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "success");
     }
+    
+    async fn probes_reaching_provider(half_open_max_calls: u32) -> u32 {
+        let config = LLMCircuitBreakerConfig {
+            failure_threshold: 2,
+            timeout: Duration::from_millis(50),
+            success_threshold: 1,
+            half_open_max_calls,
+            provider_name: "test-provider".to_string(),
+            ..LLMCircuitBreakerConfig::default()
+        };
+        let cb = LLMCircuitBreaker::new(config);
+        
+        // Open the circuit
+        for _ in 0..2 {
+            let _ = cb.call(|| {
+                Box::pin(async {
+                    Err::<(), _>(std::io::Error::new(std::io::ErrorKind::Other, "down"))
+                })
+            }).await;
+        }
+        assert!(cb.is_open());
+        
+        // Past the timeout the circuit is half-open
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        
+        let reached = Arc::new(AtomicU32::new(0));
+        let calls = (0..10).map(|_| {
+            let reached = reached.clone();
+            cb.call(move || {
+                Box::pin(async move {
+                    reached.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok::<_, std::io::Error>(())
+                })
+            })
+        });
+        let results = futures::future::join_all(calls).await;
+        
+        let rejected = results
+            .iter()
+            .filter(|r| matches!(r, Err(CircuitBreakerError::Open(_))))
+            .count() as u32;
+        assert_eq!(rejected, 10 - reached.load(Ordering::SeqCst));
+        
+        reached.load(Ordering::SeqCst)
+    }
+    
+    fn slow_call_breaker() -> LLMCircuitBreaker {
+        LLMCircuitBreaker::new(LLMCircuitBreakerConfig {
+            timeout: Duration::from_secs(30),
+            slow_call_threshold: Some(Duration::from_millis(20)),
+            slow_call_rate_threshold: 0.5,
+            slow_call_window: 4,
+            provider_name: "test-provider".to_string(),
+            ..LLMCircuitBreakerConfig::default()
+        })
+    }
+    
+    async fn call_taking(
+        cb: &LLMCircuitBreaker,
+        duration: Duration,
+    ) -> Result<(), CircuitBreakerError> {
+        cb.call(move || {
+            Box::pin(async move {
+                tokio::time::sleep(duration).await;
+                Ok::<_, std::io::Error>(())
+            })
+        }).await
+    }
+    
+    #[tokio::test]
+    async fn test_slow_successful_calls_open_circuit() {
+        let cb = slow_call_breaker();
+        
+        for _ in 0..4 {
+            assert!(call_taking(&cb, Duration::from_millis(40)).await.is_ok());
+        }
+        
+        // Every call succeeded, but too slowly
+        assert!(cb.is_open());
+        assert!(!cb.health().is_healthy);
+        assert!(matches!(
+            call_taking(&cb, Duration::ZERO).await,
+            Err(CircuitBreakerError::Open(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_occasional_slow_call_keeps_circuit_closed() {
+        let cb = slow_call_breaker();
+        
+        // One slow call in each window of four stays below the 50% rate
+        for i in 0..8 {
+            let duration = if i % 4 == 0 {
+                Duration::from_millis(40)
+            } else {
+                Duration::ZERO
+            };
+            assert!(call_taking(&cb, duration).await.is_ok());
+        }
+        assert!(!cb.is_open());
+        
+        // Slow-call tracking is off by default
+        let cb = LLMCircuitBreaker::new(LLMCircuitBreakerConfig::default());
+        for _ in 0..4 {
+            assert!(call_taking(&cb, Duration::from_millis(40)).await.is_ok());
+        }
+        assert!(!cb.is_open());
+    }
+    
+    #[tokio::test]
+    async fn test_half_open_admits_single_probe_by_default() {
+        assert_eq!(probes_reaching_provider(1).await, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_half_open_max_calls_bounds_concurrent_probes() {
+        assert_eq!(probes_reaching_provider(3).await, 3);
+    }
 }
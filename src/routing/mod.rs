@@ -1,20 +1,24 @@
 //! Routing engine for LLM Edge Agent
 //!
 //! This module provides intelligent routing capabilities for LLM requests:
-//! - Multiple routing strategies (round-robin, failover, least-latency, cost-optimized)
+//! - Multiple routing strategies (round-robin, failover, least-latency, cost-optimized,
+//!   per-model preferences, weighted with error-rate penalties)
 //! - Circuit breaker pattern for resilience
 //! - Provider health monitoring
 //! - Automatic failover and retry with exponential backoff
+//! - Providers rejecting their API key are taken out of rotation
 
 pub mod circuit_breaker;
 pub mod strategies;
 
+use crate::observability::{ProviderMetrics, RoutingMetrics};
 use crate::routing::circuit_breaker::{CircuitBreakerHealth, LLMCircuitBreaker, LLMCircuitBreakerConfig};
 use crate::routing::strategies::{
     Provider, ProviderWithHealth, RoutingStrategy, RoundRobinStrategy,
-    FailoverChainStrategy, LeastLatencyStrategy, CostOptimizedStrategy, RetryConfig,
+    FailoverChainStrategy, LeastLatencyStrategy, CostOptimizedStrategy, RetryConfig, RetryBudget,
+    ModelRoutingStrategy, RoutingTable, ClassifyFailure, StatusClass, WeightedStrategy,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -38,8 +42,26 @@ pub enum RoutingError {
     
     #[error("Provider error: {0}")]
     ProviderError(String),
+
+    #[error("Provider {provider} failed with {class:?}, not failing over: {message}")]
+    NonRetryable {
+        provider: String,
+        class: StatusClass,
+        message: String,
+    },
 }
 
+/// Default half-life for the decayed success/failure counters
+pub const DEFAULT_HEALTH_HALF_LIFE: Duration = Duration::from_secs(300);
+
+/// Decayed outcome count below which a provider's history is only partly
+/// trusted: as its outcomes fade, its success rate drifts back towards 1.0
+pub const MIN_HEALTH_EVIDENCE: f64 = 1.0;
+
+/// Default time a provider that rejected its API key sits out before one
+/// request is let through to probe it again
+pub const DEFAULT_AUTH_RETRY_AFTER: Duration = Duration::from_secs(300);
+
 /// Health metrics for a provider
 #[derive(Debug, Clone)]
 pub struct ProviderHealth {
@@ -49,6 +71,16 @@ pub struct ProviderHealth {
     pub avg_latency_ms: f64,
     pub last_success: Option<Instant>,
     pub last_failure: Option<Instant>,
+    /// Exponentially time-decayed success count
+    pub decayed_successes: f64,
+    /// Exponentially time-decayed failure count
+    pub decayed_failures: f64,
+    /// When the decayed counters were last brought up to date
+    pub last_decay: Option<Instant>,
+    /// When the provider last rejected our API key. Excluded from routing
+    /// for the engine's auth retry period, until it next succeeds, or until
+    /// [`RoutingEngine::clear_auth_failure`]
+    pub auth_failed_at: Option<Instant>,
 }
 
 impl Default for ProviderHealth {
@@ -60,36 +92,100 @@ impl Default for ProviderHealth {
             avg_latency_ms: 0.0,
             last_success: None,
             last_failure: None,
+            decayed_successes: 0.0,
+            decayed_failures: 0.0,
+            last_decay: None,
+            auth_failed_at: None,
         }
     }
 }
 
 impl ProviderHealth {
-    /// Calculate success rate (0.0 to 1.0)
-    pub fn success_rate(&self) -> f64 {
-        if self.total_requests == 0 {
-            1.0
+    /// Calculate success rate (0.0 to 1.0) as of `now`
+    ///
+    /// Based on the time-decayed counters, decayed up to `now`, so recent
+    /// outcomes dominate and old failure bursts fade out with the half-life.
+    /// Once less than [`MIN_HEALTH_EVIDENCE`] is left, an idle provider
+    /// recovers instead of being judged on a burst nobody has retried.
+    pub fn success_rate(&self, now: Instant, half_life: Duration) -> f64 {
+        let factor = self.decay_factor(now, half_life);
+        let successes = self.decayed_successes * factor;
+        let failures = self.decayed_failures * factor;
+        let total = successes + failures;
+        if total <= f64::EPSILON {
+            return 1.0;
+        }
+        
+        let confidence = (total / MIN_HEALTH_EVIDENCE).min(1.0);
+        1.0 - confidence * failures / total
+    }
+    
+    /// Record a request outcome, decaying older outcomes first
+    pub fn record_outcome(&mut self, success: bool, now: Instant, half_life: Duration) {
+        self.decay(now, half_life);
+        
+        self.total_requests += 1;
+        if success {
+            self.successful_requests += 1;
+            self.decayed_successes += 1.0;
+            self.last_success = Some(now);
         } else {
-            self.successful_requests as f64 / self.total_requests as f64
+            self.failed_requests += 1;
+            self.decayed_failures += 1.0;
+            self.last_failure = Some(now);
         }
     }
     
-    /// Determine if provider is healthy
-    pub fn is_healthy(&self) -> bool {
+    /// Scale the decayed counters by 0.5^(elapsed / half_life)
+    fn decay(&mut self, now: Instant, half_life: Duration) {
+        let factor = self.decay_factor(now, half_life);
+        self.decayed_successes *= factor;
+        self.decayed_failures *= factor;
+        self.last_decay = Some(now);
+    }
+    
+    /// How much the counters have faded between the last decay and `now`
+    fn decay_factor(&self, now: Instant, half_life: Duration) -> f64 {
+        let Some(last) = self.last_decay else {
+            return 1.0;
+        };
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        let half_life = half_life.as_secs_f64();
+        if elapsed > 0.0 && half_life > 0.0 {
+            0.5f64.powf(elapsed / half_life)
+        } else {
+            1.0
+        }
+    }
+    
+    /// Whether a rejected API key still keeps the provider out of routing
+    ///
+    /// Once `retry_after` has passed the provider is probed again (half
+    /// open): a success clears the failure, another rejection restarts the
+    /// period.
+    pub fn auth_excluded(&self, now: Instant, retry_after: Duration) -> bool {
+        self.auth_failed_at
+            .is_some_and(|at| now.saturating_duration_since(at) < retry_after)
+    }
+    
+    /// Determine if provider is healthy as of `now`
+    ///
+    /// Does not cover API key rejections; see [`auth_excluded`](Self::auth_excluded).
+    pub fn is_healthy(&self, now: Instant, half_life: Duration) -> bool {
         // Consider healthy if:
         // - No requests yet, OR
-        // - Success rate >= 80%, OR
+        // - Decayed success rate >= 80%, OR
         // - Last request was successful and within last 5 minutes
         if self.total_requests == 0 {
             return true;
         }
         
-        if self.success_rate() >= 0.8 {
+        if self.success_rate(now, half_life) >= 0.8 {
             return true;
         }
         
         if let Some(last_success) = self.last_success {
-            if last_success.elapsed() < Duration::from_secs(300) {
+            if now.saturating_duration_since(last_success) < Duration::from_secs(300) {
                 return true;
             }
         }
@@ -98,7 +194,59 @@ impl ProviderHealth {
     }
 }
 
+/// Request attributes visible to a [`PreSelectHook`]
+#[derive(Debug, Clone, Default)]
+pub struct RoutingContext {
+    /// Requested model
+    pub model: String,
+
+    /// Request metadata (tenant, user, tags, ...)
+    pub metadata: HashMap<String, String>,
+
+    /// Inbound request headers (lowercase names)
+    pub headers: HashMap<String, String>,
+}
+
+impl RoutingContext {
+    /// Create a context for the given model
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a metadata entry
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a header (name is lowercased)
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into().to_ascii_lowercase(), value.into());
+        self
+    }
+}
+
+/// Handle to a [`RoutingEngine`] for storing in shared state
+///
+/// Clone it per request or per worker instead of wrapping the engine in
+/// another `Arc`; see [`RoutingEngine::handle`].
+pub type RoutingEngineHandle = RoutingEngine;
+
+/// Callback that can pin a request to a provider id before the strategy runs.
+/// Returning `None` (or an id that is unknown/unhealthy) defers to the strategy.
+pub type PreSelectHook = Arc<dyn Fn(&RoutingContext) -> Option<String> + Send + Sync>;
+
+/// `strategy` label of selections made by the [`PreSelectHook`]
+pub const PRE_SELECT_STRATEGY: &str = "pre-select";
+
 /// Main routing engine
+///
+/// Cloning is cheap: every field is shared, so all clones route over the
+/// same providers, circuit breakers, health metrics and strategy state.
+#[derive(Clone)]
 pub struct RoutingEngine {
     /// Available providers
     providers: Arc<RwLock<Vec<Provider>>>,
@@ -113,7 +261,16 @@ pub struct RoutingEngine {
     strategy: Arc<dyn RoutingStrategy>,
     
     /// Retry configuration
-    retry_config: RetryConfig,
+    retry_config: Arc<RetryConfig>,
+
+    /// Optional selection override consulted before the strategy
+    pre_select: Option<PreSelectHook>,
+
+    /// Half-life of the decayed health counters
+    health_half_life: Duration,
+
+    /// How long a provider that rejected its API key is excluded
+    auth_retry_after: Duration,
 }
 
 impl RoutingEngine {
@@ -136,7 +293,9 @@ impl RoutingEngine {
                 failure_threshold: 5,
                 timeout: Duration::from_secs(30),
                 success_threshold: 2,
+                half_open_max_calls: 1,
                 provider_name: provider.id.clone(),
+                ..LLMCircuitBreakerConfig::default()
             };
             circuit_breakers.insert(
                 provider.id.clone(),
@@ -149,9 +308,42 @@ impl RoutingEngine {
             circuit_breakers: Arc::new(RwLock::new(circuit_breakers)),
             health_metrics: Arc::new(RwLock::new(HashMap::new())),
             strategy,
-            retry_config,
+            retry_config: Arc::new(retry_config),
+            pre_select: None,
+            health_half_life: DEFAULT_HEALTH_HALF_LIFE,
+            auth_retry_after: DEFAULT_AUTH_RETRY_AFTER,
         }
     }
+
+    /// A handle sharing this engine's state
+    pub fn handle(&self) -> RoutingEngineHandle {
+        self.clone()
+    }
+
+    /// Set how quickly old outcomes fade from provider health
+    pub fn with_health_half_life(mut self, half_life: Duration) -> Self {
+        self.health_half_life = half_life;
+        self
+    }
+
+    /// Set how long a provider that rejected its API key is left out before
+    /// it is probed again
+    pub fn with_auth_retry_after(mut self, retry_after: Duration) -> Self {
+        self.auth_retry_after = retry_after;
+        self
+    }
+
+    /// Only fail over to another provider for these failure classes
+    pub fn with_failover_on(mut self, classes: HashSet<StatusClass>) -> Self {
+        Arc::make_mut(&mut self.retry_config).failover_on = classes;
+        self
+    }
+
+    /// Install a hook that can override provider selection per request
+    pub fn with_pre_select(mut self, hook: PreSelectHook) -> Self {
+        self.pre_select = Some(hook);
+        self
+    }
     
     /// Create engine with round-robin strategy
     pub fn with_round_robin(providers: Vec<Provider>) -> Self {
@@ -189,34 +381,87 @@ impl RoutingEngine {
         )
     }
     
+    /// Create engine with per-model provider preferences
+    ///
+    /// Models missing from `table` are routed by the failover chain strategy.
+    pub fn with_model_routing(providers: Vec<Provider>, table: RoutingTable) -> Self {
+        Self::new(
+            providers,
+            Arc::new(ModelRoutingStrategy::new(
+                table,
+                Arc::new(FailoverChainStrategy::new(3)),
+            )),
+            RetryConfig::default(),
+        )
+    }
+    
+    /// Create engine splitting traffic by `weights` (provider id to weight)
+    ///
+    /// Providers without a weight get 1.0. Each weight is scaled by the
+    /// provider's decayed success rate, so traffic shifts away from a
+    /// degrading provider gradually instead of only when its breaker opens.
+    pub fn with_weighted(providers: Vec<Provider>, weights: HashMap<String, f64>) -> Self {
+        Self::new(
+            providers,
+            Arc::new(
+                WeightedStrategy::new(weights)
+                    .with_error_penalty(strategies::DEFAULT_ERROR_PENALTY),
+            ),
+            RetryConfig::default(),
+        )
+    }
+    
     /// Route a request to an appropriate provider
-    #[instrument(skip(self, request_fn), fields(strategy = self.strategy.name()))]
     pub async fn route<F, T, E>(
         &self,
         request_fn: F,
     ) -> Result<T, RoutingError>
     where
         F: Fn(Provider) -> futures::future::BoxFuture<'static, Result<T, E>> + Send + Sync,
-        E: std::error::Error + Send + Sync + 'static,
-        T: Send,
+        E: std::error::Error + ClassifyFailure + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        self.route_with_context(&RoutingContext::default(), request_fn)
+            .await
+    }
+
+    /// Route a request, exposing `context` to the pre-select hook
+    #[instrument(
+        skip(self, context, request_fn),
+        fields(strategy = self.strategy.name(), model = %context.model)
+    )]
+    pub async fn route_with_context<F, T, E>(
+        &self,
+        context: &RoutingContext,
+        request_fn: F,
+    ) -> Result<T, RoutingError>
+    where
+        F: Fn(Provider) -> futures::future::BoxFuture<'static, Result<T, E>> + Send + Sync,
+        E: std::error::Error + ClassifyFailure + Send + Sync + 'static,
+        T: Send + 'static,
     {
         let mut attempt = 0;
-        let mut last_error = None;
+        let mut budget = RetryBudget::default();
+        let mut auth_failure = None;
         
-        while attempt < self.retry_config.max_retries {
-            // Select provider
-            let provider = self.select_provider().await?;
+        loop {
+            // Select provider; once every provider has rejected its key,
+            // report that rather than "no providers"
+            let provider = match self.select_provider(context).await {
+                Ok(provider) => provider,
+                Err(e) => return Err(auth_failure.unwrap_or(e)),
+            };
+            attempt += 1;
             
             debug!(
                 provider = %provider.id,
-                attempt = attempt + 1,
-                max_retries = self.retry_config.max_retries,
+                attempt,
                 "Attempting request"
             );
             
             // Execute request through circuit breaker
             let start = Instant::now();
-            let result = self.execute_with_circuit_breaker(&provider, &request_fn).await;
+            let (result, class) = self.execute_with_circuit_breaker(&provider, &request_fn).await;
             let latency = start.elapsed();
             
             match result {
@@ -240,42 +485,69 @@ impl RoutingEngine {
                     
                     warn!(
                         provider = %provider.id,
-                        attempt = attempt + 1,
+                        attempt,
                         error = %e,
+                        class = ?class,
                         "Request failed"
                     );
-                    
-                    last_error = Some(e);
-                    attempt += 1;
-                    
-                    // Exponential backoff before retry
-                    if attempt < self.retry_config.max_retries {
-                        let backoff = self.retry_config.backoff_duration(attempt - 1);
-                        debug!(
-                            backoff_ms = backoff.as_millis(),
-                            "Backing off before retry"
-                        );
-                        tokio::time::sleep(backoff).await;
+
+                    // A rejected key fails the same way on every retry, but
+                    // not on other providers: exclude this one and move on
+                    if class == Some(StatusClass::Auth) {
+                        self.record_auth_failure(&provider.id).await;
+                        auth_failure = Some(RoutingError::NonRetryable {
+                            provider: provider.id.clone(),
+                            class: StatusClass::Auth,
+                            message: e.to_string(),
+                        });
+                        continue;
                     }
+
+                    // Client errors would fail the same way on every provider
+                    if let Some(class) =
+                        class.filter(|c| !self.retry_config.failover_on.contains(c))
+                    {
+                        return Err(RoutingError::NonRetryable {
+                            provider: provider.id.clone(),
+                            class,
+                            message: e.to_string(),
+                        });
+                    }
+                    
+                    // Timeouts and other failures draw on separate retry budgets
+                    let timed_out = class == Some(StatusClass::Timeout);
+                    let Some(backoff) = self.retry_config.next_retry(&mut budget, timed_out) else {
+                        break;
+                    };
+                    debug!(
+                        backoff_ms = backoff.as_millis(),
+                        timed_out,
+                        "Backing off before retry"
+                    );
+                    self.retry_config.sleep(backoff).await;
                 }
             }
         }
         
         error!(
             attempts = attempt,
+            error_retries = budget.errors,
+            timeout_retries = budget.timeouts,
             "All retry attempts exhausted"
         );
         
         Err(RoutingError::AllProvidersFailed)
     }
     
-    /// Select a provider using the current strategy
-    async fn select_provider(&self) -> Result<Provider, RoutingError> {
+    /// Select a provider using the pre-select hook, then the current strategy
+    async fn select_provider(&self, context: &RoutingContext) -> Result<Provider, RoutingError> {
         let providers = self.providers.read().await;
         let health_metrics = self.health_metrics.read().await;
         let circuit_breakers = self.circuit_breakers.read().await;
+        let now = self.retry_config.clock.now();
         
         // Build list of providers with health status
+        let mut auth_excluded = Vec::new();
         let providers_with_health: Vec<ProviderWithHealth> = providers
             .iter()
             .map(|p| {
@@ -289,41 +561,111 @@ impl RoutingEngine {
                     .map(|cb| !cb.is_open())
                     .unwrap_or(true);
                 
+                // A rejected key won't fix itself, however good the history
+                let key_rejected = health.auth_excluded(now, self.auth_retry_after);
+                if key_rejected && p.enabled {
+                    auth_excluded.push(p.id.clone());
+                }
+                
                 ProviderWithHealth {
                     provider: p.clone(),
-                    is_healthy: health.is_healthy() && circuit_healthy,
+                    is_healthy: health.is_healthy(now, self.health_half_life)
+                        && circuit_healthy
+                        && !key_rejected,
                     avg_latency_ms: health.avg_latency_ms,
-                    success_rate: health.success_rate(),
+                    success_rate: health.success_rate(now, self.health_half_life),
                 }
             })
             .collect();
         
-        self.strategy
-            .select_provider(&providers_with_health)
+        let candidates = providers_with_health
+            .iter()
+            .filter(|p| p.is_healthy && p.provider.enabled)
+            .count();
+        RoutingMetrics::record_candidates(self.strategy.name(), candidates);
+        
+        if let Some(hook) = &self.pre_select {
+            if let Some(provider_id) = hook(context) {
+                match providers_with_health
+                    .iter()
+                    .find(|p| p.provider.id == provider_id)
+                {
+                    Some(p) if p.is_healthy && p.provider.enabled => {
+                        debug!(provider = %provider_id, "Provider pinned by pre-select hook");
+                        RoutingMetrics::record_selection(PRE_SELECT_STRATEGY, &provider_id);
+                        return Ok(p.provider.clone());
+                    }
+                    Some(_) => {
+                        warn!(provider = %provider_id, "Pre-selected provider is unhealthy, using strategy");
+                    }
+                    None => {
+                        warn!(provider = %provider_id, "Pre-selected provider is unknown, using strategy");
+                    }
+                }
+            }
+        }
+        
+        match self
+            .strategy
+            .select_provider(&context.model, &providers_with_health)
             .await
-            .ok_or(RoutingError::NoProvidersAvailable)
+        {
+            Some(provider) => {
+                RoutingMetrics::record_selection(self.strategy.name(), &provider.id);
+                Ok(provider)
+            }
+            None => {
+                RoutingMetrics::record_no_provider(self.strategy.name());
+                // Every enabled provider has a rejected key: that's what
+                // needs fixing, not provider availability
+                let enabled = providers.iter().filter(|p| p.enabled).count();
+                if enabled > 0 && auth_excluded.len() == enabled {
+                    return Err(RoutingError::NonRetryable {
+                        provider: auth_excluded.join(","),
+                        class: StatusClass::Auth,
+                        message: "every provider has rejected its API key".to_string(),
+                    });
+                }
+                Err(RoutingError::NoProvidersAvailable)
+            }
+        }
     }
     
     /// Execute request through circuit breaker
+    ///
+    /// Also returns the [`StatusClass`] of the provider error, if any, since
+    /// the circuit breaker only passes the error message through.
     async fn execute_with_circuit_breaker<F, T, E>(
         &self,
         provider: &Provider,
         request_fn: &F,
-    ) -> Result<T, RoutingError>
+    ) -> (Result<T, RoutingError>, Option<StatusClass>)
     where
         F: Fn(Provider) -> futures::future::BoxFuture<'static, Result<T, E>> + Send + Sync,
-        E: std::error::Error + Send + Sync + 'static,
-        T: Send,
+        E: std::error::Error + ClassifyFailure + Send + Sync + 'static,
+        T: Send + 'static,
     {
         let circuit_breakers = self.circuit_breakers.read().await;
-        let cb = circuit_breakers
-            .get(&provider.id)
-            .ok_or_else(|| RoutingError::ProviderError("Circuit breaker not found".to_string()))?;
+        let Some(cb) = circuit_breakers.get(&provider.id) else {
+            return (
+                Err(RoutingError::ProviderError("Circuit breaker not found".to_string())),
+                None,
+            );
+        };
         
+        let class = Arc::new(std::sync::Mutex::new(None));
         let provider_clone = provider.clone();
-        cb.call(|| {
+        let result = cb.call(|| {
             let p = provider_clone.clone();
-            request_fn(p)
+            let request = request_fn(p);
+            let class = class.clone();
+            Box::pin(async move {
+                let result = request.await;
+                if let Err(e) = &result {
+                    *class.lock().unwrap() = e.status_class();
+                }
+                result
+            }) as futures::future::BoxFuture<'static, Result<T, E>>
         })
         .await
         .map_err(|e| match e {
@@ -336,7 +678,10 @@ impl RoutingEngine {
             circuit_breaker::CircuitBreakerError::Timeout(msg) => {
                 RoutingError::ProviderError(msg)
             }
-        })
+        });
+
+        let class = *class.lock().unwrap();
+        (result, class)
     }
     
     /// Record successful request
@@ -344,9 +689,9 @@ impl RoutingEngine {
         let mut metrics = self.health_metrics.write().await;
         let health = metrics.entry(provider_id.to_string()).or_default();
         
-        health.total_requests += 1;
-        health.successful_requests += 1;
-        health.last_success = Some(Instant::now());
+        health.record_outcome(true, self.retry_config.clock.now(), self.health_half_life);
+        // Succeeding proves the key works again, e.g. after a half-open probe
+        health.auth_failed_at = None;
         
         // Update average latency (exponential moving average)
         let alpha = 0.3; // Smoothing factor
@@ -359,13 +704,40 @@ impl RoutingEngine {
     }
     
     /// Record failed request
-    async fn record_failure(&self, provider_id: &str, latency: Duration) {
+    async fn record_failure(&self, provider_id: &str, _latency: Duration) {
         let mut metrics = self.health_metrics.write().await;
         let health = metrics.entry(provider_id.to_string()).or_default();
         
-        health.total_requests += 1;
-        health.failed_requests += 1;
-        health.last_failure = Some(Instant::now());
+        health.record_outcome(false, self.retry_config.clock.now(), self.health_half_life);
+    }
+    
+    /// Take a provider that rejected its API key out of rotation
+    ///
+    /// Usually needs a human to fix the key, so this is logged as an error
+    /// and counted in `llm_provider_auth_failures_total`. The provider is
+    /// probed again after the auth retry period, in case the key was fixed
+    /// upstream.
+    async fn record_auth_failure(&self, provider_id: &str) {
+        error!(
+            provider = %provider_id,
+            retry_after_secs = self.auth_retry_after.as_secs(),
+            "Provider rejected its API key; excluding it from routing"
+        );
+        ProviderMetrics::record_auth_failure(provider_id);
+        
+        let now = self.retry_config.clock.now();
+        let mut metrics = self.health_metrics.write().await;
+        metrics.entry(provider_id.to_string()).or_default().auth_failed_at = Some(now);
+    }
+    
+    /// Route to a provider excluded after an auth failure again right away
+    ///
+    /// Call this whenever the provider's API key is replaced, rather than
+    /// waiting out the auth retry period.
+    pub async fn clear_auth_failure(&self, provider_id: &str) {
+        if let Some(health) = self.health_metrics.write().await.get_mut(provider_id) {
+            health.auth_failed_at = None;
+        }
     }
     
     /// Get health status for all providers
@@ -435,4 +807,619 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "success");
     }
+    
+    #[tokio::test]
+    async fn test_cloned_handles_share_health_metrics() {
+        let engine = RoutingEngine::with_round_robin(create_test_providers());
+        
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let handle = engine.handle();
+                tokio::spawn(async move {
+                    for _ in 0..25 {
+                        handle
+                            .route(|_provider| {
+                                Box::pin(async { Ok::<_, std::io::Error>(()) })
+                            })
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        
+        // Every handle recorded into the same metrics, and round-robin state
+        // was shared, so the requests were split evenly
+        let metrics = engine.get_metrics().await;
+        assert_eq!(metrics.values().map(|h| h.total_requests).sum::<u64>(), 200);
+        assert_eq!(metrics["provider1"].successful_requests, 100);
+        assert_eq!(metrics["provider2"].successful_requests, 100);
+    }
+    
+    #[test]
+    fn test_selection_metrics_follow_observed_distribution() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+        
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let engine = RoutingEngine::with_round_robin(create_test_providers());
+        let disabled = RoutingEngine::with_round_robin(
+            create_test_providers()
+                .into_iter()
+                .map(|p| Provider { enabled: false, ..p })
+                .collect(),
+        );
+        
+        let observed = ::metrics::with_local_recorder(&recorder, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(async {
+                    for _ in 0..9 {
+                        engine
+                            .route(|_provider| {
+                                Box::pin(async { Ok::<_, std::io::Error>(()) })
+                            })
+                            .await
+                            .unwrap();
+                    }
+                    let err = disabled
+                        .route(|_provider| {
+                            Box::pin(async { Ok::<_, std::io::Error>(()) })
+                        })
+                        .await
+                        .unwrap_err();
+                    assert!(matches!(err, RoutingError::NoProvidersAvailable));
+                    
+                    engine.get_metrics().await
+                })
+        });
+        
+        let mut selections = HashMap::new();
+        let mut no_provider = 0;
+        let mut candidates = Vec::new();
+        for (key, _, _, value) in snapshotter.snapshot().into_vec() {
+            let key = key.key();
+            let label = |name: &str| {
+                key.labels()
+                    .find(|l| l.key() == name)
+                    .map(|l| l.value().to_string())
+                    .unwrap()
+            };
+            match (key.name(), value) {
+                ("llm_routing_selections_total", DebugValue::Counter(n)) => {
+                    assert_eq!(label("strategy"), "round-robin");
+                    selections.insert(label("provider"), n);
+                }
+                ("llm_routing_no_provider_total", DebugValue::Counter(n)) => no_provider += n,
+                ("llm_routing_candidates", DebugValue::Histogram(values)) => {
+                    candidates.extend(values.into_iter().map(|v| v.into_inner()))
+                }
+                _ => {}
+            }
+        }
+        
+        // One selection per request, split as the requests actually were
+        assert_eq!(selections.len(), 2);
+        for (provider, count) in &selections {
+            assert_eq!(*count, observed[provider].total_requests);
+        }
+        assert_eq!(selections["provider1"], 5);
+        assert_eq!(selections["provider2"], 4);
+        assert_eq!(no_provider, 1);
+        
+        // Both providers were candidates for every request; none for the failure
+        assert_eq!(candidates.iter().filter(|c| **c == 2.0).count(), 9);
+        assert_eq!(candidates.iter().filter(|c| **c == 0.0).count(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_pre_select_hook_pins_provider() {
+        let providers = create_test_providers();
+        let hook: PreSelectHook = Arc::new(|ctx: &RoutingContext| {
+            (ctx.headers.get("x-route-to").map(String::as_str) == Some("provider2"))
+                .then(|| "provider2".to_string())
+        });
+        let engine = RoutingEngine::with_round_robin(providers).with_pre_select(hook);
+        let context = RoutingContext::new("gpt-4").with_header("X-Route-To", "provider2");
+        
+        // Round-robin would alternate; the hook pins every request
+        for _ in 0..4 {
+            let provider = engine.select_provider(&context).await.unwrap();
+            assert_eq!(provider.id, "provider2");
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_pre_select_hook_none_defers_to_strategy() {
+        let providers = create_test_providers();
+        let hook: PreSelectHook = Arc::new(|_: &RoutingContext| None);
+        let engine = RoutingEngine::with_failover(providers).with_pre_select(hook);
+        
+        let provider = engine
+            .select_provider(&RoutingContext::new("gpt-4"))
+            .await
+            .unwrap();
+        assert_eq!(provider.id, "provider1");
+    }
+    
+    #[tokio::test]
+    async fn test_pre_select_hook_unknown_provider_falls_back() {
+        let providers = create_test_providers();
+        let hook: PreSelectHook = Arc::new(|_: &RoutingContext| Some("missing".to_string()));
+        let engine = RoutingEngine::with_failover(providers).with_pre_select(hook);
+        
+        let provider = engine
+            .select_provider(&RoutingContext::new("gpt-4"))
+            .await
+            .unwrap();
+        assert_eq!(provider.id, "provider1");
+    }
+    
+    #[tokio::test]
+    async fn test_model_routing_engine_uses_request_model() {
+        let table = RoutingTable::new()
+            .with_route("gpt-4", ["provider2", "provider1"])
+            .with_route("gpt-3.5-turbo", ["provider1"]);
+        let engine = RoutingEngine::with_model_routing(create_test_providers(), table);
+        
+        let gpt4 = engine
+            .select_provider(&RoutingContext::new("gpt-4"))
+            .await
+            .unwrap();
+        let gpt35 = engine
+            .select_provider(&RoutingContext::new("gpt-3.5-turbo"))
+            .await
+            .unwrap();
+        let other = engine
+            .select_provider(&RoutingContext::new("claude-3-opus"))
+            .await
+            .unwrap();
+        
+        assert_eq!(gpt4.id, "provider2");
+        assert_eq!(gpt35.id, "provider1");
+        // Not in the table: failover chain picks the highest priority
+        assert_eq!(other.id, "provider1");
+    }
+    
+    #[test]
+    fn test_provider_health_recovers_after_failure_burst() {
+        let half_life = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut health = ProviderHealth::default();
+        
+        // Long good history, then a burst of failures
+        for i in 0..100 {
+            health.record_outcome(true, start + Duration::from_secs(i), half_life);
+        }
+        let burst = start + Duration::from_secs(600);
+        for i in 0..20 {
+            health.record_outcome(false, burst + Duration::from_millis(i * 10), half_life);
+        }
+        
+        // The old history has decayed, so the burst dominates
+        let burst_end = burst + Duration::from_millis(190);
+        assert!(health.success_rate(burst_end, half_life) < 0.5);
+        
+        // Sustained successes afterwards restore the score
+        let recovery = burst + Duration::from_secs(60);
+        for i in 0..30 {
+            health.record_outcome(true, recovery + Duration::from_secs(i * 10), half_life);
+        }
+        let recovered = recovery + Duration::from_secs(290);
+        assert!(health.success_rate(recovered, half_life) > 0.9);
+        
+        // Lifetime counters are still tracked for reporting
+        assert_eq!(health.total_requests, 150);
+        assert_eq!(health.failed_requests, 20);
+    }
+    
+    #[test]
+    fn test_provider_health_decays_with_half_life() {
+        let half_life = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut health = ProviderHealth::default();
+        
+        health.record_outcome(false, start, half_life);
+        health.record_outcome(true, start + half_life, half_life);
+        
+        // The failure has halved in weight: 1 / (1 + 0.5)
+        assert!((health.decayed_failures - 0.5).abs() < 1e-9);
+        assert!((health.success_rate(start + half_life, half_life) - 2.0 / 3.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_provider_health_recovers_while_idle() {
+        let half_life = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut health = ProviderHealth::default();
+        
+        for i in 0..20 {
+            health.record_outcome(false, start + Duration::from_millis(i * 10), half_life);
+        }
+        assert!(health.success_rate(start, half_life) < 0.01);
+        assert!(!health.is_healthy(start + Duration::from_secs(1), half_life));
+        
+        // Nothing was recorded since, but reading the health decays it: the
+        // burst is still counted one half-life later...
+        let later = start + half_life;
+        assert!(health.success_rate(later, half_life) < 0.01);
+        assert!(!health.is_healthy(later, half_life));
+        
+        // ...and has faded once under one outcome's worth is left
+        let idle = start + half_life * 8;
+        assert!(health.success_rate(idle, half_life) > 0.8);
+        assert!(health.is_healthy(idle, half_life));
+        // Reads leave the counters alone
+        assert_eq!(health.last_decay, Some(start + Duration::from_millis(190)));
+    }
+    
+    #[test]
+    fn test_provider_health_no_requests() {
+        let health = ProviderHealth::default();
+        let now = Instant::now();
+        assert_eq!(health.success_rate(now, DEFAULT_HEALTH_HALF_LIFE), 1.0);
+        assert!(health.is_healthy(now, DEFAULT_HEALTH_HALF_LIFE));
+    }
+
+    /// Provider error carrying an HTTP status
+    #[derive(Debug)]
+    struct StatusError(u16);
+
+    impl std::fmt::Display for StatusError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "HTTP {}", self.0)
+        }
+    }
+
+    impl std::error::Error for StatusError {}
+
+    impl ClassifyFailure for StatusError {
+        fn status_class(&self) -> Option<StatusClass> {
+            StatusClass::from_status(self.0)
+        }
+    }
+
+    /// Route once where provider1 fails with `status`; returns the result and
+    /// the providers that were called
+    async fn route_failing_primary(
+        status: u16,
+    ) -> (Result<&'static str, RoutingError>, Vec<String>) {
+        let engine = RoutingEngine::with_failover(create_test_providers());
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let result = engine
+            .route({
+                let calls = calls.clone();
+                move |provider: Provider| {
+                    calls.lock().unwrap().push(provider.id.clone());
+                    Box::pin(async move {
+                        if provider.id == "provider1" {
+                            Err(StatusError(status))
+                        } else {
+                            Ok("ok")
+                        }
+                    })
+                }
+            })
+            .await;
+
+        let calls = calls.lock().unwrap().clone();
+        (result, calls)
+    }
+
+    #[tokio::test]
+    async fn test_client_error_fails_fast_without_failover() {
+        let (result, calls) = route_failing_primary(400).await;
+
+        assert_eq!(calls, vec!["provider1"]);
+        match result {
+            Err(RoutingError::NonRetryable { provider, class, .. }) => {
+                assert_eq!(provider, "provider1");
+                assert_eq!(class, StatusClass::Client4xx);
+            }
+            other => panic!("expected NonRetryable, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_error_fails_over() {
+        let (result, calls) = route_failing_primary(503).await;
+
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(calls, vec!["provider1", "provider2"]);
+    }
+
+    #[tokio::test]
+    async fn test_failover_on_is_configurable() {
+        let engine = RoutingEngine::with_failover(create_test_providers())
+            .with_failover_on(HashSet::from([StatusClass::Server5xx]));
+
+        let result = engine
+            .route(|_provider: Provider| {
+                Box::pin(async { Err::<(), _>(StatusError(429)) })
+            })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(RoutingError::NonRetryable { class: StatusClass::RateLimit429, .. })
+        ));
+    }
+
+    /// Failover engine with the given retry budgets and no backoff delay
+    ///
+    /// Both providers start with a good track record, so a few failures
+    /// do not mark them unhealthy and cut the retries short.
+    async fn engine_with_retries(
+        max_retries_on_error: u32,
+        max_retries_on_timeout: u32,
+    ) -> RoutingEngine {
+        let no_delay = strategies::Backoff {
+            initial: Duration::ZERO,
+            ..strategies::Backoff::default()
+        };
+        let engine = RoutingEngine::new(
+            create_test_providers(),
+            Arc::new(FailoverChainStrategy::new(3)),
+            RetryConfig {
+                max_retries_on_error,
+                max_retries_on_timeout,
+                error_backoff: no_delay.clone(),
+                timeout_backoff: no_delay,
+                ..RetryConfig::default()
+            },
+        );
+        for provider in ["provider1", "provider2"] {
+            for _ in 0..20 {
+                engine.record_success(provider, Duration::from_millis(10)).await;
+            }
+        }
+        engine
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_use_timeout_retry_budget() {
+        let engine = engine_with_retries(5, 2).await;
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+
+        let result = engine
+            .route({
+                let calls = calls.clone();
+                move |_provider: Provider| {
+                    calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Box::pin(async {
+                        Err::<(), _>(std::io::Error::from(std::io::ErrorKind::TimedOut))
+                    })
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(RoutingError::AllProvidersFailed)));
+        // First attempt plus two timeout retries; the error budget is unused
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_errors_use_error_retry_budget() {
+        let engine = engine_with_retries(2, 0).await;
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+
+        let result = engine
+            .route({
+                let calls = calls.clone();
+                move |_provider: Provider| {
+                    let call = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                    Box::pin(async move {
+                        if call <= 2 {
+                            Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                        } else {
+                            Ok("ok")
+                        }
+                    })
+                }
+            })
+            .await;
+
+        // Two connection errors retried even with no timeout retries allowed
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_loop_sleeps_on_injected_clock() {
+        use strategies::Clock;
+
+        let clock = Arc::new(strategies::ManualClock::new());
+        let start = clock.now();
+        let retry = RetryConfig {
+            max_retries_on_error: 3,
+            error_backoff: strategies::Backoff {
+                initial: Duration::from_secs(1),
+                jitter: 0.25,
+                ..strategies::Backoff::default()
+            },
+            ..RetryConfig::default()
+        }
+        .with_time_source(clock.clone(), Arc::new(strategies::SeededRng::new(42)));
+        let expected: Vec<Duration> = {
+            let rng = strategies::SeededRng::new(42);
+            (0..3)
+                .map(|retry_number| retry.error_backoff.jittered_duration(retry_number, &rng))
+                .collect()
+        };
+        let engine = RoutingEngine::new(
+            create_test_providers(),
+            Arc::new(FailoverChainStrategy::new(3)),
+            retry,
+        );
+        for provider in ["provider1", "provider2"] {
+            for _ in 0..20 {
+                engine.record_success(provider, Duration::from_millis(10)).await;
+            }
+        }
+
+        let result = engine
+            .route(|_provider: Provider| {
+                Box::pin(async {
+                    Err::<(), _>(std::io::Error::from(std::io::ErrorKind::ConnectionReset))
+                })
+            })
+            .await;
+
+        assert!(matches!(result, Err(RoutingError::AllProvidersFailed)));
+        // Seconds of backoff went by on the fake clock, not in the test
+        assert_eq!(clock.sleeps(), expected);
+        assert_eq!(clock.now() - start, expected.iter().sum::<Duration>());
+        for (retry_number, delay) in expected.iter().enumerate() {
+            let base = Duration::from_secs(1 << retry_number);
+            assert!(*delay >= base.mul_f64(0.75) && *delay <= base.mul_f64(1.25));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bad_key_on_primary_fails_over_to_secondary() {
+        // No retries: switching away from a rejected key is not a retry
+        let engine = engine_with_retries(0, 0).await;
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let route = || {
+            engine.route({
+                let calls = calls.clone();
+                move |provider: Provider| {
+                    calls.lock().unwrap().push(provider.id.clone());
+                    Box::pin(async move {
+                        if provider.id == "provider1" {
+                            Err(StatusError(401))
+                        } else {
+                            Ok("ok")
+                        }
+                    })
+                }
+            })
+        };
+
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(*calls.lock().unwrap(), vec!["provider1", "provider2"]);
+        assert!(engine.get_metrics().await["provider1"].auth_failed_at.is_some());
+
+        // The primary stays out of rotation rather than failing every request
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(calls.lock().unwrap().len(), 3);
+        assert_eq!(calls.lock().unwrap()[2], "provider2");
+
+        // Until the key is fixed; this one still isn't
+        engine.clear_auth_failure("provider1").await;
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(calls.lock().unwrap()[3..], ["provider1", "provider2"]);
+    }
+
+    #[tokio::test]
+    async fn test_bad_key_on_only_provider_returns_auth_error() {
+        let providers = create_test_providers().into_iter().take(1).collect();
+        let engine = RoutingEngine::with_failover(providers);
+
+        let result = engine
+            .route(|_provider: Provider| {
+                Box::pin(async { Err::<(), _>(StatusError(401)) })
+            })
+            .await;
+
+        match result {
+            Err(RoutingError::NonRetryable { provider, class, .. }) => {
+                assert_eq!(provider, "provider1");
+                assert_eq!(class, StatusClass::Auth);
+            }
+            other => panic!("expected NonRetryable, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    /// Healthy engine on a [`strategies::ManualClock`] whose auth
+    /// exclusions can be aged without sleeping
+    async fn engine_on_clock(clock: Arc<strategies::ManualClock>) -> RoutingEngine {
+        let engine = RoutingEngine::new(
+            create_test_providers(),
+            Arc::new(FailoverChainStrategy::new(3)),
+            RetryConfig::uniform(0)
+                .with_time_source(clock, Arc::new(strategies::SeededRng::new(42))),
+        );
+        for provider in ["provider1", "provider2"] {
+            for _ in 0..20 {
+                engine.record_success(provider, Duration::from_millis(10)).await;
+            }
+        }
+        engine
+    }
+
+    #[tokio::test]
+    async fn test_rejected_key_probed_again_after_retry_period() {
+        let clock = Arc::new(strategies::ManualClock::new());
+        let engine = engine_on_clock(clock.clone()).await;
+        let key_fixed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let route = || {
+            engine.route({
+                let calls = calls.clone();
+                let key_fixed = key_fixed.clone();
+                move |provider: Provider| {
+                    calls.lock().unwrap().push(provider.id.clone());
+                    let fixed = key_fixed.load(std::sync::atomic::Ordering::SeqCst);
+                    Box::pin(async move {
+                        if provider.id == "provider1" && !fixed {
+                            Err(StatusError(401))
+                        } else {
+                            Ok("ok")
+                        }
+                    })
+                }
+            })
+        };
+
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(*calls.lock().unwrap(), vec!["provider1", "provider2", "provider2"]);
+
+        // The key was fixed upstream; the next request after the retry
+        // period probes the primary again and its success clears the failure
+        key_fixed.store(true, std::sync::atomic::Ordering::SeqCst);
+        clock.advance(DEFAULT_AUTH_RETRY_AFTER);
+        assert_eq!(route().await.unwrap(), "ok");
+        assert_eq!(calls.lock().unwrap()[3..], ["provider1"]);
+        assert!(engine.get_metrics().await["provider1"].auth_failed_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_every_key_rejected_returns_auth_error() {
+        let clock = Arc::new(strategies::ManualClock::new());
+        let engine = engine_on_clock(clock).await;
+        let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let route = || {
+            engine.route({
+                let calls = calls.clone();
+                move |_provider: Provider| {
+                    calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Box::pin(async { Err::<(), _>(StatusError(401)) })
+                }
+            })
+        };
+
+        assert!(matches!(
+            route().await,
+            Err(RoutingError::NonRetryable { class: StatusClass::Auth, .. })
+        ));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Later requests reach no provider, and still say why
+        match route().await {
+            Err(RoutingError::NonRetryable { provider, class, .. }) => {
+                assert_eq!(class, StatusClass::Auth);
+                assert_eq!(provider, "provider1,provider2");
+            }
+            other => panic!("expected an auth error, got {:?}", other.map(|_| ())),
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 }
@@ -5,12 +5,16 @@
 //! - Failover Chain: Tries providers in priority order until one succeeds
 //! - Least Latency: Routes to the provider with lowest average latency
 //! - Cost Optimized: Routes to the cheapest provider that meets requirements
+//! - Model Routing: Per-model provider preference, falling back to another strategy
+//! - Weighted: Splits traffic in proportion to per-provider weights, adjusted by
+//!   scoring hooks such as [`error_penalty`]
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
 /// Provider information for routing decisions
@@ -50,9 +54,10 @@ pub struct ProviderWithHealth {
 /// Trait for routing strategies
 #[async_trait]
 pub trait RoutingStrategy: Send + Sync {
-    /// Select a provider based on the strategy
+    /// Select a provider for a request for `model`
     async fn select_provider(
         &self,
+        model: &str,
         providers: &[ProviderWithHealth],
     ) -> Option<Provider>;
     
@@ -94,6 +99,7 @@ impl Default for RoundRobinStrategy {
 impl RoutingStrategy for RoundRobinStrategy {
     async fn select_provider(
         &self,
+        _model: &str,
         providers: &[ProviderWithHealth],
     ) -> Option<Provider> {
         // Filter to only healthy providers
@@ -162,6 +168,7 @@ impl Default for FailoverChainStrategy {
 impl RoutingStrategy for FailoverChainStrategy {
     async fn select_provider(
         &self,
+        _model: &str,
         providers: &[ProviderWithHealth],
     ) -> Option<Provider> {
         // Sort by priority (lower number = higher priority)
@@ -227,6 +234,7 @@ impl Default for LeastLatencyStrategy {
 impl RoutingStrategy for LeastLatencyStrategy {
     async fn select_provider(
         &self,
+        _model: &str,
         providers: &[ProviderWithHealth],
     ) -> Option<Provider> {
         let healthy: Vec<_> = providers
@@ -301,6 +309,7 @@ impl Default for CostOptimizedStrategy {
 impl RoutingStrategy for CostOptimizedStrategy {
     async fn select_provider(
         &self,
+        _model: &str,
         providers: &[ProviderWithHealth],
     ) -> Option<Provider> {
         let healthy: Vec<_> = providers
@@ -350,6 +359,242 @@ impl RoutingStrategy for CostOptimizedStrategy {
     }
 }
 
+/// Ordered provider preferences per model
+///
+/// Maps a model name (exact match) to provider ids, most preferred first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingTable {
+    routes: HashMap<String, Vec<String>>,
+}
+
+impl RoutingTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set the provider preference order for `model`
+    pub fn with_route<I, S>(mut self, model: impl Into<String>, providers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.routes
+            .insert(model.into(), providers.into_iter().map(Into::into).collect());
+        self
+    }
+    
+    /// Preferred provider ids for `model`, if it has an entry
+    pub fn preferred(&self, model: &str) -> Option<&[String]> {
+        self.routes.get(model).map(Vec::as_slice)
+    }
+}
+
+/// Per-model routing strategy
+///
+/// For models in the [`RoutingTable`], selects the first healthy provider in
+/// the model's preference order. Models without an entry, or whose preferred
+/// providers are all unavailable, are routed by the fallback strategy.
+pub struct ModelRoutingStrategy {
+    table: RoutingTable,
+    fallback: Arc<dyn RoutingStrategy>,
+}
+
+impl ModelRoutingStrategy {
+    pub fn new(table: RoutingTable, fallback: Arc<dyn RoutingStrategy>) -> Self {
+        info!(
+            models = table.routes.len(),
+            fallback = fallback.name(),
+            "Initialized Model routing strategy"
+        );
+        Self { table, fallback }
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for ModelRoutingStrategy {
+    async fn select_provider(
+        &self,
+        model: &str,
+        providers: &[ProviderWithHealth],
+    ) -> Option<Provider> {
+        if let Some(preferred) = self.table.preferred(model) {
+            let selected = preferred.iter().find_map(|id| {
+                providers
+                    .iter()
+                    .find(|p| &p.provider.id == id && p.provider.enabled && p.is_healthy)
+            });
+            
+            match selected {
+                Some(p) => {
+                    debug!(
+                        provider = %p.provider.id,
+                        model = model,
+                        "Selected preferred provider for model"
+                    );
+                    return Some(p.provider.clone());
+                }
+                None => {
+                    warn!(
+                        model = model,
+                        "No preferred provider available for model, using fallback strategy"
+                    );
+                }
+            }
+        }
+        
+        self.fallback.select_provider(model, providers).await
+    }
+    
+    async fn record_result(
+        &self,
+        provider_id: &str,
+        latency: Duration,
+        success: bool,
+    ) {
+        self.fallback.record_result(provider_id, latency, success).await;
+    }
+    
+    fn name(&self) -> &str {
+        "model-routing"
+    }
+}
+
+/// Adjusts a provider's weight given its runtime health
+///
+/// Receives the weight produced so far and returns the new one; a weight of
+/// zero or less takes the provider out of rotation.
+pub type ScoringHook = Arc<dyn Fn(&ProviderWithHealth, f64) -> f64 + Send + Sync>;
+
+/// Default exponent for [`error_penalty`]: traffic proportional to success rate
+pub const DEFAULT_ERROR_PENALTY: f64 = 1.0;
+
+/// Scoring hook scaling weight by `success_rate ^ penalty`
+///
+/// Uses the decayed success rate, so a provider at 70% success keeps 70% of
+/// its weight with the default penalty of 1.0 and traffic shifts away
+/// gradually, before the circuit breaker trips. Larger penalties shift
+/// traffic faster; 0.0 disables the penalty.
+pub fn error_penalty(penalty: f64) -> ScoringHook {
+    Arc::new(move |provider, weight| {
+        weight * provider.success_rate.clamp(0.0, 1.0).powf(penalty)
+    })
+}
+
+/// Weighted routing strategy
+///
+/// Splits traffic across healthy providers in proportion to their weights
+/// (1.0 unless configured), after applying each [`ScoringHook`] in order.
+/// Uses smooth weighted round-robin, so shares are exact over a cycle rather
+/// than random.
+pub struct WeightedStrategy {
+    weights: HashMap<String, f64>,
+    scoring: Vec<ScoringHook>,
+    current: Mutex<HashMap<String, f64>>,
+}
+
+impl WeightedStrategy {
+    pub fn new(weights: HashMap<String, f64>) -> Self {
+        info!(
+            weighted_providers = weights.len(),
+            "Initialized Weighted routing strategy"
+        );
+        Self {
+            weights,
+            scoring: Vec::new(),
+            current: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Add a hook adjusting each provider's weight per selection
+    pub fn with_scoring_hook(mut self, hook: ScoringHook) -> Self {
+        self.scoring.push(hook);
+        self
+    }
+    
+    /// De-prioritize providers by their recent error rate (see [`error_penalty`])
+    pub fn with_error_penalty(self, penalty: f64) -> Self {
+        self.with_scoring_hook(error_penalty(penalty))
+    }
+    
+    /// Effective weight of `provider` after the scoring hooks
+    pub fn score(&self, provider: &ProviderWithHealth) -> f64 {
+        let base = self
+            .weights
+            .get(&provider.provider.id)
+            .copied()
+            .unwrap_or(1.0);
+        self.scoring
+            .iter()
+            .fold(base, |weight, hook| hook(provider, weight))
+    }
+}
+
+impl Default for WeightedStrategy {
+    fn default() -> Self {
+        Self::new(HashMap::new()).with_error_penalty(DEFAULT_ERROR_PENALTY)
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for WeightedStrategy {
+    async fn select_provider(
+        &self,
+        _model: &str,
+        providers: &[ProviderWithHealth],
+    ) -> Option<Provider> {
+        let weighted: Vec<_> = providers
+            .iter()
+            .filter(|p| p.provider.enabled && p.is_healthy)
+            .map(|p| (p, self.score(p)))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        
+        if weighted.is_empty() {
+            warn!("No healthy providers with positive weight for weighted routing");
+            return None;
+        }
+        
+        // Smooth weighted round-robin: every candidate earns its weight, the
+        // richest is picked and pays back the total
+        let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+        let mut current = self.current.lock().unwrap();
+        let mut selected: Option<(&ProviderWithHealth, f64)> = None;
+        for (p, weight) in &weighted {
+            let credit = current.entry(p.provider.id.clone()).or_insert(0.0);
+            *credit += weight;
+            if selected.map_or(true, |(_, best)| *credit > best) {
+                selected = Some((p, *credit));
+            }
+        }
+        
+        let (selected, _) = selected?;
+        if let Some(credit) = current.get_mut(&selected.provider.id) {
+            *credit -= total;
+        }
+        
+        debug!(
+            provider = %selected.provider.id,
+            total_weight = total,
+            "Selected provider via weighted routing"
+        );
+        
+        Some(selected.provider.clone())
+    }
+    
+    async fn record_result(
+        &self,
+        _provider_id: &str,
+        _latency: Duration,
+        _success: bool,
+    ) {
+        // Health feeds in through ProviderWithHealth on every selection
+    }
+    
+    fn name(&self) -> &str {
+        "weighted"
+    }
+}
+
 /// Tracks latency metrics for providers
 struct LatencyTracker {
     // In production, use a more sophisticated data structure
@@ -367,41 +612,316 @@ impl LatencyTracker {
     }
 }
 
+/// Coarse class of a failed provider call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusClass {
+    /// 4xx other than 401 and 429 (bad request, forbidden, not found, ...)
+    Client4xx,
+    /// 401: the provider rejected our credentials
+    Auth,
+    /// 429 Too Many Requests
+    RateLimit429,
+    /// 5xx
+    Server5xx,
+    /// The call timed out
+    Timeout,
+}
+
+impl StatusClass {
+    /// Class of an HTTP status code, `None` for non-error statuses
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            401 => Some(StatusClass::Auth),
+            429 => Some(StatusClass::RateLimit429),
+            400..=499 => Some(StatusClass::Client4xx),
+            500..=599 => Some(StatusClass::Server5xx),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can tell the routing engine which [`StatusClass`] they are
+///
+/// Errors returning `None` (e.g. connection failures) are always failed over.
+pub trait ClassifyFailure {
+    fn status_class(&self) -> Option<StatusClass>;
+}
+
+impl ClassifyFailure for std::io::Error {
+    fn status_class(&self) -> Option<StatusClass> {
+        (self.kind() == std::io::ErrorKind::TimedOut).then_some(StatusClass::Timeout)
+    }
+}
+
+/// Source of time for retry delays
+///
+/// Lets tests replace real sleeping with [`ManualClock`].
+#[async_trait]
+pub trait Clock: std::fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Wall clock backed by tokio's timer
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// Clock whose sleeps return at once and move its time forward
+///
+/// Records every sleep so tests can assert the exact backoff schedule.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<Instant>,
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+            sleeps: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Move time forward without sleeping
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    /// Every sleep requested so far, in order
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.sleeps.lock().unwrap().push(duration);
+        self.advance(duration);
+    }
+}
+
+/// Random numbers for backoff jitter
+pub trait RngSource: std::fmt::Debug + Send + Sync {
+    /// Uniformly distributed in `[0, 1)`
+    fn next_f64(&self) -> f64;
+}
+
+/// SplitMix64 generator; the same seed always yields the same jitter
+#[derive(Debug)]
+pub struct SeededRng {
+    state: AtomicU64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Seeded from the per-process random keys std uses for `HashMap`
+    pub fn from_entropy() -> Self {
+        use std::hash::{BuildHasher, Hasher};
+
+        Self::new(std::collections::hash_map::RandomState::new().build_hasher().finish())
+    }
+}
+
+impl RngSource for SeededRng {
+    fn next_f64(&self) -> f64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        // Top 53 bits, the precision of an f64 mantissa
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Exponential backoff schedule
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial: Duration,
+
+    /// Upper bound for any single delay
+    pub max: Duration,
+
+    /// Factor applied to the delay after each retry
+    pub multiplier: f64,
+
+    /// Each delay is moved randomly by up to this fraction either way, so
+    /// clients failing together do not retry together; 0.0 disables it
+    pub jitter: f64,
+}
+
+impl Backoff {
+    /// Delay before retry number `retry` (0-based), without jitter
+    pub fn duration(&self, retry: u32) -> Duration {
+        let backoff_ms = self.initial.as_millis() as f64 * self.multiplier.powi(retry as i32);
+
+        let backoff = Duration::from_millis(backoff_ms as u64);
+        std::cmp::min(backoff, self.max)
+    }
+
+    /// Delay before retry number `retry` with jitter drawn from `rng`
+    ///
+    /// Jittered delays never exceed `max`.
+    pub fn jittered_duration(&self, retry: u32, rng: &dyn RngSource) -> Duration {
+        let backoff = self.duration(retry);
+        if self.jitter <= 0.0 {
+            return backoff;
+        }
+
+        let factor = 1.0 + self.jitter * (2.0 * rng.next_f64() - 1.0);
+        let jittered = Duration::from_nanos((backoff.as_nanos() as f64 * factor) as u64);
+        std::cmp::min(jittered, self.max)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.0,
+        }
+    }
+}
+
 /// Retry configuration with exponential backoff
+///
+/// Timeouts and other failures have separate budgets: retrying a slow
+/// provider adds load to it, while a dropped connection is usually worth
+/// retrying straight away.
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
-    /// Maximum number of retry attempts
-    pub max_retries: u32,
-    
-    /// Initial backoff duration
-    pub initial_backoff: Duration,
-    
-    /// Maximum backoff duration
-    pub max_backoff: Duration,
-    
-    /// Backoff multiplier
-    pub backoff_multiplier: f64,
+    /// Maximum number of retries after failures other than timeouts
+    pub max_retries_on_error: u32,
+
+    /// Maximum number of retries after timeouts
+    pub max_retries_on_timeout: u32,
+
+    /// Backoff between retries after failures other than timeouts
+    pub error_backoff: Backoff,
+
+    /// Backoff between retries after timeouts
+    pub timeout_backoff: Backoff,
+
+    /// Failure classes worth retrying on another provider
+    ///
+    /// Anything else is returned to the caller after the first attempt.
+    pub failover_on: HashSet<StatusClass>,
+
+    /// Time source for backoff sleeps
+    pub clock: Arc<dyn Clock>,
+
+    /// Randomness for backoff jitter
+    pub rng: Arc<dyn RngSource>,
+}
+
+/// Retries spent so far against a [`RetryConfig`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryBudget {
+    pub errors: u32,
+    pub timeouts: u32,
 }
 
 impl Default for RetryConfig {
     fn default() -> Self {
-        Self {
-            max_retries: 3,
-            initial_backoff: Duration::from_millis(100),
-            max_backoff: Duration::from_secs(10),
-            backoff_multiplier: 2.0,
-        }
+        Self::uniform(3)
     }
 }
 
 impl RetryConfig {
-    /// Calculate backoff duration for a given attempt
+    /// Same retry count for errors and timeouts, with the default backoff
+    pub fn uniform(max_retries: u32) -> Self {
+        Self {
+            max_retries_on_error: max_retries,
+            max_retries_on_timeout: max_retries,
+            error_backoff: Backoff::default(),
+            timeout_backoff: Backoff::default(),
+            failover_on: HashSet::from([
+                StatusClass::Server5xx,
+                StatusClass::RateLimit429,
+                StatusClass::Timeout,
+            ]),
+            clock: Arc::new(SystemClock),
+            rng: Arc::new(SeededRng::from_entropy()),
+        }
+    }
+
+    /// Use `clock` and `rng` instead of real time and entropy
+    ///
+    /// With a [`ManualClock`] and a [`SeededRng`] every delay is reproducible
+    /// and retries run without waiting.
+    pub fn with_time_source(mut self, clock: Arc<dyn Clock>, rng: Arc<dyn RngSource>) -> Self {
+        self.clock = clock;
+        self.rng = rng;
+        self
+    }
+
+    /// Calculate backoff duration for a given retry after an error
     pub fn backoff_duration(&self, attempt: u32) -> Duration {
-        let backoff_ms = self.initial_backoff.as_millis() as f64
-            * self.backoff_multiplier.powi(attempt as i32);
-        
-        let backoff = Duration::from_millis(backoff_ms as u64);
-        std::cmp::min(backoff, self.max_backoff)
+        self.error_backoff.jittered_duration(attempt, self.rng.as_ref())
+    }
+
+    /// Record a failed attempt and return the delay before the next one
+    ///
+    /// Returns `None` once the budget for this kind of failure is spent.
+    pub fn next_retry(&self, budget: &mut RetryBudget, timed_out: bool) -> Option<Duration> {
+        let (spent, max, backoff) = if timed_out {
+            (&mut budget.timeouts, self.max_retries_on_timeout, &self.timeout_backoff)
+        } else {
+            (&mut budget.errors, self.max_retries_on_error, &self.error_backoff)
+        };
+
+        if *spent >= max {
+            return None;
+        }
+        let delay = backoff.jittered_duration(*spent, self.rng.as_ref());
+        *spent += 1;
+        Some(delay)
+    }
+
+    /// Wait out a delay returned by [`next_retry`](Self::next_retry)
+    pub async fn sleep(&self, delay: Duration) {
+        self.clock.sleep(delay).await
+    }
+
+    /// Whether a failure of `class` should be retried on another provider
+    ///
+    /// Unclassified failures are always failed over.
+    pub fn should_failover(&self, class: Option<StatusClass>) -> bool {
+        class.map_or(true, |class| self.failover_on.contains(&class))
     }
 }
 
@@ -447,8 +967,8 @@ mod tests {
         let strategy = RoundRobinStrategy::new();
         let providers = create_test_providers();
         
-        let first = strategy.select_provider(&providers).await.unwrap();
-        let second = strategy.select_provider(&providers).await.unwrap();
+        let first = strategy.select_provider("gpt-4", &providers).await.unwrap();
+        let second = strategy.select_provider("gpt-4", &providers).await.unwrap();
         
         // Should alternate
         assert_ne!(first.id, second.id);
@@ -459,7 +979,7 @@ mod tests {
         let strategy = FailoverChainStrategy::new(3);
         let providers = create_test_providers();
         
-        let selected = strategy.select_provider(&providers).await.unwrap();
+        let selected = strategy.select_provider("gpt-4", &providers).await.unwrap();
         
         // Should select provider with priority 1
         assert_eq!(selected.priority, 1);
@@ -470,12 +990,122 @@ mod tests {
         let strategy = CostOptimizedStrategy::new();
         let providers = create_test_providers();
         
-        let selected = strategy.select_provider(&providers).await.unwrap();
+        let selected = strategy.select_provider("gpt-4", &providers).await.unwrap();
         
         // Should select cheaper provider (provider2)
         assert_eq!(selected.id, "provider2");
     }
     
+    fn model_routing_strategy() -> ModelRoutingStrategy {
+        let table = RoutingTable::new()
+            .with_route("gpt-4", ["provider2", "provider1"])
+            .with_route("gpt-3.5-turbo", ["provider1", "provider2"]);
+        ModelRoutingStrategy::new(table, Arc::new(FailoverChainStrategy::new(3)))
+    }
+    
+    #[tokio::test]
+    async fn test_model_routing_prefers_per_model_provider() {
+        let strategy = model_routing_strategy();
+        let providers = create_test_providers();
+        
+        let gpt4 = strategy.select_provider("gpt-4", &providers).await.unwrap();
+        let gpt35 = strategy
+            .select_provider("gpt-3.5-turbo", &providers)
+            .await
+            .unwrap();
+        
+        assert_eq!(gpt4.id, "provider2");
+        assert_eq!(gpt35.id, "provider1");
+    }
+    
+    #[tokio::test]
+    async fn test_model_routing_skips_unhealthy_preferred_provider() {
+        let strategy = model_routing_strategy();
+        let mut providers = create_test_providers();
+        providers[1].is_healthy = false;
+        
+        let selected = strategy.select_provider("gpt-4", &providers).await.unwrap();
+        assert_eq!(selected.id, "provider1");
+    }
+    
+    #[tokio::test]
+    async fn test_model_routing_unknown_model_uses_fallback() {
+        let table = RoutingTable::new().with_route("gpt-4", ["provider1"]);
+        let strategy = ModelRoutingStrategy::new(table, Arc::new(CostOptimizedStrategy::new()));
+        let providers = create_test_providers();
+        
+        let selected = strategy
+            .select_provider("claude-3-opus", &providers)
+            .await
+            .unwrap();
+        assert_eq!(selected.id, "provider2");
+    }
+    
+    /// Share of `rounds` selections that went to provider2
+    async fn provider2_share(
+        strategy: &WeightedStrategy,
+        providers: &[ProviderWithHealth],
+        rounds: usize,
+    ) -> f64 {
+        let mut hits = 0;
+        for _ in 0..rounds {
+            if strategy.select_provider("gpt-4", providers).await.unwrap().id == "provider2" {
+                hits += 1;
+            }
+        }
+        hits as f64 / rounds as f64
+    }
+    
+    #[tokio::test]
+    async fn test_weighted_strategy_respects_weights() {
+        let weights = HashMap::from([("provider1".to_string(), 3.0)]);
+        let strategy = WeightedStrategy::new(weights);
+        let providers = create_test_providers();
+        
+        assert!((provider2_share(&strategy, &providers, 400).await - 0.25).abs() < 0.01);
+    }
+    
+    #[tokio::test]
+    async fn test_error_penalty_shifts_traffic_continuously() {
+        let mut providers = create_test_providers();
+        providers[0].success_rate = 1.0;
+        
+        let mut previous = 1.0;
+        for success_rate in [1.0, 0.95, 0.9, 0.8, 0.7, 0.6, 0.5, 0.3, 0.1] {
+            providers[1].success_rate = success_rate;
+            let strategy = WeightedStrategy::default();
+            
+            let share = provider2_share(&strategy, &providers, 1000).await;
+            
+            // Proportional to success rate, not all-or-nothing
+            let expected = success_rate / (1.0 + success_rate);
+            assert!(
+                (share - expected).abs() < 0.01,
+                "success rate {}: share {} != {}",
+                success_rate,
+                share,
+                expected
+            );
+            assert!(share < previous || success_rate == 1.0);
+            assert!(share > 0.0);
+            previous = share;
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_error_penalty_exponent() {
+        let mut providers = create_test_providers();
+        providers[0].success_rate = 1.0;
+        providers[1].success_rate = 0.7;
+        
+        let disabled = WeightedStrategy::new(HashMap::new()).with_error_penalty(0.0);
+        let steep = WeightedStrategy::new(HashMap::new()).with_error_penalty(4.0);
+        
+        assert!((provider2_share(&disabled, &providers, 1000).await - 0.5).abs() < 0.01);
+        let expected = 0.7f64.powi(4) / (1.0 + 0.7f64.powi(4));
+        assert!((provider2_share(&steep, &providers, 1000).await - expected).abs() < 0.01);
+    }
+    
     #[test]
     fn test_retry_backoff() {
         let config = RetryConfig::default();
@@ -488,4 +1118,111 @@ mod tests {
         assert!(backoff2 > backoff1);
         assert!(backoff3 > backoff2);
     }
+
+    #[test]
+    fn test_default_failover_classes() {
+        let config = RetryConfig::default();
+
+        assert!(config.should_failover(StatusClass::from_status(503)));
+        assert!(config.should_failover(StatusClass::from_status(429)));
+        assert!(config.should_failover(Some(StatusClass::Timeout)));
+        assert!(config.should_failover(None));
+        assert!(!config.should_failover(StatusClass::from_status(400)));
+        assert!(!config.should_failover(StatusClass::from_status(401)));
+    }
+
+    #[test]
+    fn test_retry_budgets_are_independent() {
+        let config = RetryConfig {
+            max_retries_on_error: 2,
+            max_retries_on_timeout: 1,
+            timeout_backoff: Backoff {
+                initial: Duration::from_secs(1),
+                ..Backoff::default()
+            },
+            ..RetryConfig::default()
+        };
+        let mut budget = RetryBudget::default();
+
+        assert_eq!(config.next_retry(&mut budget, true), Some(Duration::from_secs(1)));
+        assert_eq!(config.next_retry(&mut budget, true), None);
+
+        // Spent timeout retries leave the error budget untouched
+        assert_eq!(config.next_retry(&mut budget, false), Some(Duration::from_millis(100)));
+        assert_eq!(config.next_retry(&mut budget, false), Some(Duration::from_millis(200)));
+        assert_eq!(config.next_retry(&mut budget, false), None);
+        assert_eq!(budget, RetryBudget { errors: 2, timeouts: 1 });
+    }
+
+    /// Replays fixed values, so jitter factors are known exactly
+    #[derive(Debug)]
+    struct ScriptedRng(Mutex<std::vec::IntoIter<f64>>);
+
+    impl ScriptedRng {
+        fn new(values: Vec<f64>) -> Self {
+            Self(Mutex::new(values.into_iter()))
+        }
+    }
+
+    impl RngSource for ScriptedRng {
+        fn next_f64(&self) -> f64 {
+            self.0.lock().unwrap().next().expect("rng script exhausted")
+        }
+    }
+
+    fn jittered_config() -> RetryConfig {
+        RetryConfig {
+            max_retries_on_error: 4,
+            error_backoff: Backoff {
+                max: Duration::from_millis(500),
+                jitter: 0.5,
+                ..Backoff::default()
+            },
+            ..RetryConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fake_clock_records_exact_jittered_backoffs() {
+        let clock = Arc::new(ManualClock::new());
+        let start = clock.now();
+        let config = jittered_config().with_time_source(
+            clock.clone(),
+            // Factors 0.5, 1.25, 0.75 and 1.375; the last delay is capped
+            Arc::new(ScriptedRng::new(vec![0.0, 0.75, 0.25, 0.875])),
+        );
+
+        let mut budget = RetryBudget::default();
+        while let Some(delay) = config.next_retry(&mut budget, false) {
+            config.sleep(delay).await;
+        }
+
+        assert_eq!(
+            clock.sleeps(),
+            vec![
+                Duration::from_millis(50),
+                Duration::from_millis(250),
+                Duration::from_millis(300),
+                Duration::from_millis(500),
+            ]
+        );
+        assert_eq!(clock.now() - start, Duration::from_millis(1100));
+    }
+
+    #[test]
+    fn test_seeded_jitter_is_reproducible() {
+        let schedule = |seed| {
+            let config = jittered_config()
+                .with_time_source(Arc::new(ManualClock::new()), Arc::new(SeededRng::new(seed)));
+            let mut budget = RetryBudget::default();
+            std::iter::from_fn(|| config.next_retry(&mut budget, false)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(schedule(7), schedule(7));
+        assert_ne!(schedule(7), schedule(8));
+        for (retry, delay) in schedule(7).into_iter().enumerate() {
+            let base = jittered_config().error_backoff.duration(retry as u32);
+            assert!(delay >= base / 2 && delay <= Duration::from_millis(500));
+        }
+    }
 }